@@ -16,15 +16,19 @@ use search_core::problems::tpp_problem::tpp_problem::TppProblem;
 use search_core::problems::zenotravel_fuel_problem::zenotravel_fuel_problem::ZenoTravelFuelProblem;
 use search_core::problems::zenotravel_problem::zenotravel_problem::ZenoTravelProblem;
 use search_core::problems::zenotravel_time_problem::zenotravel_time_problem::ZenoTravelTimeProblem;
-use search_core::search::solve::solve_problem;
+use search_core::search::solve::solve_problem_observed;
 use std::time;
 use time::Instant;
 
 fn main() {
+    // Search observers, e.g. `progress:5 trace:trace.json tree:tree.json`
+    let observers: Vec<String> = std::env::args().skip(1).collect();
+
     let start_time = Instant::now();
-    solve_problem::<ComputeNumberProblem>(
+    solve_problem_observed::<ComputeNumberProblem, _>(
         "search_core/src/inputs/compute_number_problem/input_1.json",
         "GBFS",
+        &observers,
     );
 
     let elapsed_time = start_time.elapsed(); // Calculate elapsed time
@@ -1,20 +1,68 @@
 use pyo3::prelude::*;
 use search_core::problems::compute_number_problem::compute_number_problem::ComputeNumberProblem;
+use search_core::problems::delivery_problem::delivery_problem::DeliveryProblem;
+use search_core::problems::drone_problem::drone_problem::DroneProblem;
+use search_core::problems::expedition_problem::expedition_problem::ExpeditionProblem;
 use search_core::problems::farm_problem::farm_problem::FarmProblem;
+use search_core::problems::hydropower_problem::hydropower_problem::HydropowerProblem;
 use search_core::problems::market_trader_problem::market_trader_problem::MarketTraderProblem;
-use search_core::search::solve::solve_problem;
+use search_core::problems::rover_problem::rover_problem::RoverProblem;
+use search_core::problems::sailing_problem::sailing_problem::SailingProblem;
+use search_core::problems::settlersnumeric_problem::settlersnumeric_problem::SettlersNumericProblem;
+use search_core::search::solve::solve_problem_observed;
 
+/// `observers` takes specifications such as `["progress:5", "trace:trace.json"]`, see
+/// `Observers::from_specs`.
 #[pyfunction]
-fn solve_problem_py(json_path: &str, search_strategy: &str, problem_type: &str) -> PyResult<()> {
+#[pyo3(signature = (json_path, search_strategy, problem_type, observers = Vec::new()))]
+fn solve_problem_py(
+    json_path: &str,
+    search_strategy: &str,
+    problem_type: &str,
+    observers: Vec<String>,
+) -> PyResult<()> {
     match problem_type {
         "MarketTraderProblem" => {
-            solve_problem::<MarketTraderProblem>(json_path, search_strategy);
+            solve_problem_observed::<MarketTraderProblem, _>(
+                json_path,
+                search_strategy,
+                &observers,
+            );
         }
         "FarmProblem" => {
-            solve_problem::<FarmProblem>(json_path, search_strategy);
+            solve_problem_observed::<FarmProblem, _>(json_path, search_strategy, &observers);
         }
         "ComputeNumberProblem" => {
-            solve_problem::<ComputeNumberProblem>(json_path, search_strategy);
+            solve_problem_observed::<ComputeNumberProblem, _>(
+                json_path,
+                search_strategy,
+                &observers,
+            );
+        }
+        "DeliveryProblem" => {
+            solve_problem_observed::<DeliveryProblem, _>(json_path, search_strategy, &observers);
+        }
+        "DroneProblem" => {
+            solve_problem_observed::<DroneProblem, _>(json_path, search_strategy, &observers);
+        }
+        "ExpeditionProblem" => {
+            solve_problem_observed::<ExpeditionProblem, _>(json_path, search_strategy, &observers);
+        }
+        "HydropowerProblem" => {
+            solve_problem_observed::<HydropowerProblem, _>(json_path, search_strategy, &observers);
+        }
+        "RoverProblem" => {
+            solve_problem_observed::<RoverProblem, _>(json_path, search_strategy, &observers);
+        }
+        "SailingProblem" => {
+            solve_problem_observed::<SailingProblem, _>(json_path, search_strategy, &observers);
+        }
+        "SettlersNumericProblem" => {
+            solve_problem_observed::<SettlersNumericProblem, _>(
+                json_path,
+                search_strategy,
+                &observers,
+            );
         }
         // Add more matches for other problem types
         _ => {
@@ -1,9 +1,13 @@
 use crate::algorithms::priority_queue::PriorityQueue;
+use crate::search::cost::CostTrait;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 
+#[derive(Serialize, Deserialize)]
 pub struct AStarQueue {
-    heap: BinaryHeap<Reverse<(i32, usize)>>, // (priority, node_index)
+    heap: BinaryHeap<Reverse<(OrderedFloat<f64>, usize)>>, // (priority, node_index)
 }
 
 impl AStarQueue {
@@ -15,8 +19,8 @@ impl AStarQueue {
 }
 
 impl PriorityQueue for AStarQueue {
-    fn insert(&mut self, node_index: usize, cost: i32, heuristic: f64) {
-        let priority = (cost as f64 + heuristic) as i32;
+    fn insert<C: CostTrait>(&mut self, node_index: usize, cost: C, heuristic: f64) {
+        let priority = OrderedFloat(cost.to_f64() + heuristic);
         self.heap.push(Reverse((priority, node_index)));
     }
 
@@ -1,7 +1,10 @@
 use crate::algorithms::priority_queue::PriorityQueue;
+use crate::search::cost::CostTrait;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 // A simple FIFO queue for BFS
+#[derive(Serialize, Deserialize)]
 pub struct BfsQueue {
     queue: VecDeque<usize>,
 }
@@ -15,7 +18,7 @@ impl BfsQueue {
 }
 
 impl PriorityQueue for BfsQueue {
-    fn insert(&mut self, node_index: usize, _cost: i32, _heuristic_value: f64) {
+    fn insert<C: CostTrait>(&mut self, node_index: usize, _cost: C, _heuristic_value: f64) {
         self.queue.push_back(node_index);
     }
 
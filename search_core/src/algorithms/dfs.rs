@@ -1,5 +1,8 @@
 use crate::algorithms::priority_queue::PriorityQueue;
+use crate::search::cost::CostTrait;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct DfsQueue {
     stack: Vec<usize>,
 }
@@ -11,7 +14,7 @@ impl DfsQueue {
 }
 
 impl PriorityQueue for DfsQueue {
-    fn insert(&mut self, node_index: usize, _cost: i32, _heuristic_value: f64) {
+    fn insert<C: CostTrait>(&mut self, node_index: usize, _cost: C, _heuristic_value: f64) {
         self.stack.push(node_index);
     }
 
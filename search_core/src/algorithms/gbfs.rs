@@ -1,7 +1,10 @@
 use crate::algorithms::priority_queue::PriorityQueue;
+use crate::search::cost::CostTrait;
+use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 
+#[derive(Serialize, Deserialize)]
 pub struct GBFSQueue {
     heap: BinaryHeap<Reverse<(i32, usize)>>, // (priority, node_index)
 }
@@ -15,7 +18,7 @@ impl crate::algorithms::gbfs::GBFSQueue {
 }
 
 impl PriorityQueue for crate::algorithms::gbfs::GBFSQueue {
-    fn insert(&mut self, node_index: usize, _cost: i32, heuristic: f64) {
+    fn insert<C: CostTrait>(&mut self, node_index: usize, _cost: C, heuristic: f64) {
         let priority = heuristic as i32;
         self.heap.push(Reverse((priority, node_index)));
     }
@@ -3,7 +3,10 @@ use crate::algorithms::bfs::BfsQueue;
 use crate::algorithms::dfs::DfsQueue;
 use crate::algorithms::gbfs::GBFSQueue;
 use crate::algorithms::priority_queue::PriorityQueue;
+use crate::search::cost::CostTrait;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub enum SearchQueue {
     AStar(AStarQueue),
     GBFS(GBFSQueue),
@@ -12,7 +15,7 @@ pub enum SearchQueue {
 }
 
 impl PriorityQueue for SearchQueue {
-    fn insert(&mut self, node_index: usize, cost: i32, heuristic_value: f64) {
+    fn insert<C: CostTrait>(&mut self, node_index: usize, cost: C, heuristic_value: f64) {
         match self {
             SearchQueue::AStar(queue) => queue.insert(node_index, cost, heuristic_value),
             SearchQueue::GBFS(queue) => queue.insert(node_index, cost, heuristic_value),
@@ -0,0 +1,267 @@
+use crate::problems::problem::Problem;
+use crate::search::cost::CostTrait;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/// Tolerance used when comparing heuristic values against exact costs.
+const EPSILON: f64 = 1e-9;
+
+/// Limits for the exhaustive state-space enumeration.
+#[derive(Debug, Clone)]
+pub struct HeuristicCheckConfig {
+    pub max_states: usize,   // Enumeration stops once this many states are known
+    pub max_reported: usize, // Cap on the number of violations kept in the report
+}
+
+impl Default for HeuristicCheckConfig {
+    fn default() -> Self {
+        HeuristicCheckConfig {
+            max_states: 200_000,
+            max_reported: 50,
+        }
+    }
+}
+
+/// A state whose heuristic value exceeds the true goal distance.
+#[derive(Debug, Clone)]
+pub struct Overestimate {
+    pub state: String,
+    pub h: f64,
+    pub h_star: f64,
+}
+
+/// A transition for which h(s) > c(s,a) + h(s').
+#[derive(Debug, Clone)]
+pub struct Inconsistency {
+    pub state: String,
+    pub action: String,
+    pub cost: f64,
+    pub h: f64,
+    pub h_successor: f64,
+}
+
+/// Result of comparing a heuristic with the exact goal distances of an instance.
+#[derive(Debug, Clone, Default)]
+pub struct HeuristicReport {
+    pub states: usize,            // Reachable states enumerated
+    pub transitions: usize,       // Transitions between them
+    pub solvable_states: usize,   // States from which a goal is reachable
+    pub complete: bool,           // False if `max_states` cut the enumeration short
+    pub inadmissible_states: usize,
+    pub inconsistent_transitions: usize,
+    pub overestimates: Vec<Overestimate>,
+    pub inconsistencies: Vec<Inconsistency>,
+    pub average_error: f64,          // Mean of h*(s) - h(s) over solvable states
+    pub average_absolute_error: f64, // Mean of |h*(s) - h(s)| over solvable states
+    pub correlation: f64,            // Pearson correlation between h and h*
+}
+
+impl HeuristicReport {
+    pub fn is_admissible(&self) -> bool {
+        self.inadmissible_states == 0
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistent_transitions == 0
+    }
+
+    pub fn print_summary(&self) {
+        if !self.complete {
+            println!("Warning: state space truncated, exact distances are only upper bounds");
+        }
+        println!(
+            "States: {}, transitions: {}, solvable: {}",
+            self.states, self.transitions, self.solvable_states
+        );
+        println!(
+            "Inadmissible states: {}, inconsistent transitions: {}",
+            self.inadmissible_states, self.inconsistent_transitions
+        );
+        println!(
+            "Average error: {:.4}, average absolute error: {:.4}, correlation: {:.4}",
+            self.average_error, self.average_absolute_error, self.correlation
+        );
+        for o in &self.overestimates {
+            println!("  overestimate h={} h*={} at {}", o.h, o.h_star, o.state);
+        }
+        for i in &self.inconsistencies {
+            println!(
+                "  inconsistent h={} > {} + {} via {} at {}",
+                i.h, i.cost, i.h_successor, i.action, i.state
+            );
+        }
+    }
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x) * (x - mean_x);
+        var_y += (y - mean_y) * (y - mean_y);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        0.0
+    } else {
+        cov / (var_x.sqrt() * var_y.sqrt())
+    }
+}
+
+/// Enumerates the reachable state space of a (small) instance, computes exact goal distances
+/// by a backward Dijkstra from all goal states, and checks `heuristic` against them.
+pub fn check_heuristic<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    heuristic: H,
+    config: &HeuristicCheckConfig,
+) -> HeuristicReport
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    // Forward enumeration: states are numbered in discovery order.
+    let mut ids: HashMap<P::State, usize> = HashMap::new();
+    let mut states: Vec<P::State> = Vec::new();
+    let mut edges: Vec<(usize, usize, P::Cost, P::Action)> = Vec::new(); // (from, to, cost, action)
+    let mut queue = VecDeque::new();
+    let mut complete = true;
+
+    ids.insert(initial_state.clone(), 0);
+    states.push(initial_state.clone());
+    queue.push_back(0);
+    while let Some(idx) = queue.pop_front() {
+        let state = states[idx].clone();
+        for action in problem.get_possible_actions(&state) {
+            let next = problem.apply_action(&state, &action);
+            let cost = problem.action_cost(&state, &action);
+            let next_idx = match ids.get(&next) {
+                Some(&i) => i,
+                None => {
+                    if states.len() >= config.max_states {
+                        complete = false;
+                        continue;
+                    }
+                    let i = states.len();
+                    ids.insert(next.clone(), i);
+                    states.push(next);
+                    queue.push_back(i);
+                    i
+                }
+            };
+            edges.push((idx, next_idx, cost, action));
+        }
+    }
+
+    // Backward Dijkstra over the reversed transitions.
+    let mut reverse: Vec<Vec<(usize, P::Cost)>> = vec![Vec::new(); states.len()];
+    for &(from, to, cost, _) in &edges {
+        reverse[to].push((from, cost));
+    }
+    let mut distance: Vec<Option<P::Cost>> = vec![None; states.len()];
+    let mut heap = BinaryHeap::new();
+    for (i, state) in states.iter().enumerate() {
+        if problem.is_goal_state(state) {
+            distance[i] = Some(P::Cost::default());
+            heap.push(Reverse((P::Cost::default(), i)));
+        }
+    }
+    while let Some(Reverse((d, i))) = heap.pop() {
+        if distance[i].is_some_and(|known| known < d) {
+            continue;
+        }
+        for &(pred, cost) in &reverse[i] {
+            let nd = d + cost;
+            if distance[pred].is_none_or(|known| nd < known) {
+                distance[pred] = Some(nd);
+                heap.push(Reverse((nd, pred)));
+            }
+        }
+    }
+
+    let h_values: Vec<f64> = states.iter().map(&heuristic).collect();
+    let mut report = HeuristicReport {
+        states: states.len(),
+        transitions: edges.len(),
+        complete,
+        ..Default::default()
+    };
+
+    let (mut hs, mut h_stars) = (Vec::new(), Vec::new());
+    for (i, state) in states.iter().enumerate() {
+        let Some(d) = distance[i] else { continue };
+        let h_star = d.to_f64();
+        hs.push(h_values[i]);
+        h_stars.push(h_star);
+        if h_values[i] > h_star + EPSILON {
+            report.inadmissible_states += 1;
+            if report.overestimates.len() < config.max_reported {
+                report.overestimates.push(Overestimate {
+                    state: format!("{:?}", state),
+                    h: h_values[i],
+                    h_star,
+                });
+            }
+        }
+    }
+
+    for (from, to, cost, action) in &edges {
+        if h_values[*from] > cost.to_f64() + h_values[*to] + EPSILON {
+            report.inconsistent_transitions += 1;
+            if report.inconsistencies.len() < config.max_reported {
+                report.inconsistencies.push(Inconsistency {
+                    state: format!("{:?}", states[*from]),
+                    action: problem.describe_action(&states[*from], action).name,
+                    cost: cost.to_f64(),
+                    h: h_values[*from],
+                    h_successor: h_values[*to],
+                });
+            }
+        }
+    }
+
+    report.solvable_states = hs.len();
+    if !hs.is_empty() {
+        let n = hs.len() as f64;
+        report.average_error = hs.iter().zip(&h_stars).map(|(h, s)| s - h).sum::<f64>() / n;
+        report.average_absolute_error =
+            hs.iter().zip(&h_stars).map(|(h, s)| (s - h).abs()).sum::<f64>() / n;
+        report.correlation = pearson(&hs, &h_stars);
+    }
+    report
+}
+
+/// Analysis mode: loads an instance and checks the problem's own `heuristic`.
+pub fn analyze_heuristic<P: Problem>(json_path: &str, config: &HeuristicCheckConfig) -> HeuristicReport {
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let report = check_heuristic(&problem, &initial_state, |s| problem.heuristic(s), config);
+    report.print_summary();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+
+    #[test]
+    fn test_detects_overestimating_heuristic() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let config = HeuristicCheckConfig::default();
+
+        let zero = check_heuristic(&problem, &state, |_| 0.0, &config);
+        assert!(zero.complete && zero.is_admissible() && zero.is_consistent());
+        assert!(zero.solvable_states > 0);
+
+        let inflated = check_heuristic(&problem, &state, |s| 100.0 * s.counters[0].value as f64, &config);
+        assert!(!inflated.is_admissible());
+        assert!(!inflated.is_consistent());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod heuristic_check;
+pub mod profiler;
+pub mod properties;
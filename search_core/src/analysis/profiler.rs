@@ -0,0 +1,308 @@
+use crate::problems::problem::Problem;
+use crate::search::action::Action;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::hint::black_box;
+use std::mem::{discriminant, Discriminant};
+use std::time::{Duration, Instant};
+
+/// Call count and timings of one callback. Bucket i of the histogram counts the calls that
+/// took between 2^i and 2^(i+1) nanoseconds.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CallProfile {
+    pub calls: u64,
+    pub total_ns: u64,
+    pub max_ns: u64,
+    pub histogram: Vec<u64>,
+}
+
+impl CallProfile {
+    fn record(&mut self, elapsed: Duration) {
+        let ns = elapsed.as_nanos() as u64;
+        self.calls += 1;
+        self.total_ns += ns;
+        self.max_ns = self.max_ns.max(ns);
+        let bucket = (u64::BITS - ns.leading_zeros()).saturating_sub(1) as usize;
+        if bucket >= self.histogram.len() {
+            self.histogram.resize(bucket + 1, 0);
+        }
+        self.histogram[bucket] += 1;
+    }
+
+    pub fn mean_ns(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.total_ns as f64 / self.calls as f64
+        }
+    }
+
+    /// Upper bound of the histogram bucket holding the `q` quantile, 0 <= q <= 1.
+    pub fn quantile_ns(&self, q: f64) -> u64 {
+        let target = (q * self.calls as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, &count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return (1u64 << (bucket + 1)).min(self.max_ns);
+            }
+        }
+        self.max_ns
+    }
+}
+
+/// What `ProfiledProblem` measured. Hashing and cloning are not callbacks of `Problem`;
+/// they are estimated by hashing and cloning every successor once more, outside the
+/// timings of the other callbacks.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProfileReport {
+    pub get_possible_actions: CallProfile,
+    pub apply_action: CallProfile,
+    pub action_cost: CallProfile,
+    pub is_goal_state: CallProfile,
+    pub heuristic: CallProfile,
+    pub state_hash: CallProfile,
+    pub state_clone: CallProfile,
+    pub actions_generated: u64, // Actions returned by get_possible_actions
+    pub action_frequency: HashMap<String, u64>, // Applications per action variant
+}
+
+impl ProfileReport {
+    pub fn callbacks(&self) -> [(&'static str, &CallProfile); 7] {
+        [
+            ("get_possible_actions", &self.get_possible_actions),
+            ("apply_action", &self.apply_action),
+            ("action_cost", &self.action_cost),
+            ("is_goal_state", &self.is_goal_state),
+            ("heuristic", &self.heuristic),
+            ("state_hash", &self.state_hash),
+            ("state_clone", &self.state_clone),
+        ]
+    }
+
+    /// Average number of applicable actions per state whose actions were listed.
+    pub fn branching_factor(&self) -> f64 {
+        if self.get_possible_actions.calls == 0 {
+            0.0
+        } else {
+            self.actions_generated as f64 / self.get_possible_actions.calls as f64
+        }
+    }
+
+    /// Action variants by decreasing number of applications.
+    pub fn most_frequent_actions(&self) -> Vec<(&str, u64)> {
+        let mut actions: Vec<_> = self
+            .action_frequency
+            .iter()
+            .map(|(name, &count)| (name.as_str(), count))
+            .collect();
+        actions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        actions
+    }
+
+    /// Prints the timings of every callback and the `max_actions` most frequent action variants.
+    pub fn print_summary(&self, max_actions: usize) {
+        println!(
+            "{:<22}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}",
+            "callback", "calls", "total ms", "mean ns", "p50 ns", "p99 ns", "max ns"
+        );
+        for (name, profile) in self.callbacks() {
+            println!(
+                "{:<22}{:>12}{:>12.3}{:>12.0}{:>12}{:>12}{:>12}",
+                name,
+                profile.calls,
+                profile.total_ns as f64 / 1e6,
+                profile.mean_ns(),
+                profile.quantile_ns(0.5),
+                profile.quantile_ns(0.99),
+                profile.max_ns
+            );
+        }
+        println!(
+            "Branching factor: {:.2} ({} actions in {} states)",
+            self.branching_factor(),
+            self.actions_generated,
+            self.get_possible_actions.calls
+        );
+        let actions = self.most_frequent_actions();
+        println!("Most frequent actions ({} variants):", actions.len());
+        for (name, count) in actions.into_iter().take(max_actions) {
+            println!("  {:>10}  {}", count, name);
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+/// A problem that times every call made to the problem it wraps. Searching it instead of
+/// the wrapped problem gives the same plans, more slowly.
+pub struct ProfiledProblem<P: Problem> {
+    pub inner: P,
+    report: RefCell<ProfileReport>,
+    applications: RefCell<Applications<P::Action>>,
+}
+
+/// Applications per action variant, with one action of the variant to name it by.
+type Applications<A> = HashMap<Discriminant<A>, (A, u64)>;
+
+impl<P: Problem> ProfiledProblem<P> {
+    pub fn new(inner: P) -> Self {
+        ProfiledProblem {
+            inner,
+            report: RefCell::new(ProfileReport::default()),
+            applications: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        let mut report = self.report.borrow().clone();
+        for (action, count) in self.applications.borrow().values() {
+            *report.action_frequency.entry(variant_name(action)).or_insert(0) += count;
+        }
+        report
+    }
+
+    fn timed<T>(
+        &self,
+        profile: fn(&mut ProfileReport) -> &mut CallProfile,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        profile(&mut self.report.borrow_mut()).record(elapsed);
+        result
+    }
+}
+
+/// Name of the enum variant of an action, e.g. "Drive" for `Drive { rover: 0, .. }`.
+fn variant_name<A: std::fmt::Debug>(action: &A) -> String {
+    let debug = format!("{:?}", action);
+    let end = debug
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(debug.len());
+    debug[..end].to_string()
+}
+
+impl<P: Problem> Problem for ProfiledProblem<P> {
+    type State = P::State;
+    type Action = P::Action;
+    type Cost = P::Cost;
+
+    fn get_possible_actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        let actions = self.timed(
+            |r| &mut r.get_possible_actions,
+            || self.inner.get_possible_actions(state),
+        );
+        self.report.borrow_mut().actions_generated += actions.len() as u64;
+        actions
+    }
+
+    fn apply_action(&self, state: &Self::State, action: &Self::Action) -> Self::State {
+        let successor = self.timed(
+            |r| &mut r.apply_action,
+            || self.inner.apply_action(state, action),
+        );
+        self.timed(
+            |r| &mut r.state_hash,
+            || {
+                let mut hasher = DefaultHasher::new();
+                black_box(&successor).hash(&mut hasher);
+                black_box(hasher.finish())
+            },
+        );
+        self.timed(|r| &mut r.state_clone, || black_box(successor.clone()));
+        self.applications
+            .borrow_mut()
+            .entry(discriminant(action))
+            .or_insert((*action, 0))
+            .1 += 1;
+        successor
+    }
+
+    fn action_cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.timed(
+            |r| &mut r.action_cost,
+            || self.inner.action_cost(state, action),
+        )
+    }
+
+    fn describe_action(&self, state: &Self::State, action: &Self::Action) -> Action {
+        self.inner.describe_action(state, action)
+    }
+
+    fn is_goal_state(&self, state: &Self::State) -> bool {
+        self.timed(|r| &mut r.is_goal_state, || self.inner.is_goal_state(state))
+    }
+
+    fn heuristic(&self, state: &Self::State) -> f64 {
+        self.timed(|r| &mut r.heuristic, || self.inner.heuristic(state))
+    }
+
+    fn preferred_actions(
+        &self,
+        state: &Self::State,
+        actions: &[Self::Action],
+    ) -> Vec<Self::Action> {
+        self.inner.preferred_actions(state, actions)
+    }
+
+    fn load_state_from_json(json_path: &str) -> (Self::State, Self) {
+        let (state, problem) = P::load_state_from_json(json_path);
+        (state, ProfiledProblem::new(problem))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::search::search::generic_search;
+    use crate::search::search_tree::SearchTree;
+
+    fn search<P: Problem>(problem: &P, state: &P::State) -> Vec<P::Action> {
+        let mut tree = SearchTree::new(state.clone());
+        generic_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |s| problem.heuristic(s),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_profiled_problem_counts_every_call() {
+        let (state, problem) = ProfiledProblem::<CountersProblem>::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        assert_eq!(search(&problem, &state), search(&problem.inner, &state));
+
+        let report = problem.report();
+        let generated = report.apply_action.calls;
+        assert_eq!(report.actions_generated, generated);
+        assert_eq!(report.action_cost.calls, generated);
+        assert_eq!(report.state_hash.calls, generated);
+        assert_eq!(report.action_frequency.values().sum::<u64>(), generated);
+        assert!(report
+            .action_frequency
+            .keys()
+            .all(|name| !name.is_empty() && name.chars().all(char::is_alphanumeric)));
+        for (_, profile) in report.callbacks() {
+            assert_eq!(profile.histogram.iter().sum::<u64>(), profile.calls);
+            assert!(profile.quantile_ns(0.5) <= profile.quantile_ns(0.99));
+        }
+        assert!(report.branching_factor() > 1.0);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::problems::problem::Problem;
+use crate::search::rng::Rng;
+use crate::search::search::state_hash;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Controls how many random walks are made and how long each one is.
+#[derive(Debug, Clone)]
+pub struct PropertyConfig {
+    pub walks: usize,       // Number of random walks started from the initial state
+    pub walk_length: usize, // Maximum number of steps in one walk
+    pub seed: u64,          // Seed for the walk generator
+}
+
+impl Default for PropertyConfig {
+    fn default() -> Self {
+        PropertyConfig {
+            walks: 20,
+            walk_length: 30,
+            seed: 0,
+        }
+    }
+}
+
+/// A single broken invariant, with the offending state and action in debug form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyViolation {
+    ApplyPanicked { state: String, action: String, message: String },
+    SerdeRoundTrip { state: String, format: &'static str },
+    HashEqMismatch { first: String, second: String },
+    NegativeCost { state: String, action: String, cost: String },
+    GoalHeuristic { state: String, value: f64 },
+}
+
+/// Outcome of `check_problem`.
+#[derive(Debug, Clone, Default)]
+pub struct PropertyReport {
+    pub states_checked: usize,
+    pub actions_checked: usize,
+    pub violations: Vec<PropertyViolation>,
+}
+
+impl PropertyReport {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+/// Checks the per-state invariants: serde round trip, Hash/Eq agreement with the
+/// round-tripped copy, and a finite heuristic at goal states.
+fn check_state<P: Problem>(problem: &P, state: &P::State, report: &mut PropertyReport) {
+    report.states_checked += 1;
+
+    match serde_json::to_string(state).map(|s| serde_json::from_str::<P::State>(&s)) {
+        Ok(Ok(copy)) if copy == *state && state_hash(&copy) == state_hash(state) => {}
+        _ => report.violations.push(PropertyViolation::SerdeRoundTrip {
+            state: format!("{:?}", state),
+            format: "json",
+        }),
+    }
+    match serde_cbor::to_vec(state).map(|bytes| serde_cbor::from_slice::<P::State>(&bytes)) {
+        Ok(Ok(copy)) if copy == *state && state_hash(&copy) == state_hash(state) => {}
+        _ => report.violations.push(PropertyViolation::SerdeRoundTrip {
+            state: format!("{:?}", state),
+            format: "cbor",
+        }),
+    }
+
+    if problem.is_goal_state(state) {
+        let value = problem.heuristic(state);
+        if !value.is_finite() {
+            report.violations.push(PropertyViolation::GoalHeuristic {
+                state: format!("{:?}", state),
+                value,
+            });
+        }
+    }
+}
+
+/// Explores `config.walks` random walks from `initial_state` and checks, at every visited state:
+/// - `apply_action` does not panic for any action from `get_possible_actions`;
+/// - the state survives a serde round trip (JSON and CBOR);
+/// - equal states hash equally;
+/// - action costs are non-negative;
+/// - the heuristic is finite at goal states.
+pub fn check_problem<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &PropertyConfig,
+) -> PropertyReport {
+    let mut rng = Rng::new(config.seed);
+    let mut report = PropertyReport::default();
+
+    for _ in 0..config.walks {
+        let mut current = initial_state.clone();
+        let mut visited = vec![current.clone()];
+        check_state(problem, &current, &mut report);
+
+        for _ in 0..config.walk_length {
+            let mut successors = Vec::new();
+            for action in problem.get_possible_actions(&current) {
+                report.actions_checked += 1;
+                let cost = problem.action_cost(&current, &action);
+                if cost < P::Cost::default() {
+                    report.violations.push(PropertyViolation::NegativeCost {
+                        state: format!("{:?}", current),
+                        action: format!("{:?}", action),
+                        cost: cost.to_string(),
+                    });
+                }
+                match panic::catch_unwind(AssertUnwindSafe(|| {
+                    problem.apply_action(&current, &action)
+                })) {
+                    Ok(next) => successors.push(next),
+                    Err(payload) => report.violations.push(PropertyViolation::ApplyPanicked {
+                        state: format!("{:?}", current),
+                        action: format!("{:?}", action),
+                        message: panic_message(payload.as_ref()),
+                    }),
+                }
+            }
+
+            let next = match rng.choose(&successors) {
+                Some(next) => next.clone(),
+                None => break,
+            };
+            check_state(problem, &next, &mut report);
+
+            // States of one walk are compared pairwise: equal states must hash equally.
+            for earlier in &visited {
+                if *earlier == next && state_hash(earlier) != state_hash(&next) {
+                    report.violations.push(PropertyViolation::HashEqMismatch {
+                        first: format!("{:?}", earlier),
+                        second: format!("{:?}", next),
+                    });
+                }
+            }
+            visited.push(next.clone());
+            current = next;
+        }
+    }
+    report
+}
+
+/// Loads an instance and panics with the full list of violations if any invariant fails.
+/// Meant to be called from a domain's `#[test]`.
+pub fn assert_problem_properties<P: Problem>(json_path: &str, config: &PropertyConfig) {
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let report = check_problem(&problem, &initial_state, config);
+    assert!(
+        report.is_ok(),
+        "{} property violations in {}: {:#?}",
+        report.violations.len(),
+        json_path,
+        report.violations
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::block_grouping_problem::block_grouping_problem::BlockGroupingProblem;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::delivery_problem::delivery_problem::DeliveryProblem;
+    use crate::problems::drone_problem::drone_problem::DroneProblem;
+    use crate::problems::expedition_problem::expedition_problem::ExpeditionProblem;
+    use crate::problems::hydropower_problem::hydropower_problem::HydropowerProblem;
+    use crate::problems::rover_problem::rover_problem::RoverProblem;
+    use crate::problems::sailing_problem::sailing_problem::SailingProblem;
+    use crate::problems::settlersnumeric_problem::settlersnumeric_problem::SettlersNumericProblem;
+    use std::fs;
+
+    /// Runs the property checks on every instance shipped in `dir`.
+    fn assert_all_instances<P: Problem>(dir: &str) {
+        let config = PropertyConfig {
+            walks: 5,
+            ..PropertyConfig::default()
+        };
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .expect("Failed to read input directory")
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty(), "No instances in {}", dir);
+        for path in paths {
+            assert_problem_properties::<P>(path.to_str().unwrap(), &config);
+        }
+    }
+
+    #[test]
+    fn test_counters_properties() {
+        assert_problem_properties::<CountersProblem>(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+            &PropertyConfig::default(),
+        );
+    }
+
+    #[test]
+    fn test_block_grouping_properties() {
+        assert_problem_properties::<BlockGroupingProblem>(
+            "src/inputs/block_grouping_problem/problems_json/pfile1.json",
+            &PropertyConfig::default(),
+        );
+    }
+
+    #[test]
+    fn test_late_registered_domains_properties() {
+        assert_all_instances::<DeliveryProblem>("src/inputs/delivery_problem");
+        assert_all_instances::<DroneProblem>("src/inputs/drone_problem");
+        assert_all_instances::<ExpeditionProblem>("src/inputs/expedition_problem");
+        assert_all_instances::<HydropowerProblem>("src/inputs/hydropower_problem");
+        assert_all_instances::<RoverProblem>("src/inputs/rover_problem");
+        assert_all_instances::<SailingProblem>("src/inputs/sailing_problem");
+        assert_all_instances::<SettlersNumericProblem>("src/inputs/settlersnumeric_problem");
+    }
+}
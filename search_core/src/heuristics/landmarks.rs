@@ -0,0 +1,80 @@
+use crate::heuristics::numeric_task::{NumericCondition, NumericEffect, NumericTask};
+use crate::heuristics::relaxation::{
+    expression_interval, needed_directions, RelaxedGraph, HIGH, LOW,
+};
+use std::collections::HashSet;
+
+/// Actions reachable in the relaxation that can move some variable of `condition`
+/// in the direction the condition needs.
+fn achievers<A>(
+    task: &NumericTask<A>,
+    values: &[f64],
+    graph: &RelaxedGraph,
+    condition: &NumericCondition,
+) -> Vec<usize> {
+    let needed = needed_directions(condition, values);
+    let last = graph
+        .layers
+        .last()
+        .expect("relaxed graph always has a first layer");
+    task.actions
+        .iter()
+        .enumerate()
+        .filter(|(a, _)| graph.applicable_from[*a].is_some())
+        .filter(|(_, action)| {
+            action.effects.iter().any(|effect| {
+                let v = effect.variable();
+                let helps = match effect {
+                    NumericEffect::Increase(_, e) => {
+                        let range = expression_interval(e, last);
+                        let mut helps = [false; 2];
+                        helps[LOW] = range.lo < 0.0;
+                        helps[HIGH] = range.hi > 0.0;
+                        helps
+                    }
+                    NumericEffect::Assign(_, _) => [true, true],
+                };
+                needed.iter().any(|&(nv, dir)| nv == v && helps[dir])
+            })
+        })
+        .map(|(a, _)| a)
+        .collect()
+}
+
+/// Landmark counting over numeric conditions. Every unsatisfied goal condition is a landmark;
+/// when it has a single relaxed achiever, that action's unsatisfied preconditions become
+/// landmarks as well (backchaining). The value is the number of distinct landmarks found.
+/// Needs a saturated graph, otherwise achievers that are merely late look unique.
+pub fn landmark_cost<A>(task: &NumericTask<A>, values: &[f64], graph: &RelaxedGraph) -> f64 {
+    if !graph.goals_reached() {
+        return f64::INFINITY;
+    }
+    let mut agenda: Vec<&NumericCondition> = task
+        .goals
+        .iter()
+        .filter(|g| !g.is_satisfied(values))
+        .collect();
+    let mut expanded: HashSet<usize> = HashSet::new();
+    let mut counted: Vec<&NumericCondition> = Vec::new();
+    let mut total = 0.0;
+
+    while let Some(condition) = agenda.pop() {
+        if counted.contains(&condition) {
+            continue;
+        }
+        counted.push(condition);
+        let candidates = achievers(task, values, graph, condition);
+        total += 1.0;
+        if let [only] = candidates[..] {
+            if expanded.insert(only) {
+                agenda.extend(
+                    task.actions[only]
+                        .preconditions
+                        .iter()
+                        .filter(|p| !p.is_satisfied(values)),
+                );
+            }
+        }
+    }
+    total
+}
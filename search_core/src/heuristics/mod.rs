@@ -0,0 +1,5 @@
+pub mod landmarks;
+pub mod numeric_heuristic;
+pub mod numeric_task;
+pub mod pattern_database;
+pub mod relaxation;
@@ -0,0 +1,190 @@
+use crate::heuristics::landmarks::landmark_cost;
+use crate::heuristics::numeric_task::NumericTask;
+use crate::heuristics::relaxation::{
+    build_relaxed_graph, relaxed_cost, relaxed_plan, relaxed_plan_cost, Aggregation,
+};
+
+/// The domain-independent heuristics available for a `NumericProblem`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericHeuristicKind {
+    GoalCount,
+    HAdd,
+    HMax,
+    HFF,
+    Landmarks,
+}
+
+impl NumericHeuristicKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "goal_count" => Some(NumericHeuristicKind::GoalCount),
+            "h_add" => Some(NumericHeuristicKind::HAdd),
+            "h_max" => Some(NumericHeuristicKind::HMax),
+            "h_ff" => Some(NumericHeuristicKind::HFF),
+            "landmarks" => Some(NumericHeuristicKind::Landmarks),
+            _ => None,
+        }
+    }
+}
+
+/// A heuristic evaluated on the variable values of a grounded `NumericTask`.
+pub struct NumericHeuristic<A> {
+    pub task: NumericTask<A>,
+    pub kind: NumericHeuristicKind,
+    pub max_layers: usize, // Cap on relaxed-graph layers for unbounded numeric growth
+}
+
+impl<A: Copy> NumericHeuristic<A> {
+    pub fn new(task: NumericTask<A>, kind: NumericHeuristicKind) -> Self {
+        NumericHeuristic {
+            task,
+            kind,
+            max_layers: 1000,
+        }
+    }
+
+    pub fn with_max_layers(mut self, max_layers: usize) -> Self {
+        self.max_layers = max_layers;
+        self
+    }
+
+    pub fn evaluate(&self, values: &[f64]) -> f64 {
+        match self.kind {
+            NumericHeuristicKind::GoalCount => self
+                .task
+                .goals
+                .iter()
+                .filter(|g| !g.is_satisfied(values))
+                .count() as f64,
+            NumericHeuristicKind::HAdd => {
+                let graph = build_relaxed_graph(
+                    &self.task,
+                    values,
+                    Aggregation::Sum,
+                    self.max_layers,
+                    false,
+                );
+                relaxed_cost(&graph, Aggregation::Sum)
+            }
+            NumericHeuristicKind::HMax => {
+                let graph = build_relaxed_graph(
+                    &self.task,
+                    values,
+                    Aggregation::Max,
+                    self.max_layers,
+                    false,
+                );
+                relaxed_cost(&graph, Aggregation::Max)
+            }
+            NumericHeuristicKind::HFF => {
+                let graph = build_relaxed_graph(
+                    &self.task,
+                    values,
+                    Aggregation::Sum,
+                    self.max_layers,
+                    false,
+                );
+                relaxed_plan_cost(&self.task, values, &graph)
+            }
+            NumericHeuristicKind::Landmarks => {
+                let graph = build_relaxed_graph(
+                    &self.task,
+                    values,
+                    Aggregation::Sum,
+                    self.max_layers,
+                    true,
+                );
+                landmark_cost(&self.task, values, &graph)
+            }
+        }
+    }
+
+    /// Helpful actions in the state with `values`: the problem's actions encoded by the
+    /// actions of its h_FF relaxed plan that are applicable in the state itself. Whatever
+    /// the kind of the heuristic, they come from the relaxed plan.
+    pub fn helpful_actions(&self, values: &[f64]) -> Vec<A> {
+        let graph =
+            build_relaxed_graph(&self.task, values, Aggregation::Sum, self.max_layers, false);
+        let Some(selected) = relaxed_plan(&self.task, values, &graph) else {
+            return Vec::new();
+        };
+        let mut actions: Vec<usize> = selected
+            .into_iter()
+            .map(|(_, a)| a)
+            .filter(|&a| graph.applicable_from[a] == Some(0))
+            .collect();
+        actions.sort_unstable();
+        actions.dedup();
+        actions
+            .into_iter()
+            .map(|a| self.task.actions[a].operator)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::numeric_task::NumericProblem;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::farmland_problem::farmland_problem::FarmLandProblem;
+    use crate::problems::fo_counters_problem::fo_counters_problem::FoCountersProblem;
+    use crate::problems::zenotravel_problem::zenotravel_problem::ZenoTravelProblem;
+    use crate::search::rng::Rng;
+
+    /// Walks randomly through the domain and checks that the numeric encoding agrees with
+    /// `apply_action` and that every heuristic is zero at goals and finite elsewhere.
+    fn check_encoding<P: NumericProblem>(json_path: &str) {
+        let (mut state, problem) = P::load_state_from_json(json_path);
+        let task = problem.numeric_task(&state);
+        let heuristics: Vec<NumericHeuristic<P::Action>> = [
+            NumericHeuristicKind::GoalCount,
+            NumericHeuristicKind::HAdd,
+            NumericHeuristicKind::HMax,
+            NumericHeuristicKind::HFF,
+            NumericHeuristicKind::Landmarks,
+        ]
+        .into_iter()
+        .map(|kind| NumericHeuristic::new(task.clone(), kind))
+        .collect();
+        let mut rng = Rng::new(7);
+
+        for _ in 0..30 {
+            let values = problem.numeric_values(&state);
+            assert_eq!(task.is_goal(&values), problem.is_goal_state(&state));
+            for h in &heuristics {
+                let value = h.evaluate(&values);
+                assert!(value >= 0.0, "{:?} is negative", h.kind);
+                if problem.is_goal_state(&state) {
+                    assert_eq!(value, 0.0, "{:?} is not zero at a goal", h.kind);
+                }
+            }
+
+            let actions = problem.get_possible_actions(&state);
+            let Some(action) = rng.choose(&actions) else {
+                break;
+            };
+            let next = problem.apply_action(&state, action);
+            let encoded = task
+                .actions
+                .iter()
+                .filter(|a| a.operator == *action)
+                .find(|a| a.preconditions.iter().all(|p| p.is_satisfied(&values)))
+                .unwrap_or_else(|| panic!("{:?} has no applicable numeric action", action));
+            assert_eq!(task.apply(encoded, &values), problem.numeric_values(&next));
+            state = next;
+        }
+    }
+
+    #[test]
+    fn test_numeric_encodings() {
+        check_encoding::<CountersProblem>("src/inputs/counters_problem/problems_json/pfile1.json");
+        check_encoding::<FoCountersProblem>(
+            "src/inputs/fo_counters_problem/problems_json/pfile1.json",
+        );
+        check_encoding::<FarmLandProblem>("src/inputs/farmland_problem/problems_json/pfile1.json");
+        check_encoding::<ZenoTravelProblem>(
+            "src/inputs/zenotravel_problem/problems_json/pfile1.json",
+        );
+    }
+}
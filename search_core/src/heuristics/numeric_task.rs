@@ -0,0 +1,190 @@
+use crate::problems::problem::Problem;
+
+/// Tolerance used when comparing numeric expressions with zero.
+pub const EPSILON: f64 = 1e-9;
+
+/// Comparison of a linear expression against zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Ge,
+    Gt,
+    Eq,
+    Le,
+    Lt,
+}
+
+impl Comparison {
+    /// Parses the operator strings used in the JSON instances ("=", "==", "<=", ...).
+    pub fn from_operator(operator: &str) -> Self {
+        match operator {
+            ">=" => Comparison::Ge,
+            ">" => Comparison::Gt,
+            "=" | "==" => Comparison::Eq,
+            "<=" => Comparison::Le,
+            "<" => Comparison::Lt,
+            _ => panic!("Unknown operator: {}", operator),
+        }
+    }
+}
+
+/// A linear expression c₁·x₁ + ... + cₙ·xₙ + constant over task variable indices.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LinearExpression {
+    pub terms: Vec<(f64, usize)>, // (coefficient, variable index)
+    pub constant: f64,
+}
+
+impl LinearExpression {
+    pub fn constant(value: f64) -> Self {
+        LinearExpression {
+            terms: Vec::new(),
+            constant: value,
+        }
+    }
+
+    pub fn variable(index: usize) -> Self {
+        LinearExpression {
+            terms: vec![(1.0, index)],
+            constant: 0.0,
+        }
+    }
+
+    pub fn term(mut self, coefficient: f64, index: usize) -> Self {
+        self.terms.push((coefficient, index));
+        self
+    }
+
+    pub fn plus(mut self, value: f64) -> Self {
+        self.constant += value;
+        self
+    }
+
+    /// Returns `self - other`.
+    pub fn minus(mut self, other: &LinearExpression) -> Self {
+        self.terms.extend(other.terms.iter().map(|&(c, v)| (-c, v)));
+        self.constant -= other.constant;
+        self
+    }
+
+    pub fn evaluate(&self, values: &[f64]) -> f64 {
+        self.terms
+            .iter()
+            .fold(self.constant, |sum, &(c, v)| sum + c * values[v])
+    }
+}
+
+/// A numeric condition `expression <cmp> 0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericCondition {
+    pub expression: LinearExpression,
+    pub comparison: Comparison,
+}
+
+impl NumericCondition {
+    /// Builds `left <cmp> right` by moving everything to the left-hand side.
+    pub fn new(left: LinearExpression, comparison: Comparison, right: &LinearExpression) -> Self {
+        NumericCondition {
+            expression: left.minus(right),
+            comparison,
+        }
+    }
+
+    /// Boolean-style condition `variable == value`.
+    pub fn equals(index: usize, value: f64) -> Self {
+        Self::new(
+            LinearExpression::variable(index),
+            Comparison::Eq,
+            &LinearExpression::constant(value),
+        )
+    }
+
+    pub fn holds_for(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Ge => value >= -EPSILON,
+            Comparison::Gt => value > EPSILON,
+            Comparison::Eq => value.abs() <= EPSILON,
+            Comparison::Le => value <= EPSILON,
+            Comparison::Lt => value < -EPSILON,
+        }
+    }
+
+    pub fn is_satisfied(&self, values: &[f64]) -> bool {
+        self.holds_for(self.expression.evaluate(values))
+    }
+}
+
+/// Numeric effect on one variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumericEffect {
+    Increase(usize, LinearExpression), // x += expression
+    Assign(usize, LinearExpression),   // x := expression
+}
+
+impl NumericEffect {
+    pub fn variable(&self) -> usize {
+        match self {
+            NumericEffect::Increase(v, _) | NumericEffect::Assign(v, _) => *v,
+        }
+    }
+}
+
+/// A grounded action of the numeric task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericAction<A> {
+    pub operator: A, // The problem's action this encodes
+    pub cost: f64,
+    pub preconditions: Vec<NumericCondition>,
+    pub effects: Vec<NumericEffect>,
+}
+
+/// Grounded numeric planning task: variables, actions with linear preconditions
+/// and effects, and a conjunctive goal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericTask<A> {
+    pub variables: Vec<String>,
+    pub actions: Vec<NumericAction<A>>,
+    pub goals: Vec<NumericCondition>,
+}
+
+impl<A> Default for NumericTask<A> {
+    fn default() -> Self {
+        NumericTask {
+            variables: Vec::new(),
+            actions: Vec::new(),
+            goals: Vec::new(),
+        }
+    }
+}
+
+impl<A> NumericTask<A> {
+    pub fn add_variable(&mut self, name: String) -> usize {
+        self.variables.push(name);
+        self.variables.len() - 1
+    }
+
+    pub fn is_goal(&self, values: &[f64]) -> bool {
+        self.goals.iter().all(|g| g.is_satisfied(values))
+    }
+
+    /// Exact successor values, useful for checking a domain's encoding against its `apply_action`.
+    pub fn apply(&self, action: &NumericAction<A>, values: &[f64]) -> Vec<f64> {
+        let mut next = values.to_vec();
+        for effect in &action.effects {
+            match effect {
+                NumericEffect::Increase(v, e) => next[*v] += e.evaluate(values),
+                NumericEffect::Assign(v, e) => next[*v] = e.evaluate(values),
+            }
+        }
+        next
+    }
+}
+
+/// Problems that can describe themselves as a grounded numeric task.
+/// Implementing it gives access to the domain-independent heuristics in `heuristics`.
+pub trait NumericProblem: Problem {
+    /// Grounds the task; `state` supplies the objects (normally the initial state).
+    fn numeric_task(&self, state: &Self::State) -> NumericTask<Self::Action>;
+
+    /// Values of the task variables in `state`, in `NumericTask::variables` order.
+    fn numeric_values(&self, state: &Self::State) -> Vec<f64>;
+}
@@ -0,0 +1,340 @@
+use crate::heuristics::numeric_task::NumericCondition;
+use crate::problems::factored_problem::{value_as_f64, FactoredProblem, VariableDomain};
+use crate::search::state::Value;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+
+/// Table entry of an abstract state that was never reached while building.
+const UNREACHED: u32 = u32::MAX;
+/// Table entry of an abstract state from which no abstract goal is reachable.
+const DEAD_END: u32 = u32::MAX - 1;
+
+/// Problems that can build an abstract instance containing only some of their objects.
+/// Removing objects must only add behaviour (fewer obstacles, fewer constraints), so that
+/// abstract distances never exceed concrete ones. Tables hold integer distances, so the
+/// action costs must be integers.
+pub trait ProjectableProblem: FactoredProblem<Cost = i32> + Sized {
+    /// Groups of factored variables that are projected together, usually one per object.
+    fn variable_groups(&self, state: &Self::State) -> Vec<Vec<usize>>;
+
+    /// Abstract instance keeping only the given groups. Its factored variables must be the
+    /// variables of those groups, concatenated in the order given.
+    fn project(&self, state: &Self::State, groups: &[usize]) -> (Self, Self::State);
+}
+
+/// How the values of several pattern databases are combined. `Additive` is admissible
+/// only when the patterns are disjoint and no action changes variables of two patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Combination {
+    Additive,
+    Max,
+}
+
+/// Exact goal distances of an abstraction, ranked over the pattern variables' bounds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternDatabase {
+    pub pattern: Vec<usize>, // concrete variable indices, in abstract variable order
+    lower: Vec<i64>,         // lower bound of every pattern variable
+    radices: Vec<u64>,       // number of values of every pattern variable
+    distances: Vec<u32>,
+}
+
+/// Lower bound and number of values of a variable; panics on unbounded variables.
+fn range(name: &str, domain: &VariableDomain) -> (i64, u64) {
+    match domain {
+        VariableDomain::Boolean => (0, 2),
+        VariableDomain::Integer {
+            lower: Some(lower),
+            upper: Some(upper),
+        } => (*lower, (upper - lower + 1) as u64),
+        _ => panic!("Pattern variable {} needs finite integer bounds", name),
+    }
+}
+
+/// Goal conditions that only mention pattern variables, renumbered to abstract indices.
+/// Dropping the others relaxes the goal, which keeps the abstraction admissible.
+fn project_goal(conditions: &[NumericCondition], pattern: &[usize]) -> Vec<NumericCondition> {
+    conditions
+        .iter()
+        .filter_map(|condition| {
+            let mut projected = condition.clone();
+            for term in &mut projected.expression.terms {
+                term.1 = pattern.iter().position(|&v| v == term.1)?;
+            }
+            Some(projected)
+        })
+        .collect()
+}
+
+impl PatternDatabase {
+    /// Builds the database for the given variable groups: the abstract instance is explored
+    /// forward from the projected state, then distances are computed by a Dijkstra search
+    /// backward from the abstract goal states.
+    pub fn build<P: ProjectableProblem>(problem: &P, state: &P::State, groups: &[usize]) -> Self {
+        let all_groups = problem.variable_groups(state);
+        let pattern: Vec<usize> = groups
+            .iter()
+            .flat_map(|&g| all_groups[g].iter().copied())
+            .collect();
+        let variables = problem.variables(state);
+        let (lower, radices): (Vec<i64>, Vec<u64>) = pattern
+            .iter()
+            .map(|&v| range(&variables[v].name, &variables[v].domain))
+            .unzip();
+        let size = radices
+            .iter()
+            .try_fold(1u64, |acc, &r| acc.checked_mul(r))
+            .filter(|&s| s < DEAD_END as u64)
+            .expect("Pattern database too large");
+
+        let mut database = PatternDatabase {
+            pattern,
+            lower,
+            radices,
+            distances: vec![UNREACHED; size as usize],
+        };
+        let goal = project_goal(&problem.goal(state).conditions, &database.pattern);
+        let (abstract_problem, abstract_state) = problem.project(state, groups);
+
+        // Forward exploration, keyed by rank; reverse edges are kept for the backward pass.
+        let mut reverse: HashMap<usize, Vec<(usize, u32)>> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        let start = database.rank(&abstract_problem.values(&abstract_state));
+        let mut seen = vec![false; size as usize];
+        seen[start] = true;
+        let mut stack = vec![abstract_state];
+        while let Some(current) = stack.pop() {
+            let values = abstract_problem.values(&current);
+            let from = database.rank(&values);
+            let numeric: Vec<f64> = values.iter().map(value_as_f64).collect();
+            if goal.iter().all(|c| c.is_satisfied(&numeric)) {
+                database.distances[from] = 0;
+                heap.push(Reverse((0u32, from)));
+            }
+            for action in abstract_problem.get_possible_actions(&current) {
+                let next = abstract_problem.apply_action(&current, &action);
+                let to = database.rank(&abstract_problem.values(&next));
+                reverse
+                    .entry(to)
+                    .or_default()
+                    .push((from, abstract_problem.action_cost(&current, &action) as u32));
+                if !seen[to] {
+                    seen[to] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        for (rank, &reached) in seen.iter().enumerate() {
+            if reached && database.distances[rank] == UNREACHED {
+                database.distances[rank] = DEAD_END;
+            }
+        }
+
+        while let Some(Reverse((d, rank))) = heap.pop() {
+            if database.distances[rank] < d {
+                continue;
+            }
+            for &(pred, cost) in reverse.get(&rank).into_iter().flatten() {
+                let nd = d + cost;
+                if nd < database.distances[pred] {
+                    database.distances[pred] = nd;
+                    heap.push(Reverse((nd, pred)));
+                }
+            }
+        }
+        database
+    }
+
+    /// Mixed-radix index of the abstract values.
+    fn rank(&self, values: &[Value]) -> usize {
+        let mut rank = 0u64;
+        for ((value, &lower), &radix) in values.iter().zip(&self.lower).zip(&self.radices) {
+            let offset = value_as_f64(value) as i64 - lower;
+            assert!(
+                offset >= 0 && (offset as u64) < radix,
+                "Value {:?} outside the bounds of its pattern variable",
+                value
+            );
+            rank = rank * radix + offset as u64;
+        }
+        rank as usize
+    }
+
+    /// Heuristic value of a concrete state, given its factored values. States whose
+    /// projection was never reached while building get 0.
+    pub fn evaluate(&self, values: &[Value]) -> f64 {
+        let projected: Vec<Value> = self.pattern.iter().map(|&v| values[v].clone()).collect();
+        match self.distances[self.rank(&projected)] {
+            UNREACHED => 0.0,
+            DEAD_END => f64::INFINITY,
+            d => d as f64,
+        }
+    }
+
+    /// Number of table entries.
+    pub fn size(&self) -> usize {
+        self.distances.len()
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let bytes = serde_cbor::to_vec(self).map_err(|e| e.to_string())?;
+        fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        serde_cbor::from_slice(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Several pattern databases evaluated together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternDatabases {
+    pub databases: Vec<PatternDatabase>,
+    pub combination: Combination,
+}
+
+/// Identifies what a saved set of pattern databases was built for, so that a file is
+/// never used with another instance or other parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PdbHeader {
+    pub json_path: String,
+    pub max_size: u64,
+    pub combination: Combination,
+    pub patterns: Vec<Vec<usize>>, // variable groups of every pattern, see `patterns`
+}
+
+impl PatternDatabases {
+    /// Greedily packs consecutive variable groups into patterns whose tables have at most
+    /// `max_size` entries, and builds one database per pattern. With disjoint patterns
+    /// built this way, `Additive` is admissible for domains whose actions move one object.
+    pub fn build<P: ProjectableProblem>(
+        problem: &P,
+        state: &P::State,
+        max_size: u64,
+        combination: Combination,
+    ) -> Self {
+        PatternDatabases {
+            databases: Self::patterns(problem, state, max_size)
+                .iter()
+                .map(|groups| PatternDatabase::build(problem, state, groups))
+                .collect(),
+            combination,
+        }
+    }
+
+    /// The variable groups of every pattern `build` makes, without building the tables.
+    pub fn patterns<P: ProjectableProblem>(
+        problem: &P,
+        state: &P::State,
+        max_size: u64,
+    ) -> Vec<Vec<usize>> {
+        let variables = problem.variables(state);
+        let group_size = |group: &Vec<usize>| {
+            group
+                .iter()
+                .map(|&v| range(&variables[v].name, &variables[v].domain).1)
+                .product::<u64>()
+        };
+
+        let mut patterns: Vec<Vec<usize>> = Vec::new();
+        let mut current: Vec<usize> = Vec::new();
+        let mut current_size = 1u64;
+        for (g, group) in problem.variable_groups(state).iter().enumerate() {
+            let size = group_size(group);
+            if !current.is_empty() && current_size.saturating_mul(size) > max_size {
+                patterns.push(std::mem::take(&mut current));
+                current_size = 1;
+            }
+            current.push(g);
+            current_size = current_size.saturating_mul(size);
+        }
+        if !current.is_empty() {
+            patterns.push(current);
+        }
+        patterns
+    }
+
+    pub fn evaluate(&self, values: &[Value]) -> f64 {
+        let mut result = 0.0;
+        for database in &self.databases {
+            let h = database.evaluate(values);
+            result = match self.combination {
+                Combination::Additive => result + h,
+                Combination::Max => f64::max(result, h),
+            };
+        }
+        result
+    }
+
+    /// Writes the header and the databases to `path`.
+    pub fn save(&self, path: &str, header: &PdbHeader) -> Result<(), String> {
+        let bytes = serde_cbor::to_vec(&(header, self)).map_err(|e| e.to_string())?;
+        fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+
+    /// Loads the databases at `path`, refusing a file saved with another header.
+    pub fn load(path: &str, header: &PdbHeader) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let (saved, databases): (PdbHeader, Self) =
+            serde_cbor::from_slice(&bytes).map_err(|e| e.to_string())?;
+        if saved != *header {
+            return Err(format!(
+                "{} was built for {:?}, not {:?}",
+                path, saved, header
+            ));
+        }
+        Ok(databases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::heuristic_check::{check_heuristic, HeuristicCheckConfig};
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::problem::Problem;
+
+    #[test]
+    fn test_pattern_databases_are_admissible() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let config = HeuristicCheckConfig::default();
+        for combination in [Combination::Additive, Combination::Max] {
+            let databases = PatternDatabases::build(&problem, &state, 100, combination);
+            assert!(databases.databases.len() > 1);
+            let report = check_heuristic(
+                &problem,
+                &state,
+                |s| databases.evaluate(&problem.values(s)),
+                &config,
+            );
+            assert!(report.complete && report.is_admissible() && report.is_consistent());
+        }
+
+        let path = std::env::temp_dir()
+            .join(format!("counters_pfile1_{}.pdb", std::process::id()));
+        let path = path.to_str().unwrap();
+        let header = PdbHeader {
+            json_path: "src/inputs/counters_problem/problems_json/pfile1.json".to_string(),
+            max_size: 100,
+            combination: Combination::Additive,
+            patterns: PatternDatabases::patterns(&problem, &state, 100),
+        };
+        let databases = PatternDatabases::build(&problem, &state, 100, Combination::Additive);
+        databases.save(path, &header).unwrap();
+        assert_eq!(PatternDatabases::load(path, &header).unwrap(), databases);
+        // Files of other parameters are refused, and so are corrupt ones.
+        let other = PdbHeader {
+            combination: Combination::Max,
+            ..header.clone()
+        };
+        assert!(PatternDatabases::load(path, &other).is_err());
+        std::fs::write(path, b"not a database").unwrap();
+        assert!(PatternDatabases::load(path, &header).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
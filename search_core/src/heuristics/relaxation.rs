@@ -0,0 +1,307 @@
+use crate::heuristics::numeric_task::{
+    Comparison, LinearExpression, NumericCondition, NumericEffect, NumericTask, EPSILON,
+};
+use std::collections::HashSet;
+
+/// Direction in which a variable's reachable interval is extended.
+pub const LOW: usize = 0;
+pub const HIGH: usize = 1;
+
+/// Reachable values of one variable in the interval relaxation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+/// How the costs of several subgoals are combined: `Sum` gives h_add, `Max` gives h_max.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Sum,
+    Max,
+}
+
+impl Aggregation {
+    fn combine(self, a: f64, b: f64) -> f64 {
+        match self {
+            Aggregation::Sum => a + b,
+            Aggregation::Max => a.max(b),
+        }
+    }
+}
+
+pub fn expression_interval(expression: &LinearExpression, intervals: &[Interval]) -> Interval {
+    let mut result = Interval {
+        lo: expression.constant,
+        hi: expression.constant,
+    };
+    for &(c, v) in &expression.terms {
+        if c > 0.0 {
+            result.lo += c * intervals[v].lo;
+            result.hi += c * intervals[v].hi;
+        } else if c < 0.0 {
+            result.lo += c * intervals[v].hi;
+            result.hi += c * intervals[v].lo;
+        }
+    }
+    result
+}
+
+/// True if some assignment within the intervals satisfies the condition.
+pub fn is_satisfiable(condition: &NumericCondition, intervals: &[Interval]) -> bool {
+    let range = expression_interval(&condition.expression, intervals);
+    match condition.comparison {
+        Comparison::Ge => range.hi >= -EPSILON,
+        Comparison::Gt => range.hi > EPSILON,
+        Comparison::Eq => range.lo <= EPSILON && range.hi >= -EPSILON,
+        Comparison::Le => range.lo <= EPSILON,
+        Comparison::Lt => range.lo < -EPSILON,
+    }
+}
+
+/// Variables a condition needs to move, and in which direction, starting from `values`.
+/// Equalities are pushed towards zero from whichever side `values` lies on.
+pub fn needed_directions(condition: &NumericCondition, values: &[f64]) -> Vec<(usize, usize)> {
+    let increase = match condition.comparison {
+        Comparison::Ge | Comparison::Gt => true,
+        Comparison::Le | Comparison::Lt => false,
+        Comparison::Eq => condition.expression.evaluate(values) < 0.0,
+    };
+    condition
+        .expression
+        .terms
+        .iter()
+        .filter(|&&(c, _)| c != 0.0)
+        .map(|&(c, v)| (v, if (c > 0.0) == increase { HIGH } else { LOW }))
+        .collect()
+}
+
+/// Layered interval relaxation (Metric-FF style): at every layer each applicable action is
+/// applied once and the reachable interval of every variable can only grow.
+#[derive(Debug, Clone)]
+pub struct RelaxedGraph {
+    pub layers: Vec<Vec<Interval>>, // layers[0] is the evaluated state itself
+    pub achievers: Vec<Vec<[Option<usize>; 2]>>, // achievers[k][v][dir]: cheapest action extending v from layer k to k + 1
+    pub applicable_from: Vec<Option<usize>>,     // first layer at which each action is applicable
+    pub goal_costs: Vec<Option<f64>>,            // aggregated cost of every goal condition
+    pub goal_layers: Vec<Option<usize>>,         // first layer at which every goal condition holds
+}
+
+impl RelaxedGraph {
+    pub fn goals_reached(&self) -> bool {
+        self.goal_costs.iter().all(|c| c.is_some())
+    }
+
+    pub fn first_satisfiable_layer(&self, condition: &NumericCondition) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|intervals| is_satisfiable(condition, intervals))
+    }
+}
+
+fn condition_cost(
+    condition: &NumericCondition,
+    values: &[f64],
+    variable_costs: &[[f64; 2]],
+    aggregation: Aggregation,
+) -> f64 {
+    if condition.is_satisfied(values) {
+        return 0.0;
+    }
+    needed_directions(condition, values)
+        .into_iter()
+        .fold(0.0, |acc, (v, dir)| {
+            aggregation.combine(acc, variable_costs[v][dir])
+        })
+}
+
+/// Builds the relaxed graph from `values`, stopping at a fixpoint, after `max_layers` layers,
+/// or once all goals are reached. With `saturate`, it keeps going past the goals until a layer
+/// makes no new action applicable, so that `applicable_from` lists every reachable action.
+pub fn build_relaxed_graph<A>(
+    task: &NumericTask<A>,
+    values: &[f64],
+    aggregation: Aggregation,
+    max_layers: usize,
+    saturate: bool,
+) -> RelaxedGraph {
+    let n = values.len();
+    let mut current: Vec<Interval> = values.iter().map(|&x| Interval { lo: x, hi: x }).collect();
+    let mut variable_costs = vec![[0.0f64; 2]; n];
+    let mut pre_costs = vec![0.0f64; task.actions.len()];
+
+    let mut graph = RelaxedGraph {
+        layers: vec![current.clone()],
+        achievers: Vec::new(),
+        applicable_from: vec![None; task.actions.len()],
+        goal_costs: vec![None; task.goals.len()],
+        goal_layers: vec![None; task.goals.len()],
+    };
+    for (g, goal) in task.goals.iter().enumerate() {
+        if goal.is_satisfied(values) {
+            graph.goal_costs[g] = Some(0.0);
+            graph.goal_layers[g] = Some(0);
+        }
+    }
+
+    for layer in 0..max_layers {
+        if graph.goals_reached() && !saturate {
+            break;
+        }
+
+        let mut newly_applicable = false;
+        for (a, action) in task.actions.iter().enumerate() {
+            if graph.applicable_from[a].is_some() {
+                continue;
+            }
+            if action
+                .preconditions
+                .iter()
+                .all(|p| is_satisfiable(p, &current))
+            {
+                graph.applicable_from[a] = Some(layer);
+                pre_costs[a] = action.preconditions.iter().fold(0.0, |acc, p| {
+                    aggregation
+                        .combine(acc, condition_cost(p, values, &variable_costs, aggregation))
+                });
+                newly_applicable = true;
+            }
+        }
+        if graph.goals_reached() && !newly_applicable {
+            break;
+        }
+
+        let mut next = current.clone();
+        let mut best: Vec<[Option<(f64, usize)>; 2]> = vec![[None; 2]; n];
+        for (a, action) in task.actions.iter().enumerate() {
+            if graph.applicable_from[a].is_none() {
+                continue;
+            }
+            for effect in &action.effects {
+                let v = effect.variable();
+                let (lo, hi, base) = match effect {
+                    NumericEffect::Increase(_, e) => {
+                        let range = expression_interval(e, &current);
+                        (
+                            current[v].lo + range.lo.min(0.0),
+                            current[v].hi + range.hi.max(0.0),
+                            variable_costs[v],
+                        )
+                    }
+                    NumericEffect::Assign(_, e) => {
+                        let range = expression_interval(e, &current);
+                        (range.lo, range.hi, [0.0, 0.0])
+                    }
+                };
+                for (dir, extends) in [
+                    (LOW, lo < current[v].lo - EPSILON),
+                    (HIGH, hi > current[v].hi + EPSILON),
+                ] {
+                    if !extends {
+                        continue;
+                    }
+                    if dir == LOW {
+                        next[v].lo = next[v].lo.min(lo);
+                    } else {
+                        next[v].hi = next[v].hi.max(hi);
+                    }
+                    let cost = action.cost + aggregation.combine(pre_costs[a], base[dir]);
+                    if best[v][dir].is_none_or(|(c, _)| cost < c) {
+                        best[v][dir] = Some((cost, a));
+                    }
+                }
+            }
+        }
+
+        if best.iter().all(|b| b[LOW].is_none() && b[HIGH].is_none()) {
+            break; // fixpoint: nothing can be extended any more
+        }
+        for (v, b) in best.iter().enumerate() {
+            for dir in [LOW, HIGH] {
+                if let Some((cost, _)) = b[dir] {
+                    variable_costs[v][dir] = cost;
+                }
+            }
+        }
+        graph.achievers.push(
+            best.iter()
+                .map(|b| [b[LOW].map(|x| x.1), b[HIGH].map(|x| x.1)])
+                .collect(),
+        );
+        current = next;
+        graph.layers.push(current.clone());
+
+        for (g, goal) in task.goals.iter().enumerate() {
+            if graph.goal_costs[g].is_none() && is_satisfiable(goal, &current) {
+                graph.goal_costs[g] =
+                    Some(condition_cost(goal, values, &variable_costs, aggregation));
+                graph.goal_layers[g] = Some(layer + 1);
+            }
+        }
+    }
+    graph
+}
+
+/// h_add or h_max value; infinite if the relaxation cannot reach the goal.
+pub fn relaxed_cost(graph: &RelaxedGraph, aggregation: Aggregation) -> f64 {
+    if !graph.goals_reached() {
+        return f64::INFINITY;
+    }
+    graph
+        .goal_costs
+        .iter()
+        .fold(0.0, |acc, c| aggregation.combine(acc, c.unwrap_or(0.0)))
+}
+
+/// Extracts a relaxed plan backwards from the goal layers and returns its cost (h_FF).
+/// An action may be selected at several layers, which accounts for repeated numeric effects.
+pub fn relaxed_plan_cost<A>(
+    task: &NumericTask<A>,
+    values: &[f64],
+    graph: &RelaxedGraph,
+) -> f64 {
+    match relaxed_plan(task, values, graph) {
+        Some(selected) => selected.iter().map(|&(_, a)| task.actions[a].cost).sum(),
+        None => f64::INFINITY,
+    }
+}
+
+/// Actions of the relaxed plan as (layer, action) pairs, or `None` if the goals are not
+/// reached in the graph.
+pub fn relaxed_plan<A>(
+    task: &NumericTask<A>,
+    values: &[f64],
+    graph: &RelaxedGraph,
+) -> Option<HashSet<(usize, usize)>> {
+    if !graph.goals_reached() {
+        return None;
+    }
+    let mut selected: HashSet<(usize, usize)> = HashSet::new(); // (layer, action)
+    let mut agenda: Vec<(&NumericCondition, usize)> = task
+        .goals
+        .iter()
+        .zip(&graph.goal_layers)
+        .filter(|(goal, _)| !goal.is_satisfied(values))
+        .map(|(goal, layer)| (goal, layer.unwrap_or(0)))
+        .collect();
+
+    while let Some((condition, limit)) = agenda.pop() {
+        for (v, dir) in needed_directions(condition, values) {
+            for k in 0..limit {
+                let Some(a) = graph.achievers[k][v][dir] else {
+                    continue;
+                };
+                if !selected.insert((k, a)) {
+                    continue;
+                }
+                for pre in &task.actions[a].preconditions {
+                    if !pre.is_satisfied(values) {
+                        let layer = graph.first_satisfiable_layer(pre).unwrap_or(k).min(k);
+                        agenda.push((pre, layer));
+                    }
+                }
+            }
+        }
+    }
+    Some(selected)
+}
@@ -1,3 +1,5 @@
 pub mod algorithms;
+pub mod analysis;
+pub mod heuristics;
 pub mod problems;
 pub mod search;
@@ -1,5 +1,13 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::heuristics::pattern_database::ProjectableProblem;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::stubborn_sets::{Footprint, InterferenceProblem};
+use crate::search::symmetry::{
+    canonical_order, groups_by_signature, ObjectGroup, SymmetricProblem,
+};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::fs;
@@ -31,6 +39,17 @@ pub struct Grid {
     pub min_y: i32, // Minimum y value allowed
 }
 
+/// Actions of the block grouping domain; blocks are referred to by their position in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlockGroupingAction {
+    MoveUp(usize),
+    MoveDown(usize),
+    MoveLeft(usize),
+    MoveRight(usize),
+}
+
+impl ActionTrait for BlockGroupingAction {}
+
 /// The block grouping problem definition.
 /// Contains the grid boundaries for the planning task.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -73,24 +92,24 @@ impl BlockGroupingProblem {
 
     /// Returns all valid actions from the given state.
     /// Filters based on grid boundaries.
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<BlockGroupingAction> {
         let mut actions = Vec::new();
 
-        for block in &state.blocks {
+        for (i, block) in state.blocks.iter().enumerate() {
             if block.y + 1 <= self.grid.max_y {
-                actions.push(Self::get_move_block_up_action(block))
+                actions.push(BlockGroupingAction::MoveUp(i))
             }
 
             if block.y - 1 >= self.grid.min_y {
-                actions.push(Self::get_move_block_down_action(block))
+                actions.push(BlockGroupingAction::MoveDown(i))
             }
 
             if block.x + 1 <= self.grid.max_x {
-                actions.push(Self::get_move_block_right_action(block))
+                actions.push(BlockGroupingAction::MoveRight(i))
             }
 
             if block.x - 1 >= self.grid.min_x {
-                actions.push(Self::get_move_block_left_action(block))
+                actions.push(BlockGroupingAction::MoveLeft(i))
             }
         }
 
@@ -100,62 +119,30 @@ impl BlockGroupingProblem {
     // === Action application functions ===
 
     /// Applies a "move up" action to the state.
-    pub fn apply_move_up_block_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_up_block_action(state: &State, block: usize) -> State {
         let mut new_state = state.clone();
-        let block_index = match action.parameters.get("block") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for block."),
-        };
-        if let Some(block) = new_state.blocks.iter_mut().find(|b| b.index == block_index) {
-            block.y += 1;
-        } else {
-            panic!("Block with index {} not found", block_index);
-        }
+        new_state.blocks[block].y += 1;
         new_state
     }
 
     /// Applies a "move down" action to the state.
-    pub fn apply_move_down_block_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_down_block_action(state: &State, block: usize) -> State {
         let mut new_state = state.clone();
-        let block_index = match action.parameters.get("block") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for block."),
-        };
-        if let Some(block) = new_state.blocks.iter_mut().find(|b| b.index == block_index) {
-            block.y -= 1;
-        } else {
-            panic!("Block with index {} not found", block_index);
-        }
+        new_state.blocks[block].y -= 1;
         new_state
     }
 
     /// Applies a "move right" action to the state.
-    pub fn apply_move_right_block_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_right_block_action(state: &State, block: usize) -> State {
         let mut new_state = state.clone();
-        let block_index = match action.parameters.get("block") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for block."),
-        };
-        if let Some(block) = new_state.blocks.iter_mut().find(|b| b.index == block_index) {
-            block.x += 1;
-        } else {
-            panic!("Block with index {} not found", block_index);
-        }
+        new_state.blocks[block].x += 1;
         new_state
     }
 
     /// Applies a "move left" action to the state.
-    pub fn apply_move_left_block_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_left_block_action(state: &State, block: usize) -> State {
         let mut new_state = state.clone();
-        let block_index = match action.parameters.get("block") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for block."),
-        };
-        if let Some(block) = new_state.blocks.iter_mut().find(|b| b.index == block_index) {
-            block.x -= 1;
-        } else {
-            panic!("Block with index {} not found", block_index);
-        }
+        new_state.blocks[block].x -= 1;
         new_state
     }
 }
@@ -164,22 +151,46 @@ impl BlockGroupingProblem {
 /// Includes goal condition, action enumeration, and action application logic.
 impl Problem for BlockGroupingProblem {
     type State = State;
+    type Action = BlockGroupingAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<BlockGroupingAction> {
         self.get_possible_actions(state)
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("move_up_") {
-            Self::apply_move_up_block_action(state, action)
-        } else if action.name.starts_with("move_down_") {
-            Self::apply_move_down_block_action(state, action)
-        } else if action.name.starts_with("move_left_") {
-            Self::apply_move_left_block_action(state, action)
-        } else if action.name.starts_with("move_right_") {
-            Self::apply_move_right_block_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    fn apply_action(&self, state: &State, action: &BlockGroupingAction) -> State {
+        match *action {
+            BlockGroupingAction::MoveUp(block) => Self::apply_move_up_block_action(state, block),
+            BlockGroupingAction::MoveDown(block) => {
+                Self::apply_move_down_block_action(state, block)
+            }
+            BlockGroupingAction::MoveLeft(block) => {
+                Self::apply_move_left_block_action(state, block)
+            }
+            BlockGroupingAction::MoveRight(block) => {
+                Self::apply_move_right_block_action(state, block)
+            }
+        }
+    }
+
+    fn action_cost(&self, _state: &State, _action: &BlockGroupingAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &BlockGroupingAction) -> Action {
+        match *action {
+            BlockGroupingAction::MoveUp(block) => {
+                Self::get_move_block_up_action(&state.blocks[block])
+            }
+            BlockGroupingAction::MoveDown(block) => {
+                Self::get_move_block_down_action(&state.blocks[block])
+            }
+            BlockGroupingAction::MoveLeft(block) => {
+                Self::get_move_block_left_action(&state.blocks[block])
+            }
+            BlockGroupingAction::MoveRight(block) => {
+                Self::get_move_block_right_action(&state.blocks[block])
+            }
         }
     }
 
@@ -232,3 +243,126 @@ impl Problem for BlockGroupingProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for BlockGroupingProblem {
+    /// Two variables per block: its x and y coordinate.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        for block in &state.blocks {
+            variables.push(Variable::integer(
+                format!("x_block{}", block.index),
+                Some(self.grid.min_x as i64),
+                Some(self.grid.max_x as i64),
+            ));
+            variables.push(Variable::integer(
+                format!("y_block{}", block.index),
+                Some(self.grid.min_y as i64),
+                Some(self.grid.max_y as i64),
+            ));
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        state
+            .blocks
+            .iter()
+            .flat_map(|b| [Value::Int(b.x), Value::Int(b.y)])
+            .collect()
+    }
+
+    /// Neighbouring blocks of one colour group must share x and y. That blocks of different
+    /// groups are apart is a disjunction, so the goal is exact only for a single group.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let mut goal = FactoredGoal {
+            conditions: Vec::new(),
+            exact: true,
+        };
+        for i in 0..state.blocks.len().saturating_sub(1) {
+            if state.blocks[i].color_group != state.blocks[i + 1].color_group {
+                goal.exact = false;
+                continue;
+            }
+            for offset in 0..2 {
+                goal.conditions.push(NumericCondition::new(
+                    LinearExpression::variable(2 * i + offset),
+                    Comparison::Eq,
+                    &LinearExpression::variable(2 * (i + 1) + offset),
+                ));
+            }
+        }
+        goal
+    }
+}
+
+impl ProjectableProblem for BlockGroupingProblem {
+    /// The x and y coordinate of each block.
+    fn variable_groups(&self, state: &State) -> Vec<Vec<usize>> {
+        (0..state.blocks.len())
+            .map(|i| vec![2 * i, 2 * i + 1])
+            .collect()
+    }
+
+    /// Blocks move independently, so the abstraction simply keeps the selected blocks.
+    fn project(&self, state: &State, groups: &[usize]) -> (Self, State) {
+        let blocks = groups.iter().map(|&i| state.blocks[i].clone()).collect();
+        (self.clone(), State { blocks })
+    }
+}
+
+impl InterferenceProblem for BlockGroupingProblem {
+    fn all_actions(&self, state: &State) -> Vec<BlockGroupingAction> {
+        (0..state.blocks.len())
+            .flat_map(|i| {
+                [
+                    BlockGroupingAction::MoveUp(i),
+                    BlockGroupingAction::MoveDown(i),
+                    BlockGroupingAction::MoveLeft(i),
+                    BlockGroupingAction::MoveRight(i),
+                ]
+            })
+            .collect()
+    }
+
+    /// Blocks may share a cell, so a move only involves the coordinate it changes.
+    fn footprint(&self, _state: &State, action: &BlockGroupingAction) -> Footprint {
+        let variable = match *action {
+            BlockGroupingAction::MoveUp(i) | BlockGroupingAction::MoveDown(i) => 2 * i + 1,
+            BlockGroupingAction::MoveLeft(i) | BlockGroupingAction::MoveRight(i) => 2 * i,
+        };
+        Footprint {
+            reads: vec![variable],
+            writes: vec![variable],
+        }
+    }
+}
+
+impl SymmetricProblem for BlockGroupingProblem {
+    /// Blocks of one colour that follow each other in the state. The goal only compares
+    /// neighbouring blocks, so same-coloured blocks further apart are not interchangeable.
+    fn symmetry_groups(&self, state: &State) -> Vec<ObjectGroup> {
+        let mut runs = Vec::with_capacity(state.blocks.len());
+        for (i, block) in state.blocks.iter().enumerate() {
+            let run = match runs.last() {
+                Some(&run) if state.blocks[i - 1].color_group != block.color_group => run + 1,
+                Some(&run) => run,
+                None => 0,
+            };
+            runs.push(run);
+        }
+        groups_by_signature("block", runs)
+    }
+
+    /// Coordinates are sorted within each group; indices and colours stay in place.
+    fn canonical_state(&self, state: &State, groups: &[ObjectGroup]) -> State {
+        let mut canonical = state.clone();
+        for group in groups {
+            let order = canonical_order(group, |b| (state.blocks[b].x, state.blocks[b].y));
+            for (&slot, &b) in group.objects.iter().zip(&order) {
+                canonical.blocks[slot].x = state.blocks[b].x;
+                canonical.blocks[slot].y = state.blocks[b].y;
+            }
+        }
+        canonical
+    }
+}
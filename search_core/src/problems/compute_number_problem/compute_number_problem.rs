@@ -1,5 +1,7 @@
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
@@ -116,16 +118,38 @@ pub struct State {
     pub goal_threshold: i32,   // The threshold value for the goal
 }
 
-impl State {}
+impl State {
+    /// Value at `index` of the initial set followed by the buffers.
+    pub fn value(&self, index: usize) -> i32 {
+        match self.initial_set.get(index) {
+            Some(&value) => value,
+            None => self.buffers[index - self.initial_set.len()],
+        }
+    }
+}
 
 impl StateTrait for State {}
 
+/// Arithmetic actions: `a` and `b` index the initial set followed by the buffers, and the
+/// result is written to `buffer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComputeNumberAction {
+    Add { a: usize, b: usize, buffer: usize },
+    Subtract { a: usize, b: usize, buffer: usize },
+    Multiply { a: usize, b: usize, buffer: usize },
+    Divide { a: usize, b: usize, buffer: usize },
+}
+
+impl ActionTrait for ComputeNumberAction {}
+
 pub struct ComputeNumberProblem {}
 
 impl ComputeNumberProblem {}
 
 impl Problem for ComputeNumberProblem {
     type State = State;
+    type Action = ComputeNumberAction;
+    type Cost = i32;
     fn load_state_from_json(json_path: &str) -> (State, Self) {
         let file = File::open(json_path).expect("Failed to open JSON file");
         let reader = BufReader::new(file);
@@ -159,36 +183,27 @@ impl Problem for ComputeNumberProblem {
         (state, Self {})
     }
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<ComputeNumberAction> {
         let mut actions = Vec::new();
 
-        let combined_values: Vec<i32> = state
-            .initial_set
-            .iter()
-            .copied()
-            .chain(state.buffers.iter().copied())
-            .collect();
-        let num_values = combined_values.len();
+        let num_values = state.initial_set.len() + state.buffers.len();
         let num_buffers = state.buffers.len();
 
-        for i in 0..num_values {
-            for j in 0..num_values {
-                if i == j {
+        for a in 0..num_values {
+            for b in 0..num_values {
+                if a == b {
                     continue; // Skip if indices are the same
                 }
-                for buffer_index in 0..num_buffers {
+                for buffer in 0..num_buffers {
                     // Generate all possible actions
-                    actions.push(create_add_action(i, j, buffer_index, &combined_values));
-                    actions.push(create_subtract_action(i, j, buffer_index, &combined_values));
-                    if let Some(multiply_action) =
-                        create_multiply_action(i, j, buffer_index, &combined_values)
-                    {
-                        actions.push(multiply_action);
+                    actions.push(ComputeNumberAction::Add { a, b, buffer });
+                    actions.push(ComputeNumberAction::Subtract { a, b, buffer });
+                    // Skip multiplications that would overflow and divisions by zero
+                    if state.value(a).checked_mul(state.value(b)).is_some() {
+                        actions.push(ComputeNumberAction::Multiply { a, b, buffer });
                     }
-                    if let Some(divide_action) =
-                        create_divide_action(i, j, buffer_index, &combined_values)
-                    {
-                        actions.push(divide_action);
+                    if state.value(b) != 0 {
+                        actions.push(ComputeNumberAction::Divide { a, b, buffer });
                     }
                 }
             }
@@ -197,44 +212,53 @@ impl Problem for ComputeNumberProblem {
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
+    fn apply_action(&self, state: &State, action: &ComputeNumberAction) -> State {
         let mut new_state = state.clone();
 
-        let _combined_values: Vec<i32> = state
-            .initial_set
-            .iter()
-            .copied()
-            .chain(state.buffers.iter().copied())
-            .collect();
-
-        let a = match action.parameters.get("a") {
-            Some(Value::Int(value)) => *value,
-            _ => panic!("Expected 'a' to be an integer in action parameters"),
-        };
-
-        let b = match action.parameters.get("b") {
-            Some(Value::Int(value)) => *value,
-            _ => panic!("Expected 'b' to be an integer in action parameters"),
+        let (buffer, result) = match *action {
+            ComputeNumberAction::Add { a, b, buffer } => (buffer, state.value(a) + state.value(b)),
+            ComputeNumberAction::Subtract { a, b, buffer } => {
+                (buffer, state.value(a) - state.value(b))
+            }
+            ComputeNumberAction::Multiply { a, b, buffer } => {
+                (buffer, state.value(a) * state.value(b))
+            }
+            ComputeNumberAction::Divide { a, b, buffer } => {
+                (buffer, state.value(a) / state.value(b))
+            }
         };
 
-        let buffer_index = match action.parameters.get("buffer") {
-            Some(Value::Int(index)) => *index as usize,
-            _ => panic!("Expected 'buffer' to be an integer in action parameters"),
-        };
+        new_state.buffers[buffer] = result;
 
-        let result = match action.name.split_whitespace().next() {
-            Some("add") => a + b,
-            Some("subtract") => a - b,
-            Some("multiply") => a * b,
-            Some("divide") => a / b,
-            Some("exponentiate") => a.wrapping_pow(b as u32),
-            Some(action) => panic!("Unexpected action name: {}", action),
-            None => panic!("Action name is empty"),
-        };
+        new_state
+    }
 
-        new_state.buffers[buffer_index] = result;
+    fn action_cost(&self, _state: &State, _action: &ComputeNumberAction) -> i32 {
+        1
+    }
 
-        new_state
+    fn describe_action(&self, state: &State, action: &ComputeNumberAction) -> Action {
+        let combined_values: Vec<i32> = state
+            .initial_set
+            .iter()
+            .copied()
+            .chain(state.buffers.iter().copied())
+            .collect();
+        match *action {
+            ComputeNumberAction::Add { a, b, buffer } => {
+                create_add_action(a, b, buffer, &combined_values)
+            }
+            ComputeNumberAction::Subtract { a, b, buffer } => {
+                create_subtract_action(a, b, buffer, &combined_values)
+            }
+            ComputeNumberAction::Multiply { a, b, buffer } => {
+                create_multiply_action(a, b, buffer, &combined_values)
+                    .expect("Multiplication overflows")
+            }
+            ComputeNumberAction::Divide { a, b, buffer } => {
+                create_divide_action(a, b, buffer, &combined_values).expect("Division by zero")
+            }
+        }
     }
 
     fn is_goal_state(&self, state: &State) -> bool {
@@ -249,3 +273,24 @@ impl Problem for ComputeNumberProblem {
         heuristic(self, state)
     }
 }
+
+impl FactoredProblem for ComputeNumberProblem {
+    /// One variable per buffer; the initial set and the threshold never change.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        (0..state.buffers.len())
+            .map(|i| Variable::integer(format!("buffer{}", i), None, None))
+            .collect()
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        state.buffers.iter().map(|&b| Value::Int(b)).collect()
+    }
+
+    /// "Some buffer holds a twin prime above the threshold" has no linear form.
+    fn goal(&self, _state: &State) -> FactoredGoal {
+        FactoredGoal {
+            conditions: Vec::new(),
+            exact: false,
+        }
+    }
+}
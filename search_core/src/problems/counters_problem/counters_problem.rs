@@ -1,5 +1,13 @@
+use crate::heuristics::numeric_task::{
+    Comparison, LinearExpression, NumericAction, NumericCondition, NumericEffect, NumericProblem,
+    NumericTask,
+};
+use crate::heuristics::pattern_database::ProjectableProblem;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::stubborn_sets::{Footprint, InterferenceProblem};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::fs;
@@ -86,6 +94,15 @@ impl Counter {
     }
 }
 
+/// Actions of the counter domain; counters are referred to by their index in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CountersAction {
+    Increase(usize),
+    Decrease(usize),
+}
+
+impl ActionTrait for CountersAction {}
+
 /// Describes the entire problem instance for the counter domain.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CountersProblem {
@@ -114,78 +131,64 @@ impl CountersProblem {
 
     /// Returns all valid actions for counters in the current state.
     /// Actions are filtered by boundaries (1 to max_value).
-    pub fn get_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_actions(&self, state: &State) -> Vec<CountersAction> {
         let mut actions = Vec::new();
-        for counter in &state.counters {
+        for (i, counter) in state.counters.iter().enumerate() {
             if counter.value + 1 <= self.max_value {
-                actions.push(Self::get_increase_action(counter));
+                actions.push(CountersAction::Increase(i));
             }
             if counter.value - 1 >= 1 {
-                actions.push(Self::get_decrease_action(counter));
+                actions.push(CountersAction::Decrease(i));
             }
         }
         actions
     }
 
     /// Applies an increase action and returns the resulting state.
-    pub fn apply_increase_action(state: &State, action: &Action) -> State {
+    pub fn apply_increase_action(state: &State, counter: usize) -> State {
         let mut new_state = state.clone();
-        let counter_name = match action.parameters.get("counter") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for counter."),
-        };
-
-        if let Some(counter) = new_state
-            .counters
-            .iter_mut()
-            .find(|v| v.name == *counter_name)
-        {
-            counter.value += 1;
-        } else {
-            panic!("Counter with name {} not found", counter_name);
-        }
-
+        new_state.counters[counter].value += 1;
         new_state
     }
 
     /// Applies a decrease action and returns the resulting state.
-    pub fn apply_decrease_action(state: &State, action: &Action) -> State {
+    pub fn apply_decrease_action(state: &State, counter: usize) -> State {
         let mut new_state = state.clone();
-        let counter_name = match action.parameters.get("counter") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for counter."),
-        };
-
-        if let Some(counter) = new_state
-            .counters
-            .iter_mut()
-            .find(|v| v.name == *counter_name)
-        {
-            counter.value -= 1;
-        } else {
-            panic!("Counter with name {} not found", counter_name);
-        }
-
+        new_state.counters[counter].value -= 1;
         new_state
     }
 }
 
 impl Problem for CountersProblem {
     type State = State;
+    type Action = CountersAction;
+    type Cost = i32;
 
     /// Lists all applicable actions from the current state.
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<CountersAction> {
         self.get_actions(state)
     }
 
     /// Applies an action to the current state and returns the resulting state.
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("increase_") {
-            Self::apply_increase_action(state, action)
-        } else if action.name.starts_with("decrease_") {
-            Self::apply_decrease_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    fn apply_action(&self, state: &State, action: &CountersAction) -> State {
+        match *action {
+            CountersAction::Increase(counter) => Self::apply_increase_action(state, counter),
+            CountersAction::Decrease(counter) => Self::apply_decrease_action(state, counter),
+        }
+    }
+
+    fn action_cost(&self, _state: &State, _action: &CountersAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &CountersAction) -> Action {
+        match *action {
+            CountersAction::Increase(counter) => {
+                Self::get_increase_action(&state.counters[counter])
+            }
+            CountersAction::Decrease(counter) => {
+                Self::get_decrease_action(&state.counters[counter])
+            }
         }
     }
 
@@ -220,3 +223,140 @@ impl Problem for CountersProblem {
         (state, problem)
     }
 }
+
+impl NumericProblem for CountersProblem {
+    /// One variable per counter; increase/decrease keep every counter within [1, max_value].
+    fn numeric_task(&self, state: &State) -> NumericTask<Self::Action> {
+        let mut task = NumericTask::default();
+        for counter in &state.counters {
+            task.add_variable(counter.name.clone());
+        }
+        let index = |name: &str| {
+            state
+                .counters
+                .iter()
+                .position(|c| c.name == name)
+                .unwrap_or_else(|| panic!("Counter {} not found in state", name))
+        };
+        let to_expression = |expr: &LinearExpr| {
+            expr.terms.iter().fold(
+                LinearExpression::constant(expr.constant as f64),
+                |e, (coeff, name)| e.term(*coeff as f64, index(name)),
+            )
+        };
+
+        for i in 0..state.counters.len() {
+            task.actions.push(NumericAction {
+                operator: CountersAction::Increase(i),
+                cost: 1.0,
+                preconditions: vec![NumericCondition::new(
+                    LinearExpression::variable(i).plus(1.0),
+                    Comparison::Le,
+                    &LinearExpression::constant(self.max_value as f64),
+                )],
+                effects: vec![NumericEffect::Increase(i, LinearExpression::constant(1.0))],
+            });
+            task.actions.push(NumericAction {
+                operator: CountersAction::Decrease(i),
+                cost: 1.0,
+                preconditions: vec![NumericCondition::new(
+                    LinearExpression::variable(i).plus(-1.0),
+                    Comparison::Ge,
+                    &LinearExpression::constant(1.0),
+                )],
+                effects: vec![NumericEffect::Increase(i, LinearExpression::constant(-1.0))],
+            });
+        }
+
+        task.goals = self
+            .goal
+            .conditions
+            .iter()
+            .map(|c| {
+                NumericCondition::new(
+                    to_expression(&c.left),
+                    Comparison::from_operator(&c.operator),
+                    &to_expression(&c.right),
+                )
+            })
+            .collect();
+        task
+    }
+
+    fn numeric_values(&self, state: &State) -> Vec<f64> {
+        state.counters.iter().map(|c| c.value as f64).collect()
+    }
+}
+
+impl FactoredProblem for CountersProblem {
+    /// One variable per counter, kept within [1, max_value] once it enters that range.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        state
+            .counters
+            .iter()
+            .map(|c| {
+                Variable::integer(
+                    c.name.clone(),
+                    Some(c.value.min(1) as i64),
+                    Some(c.value.max(self.max_value) as i64),
+                )
+            })
+            .collect()
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        state.counters.iter().map(|c| Value::Int(c.value)).collect()
+    }
+
+    fn goal(&self, state: &State) -> FactoredGoal {
+        FactoredGoal {
+            conditions: self.numeric_task(state).goals,
+            exact: true,
+        }
+    }
+}
+
+impl ProjectableProblem for CountersProblem {
+    fn variable_groups(&self, state: &State) -> Vec<Vec<usize>> {
+        (0..state.counters.len()).map(|i| vec![i]).collect()
+    }
+
+    /// Keeps the selected counters and the goal conditions that only mention them.
+    fn project(&self, state: &State, groups: &[usize]) -> (Self, State) {
+        let counters: Vec<Counter> = groups.iter().map(|&i| state.counters[i].clone()).collect();
+        let kept = |expr: &LinearExpr| {
+            expr.terms
+                .iter()
+                .all(|(_, name)| counters.iter().any(|c| c.name == *name))
+        };
+        let conditions = self
+            .goal
+            .conditions
+            .iter()
+            .filter(|c| kept(&c.left) && kept(&c.right))
+            .cloned()
+            .collect();
+        let problem = CountersProblem {
+            max_value: self.max_value,
+            goal: Goal { conditions },
+        };
+        (problem, State { counters })
+    }
+}
+
+impl InterferenceProblem for CountersProblem {
+    fn all_actions(&self, state: &State) -> Vec<CountersAction> {
+        (0..state.counters.len())
+            .flat_map(|i| [CountersAction::Increase(i), CountersAction::Decrease(i)])
+            .collect()
+    }
+
+    /// Each action reads and writes its own counter only.
+    fn footprint(&self, _state: &State, action: &CountersAction) -> Footprint {
+        let (CountersAction::Increase(i) | CountersAction::Decrease(i)) = *action;
+        Footprint {
+            reads: vec![i],
+            writes: vec![i],
+        }
+    }
+}
@@ -1,17 +1,18 @@
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs; 
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Arm {
     pub is_free: bool,
     pub side: i32, // 0 for left, 1 for right
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Bot {
     pub location: i32,
     pub load_limit: i32,
@@ -20,7 +21,7 @@ pub struct Bot {
     pub arms: Vec<Arm>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Item {
     pub location: i32,
     pub weight: i32,
@@ -29,19 +30,34 @@ pub struct Item {
     pub index: i32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The PDDL `cost` increments (move 3, pick and drop 2, tray moves 1) are exactly
+/// `action_cost`, so the state holds no accumulated cost and equal configurations reached
+/// along different paths are the same state.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct State {
     pub bots: Vec<Bot>,
     pub items: Vec<Item>,
-    pub cost: i32,
 }
 
 impl StateTrait for State {}
 
+/// Actions of the delivery domain. Bots and items are referred to by their position in the
+/// state, arms by their position in the bot's arms, rooms by their id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeliveryAction {
+    Move { bot: usize, to: i32 },
+    Pick { bot: usize, item: usize, arm: usize },
+    Drop { bot: usize, item: usize, arm: usize },
+    ToTray { bot: usize, item: usize, arm: usize },
+    FromTray { bot: usize, item: usize, arm: usize },
+}
+
+impl ActionTrait for DeliveryAction {}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeliveryProblem {
-    goal_locations: HashMap<String, i32>, // item_id -> target_room_id
-    room_connections: HashMap<String, Vec<i32>> // room_id -> connected_rooms  
+    goal_locations: BTreeMap<String, i32>, // item_id -> target_room_id
+    room_connections: BTreeMap<String, Vec<i32>> // room_id -> connected_rooms  
 }
 
 impl DeliveryProblem {
@@ -107,216 +123,103 @@ impl DeliveryProblem {
         )
     }
 
-    pub fn apply_move_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_action(state: &State, bot: usize, to: i32) -> State {
         let mut new_state = state.clone();
-        let bot_index = match action.parameters.get("bot").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for bot"),
-        };
-        let to = match action.parameters.get("to").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for destination"),
-        };
-
-        new_state
-            .bots
-            .iter_mut()
-            .find(|bot| bot.index == bot_index)
-            .unwrap()
-            .location = to;
-        new_state.cost += 3;
+        new_state.bots[bot].location = to;
         new_state
     }
 
-    pub fn apply_pick_action(state: &State, action: &Action) -> State {
+    pub fn apply_pick_action(state: &State, bot: usize, item: usize, arm: usize) -> State {
         let mut new_state = state.clone();
-        let item_index = match action.parameters.get("item").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for item"),
-        };
-        let arm_side = match action.parameters.get("arm").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for arm"),
-        };
-        let bot_index = match action.parameters.get("bot").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for bot"),
-        };
-
-        let item = new_state
-            .items
-            .iter_mut()
-            .find(|item| item.index == item_index)
-            .unwrap();
-        let bot = new_state
-            .bots
-            .iter_mut()
-            .find(|bot| bot.index == bot_index)
-            .unwrap();
-        let arm = bot
-            .arms
-            .iter_mut()
-            .find(|arm| arm.side == arm_side)
-            .unwrap();
-
-        item.in_arm = arm_side;
+        let item = &mut new_state.items[item];
+        let bot = &mut new_state.bots[bot];
+        let arm = &mut bot.arms[arm];
+
+        item.in_arm = arm.side;
         arm.is_free = false;
         bot.current_load += item.weight;
-        new_state.cost += 2;
         new_state
     }
 
-    pub fn apply_drop_action(state: &State, action: &Action) -> State {
+    pub fn apply_drop_action(state: &State, bot: usize, item: usize, arm: usize) -> State {
         let mut new_state = state.clone();
-        let item_index = match action.parameters.get("item").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for item"),
-        };
-        let room = match action.parameters.get("room").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for room"),
-        };
-        let arm_side = match action.parameters.get("arm").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for arm"),
-        };
-        let bot_index = match action.parameters.get("bot").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for bot"),
-        };
-
-        let item = new_state
-            .items
-            .iter_mut()
-            .find(|item| item.index == item_index)
-            .unwrap();
-        let bot = new_state
-            .bots
-            .iter_mut()
-            .find(|bot| bot.index == bot_index)
-            .unwrap();
-        let arm = bot
-            .arms
-            .iter_mut()
-            .find(|arm| arm.side == arm_side)
-            .unwrap();
+        let item = &mut new_state.items[item];
+        let bot = &mut new_state.bots[bot];
 
         item.in_arm = -1;
-        item.location = room;
-        arm.is_free = true;
+        item.location = bot.location;
+        bot.arms[arm].is_free = true;
         bot.current_load -= item.weight;
-        new_state.cost += 2;
         new_state
     }
 
-    pub fn apply_to_tray_action(state: &State, action: &Action) -> State {
+    pub fn apply_to_tray_action(state: &State, bot: usize, item: usize, arm: usize) -> State {
         let mut new_state = state.clone();
-        let item_index = match action.parameters.get("item").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for item"),
-        };
-        let arm_side = match action.parameters.get("arm").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for arm"),
-        };
-        let bot_index = match action.parameters.get("bot").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for bot"),
-        };
-
-        let item = new_state
-            .items
-            .iter_mut()
-            .find(|item| item.index == item_index)
-            .unwrap();
-        let bot = new_state
-            .bots
-            .iter_mut()
-            .find(|bot| bot.index == bot_index)
-            .unwrap();
-        let arm = bot
-            .arms
-            .iter_mut()
-            .find(|arm| arm.side == arm_side)
-            .unwrap();
+        let item = &mut new_state.items[item];
+        let bot = &mut new_state.bots[bot];
 
         item.in_arm = -1;
-        item.in_tray = bot_index;
-        arm.is_free = true;
-        new_state.cost += 1;
+        item.in_tray = bot.index;
+        bot.arms[arm].is_free = true;
         new_state
     }
 
-    pub fn apply_from_tray_action(state: &State, action: &Action) -> State {
+    pub fn apply_from_tray_action(state: &State, bot: usize, item: usize, arm: usize) -> State {
         let mut new_state = state.clone();
-        let item_index = match action.parameters.get("item").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for item"),
-        };
-        let arm_side = match action.parameters.get("arm").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for arm"),
-        };
-        let bot_index = match action.parameters.get("bot").unwrap() {
-            Value::Int(val) => *val,
-            _ => panic!("Expected integer value for bot"),
-        };
-
-        let item = new_state
-            .items
-            .iter_mut()
-            .find(|item| item.index == item_index)
-            .unwrap();
-        let bot = new_state
-            .bots
-            .iter_mut()
-            .find(|bot| bot.index == bot_index)
-            .unwrap();
-        let arm = bot
-            .arms
-            .iter_mut()
-            .find(|arm| arm.side == arm_side)
-            .unwrap();
+        let item = &mut new_state.items[item];
+        let arm = &mut new_state.bots[bot].arms[arm];
 
         item.in_tray = -1;
-        item.in_arm = arm_side;
+        item.in_arm = arm.side;
         arm.is_free = false;
-        new_state.cost += 1;
         new_state
     }
+
+    /// Position of the item held in the given arm.
+    fn item_in_arm(state: &State, arm: &Arm) -> usize {
+        state
+            .items
+            .iter()
+            .position(|item| item.in_arm == arm.side)
+            .unwrap()
+    }
 }
 
 impl Problem for DeliveryProblem {
     type State = State;
+    type Action = DeliveryAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<DeliveryAction> {
         let mut actions = Vec::new();
 
         // Move actions - Only generate if the bot is not already in the target room
-        for bot in &state.bots {
+        for (b, bot) in state.bots.iter().enumerate() {
             if let Some(connected_rooms) = self.room_connections.get(&format!("room{}", bot.location)) {
                 for &next_room in connected_rooms {
                     if bot.location != next_room {
-                        actions.push(Self::possible_move_action(bot.index, bot.location, next_room));
+                        actions.push(DeliveryAction::Move {
+                            bot: b,
+                            to: next_room,
+                        });
                     }
                 }
             }
         }
 
         // Pick actions - Only for items in the same room as the bot, not in arms or trays
-        for bot in &state.bots {
-            for item in &state.items {
+        for (b, bot) in state.bots.iter().enumerate() {
+            for (i, item) in state.items.iter().enumerate() {
                 if bot.location == item.location && item.in_arm == -1 && item.in_tray == -1 {
-                    for arm in &bot.arms {
+                    for (a, arm) in bot.arms.iter().enumerate() {
                         if arm.is_free {
                             let new_load = bot.current_load + item.weight;
                             if new_load <= bot.load_limit {
-                                actions.push(Self::possible_pick_action(
-                                    item.index,
-                                    item.location,
-                                    arm.side,
-                                    bot.index,
-                                ));
+                                actions.push(DeliveryAction::Pick {
+                                    bot: b,
+                                    item: i,
+                                    arm: a,
+                                });
                             }
                         }
                     }
@@ -325,58 +228,49 @@ impl Problem for DeliveryProblem {
         }
 
         // Drop actions - Only for items currently in arms
-        for bot in &state.bots {
-            for arm in &bot.arms {
+        for (b, bot) in state.bots.iter().enumerate() {
+            for (a, arm) in bot.arms.iter().enumerate() {
                 if !arm.is_free {
-                    let item = state
-                        .items
-                        .iter()
-                        .find(|item| item.in_arm == arm.side)
-                        .unwrap();
+                    let i = Self::item_in_arm(state, arm);
                     // Only drop if the item is not already in the room
-                    if item.location != bot.location {
-                        actions.push(Self::possible_drop_action(
-                            item.index,
-                            bot.location,
-                            arm.side,
-                            bot.index,
-                        ));
+                    if state.items[i].location != bot.location {
+                        actions.push(DeliveryAction::Drop {
+                            bot: b,
+                            item: i,
+                            arm: a,
+                        });
                     }
                 }
             }
         }
 
         // To-tray actions - Only for items in arms, not already in the tray
-        for bot in &state.bots {
-            for arm in &bot.arms {
+        for (b, bot) in state.bots.iter().enumerate() {
+            for (a, arm) in bot.arms.iter().enumerate() {
                 if !arm.is_free {
-                    let item = state
-                        .items
-                        .iter()
-                        .find(|item| item.in_arm == arm.side)
-                        .unwrap();
-                    if item.in_tray == -1 {
-                        actions.push(Self::possible_to_tray_action(
-                            item.index,
-                            arm.side,
-                            bot.index,
-                        ));
+                    let i = Self::item_in_arm(state, arm);
+                    if state.items[i].in_tray == -1 {
+                        actions.push(DeliveryAction::ToTray {
+                            bot: b,
+                            item: i,
+                            arm: a,
+                        });
                     }
                 }
             }
         }
 
         // From-tray actions - Only for items in the tray, and if there is a free arm
-        for bot in &state.bots {
-            for item in &state.items {
+        for (b, bot) in state.bots.iter().enumerate() {
+            for (i, item) in state.items.iter().enumerate() {
                 if item.in_tray == bot.index {
-                    for arm in &bot.arms {
+                    for (a, arm) in bot.arms.iter().enumerate() {
                         if arm.is_free {
-                            actions.push(Self::possible_from_tray_action(
-                                item.index,
-                                arm.side,
-                                bot.index,
-                            ));
+                            actions.push(DeliveryAction::FromTray {
+                                bot: b,
+                                item: i,
+                                arm: a,
+                            });
                         }
                     }
                 }
@@ -386,19 +280,54 @@ impl Problem for DeliveryProblem {
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("move_") {
-            Self::apply_move_action(state, action)
-        } else if action.name.starts_with("pick_") {
-            Self::apply_pick_action(state, action)
-        } else if action.name.starts_with("drop_") {
-            Self::apply_drop_action(state, action)
-        } else if action.name.starts_with("to_tray_") {
-            Self::apply_to_tray_action(state, action)
-        } else if action.name.starts_with("from_tray_") {
-            Self::apply_from_tray_action(state, action)
-        } else {
-            panic!("Unknown action");
+    fn apply_action(&self, state: &State, action: &DeliveryAction) -> State {
+        match *action {
+            DeliveryAction::Move { bot, to } => Self::apply_move_action(state, bot, to),
+            DeliveryAction::Pick { bot, item, arm } => {
+                Self::apply_pick_action(state, bot, item, arm)
+            }
+            DeliveryAction::Drop { bot, item, arm } => {
+                Self::apply_drop_action(state, bot, item, arm)
+            }
+            DeliveryAction::ToTray { bot, item, arm } => {
+                Self::apply_to_tray_action(state, bot, item, arm)
+            }
+            DeliveryAction::FromTray { bot, item, arm } => {
+                Self::apply_from_tray_action(state, bot, item, arm)
+            }
+        }
+    }
+
+    fn action_cost(&self, _state: &State, action: &DeliveryAction) -> i32 {
+        match action {
+            DeliveryAction::Move { .. } => 3,
+            DeliveryAction::Pick { .. } | DeliveryAction::Drop { .. } => 2,
+            DeliveryAction::ToTray { .. } | DeliveryAction::FromTray { .. } => 1,
+        }
+    }
+
+    fn describe_action(&self, state: &State, action: &DeliveryAction) -> Action {
+        match *action {
+            DeliveryAction::Move { bot, to } => {
+                let bot = &state.bots[bot];
+                Self::possible_move_action(bot.index, bot.location, to)
+            }
+            DeliveryAction::Pick { bot, item, arm } => {
+                let (bot, item) = (&state.bots[bot], &state.items[item]);
+                Self::possible_pick_action(item.index, item.location, bot.arms[arm].side, bot.index)
+            }
+            DeliveryAction::Drop { bot, item, arm } => {
+                let (bot, item) = (&state.bots[bot], &state.items[item]);
+                Self::possible_drop_action(item.index, bot.location, bot.arms[arm].side, bot.index)
+            }
+            DeliveryAction::ToTray { bot, item, arm } => {
+                let (bot, item) = (&state.bots[bot], &state.items[item]);
+                Self::possible_to_tray_action(item.index, bot.arms[arm].side, bot.index)
+            }
+            DeliveryAction::FromTray { bot, item, arm } => {
+                let (bot, item) = (&state.bots[bot], &state.items[item]);
+                Self::possible_from_tray_action(item.index, bot.arms[arm].side, bot.index)
+            }
         }
     }
 
@@ -1,28 +1,43 @@
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct State {
     pub x: i32, // x coordinate of the drone
     pub y: i32, // y coordinate of the drone
     pub z: i32, // z coordinate of the drone
     pub battery_level: i32, // battery level of the drone
-    pub visited: HashMap<String, bool>, // point => visited or not
+    pub visited: BTreeMap<String, bool>, // point => visited or not
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DroneProblem {
     pub battery_capacity: i32, // battery capacity of the drone
     pub bounds: ((i32, i32), (i32, i32), (i32, i32)), // x bounds tuple, y bounds tuple, z bounds tuple
-    pub locations: HashMap<String, (i32, i32, i32)>, // point => (x, y, z)
+    pub locations: BTreeMap<String, (i32, i32, i32)>, // point => (x, y, z)
 }
 
 impl StateTrait for State {}
 
+/// Actions of the drone domain; locations are referred to by their position in `locations`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DroneAction {
+    IncreaseX,
+    DecreaseX,
+    IncreaseY,
+    DecreaseY,
+    IncreaseZ,
+    DecreaseZ,
+    Visit(usize),
+    Recharge,
+}
+
+impl ActionTrait for DroneAction {}
 
 impl DroneProblem {
     pub fn possible_increase_x_action(state: &State) -> Action {
@@ -71,6 +86,13 @@ impl DroneProblem {
         Action::new("recharge".to_string(), 0, HashMap::new())
     }
 
+    fn location_id(&self, location: usize) -> &String {
+        self.locations
+            .keys()
+            .nth(location)
+            .expect("Location index out of range")
+    }
+
     pub fn apply_increase_x_action(state: &State) -> State {
         let mut new_state = state.clone();
         new_state.x += 1;
@@ -113,16 +135,14 @@ impl DroneProblem {
         new_state
     }
 
-    pub fn apply_visit_action(state: &State, action: &Action) -> State {
+    pub fn apply_visit_action(state: &State, loc_id: &str) -> State {
         let mut new_state = state.clone();
-        if let Some(Value::Text(loc_id)) = action.parameters.get("location") {
-            new_state.visited.insert(loc_id.clone(), true);
-            new_state.battery_level -= 1;
-        }
+        new_state.visited.insert(loc_id.to_string(), true);
+        new_state.battery_level -= 1;
         new_state
     }
 
-    pub fn apply_recharge_action(state: &State, _action: &Action, battery_capacity: i32) -> State {
+    pub fn apply_recharge_action(state: &State, battery_capacity: i32) -> State {
         let mut new_state = state.clone();
         new_state.battery_level = battery_capacity;
         new_state
@@ -131,63 +151,80 @@ impl DroneProblem {
 
 impl Problem for DroneProblem {
     type State = State;
+    type Action = DroneAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<DroneAction> {
         let mut actions = Vec::new();
 
         if state.battery_level >= 1 {
             if state.x <= self.bounds.0 .1 - 1 {
-                actions.push(Self::possible_increase_x_action(state));
+                actions.push(DroneAction::IncreaseX);
             }
             if state.x >= self.bounds.0 .0 + 1 {
-                actions.push(Self::possible_decrease_x_action(state));
+                actions.push(DroneAction::DecreaseX);
             }
             if state.y <= self.bounds.1 .1 - 1 {
-                actions.push(Self::possible_increase_y_action(state));
+                actions.push(DroneAction::IncreaseY);
             }
             if state.y >= self.bounds.1 .0 + 1 {
-                actions.push(Self::possible_decrease_y_action(state));
+                actions.push(DroneAction::DecreaseY);
             }
             if state.z <= self.bounds.2 .1 - 1 {
-                actions.push(Self::possible_increase_z_action(state));
+                actions.push(DroneAction::IncreaseZ);
             }
             if state.z >= self.bounds.2 .0 + 1 {
-                actions.push(Self::possible_decrease_z_action(state));
+                actions.push(DroneAction::DecreaseZ);
             }
 
-            for (loc_id, &(loc_x, loc_y, loc_z)) in &self.locations {
+            for (i, &(loc_x, loc_y, loc_z)) in self.locations.values().enumerate() {
                 if state.x == loc_x && state.y == loc_y && state.z == loc_z {
-                    actions.push(Self::possible_visit_action(state, loc_id.clone()));
+                    actions.push(DroneAction::Visit(i));
                 }
             }
         }
 
         if state.x == 0 && state.y == 0 && state.z == 0 {
-            actions.push(Self::possible_recharge_action(state));
+            actions.push(DroneAction::Recharge);
         }
 
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("increase_x") {
-            Self::apply_increase_x_action(state)
-        } else if action.name.starts_with("decrease_x") {
-            Self::apply_decrease_x_action(state)
-        } else if action.name.starts_with("increase_y") {
-            Self::apply_increase_y_action(state)
-        } else if action.name.starts_with("decrease_y") {
-            Self::apply_decrease_y_action(state)
-        } else if action.name.starts_with("increase_z") {
-            Self::apply_increase_z_action(state)
-        } else if action.name.starts_with("decrease_z") {
-            Self::apply_decrease_z_action(state)
-        } else if action.name.starts_with("visit") {
-            Self::apply_visit_action(state, action)
-        } else if action.name.starts_with("recharge") {
-            Self::apply_recharge_action(state, action, self.battery_capacity)
-        } else {
-            panic!("Unknown action");
+    fn apply_action(&self, state: &State, action: &DroneAction) -> State {
+        match *action {
+            DroneAction::IncreaseX => Self::apply_increase_x_action(state),
+            DroneAction::DecreaseX => Self::apply_decrease_x_action(state),
+            DroneAction::IncreaseY => Self::apply_increase_y_action(state),
+            DroneAction::DecreaseY => Self::apply_decrease_y_action(state),
+            DroneAction::IncreaseZ => Self::apply_increase_z_action(state),
+            DroneAction::DecreaseZ => Self::apply_decrease_z_action(state),
+            DroneAction::Visit(location) => {
+                Self::apply_visit_action(state, self.location_id(location))
+            }
+            DroneAction::Recharge => Self::apply_recharge_action(state, self.battery_capacity),
+        }
+    }
+
+    fn action_cost(&self, _state: &State, action: &DroneAction) -> i32 {
+        match action {
+            DroneAction::Recharge => 0,
+            _ => 1,
+        }
+    }
+
+    fn describe_action(&self, state: &State, action: &DroneAction) -> Action {
+        match *action {
+            DroneAction::IncreaseX => Self::possible_increase_x_action(state),
+            DroneAction::DecreaseX => Self::possible_decrease_x_action(state),
+            DroneAction::IncreaseY => Self::possible_increase_y_action(state),
+            DroneAction::DecreaseY => Self::possible_decrease_y_action(state),
+            DroneAction::IncreaseZ => Self::possible_increase_z_action(state),
+            DroneAction::DecreaseZ => Self::possible_decrease_z_action(state),
+            DroneAction::Visit(location) => {
+                Self::possible_visit_action(state, self.location_id(location).clone())
+            }
+            DroneAction::Recharge => Self::possible_recharge_action(state),
         }
     }
 
@@ -1,31 +1,45 @@
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Sled {
     pub location: String,
     pub supplies: i32, // Amount of supllies on the sled
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct State {
-    pub sleds: HashMap<String, Sled>,
-    pub waypoint_supplies: HashMap<String, i32>, // Supplies in each waypoint
+    pub sleds: BTreeMap<String, Sled>,
+    pub waypoint_supplies: BTreeMap<String, i32>, // Supplies in each waypoint
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExpeditionProblem {
-    goal_locations: HashMap<String, String>, // sled => goal waypoint
-    waypoint_connections: HashMap<String, Vec<String>>, // waypoint => connections to other waypoints
-    sled_capacity: HashMap<String, i32> // sled => supply capacity
+    goal_locations: BTreeMap<String, String>, // sled => goal waypoint
+    waypoint_connections: BTreeMap<String, Vec<String>>, // waypoint => connections to other waypoints
+    sled_capacity: BTreeMap<String, i32> // sled => supply capacity
 }
 
 impl StateTrait for State {}
 
+/// Actions of the expedition domain. Sleds are referred to by their position in the state;
+/// a forward move goes from the `from`-th waypoint of `waypoint_connections` to its `to`-th
+/// connection, a backward move to the `to`-th waypoint of `waypoint_connections`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExpeditionAction {
+    MoveForwards { sled: usize, from: usize, to: usize },
+    MoveBackwards { sled: usize, to: usize },
+    StoreSupplies(usize),
+    RetrieveSupplies(usize),
+}
+
+impl ActionTrait for ExpeditionAction {}
+
 impl ExpeditionProblem {
     pub fn possible_move_forwards_action(&self, sled_id: &str, from: &str, to: &str) -> Action {
         let mut parameters = HashMap::new();
@@ -58,53 +72,52 @@ impl ExpeditionProblem {
         Action::new(format!("retrieve_supplies_{}_{}", sled_id, waypoint), 1, parameters)
     }
 
-    pub fn apply_move_forwards(&self, state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let sled_id = match action.parameters.get("sled").unwrap() {
-            Value::Text(id) => id,
-            _ => panic!("Expected string value for sled"),
-        };
-        let to = match action.parameters.get("to").unwrap() {
-            Value::Text(val) => val,
-            _ => panic!("Expected string value for destination"),
-        };
+    fn sled(state: &State, sled: usize) -> (&String, &Sled) {
+        state
+            .sleds
+            .iter()
+            .nth(sled)
+            .expect("Sled index out of range")
+    }
 
+    /// Waypoint a move action leads to.
+    fn destination(&self, action: &ExpeditionAction) -> &String {
+        match *action {
+            ExpeditionAction::MoveForwards { from, to, .. } => self
+                .waypoint_connections
+                .values()
+                .nth(from)
+                .and_then(|connections| connections.get(to))
+                .expect("Waypoint index out of range"),
+            ExpeditionAction::MoveBackwards { to, .. } => self
+                .waypoint_connections
+                .keys()
+                .nth(to)
+                .expect("Waypoint index out of range"),
+            _ => panic!("Action {:?} is not a move", action),
+        }
+    }
+
+    pub fn apply_move_forwards(&self, state: &State, sled_id: &str, to: &str) -> State {
+        let mut new_state = state.clone();
         if let Some(sled) = new_state.sleds.get_mut(sled_id) {
-            sled.location = to.clone();
+            sled.location = to.to_string();
             sled.supplies -= 1;
         }
         new_state
     }
 
-    pub fn apply_move_backwards(&self, state: &State, action: &Action) -> State {
+    pub fn apply_move_backwards(&self, state: &State, sled_id: &str, to: &str) -> State {
         let mut new_state = state.clone();
-        let sled_id = match action.parameters.get("sled").unwrap() {
-            Value::Text(id) => id,
-            _ => panic!("Expected string value for sled"),
-        };
-        let to = match action.parameters.get("to").unwrap() {
-            Value::Text(val) => val,
-            _ => panic!("Expected string value for destination"),
-        };
-
         if let Some(sled) = new_state.sleds.get_mut(sled_id) {
-            sled.location = to.clone();
+            sled.location = to.to_string();
             sled.supplies -= 1;
         }
         new_state
     }
 
-    pub fn apply_store_supplies(&self, state: &State, action: &Action) -> State {
+    pub fn apply_store_supplies(&self, state: &State, sled_id: &str, waypoint: &str) -> State {
         let mut new_state = state.clone();
-        let sled_id = match action.parameters.get("sled").unwrap() {
-            Value::Text(id) => id,
-            _ => panic!("Expected string value for sled"),
-        };
-        let waypoint = match action.parameters.get("waypoint").unwrap() {
-            Value::Text(val) => val,
-            _ => panic!("Expected string value for waypoint"),
-        };
-
         if let Some(sled) = new_state.sleds.get_mut(sled_id) {
             if let Some(waypoint_supplies) = new_state.waypoint_supplies.get_mut(waypoint) {
                 *waypoint_supplies += 1;
@@ -114,17 +127,8 @@ impl ExpeditionProblem {
         new_state
     }
 
-    pub fn apply_retrieve_supplies(&self, state: &State, action: &Action) -> State {
+    pub fn apply_retrieve_supplies(&self, state: &State, sled_id: &str, waypoint: &str) -> State {
         let mut new_state = state.clone();
-        let sled_id = match action.parameters.get("sled").unwrap() {
-            Value::Text(id) => id,
-            _ => panic!("Expected string value for sled"),
-        };
-        let waypoint = match action.parameters.get("waypoint").unwrap() {
-            Value::Text(val) => val,
-            _ => panic!("Expected string value for waypoint"),
-        };
-
         if let Some(sled) = new_state.sleds.get_mut(sled_id) {
             if let Some(waypoint_supplies) = new_state.waypoint_supplies.get_mut(waypoint) {
                 if let Some(&capacity) = self.sled_capacity.get(sled_id) {
@@ -141,53 +145,43 @@ impl ExpeditionProblem {
 
 impl Problem for ExpeditionProblem {
     type State = State;
+    type Action = ExpeditionAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<ExpeditionAction> {
         let mut actions = Vec::new();
 
-        for (sled_id, sled) in &state.sleds {
+        for (i, (sled_id, sled)) in state.sleds.iter().enumerate() {
             if let Some(&capacity) = self.sled_capacity.get(sled_id) {
                 // Move forwards actions
                 if sled.supplies >= 1 {
-                    if let Some(next_waypoints) = self.waypoint_connections.get(&sled.location) {
-                        for next_waypoint in next_waypoints {
-                            actions.push(self.possible_move_forwards_action(
-                                sled_id,
-                                &sled.location,
-                                next_waypoint,
-                            ));
+                    let origin = self.waypoint_connections.iter().enumerate()
+                        .find(|(_, (waypoint, _))| **waypoint == sled.location);
+                    if let Some((from, (_, next_waypoints))) = origin {
+                        for to in 0..next_waypoints.len() {
+                            actions.push(ExpeditionAction::MoveForwards { sled: i, from, to });
                         }
                     }
                 }
 
                 // Move backwards actions
                 if sled.supplies >= 1 {
-                    for (prev_waypoint, connections) in &self.waypoint_connections {
+                    for (to, connections) in self.waypoint_connections.values().enumerate() {
                         if connections.contains(&sled.location) {
-                            actions.push(self.possible_move_backwards_action(
-                                sled_id,
-                                &sled.location,
-                                prev_waypoint,
-                            ));
+                            actions.push(ExpeditionAction::MoveBackwards { sled: i, to });
                         }
                     }
                 }
 
                 // Store supplies actions
                 if sled.supplies >= 1 {
-                    actions.push(self.possible_store_supplies_action(
-                        sled_id,
-                        &sled.location,
-                    ));
+                    actions.push(ExpeditionAction::StoreSupplies(i));
                 }
 
                 // Retrieve supplies actions
                 if let Some(&waypoint_supply) = state.waypoint_supplies.get(&sled.location) {
                     if waypoint_supply >= 1 && capacity > sled.supplies {
-                        actions.push(self.possible_retrieve_supplies_action(
-                            sled_id,
-                            &sled.location,
-                        ));
+                        actions.push(ExpeditionAction::RetrieveSupplies(i));
                     }
                 }
             }
@@ -196,17 +190,51 @@ impl Problem for ExpeditionProblem {
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("move_forwards") {
-            self.apply_move_forwards(state, action)
-        } else if action.name.starts_with("move_backwards") {
-            self.apply_move_backwards(state, action)
-        } else if action.name.starts_with("store_supplies") {
-            self.apply_store_supplies(state, action)
-        } else if action.name.starts_with("retrieve_supplies") {
-            self.apply_retrieve_supplies(state, action)
-        } else {
-            panic!("Unknown action: {}", action.name);
+    fn apply_action(&self, state: &State, action: &ExpeditionAction) -> State {
+        match *action {
+            ExpeditionAction::MoveForwards { sled, .. } => {
+                let (sled_id, _) = Self::sled(state, sled);
+                self.apply_move_forwards(state, sled_id, self.destination(action))
+            }
+            ExpeditionAction::MoveBackwards { sled, .. } => {
+                let (sled_id, _) = Self::sled(state, sled);
+                self.apply_move_backwards(state, sled_id, self.destination(action))
+            }
+            ExpeditionAction::StoreSupplies(sled) => {
+                let (sled_id, sled) = Self::sled(state, sled);
+                self.apply_store_supplies(state, sled_id, &sled.location)
+            }
+            ExpeditionAction::RetrieveSupplies(sled) => {
+                let (sled_id, sled) = Self::sled(state, sled);
+                self.apply_retrieve_supplies(state, sled_id, &sled.location)
+            }
+        }
+    }
+
+    fn action_cost(&self, _state: &State, _action: &ExpeditionAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &ExpeditionAction) -> Action {
+        match *action {
+            ExpeditionAction::MoveForwards { sled, .. } => {
+                let (sled_id, sled) = Self::sled(state, sled);
+                let to = self.destination(action);
+                self.possible_move_forwards_action(sled_id, &sled.location, to)
+            }
+            ExpeditionAction::MoveBackwards { sled, .. } => {
+                let (sled_id, sled) = Self::sled(state, sled);
+                let to = self.destination(action);
+                self.possible_move_backwards_action(sled_id, &sled.location, to)
+            }
+            ExpeditionAction::StoreSupplies(sled) => {
+                let (sled_id, sled) = Self::sled(state, sled);
+                self.possible_store_supplies_action(sled_id, &sled.location)
+            }
+            ExpeditionAction::RetrieveSupplies(sled) => {
+                let (sled_id, sled) = Self::sled(state, sled);
+                self.possible_retrieve_supplies_action(sled_id, &sled.location)
+            }
         }
     }
 
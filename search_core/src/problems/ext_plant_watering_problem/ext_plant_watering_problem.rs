@@ -1,5 +1,8 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::fs;
@@ -77,6 +80,24 @@ pub struct Condition {
     plant_index: i32,
     poured_amount: i32,
 }
+/// Actions of the plant watering domain; robots and plants are referred to by their
+/// position in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExtPlantWateringAction {
+    MoveUp(usize),
+    MoveDown(usize),
+    MoveRight(usize),
+    MoveLeft(usize),
+    MoveUpLeft(usize),
+    MoveUpRight(usize),
+    MoveDownLeft(usize),
+    MoveDownRight(usize),
+    Load(usize),
+    Pour { robot: usize, plant: usize },
+}
+
+impl ActionTrait for ExtPlantWateringAction {}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExtPlantWateringProblem {
     pub goal: Goal,
@@ -186,268 +207,149 @@ impl ExtPlantWateringProblem {
     /// Generates all valid actions for all robots based on the state.
     /// Each block below documents the specific condition for that type of movement or action.
     /// This includes directional moves, water loading, and water pouring.
-    pub fn get_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_actions(&self, state: &State) -> Vec<ExtPlantWateringAction> {
         let mut actions = Vec::new();
-        for robot in &state.robots {
+        for (i, robot) in state.robots.iter().enumerate() {
             //Condition to move up
             if robot.y + 1 <= self.max_y {
-                actions.push(Self::get_robot_move_up_action(robot));
+                actions.push(ExtPlantWateringAction::MoveUp(i));
             }
             //Condition to move down
             if robot.y - 1 >= self.min_y {
-                actions.push(Self::get_robot_move_down_action(robot));
+                actions.push(ExtPlantWateringAction::MoveDown(i));
             }
             //Condition to move right
             if robot.x + 1 <= self.max_x {
-                actions.push(Self::get_robot_move_right_action(robot));
+                actions.push(ExtPlantWateringAction::MoveRight(i));
             }
             //Condition to move left
             if robot.x - 1 >= self.min_x {
-                actions.push(Self::get_robot_move_left_action(robot));
+                actions.push(ExtPlantWateringAction::MoveLeft(i));
             }
             //Condition to move up left
             if robot.y + 1 <= self.max_y && robot.x - 1 >= self.min_x {
-                actions.push(Self::get_robot_move_up_left_action(robot));
+                actions.push(ExtPlantWateringAction::MoveUpLeft(i));
             }
             //Condition to move up right
             if robot.y + 1 <= self.max_y && robot.x + 1 <= self.max_x {
-                actions.push(Self::get_robot_move_up_right_action(robot));
+                actions.push(ExtPlantWateringAction::MoveUpRight(i));
             }
             //Condition to move down left
             if robot.y - 1 >= self.min_y && robot.x - 1 >= self.min_x {
-                actions.push(Self::get_robot_move_down_left_action(robot));
+                actions.push(ExtPlantWateringAction::MoveDownLeft(i));
             }
             //Condition to move down right
             if robot.y - 1 >= self.min_y && robot.x + 1 <= self.max_x {
-                actions.push(Self::get_robot_move_down_right_action(robot));
+                actions.push(ExtPlantWateringAction::MoveDownRight(i));
             }
 
             //Condition to load water
             if robot.y == state.tap.y && robot.x == state.tap.x {
-                actions.push(Self::get_robot_load_water_action(robot));
+                actions.push(ExtPlantWateringAction::Load(i));
             }
 
-            for plant in &state.plants {
+            for (j, plant) in state.plants.iter().enumerate() {
                 //Condition to pour water
                 if robot.y == plant.y && robot.x == plant.x && robot.carry > 0 {
-                    actions.push(Self::get_robot_pour_water_action(robot, plant));
+                    actions.push(ExtPlantWateringAction::Pour { robot: i, plant: j });
                 }
             }
         }
         actions
     }
-    /// Applies an action that moves the robot up by 1 unit.
-    pub fn apply_move_up_action(state: &State, action: &Action) -> State {
+    /// Applies an action that moves the robot by (dx, dy).
+    pub fn apply_move_action(state: &State, robot: usize, dx: i32, dy: i32) -> State {
         let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.y += 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
-        new_state
-    }
-    /// Applies an action that moves the robot down by 1 unit.
-    pub fn apply_move_down_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.y -= 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
-        new_state
-    }
-    /// Applies an action that moves the robot left by 1 unit.
-    pub fn apply_move_left_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.x -= 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
-        new_state
-    }
-    /// Applies an action that moves the robot right by 1 unit.
-    pub fn apply_move_right_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.x += 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
-        new_state
-    }
-
-    /// Applies an action that moves the robot diagonally up and left.
-    pub fn apply_move_up_left_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.y += 1;
-            robot.x -= 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
-        new_state
-    }
-    /// Applies an action that moves the robot diagonally up and right.
-    pub fn apply_move_up_right_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.y += 1;
-            robot.x += 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
-        new_state
-    }
-    /// Applies an action that moves the robot diagonally down and left.
-    pub fn apply_move_down_left_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.y -= 1;
-            robot.x -= 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
-        new_state
-    }
-    /// Applies an action that moves the robot diagonally down and right.
-    pub fn apply_move_down_right_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            robot.y -= 1;
-            robot.x += 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
+        new_state.robots[robot].x += dx;
+        new_state.robots[robot].y += dy;
         new_state
     }
     /// Applies an action where the robot loads water from the tap.
-    pub fn apply_load_action(state: &State, action: &Action) -> State {
+    pub fn apply_load_action(state: &State, robot: usize) -> State {
         let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        if let Some(robot) = new_state.robots.iter_mut().find(|v| v.index == robot_index) {
-            new_state.tap.water_amount -= 1;
-            new_state.total_loaded += 1;
-            robot.carry += 1;
-        } else {
-            panic!("Robot with index {} not found", robot_index);
-        }
-
+        new_state.tap.water_amount -= 1;
+        new_state.total_loaded += 1;
+        new_state.robots[robot].carry += 1;
         new_state
     }
     /// Applies an action where the robot pours water into a specific plant.
-    pub fn apply_pour_action(state: &State, action: &Action) -> State {
+    pub fn apply_pour_action(state: &State, robot: usize, plant: usize) -> State {
         let mut new_state = state.clone();
-        let robot_index = match action.parameters.get("robot_index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for robot."),
-        };
-
-        let plant_index = match action.parameters.get("plant_index") {
-            Some(Value::Int(index)) => *index,
-            _ => panic!("Action parameters do not contain a valid index for plant."),
-        };
-
-        if let (Some(robot), Some(plant)) = (
-            new_state.robots.iter_mut().find(|v| v.index == robot_index),
-            new_state.plants.iter_mut().find(|v| v.index == plant_index),
-        ) {
-            robot.carry -= 1;
-            plant.poured += 1;
-            new_state.total_poured += 1;
-        } else {
-            panic!(
-                "Robot with index {} or Plant with index {} not found",
-                robot_index, plant_index
-            );
-        }
-
+        new_state.robots[robot].carry -= 1;
+        new_state.plants[plant].poured += 1;
+        new_state.total_poured += 1;
         new_state
     }
 }
 
 impl Problem for ExtPlantWateringProblem {
     type State = State;
+    type Action = ExtPlantWateringAction;
+    type Cost = i32;
     /// Returns all possible actions from the current state.
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<ExtPlantWateringAction> {
         self.get_actions(state)
     }
-    /// Applies a named action to the state, dispatching to the correct handler.
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if let Some((_, pure_action)) = action.name.split_once('_') {
-            if pure_action.starts_with("poured_water_to_plant") {
-                Self::apply_pour_action(state, action)
-            } else {
-                match pure_action {
-                    "move_up" => Self::apply_move_up_action(state, action),
-                    "move_down" => Self::apply_move_down_action(state, action),
-                    "move_left" => Self::apply_move_left_action(state, action),
-                    "move_right" => Self::apply_move_right_action(state, action),
-                    "move_up_left" => Self::apply_move_up_left_action(state, action),
-                    "move_up_right" => Self::apply_move_up_right_action(state, action),
-                    "move_down_left" => Self::apply_move_down_left_action(state, action),
-                    "move_down_right" => Self::apply_move_down_right_action(state, action),
-                    "loaded" => Self::apply_load_action(state, action),
-                    unknown => panic!("Unknown action: {}", unknown),
-                }
+    /// Applies an action to the state, dispatching to the correct handler.
+    fn apply_action(&self, state: &State, action: &ExtPlantWateringAction) -> State {
+        match *action {
+            ExtPlantWateringAction::MoveUp(robot) => Self::apply_move_action(state, robot, 0, 1),
+            ExtPlantWateringAction::MoveDown(robot) => Self::apply_move_action(state, robot, 0, -1),
+            ExtPlantWateringAction::MoveRight(robot) => Self::apply_move_action(state, robot, 1, 0),
+            ExtPlantWateringAction::MoveLeft(robot) => Self::apply_move_action(state, robot, -1, 0),
+            ExtPlantWateringAction::MoveUpLeft(robot) => {
+                Self::apply_move_action(state, robot, -1, 1)
+            }
+            ExtPlantWateringAction::MoveUpRight(robot) => {
+                Self::apply_move_action(state, robot, 1, 1)
+            }
+            ExtPlantWateringAction::MoveDownLeft(robot) => {
+                Self::apply_move_action(state, robot, -1, -1)
+            }
+            ExtPlantWateringAction::MoveDownRight(robot) => {
+                Self::apply_move_action(state, robot, 1, -1)
+            }
+            ExtPlantWateringAction::Load(robot) => Self::apply_load_action(state, robot),
+            ExtPlantWateringAction::Pour { robot, plant } => {
+                Self::apply_pour_action(state, robot, plant)
+            }
+        }
+    }
+    fn action_cost(&self, _state: &State, _action: &ExtPlantWateringAction) -> i32 {
+        1
+    }
+    fn describe_action(&self, state: &State, action: &ExtPlantWateringAction) -> Action {
+        match *action {
+            ExtPlantWateringAction::MoveUp(robot) => {
+                Self::get_robot_move_up_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::MoveDown(robot) => {
+                Self::get_robot_move_down_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::MoveRight(robot) => {
+                Self::get_robot_move_right_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::MoveLeft(robot) => {
+                Self::get_robot_move_left_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::MoveUpLeft(robot) => {
+                Self::get_robot_move_up_left_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::MoveUpRight(robot) => {
+                Self::get_robot_move_up_right_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::MoveDownLeft(robot) => {
+                Self::get_robot_move_down_left_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::MoveDownRight(robot) => {
+                Self::get_robot_move_down_right_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::Load(robot) => {
+                Self::get_robot_load_water_action(&state.robots[robot])
+            }
+            ExtPlantWateringAction::Pour { robot, plant } => {
+                Self::get_robot_pour_water_action(&state.robots[robot], &state.plants[plant])
             }
-        } else {
-            panic!(
-                "Action name does not contain an underscore: {}",
-                action.name
-            );
         }
     }
     /// Checks if the current state satisfies the goal.
@@ -484,3 +386,98 @@ impl Problem for ExtPlantWateringProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for ExtPlantWateringProblem {
+    /// Robot positions and loads, water poured per plant, water left in the tap, and the
+    /// two totals. Loading is unbounded, so only the monotone sides are bounded.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        for robot in &state.robots {
+            variables.push(Variable::integer(
+                format!("x_robot{}", robot.index),
+                Some(self.min_x as i64),
+                Some(self.max_x as i64),
+            ));
+            variables.push(Variable::integer(
+                format!("y_robot{}", robot.index),
+                Some(self.min_y as i64),
+                Some(self.max_y as i64),
+            ));
+            variables.push(Variable::integer(
+                format!("carry_robot{}", robot.index),
+                Some(robot.carry.min(0) as i64),
+                None,
+            ));
+        }
+        for plant in &state.plants {
+            variables.push(Variable::integer(
+                format!("poured_plant{}", plant.index),
+                Some(plant.poured as i64),
+                None,
+            ));
+        }
+        variables.push(Variable::integer(
+            "water_tap".to_string(),
+            None,
+            Some(state.tap.water_amount as i64),
+        ));
+        variables.push(Variable::integer(
+            "total_poured".to_string(),
+            Some(state.total_poured as i64),
+            None,
+        ));
+        variables.push(Variable::integer(
+            "total_loaded".to_string(),
+            Some(state.total_loaded as i64),
+            None,
+        ));
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        for robot in &state.robots {
+            values.extend([
+                Value::Int(robot.x),
+                Value::Int(robot.y),
+                Value::Int(robot.carry),
+            ]);
+        }
+        values.extend(state.plants.iter().map(|p| Value::Int(p.poured)));
+        values.push(Value::Int(state.tap.water_amount));
+        values.push(Value::Int(state.total_poured));
+        values.push(Value::Int(state.total_loaded));
+        values
+    }
+
+    /// Poured amounts per plant plus the comparison of the totals; "!=" is left out.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let plants_start = 3 * state.robots.len();
+        let total_poured = plants_start + state.plants.len() + 1;
+        let mut goal = FactoredGoal {
+            conditions: Vec::new(),
+            exact: true,
+        };
+        for cond in &self.goal.conditions {
+            let position = state
+                .plants
+                .iter()
+                .position(|p| p.index == cond.plant_index)
+                .unwrap_or_else(|| panic!("Plant with index {} not found", cond.plant_index));
+            goal.conditions.push(NumericCondition::equals(
+                plants_start + position,
+                cond.poured_amount as f64,
+            ));
+        }
+        if self.goal.total_operator == "!=" {
+            goal.exact = false;
+        } else {
+            goal.conditions.push(NumericCondition::new(
+                LinearExpression::variable(total_poured),
+                Comparison::from_operator(&self.goal.total_operator),
+                &LinearExpression::variable(total_poured + 1),
+            ));
+        }
+        goal
+    }
+}
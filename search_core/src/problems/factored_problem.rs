@@ -0,0 +1,209 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::problems::problem::Problem;
+use crate::search::state::Value;
+use ordered_float::OrderedFloat;
+
+/// Domain of a factored variable. Bounds are `None` where the instance does not fix them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariableDomain {
+    Boolean,
+    Integer {
+        lower: Option<i64>,
+        upper: Option<i64>,
+    },
+    Real {
+        lower: Option<f64>,
+        upper: Option<f64>,
+    },
+}
+
+/// A named state variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub domain: VariableDomain,
+}
+
+impl Variable {
+    pub fn boolean(name: String) -> Self {
+        Variable {
+            name,
+            domain: VariableDomain::Boolean,
+        }
+    }
+
+    pub fn integer(name: String, lower: Option<i64>, upper: Option<i64>) -> Self {
+        Variable {
+            name,
+            domain: VariableDomain::Integer { lower, upper },
+        }
+    }
+
+    pub fn real(name: String, lower: Option<f64>, upper: Option<f64>) -> Self {
+        Variable {
+            name,
+            domain: VariableDomain::Real { lower, upper },
+        }
+    }
+
+    /// True if `value` has the variable's type and lies within its bounds.
+    pub fn admits(&self, value: &Value) -> bool {
+        match (&self.domain, value) {
+            (VariableDomain::Boolean, Value::Bool(_)) => true,
+            (VariableDomain::Integer { lower, upper }, Value::Int(_) | Value::Int64(_)) => {
+                let x = value_as_f64(value) as i64;
+                lower.is_none_or(|l| x >= l) && upper.is_none_or(|u| x <= u)
+            }
+            (VariableDomain::Real { lower, upper }, Value::OrderedFloat64(x)) => {
+                lower.is_none_or(|l| x.0 >= l) && upper.is_none_or(|u| x.0 <= u)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Goal over the factored variables, as a conjunction of linear conditions (variable
+/// indices follow `FactoredProblem::variables`). `exact` is false when the conjunction
+/// is only a necessary condition and `is_goal_state` remains the reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FactoredGoal {
+    pub conditions: Vec<NumericCondition>,
+    pub exact: bool,
+}
+
+impl FactoredGoal {
+    /// A goal no state satisfies.
+    pub fn unreachable() -> Self {
+        FactoredGoal {
+            conditions: vec![NumericCondition {
+                expression: LinearExpression::constant(1.0),
+                comparison: Comparison::Eq,
+            }],
+            exact: true,
+        }
+    }
+
+    pub fn is_satisfied(&self, values: &[Value]) -> bool {
+        let values = numeric_values(values);
+        self.conditions.iter().all(|c| c.is_satisfied(&values))
+    }
+}
+
+/// Problems whose states can be viewed as a fixed vector of named boolean and numeric
+/// variables. Used by generic tooling (pattern databases, visualisation, learning).
+pub trait FactoredProblem: Problem {
+    /// Variables of the instance; `state` supplies the objects (normally the initial state).
+    /// The bounds hold in every state reachable from `state`.
+    fn variables(&self, state: &Self::State) -> Vec<Variable>;
+
+    /// Values of the variables in `state`, in `variables` order: `Value::Bool`, `Value::Int`,
+    /// `Value::Int64` or `Value::OrderedFloat64`.
+    fn values(&self, state: &Self::State) -> Vec<Value>;
+
+    fn goal(&self, state: &Self::State) -> FactoredGoal;
+}
+
+pub fn value_as_f64(value: &Value) -> f64 {
+    match value {
+        Value::Int(x) => *x as f64,
+        Value::Int64(x) => *x as f64,
+        Value::OrderedFloat64(x) => x.into_inner(),
+        Value::Bool(b) => {
+            if *b {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        _ => panic!("Not a factored value: {:?}", value),
+    }
+}
+
+pub fn numeric_values(values: &[Value]) -> Vec<f64> {
+    values.iter().map(value_as_f64).collect()
+}
+
+pub fn float_value(x: f64) -> Value {
+    Value::OrderedFloat64(OrderedFloat(x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::block_grouping_problem::block_grouping_problem::BlockGroupingProblem;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::ext_plant_watering_problem::ext_plant_watering_problem::ExtPlantWateringProblem;
+    use crate::problems::farm_problem::farm_problem::FarmProblem;
+    use crate::problems::fo_farmland_problem::fo_farmland_problem::FoFarmLandProblem;
+    use crate::problems::fo_sailing_problem::fo_sailing_problem::FooSailingProblem;
+    use crate::problems::market_trader_problem::market_trader_problem::MarketTraderProblem;
+    use crate::problems::mprime_problem::mprime_problem::MPrimeProblem;
+    use crate::problems::path_ways_metric_problem::path_ways_metric_problem::PathWaysMetricProblem;
+    use crate::problems::red_car_problem::red_car_problem_with_enum::RedCarProblem;
+    use crate::problems::tpp_problem::tpp_problem::TppProblem;
+    use crate::problems::zenotravel_fuel_problem::zenotravel_fuel_problem::ZenoTravelFuelProblem;
+    use crate::problems::zenotravel_problem::zenotravel_problem::ZenoTravelProblem;
+    use crate::search::rng::Rng;
+
+    /// Walks randomly and checks that values fit their variables and that exact goals
+    /// agree with `is_goal_state`.
+    fn check_factored<P: FactoredProblem>(json_path: &str) {
+        let (initial_state, problem) = P::load_state_from_json(json_path);
+        let variables = problem.variables(&initial_state);
+        let goal = problem.goal(&initial_state);
+        let mut rng = Rng::new(0);
+        for _ in 0..10 {
+            let mut state = initial_state.clone();
+            for _ in 0..30 {
+                let values = problem.values(&state);
+                assert_eq!(values.len(), variables.len(), "{}", json_path);
+                for (variable, value) in variables.iter().zip(&values) {
+                    assert!(variable.admits(value), "{:?} = {:?}", variable, value);
+                }
+                if goal.exact {
+                    assert_eq!(goal.is_satisfied(&values), problem.is_goal_state(&state));
+                } else if problem.is_goal_state(&state) {
+                    assert!(goal.is_satisfied(&values), "{}", json_path);
+                }
+                let actions = problem.get_possible_actions(&state);
+                match rng.choose(&actions) {
+                    Some(action) => state = problem.apply_action(&state, action),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_factored_domains() {
+        check_factored::<CountersProblem>("src/inputs/counters_problem/problems_json/pfile1.json");
+        check_factored::<BlockGroupingProblem>(
+            "src/inputs/block_grouping_problem/problems_json/pfile1.json",
+        );
+        check_factored::<ExtPlantWateringProblem>(
+            "src/inputs/ext_plant_watering_problem/problems_json/pfile1.json",
+        );
+        check_factored::<FarmProblem>("src/inputs/farm_problem/input_1.json");
+        check_factored::<FoFarmLandProblem>(
+            "src/inputs/fo_farmland_problem/problems_json/pfile1.json",
+        );
+        check_factored::<FooSailingProblem>(
+            "src/inputs/fo_sailing_problem/problems_json/pfile1.json",
+        );
+        check_factored::<MarketTraderProblem>("src/inputs/market_trader_problem/input_1.json");
+        check_factored::<MPrimeProblem>("src/inputs/mprime_problem/problems_json/pfile1.json");
+        check_factored::<PathWaysMetricProblem>(
+            "src/inputs/path_ways_metric_problem/problems_json/pfile1.json",
+        );
+        check_factored::<RedCarProblem>(
+            "src/inputs/red_car_problem/red_car_enum/problems_json/pfile1.json",
+        );
+        check_factored::<TppProblem>("src/inputs/tpp_problem/problems_json/pfile1.json");
+        check_factored::<ZenoTravelProblem>(
+            "src/inputs/zenotravel_problem/problems_json/pfile1.json",
+        );
+        check_factored::<ZenoTravelFuelProblem>(
+            "src/inputs/zenotravel_fuel_problem/problems_json/pfile1.json",
+        );
+    }
+}
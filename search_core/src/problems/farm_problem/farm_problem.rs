@@ -1,5 +1,8 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 // use crate::problems::taxi_problem::taxi_problem::TaxiProblem;
@@ -65,6 +68,15 @@ impl WeightedSumGoal {
     }
 }
 
+/// Moves between adjacent farms; farms are referred to by their index in `FarmProblem::farms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FarmAction {
+    MoveFast { from: usize, to: usize },
+    MoveSlow { from: usize, to: usize },
+}
+
+impl ActionTrait for FarmAction {}
+
 pub struct FarmProblem {
     pub farms: Vec<String>,                         // List of farms
     pub x_values: BTreeMap<String, i32>,            // X values for each farm
@@ -172,33 +184,36 @@ impl FarmProblem {
             cost: 0, // Assuming initial cost is 0; adjust if needed
         }
     }
+
+    fn farm_index(&self, farm: &str) -> usize {
+        self.farms
+            .iter()
+            .position(|f| f == farm)
+            .unwrap_or_else(|| panic!("Unknown farm {}", farm))
+    }
 }
 
 impl Problem for FarmProblem {
     type State = State;
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    type Action = FarmAction;
+    type Cost = i32;
+    fn get_possible_actions(&self, state: &State) -> Vec<FarmAction> {
         let mut actions = Vec::new();
 
         // Retrieve adjacencies directly from the state's adjacencies field
         for (farm, neighbors) in &state.adjacencies {
             // Retrieve x_value directly from the state's x_values field
             if let Some(&x_value) = state.x_values.get(farm) {
+                let from = self.farm_index(farm);
                 // Check neighbors for possible actions
                 for neighbor in neighbors {
+                    let to = self.farm_index(neighbor);
                     if x_value >= 4 {
-                        let mut params = HashMap::new();
-                        params.insert("farm0".to_string(), Value::Text(farm.clone()));
-                        params.insert("farm1".to_string(), Value::Text(neighbor.clone()));
-
-                        actions.push(Action::new("move-fast".to_string(), 1, params));
+                        actions.push(FarmAction::MoveFast { from, to });
                     }
 
                     if x_value >= 1 {
-                        let mut params = HashMap::new();
-                        params.insert("farm0".to_string(), Value::Text(farm.clone()));
-                        params.insert("farm1".to_string(), Value::Text(neighbor.clone()));
-
-                        actions.push(Action::new("move-slow".to_string(), 1, params));
+                        actions.push(FarmAction::MoveSlow { from, to });
                     }
                 }
             }
@@ -207,44 +222,56 @@ impl Problem for FarmProblem {
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
+    fn apply_action(&self, state: &State, action: &FarmAction) -> State {
         let mut new_state = state.clone();
 
-        // Retrieve the parameters from the action
-        let farm0 = match action.parameters.get("farm0") {
-            Some(Value::Text(farm)) => farm.clone(),
-            _ => return new_state, // If farm0 is not present or not a string, return unchanged state
-        };
-
-        let farm1 = match action.parameters.get("farm1") {
-            Some(Value::Text(farm)) => farm.clone(),
-            _ => return new_state, // If farm1 is not present or not a string, return unchanged state
-        };
+        let (FarmAction::MoveFast { from, to } | FarmAction::MoveSlow { from, to }) = *action;
+        let farm0 = &self.farms[from];
+        let farm1 = &self.farms[to];
 
         // Retrieve x_values for both farms directly from the state's fields
-        let x_farm0 = match state.x_values.get(&farm0) {
+        let x_farm0 = match state.x_values.get(farm0) {
             Some(&x) => x,
             _ => return new_state, // If x value for farm0 is missing, return unchanged state
         };
 
-        let x_farm1 = match state.x_values.get(&farm1) {
+        let x_farm1 = match state.x_values.get(farm1) {
             Some(&x) => x,
             _ => return new_state, // If x value for farm1 is missing, return unchanged state
         };
 
         // Apply effects based on action type
-        if action.name == "move-fast" && x_farm0 >= 4 {
-            new_state.x_values.insert(farm0.clone(), x_farm0 - 4);
-            new_state.x_values.insert(farm1.clone(), x_farm1 + 2);
-            new_state.cost += 1; // Update the cost in the new state
-        } else if action.name == "move-slow" && x_farm0 >= 1 {
-            new_state.x_values.insert(farm0.clone(), x_farm0 - 1);
-            new_state.x_values.insert(farm1.clone(), x_farm1 + 1);
+        match action {
+            FarmAction::MoveFast { .. } if x_farm0 >= 4 => {
+                new_state.x_values.insert(farm0.clone(), x_farm0 - 4);
+                new_state.x_values.insert(farm1.clone(), x_farm1 + 2);
+                new_state.cost += 1; // Update the cost in the new state
+            }
+            FarmAction::MoveSlow { .. } if x_farm0 >= 1 => {
+                new_state.x_values.insert(farm0.clone(), x_farm0 - 1);
+                new_state.x_values.insert(farm1.clone(), x_farm1 + 1);
+            }
+            _ => {}
         }
 
         new_state
     }
 
+    fn action_cost(&self, _state: &State, _action: &FarmAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, _state: &State, action: &FarmAction) -> Action {
+        let (name, from, to) = match *action {
+            FarmAction::MoveFast { from, to } => ("move-fast", from, to),
+            FarmAction::MoveSlow { from, to } => ("move-slow", from, to),
+        };
+        let mut params = HashMap::new();
+        params.insert("farm0".to_string(), Value::Text(self.farms[from].clone()));
+        params.insert("farm1".to_string(), Value::Text(self.farms[to].clone()));
+        Action::new(name.to_string(), 1, params)
+    }
+
     fn is_goal_state(&self, state: &State) -> bool {
         // Check if all x_values meet or exceed their respective thresholds
         for (farm, threshold) in &self.goal_thresholds {
@@ -282,3 +309,68 @@ impl Problem for FarmProblem {
         (initial_state, problem)
     }
 }
+
+impl FactoredProblem for FarmProblem {
+    /// One variable per farm plus the accumulated cost. Moves never create units,
+    /// so every farm is bounded by the current total.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let total: i32 = state.x_values.values().sum();
+        let mut variables: Vec<Variable> = state
+            .x_values
+            .iter()
+            .map(|(farm, &x)| {
+                Variable::integer(farm.clone(), Some(x.min(0) as i64), Some(total as i64))
+            })
+            .collect();
+        variables.push(Variable::integer(
+            "cost".to_string(),
+            Some(state.cost as i64),
+            None,
+        ));
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values: Vec<Value> = state.x_values.values().map(|&x| Value::Int(x)).collect();
+        values.push(Value::Int(state.cost));
+        values
+    }
+
+    /// Per-farm thresholds and the (scaled) weighted sum.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let index = |farm: &String| {
+            state
+                .x_values
+                .keys()
+                .position(|f| f == farm)
+                .unwrap_or_else(|| panic!("There was a problem: missing x_value for farm {}", farm))
+        };
+        let mut conditions: Vec<NumericCondition> = self
+            .goal_thresholds
+            .iter()
+            .map(|(farm, &threshold)| {
+                NumericCondition::new(
+                    LinearExpression::variable(index(farm)),
+                    Comparison::Ge,
+                    &LinearExpression::constant(threshold as f64),
+                )
+            })
+            .collect();
+        let weighted_sum = self
+            .weighted_sum_goal
+            .weights
+            .iter()
+            .fold(LinearExpression::default(), |e, (farm, &weight)| {
+                e.term(weight as f64, index(farm))
+            });
+        conditions.push(NumericCondition::new(
+            weighted_sum,
+            Comparison::Ge,
+            &LinearExpression::constant(self.weighted_sum_goal.threshold as f64),
+        ));
+        FactoredGoal {
+            conditions,
+            exact: true,
+        }
+    }
+}
@@ -1,5 +1,11 @@
+use crate::heuristics::numeric_task::{
+    Comparison, LinearExpression, NumericAction, NumericCondition, NumericEffect, NumericProblem,
+    NumericTask,
+};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -63,6 +69,15 @@ impl Goal {
     }
 }
 
+/// Moves between adjacent farms; farms are referred to by their position in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FarmLandAction {
+    MoveSlow { from: usize, to: usize },
+    MoveFast { from: usize, to: usize },
+}
+
+impl ActionTrait for FarmLandAction {}
+
 /// Represents the problem definition, including the adjacency map and goal.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FarmLandProblem {
@@ -71,119 +86,58 @@ pub struct FarmLandProblem {
 }
 
 impl FarmLandProblem {
-    /// Generates all legal `move_fast` actions from the given farm.
-    /// Requires `farm.value >= 4` to be used (enforced externally).
-    pub fn get_move_fast_action(&self, farm: &Farm) -> Vec<Action> {
-        let mut actions = Vec::new();
+    /// Positions in the state of the farms adjacent to the farm at `from`.
+    fn neighbours(&self, state: &State, from: usize) -> Vec<usize> {
+        let farm = &state.farms[from];
+        let mut neighbours = Vec::new();
         if let Some(farms) = self.adj.get(&farm.name) {
             for farm_adj in farms {
                 if farm.name != *farm_adj {
-                    let mut parameters = HashMap::new();
-                    let action_name = format!("move_fast_{}_{}", farm.name, farm_adj);
-                    parameters.insert("from".to_string(), Value::Text(farm.name.clone()));
-                    parameters.insert("to".to_string(), Value::Text(farm_adj.clone()));
-                    actions.push(Action::new(action_name, 1, parameters));
+                    let to = state
+                        .farms
+                        .iter()
+                        .position(|v| v.name == *farm_adj)
+                        .unwrap_or_else(|| panic!("Farm with name {} not found", farm_adj));
+                    neighbours.push(to);
                 }
             }
         }
-        actions
-    }
-
-    /// Generates all legal `move_slow` actions from the given farm.
-    /// Requires `farm.value >= 1` to be used (enforced externally).
-    pub fn get_move_slow_action(&self, farm: &Farm) -> Vec<Action> {
-        let mut actions = Vec::new();
-        if let Some(farms) = self.adj.get(&farm.name) {
-            for farm_adj in farms {
-                if farm.name != *farm_adj {
-                    let mut parameters = HashMap::new();
-                    let action_name = format!("move_slow_{}_{}", farm.name, farm_adj);
-                    parameters.insert("from".to_string(), Value::Text(farm.name.clone()));
-                    parameters.insert("to".to_string(), Value::Text(farm_adj.clone()));
-                    actions.push(Action::new(action_name, 1, parameters));
-                }
-            }
-        }
-        actions
+        neighbours
     }
 
     /// Generates all applicable actions for the given state based on farm values.
-    pub fn get_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_actions(&self, state: &State) -> Vec<FarmLandAction> {
         let mut actions = Vec::new();
-        for farm in &state.farms {
+        for (from, farm) in state.farms.iter().enumerate() {
+            let neighbours = self.neighbours(state, from);
             if farm.value >= 1 {
-                actions.extend(self.get_move_slow_action(farm));
+                for &to in &neighbours {
+                    actions.push(FarmLandAction::MoveSlow { from, to });
+                }
             }
             if farm.value >= 4 {
-                actions.extend(self.get_move_fast_action(farm));
+                for &to in &neighbours {
+                    actions.push(FarmLandAction::MoveFast { from, to });
+                }
             }
         }
         actions
     }
 
     /// Applies a `move_slow` action: transfers 1 unit from `from` to `to`.
-    pub fn apply_move_slow_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_slow_action(state: &State, from: usize, to: usize) -> State {
         let mut new_state = state.clone();
-
-        // Extract farm names from action parameters.
-        let farm_from_name = match action.parameters.get("from") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for farm."),
-        };
-        let farm_to_name = match action.parameters.get("to") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for farm."),
-        };
-
-        // Find the indices of the farms.
-        let from_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_from_name)
-            .expect(&format!("Farm with name {} not found", farm_from_name));
-
-        let to_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_to_name)
-            .expect(&format!("Farm with name {} not found", farm_to_name));
-
-        // Apply value transfer.
-        new_state.farms[from_index].value -= 1;
-        new_state.farms[to_index].value += 1;
-
+        new_state.farms[from].value -= 1;
+        new_state.farms[to].value += 1;
         new_state
     }
 
     /// Applies a `move_fast` action: transfers 4 units from `from` and adds 2 to `to`.
-    pub fn apply_move_fast_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_fast_action(state: &State, from: usize, to: usize) -> State {
         let mut new_state = state.clone();
-
-        let farm_from_name = match action.parameters.get("from") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for farm."),
-        };
-        let farm_to_name = match action.parameters.get("to") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for farm."),
-        };
-
-        let from_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_from_name)
-            .expect(&format!("Farm with name {} not found", farm_from_name));
-
-        let to_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_to_name)
-            .expect(&format!("Farm with name {} not found", farm_to_name));
-
         // Apply value transfer with fast penalty/reward ratio.
-        new_state.farms[from_index].value -= 4;
-        new_state.farms[to_index].value += 2;
-
+        new_state.farms[from].value -= 4;
+        new_state.farms[to].value += 2;
         new_state
     }
 }
@@ -191,22 +145,38 @@ impl FarmLandProblem {
 impl Problem for FarmLandProblem {
     type State = State;
 
+    type Action = FarmLandAction;
+    type Cost = i32;
+
     /// Return all valid actions for a state.
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<FarmLandAction> {
         self.get_actions(state)
     }
 
     /// Apply the given action to the state.
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("move_slow_") {
-            Self::apply_move_slow_action(state, action)
-        } else if action.name.starts_with("move_fast_") {
-            Self::apply_move_fast_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    fn apply_action(&self, state: &State, action: &FarmLandAction) -> State {
+        match *action {
+            FarmLandAction::MoveSlow { from, to } => Self::apply_move_slow_action(state, from, to),
+            FarmLandAction::MoveFast { from, to } => Self::apply_move_fast_action(state, from, to),
         }
     }
 
+    fn action_cost(&self, _state: &State, _action: &FarmLandAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &FarmLandAction) -> Action {
+        let (kind, from, to) = match *action {
+            FarmLandAction::MoveSlow { from, to } => ("slow", from, to),
+            FarmLandAction::MoveFast { from, to } => ("fast", from, to),
+        };
+        let (from, to) = (&state.farms[from].name, &state.farms[to].name);
+        let mut parameters = HashMap::new();
+        parameters.insert("from".to_string(), Value::Text(from.clone()));
+        parameters.insert("to".to_string(), Value::Text(to.clone()));
+        Action::new(format!("move_{}_{}_{}", kind, from, to), 1, parameters)
+    }
+
     /// Check whether the state satisfies the goal.
     fn is_goal_state(&self, state: &State) -> bool {
         self.goal.is_goal_state(state)
@@ -238,3 +208,95 @@ impl Problem for FarmLandProblem {
         (state, problem)
     }
 }
+
+impl NumericProblem for FarmLandProblem {
+    /// One variable per farm; slow and fast moves along every adjacency.
+    fn numeric_task(&self, state: &State) -> NumericTask<Self::Action> {
+        let mut task = NumericTask::default();
+        for farm in &state.farms {
+            task.add_variable(farm.name.clone());
+        }
+        let index = |name: &str| {
+            state
+                .farms
+                .iter()
+                .position(|f| f.name == name)
+                .unwrap_or_else(|| panic!("Farm {} not found in state", name))
+        };
+
+        for farm in &state.farms {
+            let from = index(&farm.name);
+            for farm_adj in self.adj.get(&farm.name).into_iter().flatten() {
+                if farm.name == *farm_adj {
+                    continue;
+                }
+                let to = index(farm_adj);
+                for (operator, needed, taken, given) in [
+                    (FarmLandAction::MoveSlow { from, to }, 1.0, 1.0, 1.0),
+                    (FarmLandAction::MoveFast { from, to }, 4.0, 4.0, 2.0),
+                ] {
+                    task.actions.push(NumericAction {
+                        operator,
+                        cost: 1.0,
+                        preconditions: vec![NumericCondition::new(
+                            LinearExpression::variable(from),
+                            Comparison::Ge,
+                            &LinearExpression::constant(needed),
+                        )],
+                        effects: vec![
+                            NumericEffect::Increase(from, LinearExpression::constant(-taken)),
+                            NumericEffect::Increase(to, LinearExpression::constant(given)),
+                        ],
+                    });
+                }
+            }
+        }
+
+        let sum = self
+            .goal
+            .farms
+            .iter()
+            .fold(LinearExpression::default(), |e, c| {
+                e.term(c.farm_constant.into_inner(), index(&c.farm_name))
+            });
+        task.goals.push(NumericCondition::new(
+            sum,
+            Comparison::from_operator(&self.goal.operator),
+            &LinearExpression::constant(self.goal.value as f64),
+        ));
+        task
+    }
+
+    fn numeric_values(&self, state: &State) -> Vec<f64> {
+        state.farms.iter().map(|f| f.value as f64).collect()
+    }
+}
+
+impl FactoredProblem for FarmLandProblem {
+    /// One variable per farm; moves never create units, so the current total bounds each farm.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let total: i32 = state.farms.iter().map(|f| f.value).sum();
+        state
+            .farms
+            .iter()
+            .map(|f| {
+                Variable::integer(
+                    f.name.clone(),
+                    Some(f.value.min(0) as i64),
+                    Some(total as i64),
+                )
+            })
+            .collect()
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        state.farms.iter().map(|f| Value::Int(f.value)).collect()
+    }
+
+    fn goal(&self, state: &State) -> FactoredGoal {
+        FactoredGoal {
+            conditions: self.numeric_task(state).goals,
+            exact: true,
+        }
+    }
+}
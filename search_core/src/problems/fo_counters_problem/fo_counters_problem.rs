@@ -1,5 +1,11 @@
+use crate::heuristics::numeric_task::{
+    Comparison, LinearExpression, NumericAction, NumericCondition, NumericEffect, NumericProblem,
+    NumericTask,
+};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::fs;
@@ -96,6 +102,17 @@ impl Counter {
     }
 }
 
+/// Actions of the FO-Counters domain; counters are referred to by their index in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FoCountersAction {
+    Increase(usize),
+    Decrease(usize),
+    IncreaseRate(usize),
+    DecreaseRate(usize),
+}
+
+impl ActionTrait for FoCountersAction {}
+
 /// FO-Counters problem, supporting both counter manipulation and rate control.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FoCountersProblem {
@@ -151,20 +168,20 @@ impl FoCountersProblem {
 
     /// Returns all applicable actions for the current state.
     /// Guards are used to prevent invalid or unsafe operations.
-    pub fn get_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_actions(&self, state: &State) -> Vec<FoCountersAction> {
         let mut actions = Vec::new();
-        for counter in &state.counters {
+        for (i, counter) in state.counters.iter().enumerate() {
             if counter.value + counter.rate_value <= self.max_value {
-                actions.push(Self::get_increase_action(counter));
+                actions.push(FoCountersAction::Increase(i));
             }
             if counter.value - counter.rate_value >= 0 {
-                actions.push(Self::get_decrease_action(counter));
+                actions.push(FoCountersAction::Decrease(i));
             }
             if counter.rate_value + 1 <= 10 {
-                actions.push(Self::get_increase_rate_action(counter));
+                actions.push(FoCountersAction::IncreaseRate(i));
             }
             if counter.rate_value >= 1 {
-                actions.push(Self::get_decrease_rate_action(counter));
+                actions.push(FoCountersAction::DecreaseRate(i));
             }
         }
         actions
@@ -173,86 +190,32 @@ impl FoCountersProblem {
     // === ACTION APPLICATION ===
 
     /// Applies an increase action: value += rate
-    pub fn apply_increase_action(state: &State, action: &Action) -> State {
+    pub fn apply_increase_action(state: &State, counter: usize) -> State {
         let mut new_state = state.clone();
-        let counter_name = match action.parameters.get("counter") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Missing or invalid 'counter' parameter"),
-        };
-
-        if let Some(counter) = new_state
-            .counters
-            .iter_mut()
-            .find(|v| v.name == *counter_name)
-        {
-            counter.value += counter.rate_value;
-        } else {
-            panic!("Counter with name {} not found", counter_name);
-        }
-
+        let counter = &mut new_state.counters[counter];
+        counter.value += counter.rate_value;
         new_state
     }
 
     /// Applies a decrease action: value -= rate
-    pub fn apply_decrease_action(state: &State, action: &Action) -> State {
+    pub fn apply_decrease_action(state: &State, counter: usize) -> State {
         let mut new_state = state.clone();
-        let counter_name = match action.parameters.get("counter") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Missing or invalid 'counter' parameter"),
-        };
-
-        if let Some(counter) = new_state
-            .counters
-            .iter_mut()
-            .find(|v| v.name == *counter_name)
-        {
-            counter.value -= counter.rate_value;
-        } else {
-            panic!("Counter with name {} not found", counter_name);
-        }
-
+        let counter = &mut new_state.counters[counter];
+        counter.value -= counter.rate_value;
         new_state
     }
 
     /// Applies a decrease rate action: rate -= 1
-    pub fn apply_decrease_rate_action(state: &State, action: &Action) -> State {
+    pub fn apply_decrease_rate_action(state: &State, counter: usize) -> State {
         let mut new_state = state.clone();
-        let counter_name = match action.parameters.get("counter") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Missing or invalid 'counter' parameter"),
-        };
-
-        if let Some(counter) = new_state
-            .counters
-            .iter_mut()
-            .find(|v| v.name == *counter_name)
-        {
-            counter.rate_value -= 1;
-        } else {
-            panic!("Counter with name {} not found", counter_name);
-        }
-
+        new_state.counters[counter].rate_value -= 1;
         new_state
     }
 
     /// Applies an increase rate action: rate += 1
-    pub fn apply_increace_rate_action(state: &State, action: &Action) -> State {
+    pub fn apply_increace_rate_action(state: &State, counter: usize) -> State {
         let mut new_state = state.clone();
-        let counter_name = match action.parameters.get("counter") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Missing or invalid 'counter' parameter"),
-        };
-
-        if let Some(counter) = new_state
-            .counters
-            .iter_mut()
-            .find(|v| v.name == *counter_name)
-        {
-            counter.rate_value += 1;
-        } else {
-            panic!("Counter with name {} not found", counter_name);
-        }
-
+        new_state.counters[counter].rate_value += 1;
         new_state
     }
 }
@@ -261,23 +224,46 @@ impl FoCountersProblem {
 impl Problem for FoCountersProblem {
     type State = State;
 
+    type Action = FoCountersAction;
+    type Cost = i32;
+
     /// Returns all valid actions for the state.
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<FoCountersAction> {
         self.get_actions(state)
     }
 
-    /// Dispatches the action to the correct application method based on prefix.
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("increase_") {
-            Self::apply_increase_action(state, action)
-        } else if action.name.starts_with("decrease_") {
-            Self::apply_decrease_action(state, action)
-        } else if action.name.starts_with("rateI_") {
-            Self::apply_increace_rate_action(state, action)
-        } else if action.name.starts_with("rateD_") {
-            Self::apply_decrease_rate_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    /// Dispatches the action to the correct application method.
+    fn apply_action(&self, state: &State, action: &FoCountersAction) -> State {
+        match *action {
+            FoCountersAction::Increase(counter) => Self::apply_increase_action(state, counter),
+            FoCountersAction::Decrease(counter) => Self::apply_decrease_action(state, counter),
+            FoCountersAction::IncreaseRate(counter) => {
+                Self::apply_increace_rate_action(state, counter)
+            }
+            FoCountersAction::DecreaseRate(counter) => {
+                Self::apply_decrease_rate_action(state, counter)
+            }
+        }
+    }
+
+    fn action_cost(&self, _state: &State, _action: &FoCountersAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &FoCountersAction) -> Action {
+        match *action {
+            FoCountersAction::Increase(counter) => {
+                Self::get_increase_action(&state.counters[counter])
+            }
+            FoCountersAction::Decrease(counter) => {
+                Self::get_decrease_action(&state.counters[counter])
+            }
+            FoCountersAction::IncreaseRate(counter) => {
+                Self::get_increase_rate_action(&state.counters[counter])
+            }
+            FoCountersAction::DecreaseRate(counter) => {
+                Self::get_decrease_rate_action(&state.counters[counter])
+            }
         }
     }
 
@@ -312,3 +298,141 @@ impl Problem for FoCountersProblem {
         (state, problem)
     }
 }
+
+impl NumericProblem for FoCountersProblem {
+    /// Two variables per counter: its value (index 2i) and its rate (index 2i + 1).
+    fn numeric_task(&self, state: &State) -> NumericTask<Self::Action> {
+        let mut task = NumericTask::default();
+        for counter in &state.counters {
+            task.add_variable(counter.name.clone());
+            task.add_variable(format!("rate_{}", counter.name));
+        }
+        let index = |name: &str| {
+            2 * state
+                .counters
+                .iter()
+                .position(|c| c.name == name)
+                .unwrap_or_else(|| panic!("Counter {} not found in state", name))
+        };
+        let to_expression = |expr: &LinearExpr| {
+            expr.terms.iter().fold(
+                LinearExpression::constant(expr.constant as f64),
+                |e, (coeff, name)| e.term(*coeff as f64, index(name)),
+            )
+        };
+        let max_value = LinearExpression::constant(self.max_value as f64);
+
+        for i in 0..state.counters.len() {
+            let (value, rate) = (2 * i, 2 * i + 1);
+            task.actions.push(NumericAction {
+                operator: FoCountersAction::Increase(i),
+                cost: 1.0,
+                preconditions: vec![NumericCondition::new(
+                    LinearExpression::variable(value).term(1.0, rate),
+                    Comparison::Le,
+                    &max_value,
+                )],
+                effects: vec![NumericEffect::Increase(
+                    value,
+                    LinearExpression::variable(rate),
+                )],
+            });
+            task.actions.push(NumericAction {
+                operator: FoCountersAction::Decrease(i),
+                cost: 1.0,
+                preconditions: vec![NumericCondition::new(
+                    LinearExpression::variable(value).term(-1.0, rate),
+                    Comparison::Ge,
+                    &LinearExpression::constant(0.0),
+                )],
+                effects: vec![NumericEffect::Increase(
+                    value,
+                    LinearExpression::default().term(-1.0, rate),
+                )],
+            });
+            task.actions.push(NumericAction {
+                operator: FoCountersAction::IncreaseRate(i),
+                cost: 1.0,
+                preconditions: vec![NumericCondition::new(
+                    LinearExpression::variable(rate).plus(1.0),
+                    Comparison::Le,
+                    &LinearExpression::constant(10.0),
+                )],
+                effects: vec![NumericEffect::Increase(
+                    rate,
+                    LinearExpression::constant(1.0),
+                )],
+            });
+            task.actions.push(NumericAction {
+                operator: FoCountersAction::DecreaseRate(i),
+                cost: 1.0,
+                preconditions: vec![NumericCondition::new(
+                    LinearExpression::variable(rate),
+                    Comparison::Ge,
+                    &LinearExpression::constant(1.0),
+                )],
+                effects: vec![NumericEffect::Increase(
+                    rate,
+                    LinearExpression::constant(-1.0),
+                )],
+            });
+        }
+
+        task.goals = self
+            .goal
+            .conditions
+            .iter()
+            .map(|c| {
+                NumericCondition::new(
+                    to_expression(&c.left),
+                    Comparison::from_operator(&c.operator),
+                    &to_expression(&c.right),
+                )
+            })
+            .collect();
+        task
+    }
+
+    fn numeric_values(&self, state: &State) -> Vec<f64> {
+        state
+            .counters
+            .iter()
+            .flat_map(|c| [c.value as f64, c.rate_value as f64])
+            .collect()
+    }
+}
+
+impl FactoredProblem for FoCountersProblem {
+    /// Value and rate of every counter, in the same order as the numeric task.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        for c in &state.counters {
+            variables.push(Variable::integer(
+                c.name.clone(),
+                Some(c.value.min(0) as i64),
+                Some(c.value.max(self.max_value) as i64),
+            ));
+            variables.push(Variable::integer(
+                format!("rate_{}", c.name),
+                Some(c.rate_value.min(0) as i64),
+                Some(c.rate_value.max(10) as i64),
+            ));
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        state
+            .counters
+            .iter()
+            .flat_map(|c| [Value::Int(c.value), Value::Int(c.rate_value)])
+            .collect()
+    }
+
+    fn goal(&self, state: &State) -> FactoredGoal {
+        FactoredGoal {
+            conditions: self.numeric_task(state).goals,
+            exact: true,
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -72,6 +75,17 @@ impl Goal {
     }
 }
 
+/// Actions of the FO-Farmland domain; farms are referred to by their position in the state.
+/// Car moves use every car of the state they are applied in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FoFarmLandAction {
+    MoveSlow { from: usize, to: usize },
+    MoveByCar { from: usize, to: usize },
+    HireCar,
+}
+
+impl ActionTrait for FoFarmLandAction {}
+
 /// Describes the FO-Farmland problem.
 /// Includes an adjacency map (legal moves) and a goal condition.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -82,139 +96,73 @@ pub struct FoFarmLandProblem {
 }
 
 impl FoFarmLandProblem {
-    /// Generates all legal `move_slow` actions from a given farm.
-    /// These are unit transfers with cost 1.
-    pub fn get_move_slow_action(&self, farm: &Farm) -> Vec<Action> {
-        let mut actions = Vec::new();
+    /// Positions in the state of the farms directly reachable from the farm at `from`.
+    fn neighbours(&self, state: &State, from: usize) -> Vec<usize> {
+        let farm = &state.farms[from];
+        let mut neighbours = Vec::new();
         if let Some(farms) = self.adj.get(&farm.name) {
             for farm_adj in farms {
                 if farm.name != *farm_adj {
-                    let mut parameters = std::collections::HashMap::new();
-                    let action_name = format!("move_slow_{}_{}", farm.name, farm_adj);
-                    parameters.insert("from".to_string(), Value::Text(farm.name.clone()));
-                    parameters.insert("to".to_string(), Value::Text(farm_adj.clone()));
-                    actions.push(Action::new(action_name, 1, parameters));
+                    let to = state
+                        .farms
+                        .iter()
+                        .position(|v| v.name == *farm_adj)
+                        .unwrap_or_else(|| panic!("Farm {} not found", farm_adj));
+                    neighbours.push(to);
                 }
             }
         }
-        actions
-    }
-
-    /// Returns a simple `hire_car` action (adds a car, cost 1).
-    pub fn get_hire_car_action() -> Action {
-        let parameters = std::collections::HashMap::new();
-        let action_name = format!("hire_car");
-        Action::new(action_name, 1, parameters)
+        neighbours
     }
 
-    /// Generates all `move_by_car` actions from a farm using all available cars.
-    /// These have dynamic cost depending on the number of cars used.
-    pub fn get_move_by_car_action(&self, farm: &Farm, number_of_cars: &i32) -> Vec<Action> {
-        let mut actions = Vec::new();
-        if let Some(farms) = self.adj.get(&farm.name) {
-            for farm_adj in farms {
-                if farm.name != *farm_adj {
-                    let mut parameters = std::collections::HashMap::new();
-                    let action_name = format!("move_by_car_{}_{}", farm.name, farm_adj);
-                    parameters.insert("from".to_string(), Value::Text(farm.name.clone()));
-                    parameters.insert("to".to_string(), Value::Text(farm_adj.clone()));
-                    parameters.insert("cars".to_string(), Value::Int(*number_of_cars));
-
-                    // Cost grows with number of cars.
-                    let cost = (*number_of_cars as f64 * 0.4).round() as i32;
-                    actions.push(Action::new(action_name, cost, parameters));
-                }
-            }
-        }
-        actions
+    /// Cost of a `move_by_car` action; it grows with the number of cars used.
+    pub fn move_by_car_cost(number_of_cars: i32) -> i32 {
+        (number_of_cars as f64 * 0.4).round() as i32
     }
 
     /// Collects all legal actions from the current state.
-    pub fn get_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_actions(&self, state: &State) -> Vec<FoFarmLandAction> {
         let mut actions = Vec::new();
-        actions.push(Self::get_hire_car_action());
-        for farm in &state.farms {
+        actions.push(FoFarmLandAction::HireCar);
+        for (from, farm) in state.farms.iter().enumerate() {
+            let neighbours = self.neighbours(state, from);
             if farm.value >= 1 {
-                actions.extend(self.get_move_slow_action(farm));
+                for &to in &neighbours {
+                    actions.push(FoFarmLandAction::MoveSlow { from, to });
+                }
             }
             if farm.value >= 4 * state.number_of_cars {
-                actions.extend(self.get_move_by_car_action(farm, &state.number_of_cars));
+                for &to in &neighbours {
+                    actions.push(FoFarmLandAction::MoveByCar { from, to });
+                }
             }
         }
         actions
     }
 
     /// Applies a slow move: moves 1 unit from `from` to `to` farm.
-    pub fn apply_move_slow_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_slow_action(state: &State, from: usize, to: usize) -> State {
         let mut new_state = state.clone();
-
-        let farm_from_name = match action.parameters.get("from") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action missing 'from' farm name."),
-        };
-
-        let farm_to_name = match action.parameters.get("to") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action missing 'to' farm name."),
-        };
-
-        let from_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_from_name)
-            .expect(&format!("Farm {} not found", farm_from_name));
-        let to_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_to_name)
-            .expect(&format!("Farm {} not found", farm_to_name));
-
-        new_state.farms[from_index].value -= 1;
-        new_state.farms[to_index].value += 1;
-
+        new_state.farms[from].value -= 1;
+        new_state.farms[to].value += 1;
         new_state
     }
 
     /// Applies a car-based move with cost and car count.
-    pub fn apply_move_by_car_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_by_car_action(state: &State, from: usize, to: usize) -> State {
         let mut new_state = state.clone();
-
-        let farm_from_name = match action.parameters.get("from") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action missing 'from' farm name."),
-        };
-
-        let farm_to_name = match action.parameters.get("to") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action missing 'to' farm name."),
-        };
-
-        let number_of_cars = match action.parameters.get("cars") {
-            Some(Value::Int(number)) => *number,
-            _ => panic!("Missing car count in action parameters."),
-        };
-
-        let from_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_from_name)
-            .expect(&format!("Farm {} not found", farm_from_name));
-        let to_index = new_state
-            .farms
-            .iter()
-            .position(|v| v.name == *farm_to_name)
-            .expect(&format!("Farm {} not found", farm_to_name));
+        let number_of_cars = state.number_of_cars;
 
         // Move 4 * cars units, update cost
-        new_state.farms[from_index].value -= 4 * number_of_cars;
-        new_state.farms[to_index].value += 4 * number_of_cars;
-        new_state.cost += action.cost;
+        new_state.farms[from].value -= 4 * number_of_cars;
+        new_state.farms[to].value += 4 * number_of_cars;
+        new_state.cost += Self::move_by_car_cost(number_of_cars);
 
         new_state
     }
 
     /// Increments the number of cars in the state.
-    pub fn apply_hire_car_action(state: &State, _action: &Action) -> State {
+    pub fn apply_hire_car_action(state: &State) -> State {
         let mut new_state = state.clone();
         new_state.number_of_cars += 1;
         new_state
@@ -225,24 +173,56 @@ impl FoFarmLandProblem {
 impl Problem for FoFarmLandProblem {
     type State = State;
 
+    type Action = FoFarmLandAction;
+    type Cost = i32;
+
     /// Delegates to `get_actions()`.
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<FoFarmLandAction> {
         self.get_actions(state)
     }
 
-    /// Applies the action by dispatching to the right method based on name prefix.
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("move_slow_") {
-            Self::apply_move_slow_action(state, action)
-        } else if action.name.starts_with("move_by_car_") {
-            Self::apply_move_by_car_action(state, action)
-        } else if action.name.starts_with("hire_car") {
-            Self::apply_hire_car_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    /// Applies the action by dispatching to the right method.
+    fn apply_action(&self, state: &State, action: &FoFarmLandAction) -> State {
+        match *action {
+            FoFarmLandAction::MoveSlow { from, to } => {
+                Self::apply_move_slow_action(state, from, to)
+            }
+            FoFarmLandAction::MoveByCar { from, to } => {
+                Self::apply_move_by_car_action(state, from, to)
+            }
+            FoFarmLandAction::HireCar => Self::apply_hire_car_action(state),
+        }
+    }
+
+    fn action_cost(&self, state: &State, action: &FoFarmLandAction) -> i32 {
+        match action {
+            FoFarmLandAction::MoveByCar { .. } => Self::move_by_car_cost(state.number_of_cars),
+            _ => 1,
         }
     }
 
+    fn describe_action(&self, state: &State, action: &FoFarmLandAction) -> Action {
+        let mut parameters = HashMap::new();
+        let (name, from, to) = match *action {
+            FoFarmLandAction::HireCar => {
+                return Action::new("hire_car".to_string(), 1, parameters);
+            }
+            FoFarmLandAction::MoveSlow { from, to } => ("move_slow", from, to),
+            FoFarmLandAction::MoveByCar { from, to } => {
+                parameters.insert("cars".to_string(), Value::Int(state.number_of_cars));
+                ("move_by_car", from, to)
+            }
+        };
+        let (from, to) = (&state.farms[from].name, &state.farms[to].name);
+        parameters.insert("from".to_string(), Value::Text(from.clone()));
+        parameters.insert("to".to_string(), Value::Text(to.clone()));
+        Action::new(
+            format!("{}_{}_{}", name, from, to),
+            self.action_cost(state, action),
+            parameters,
+        )
+    }
+
     /// Delegates to the goal logic.
     fn is_goal_state(&self, state: &State) -> bool {
         self.goal.is_goal_state(state)
@@ -277,3 +257,64 @@ impl Problem for FoFarmLandProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for FoFarmLandProblem {
+    /// One variable per farm, then the number of cars and the accumulated cost.
+    /// Moves conserve units, so the current total bounds each farm.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let total: i32 = state.farms.iter().map(|f| f.value).sum();
+        let mut variables: Vec<Variable> = state
+            .farms
+            .iter()
+            .map(|f| {
+                Variable::integer(
+                    f.name.clone(),
+                    Some(f.value.min(0) as i64),
+                    Some(total as i64),
+                )
+            })
+            .collect();
+        variables.push(Variable::integer(
+            "number_of_cars".to_string(),
+            Some(state.number_of_cars as i64),
+            None,
+        ));
+        variables.push(Variable::integer(
+            "cost".to_string(),
+            Some(state.cost as i64),
+            None,
+        ));
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values: Vec<Value> = state.farms.iter().map(|f| Value::Int(f.value)).collect();
+        values.push(Value::Int(state.number_of_cars));
+        values.push(Value::Int(state.cost));
+        values
+    }
+
+    /// The weighted sum of the farms minus the cost, compared with the goal value.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let cost = state.farms.len() + 1;
+        let sum = self.goal.farms.iter().fold(
+            LinearExpression::constant(0.0).term(-1.0, cost),
+            |e, c| {
+                let index = state
+                    .farms
+                    .iter()
+                    .position(|f| f.name == c.farm_name)
+                    .unwrap_or_else(|| panic!("Farm {} not found in state", c.farm_name));
+                e.term(c.farm_constant.into_inner(), index)
+            },
+        );
+        FactoredGoal {
+            conditions: vec![NumericCondition::new(
+                sum,
+                Comparison::from_operator(&self.goal.operator),
+                &LinearExpression::constant(self.goal.value as f64),
+            )],
+            exact: true,
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::heuristics::numeric_task::NumericCondition;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -53,6 +56,24 @@ impl State {}
 
 impl StateTrait for State {}
 
+/// Actions of the FO-Sailing domain; boats and persons are referred to by their position
+/// in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FooSailingAction {
+    NorthEast(usize),
+    NorthWest(usize),
+    Est(usize),
+    West(usize),
+    SouthWest(usize),
+    SouthEast(usize),
+    South(usize),
+    Accelerate(usize),
+    Decelerate(usize),
+    SavePerson { boat: usize, person: usize },
+}
+
+impl ActionTrait for FooSailingAction {}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FooSailingProblem {}
 
@@ -142,24 +163,24 @@ impl FooSailingProblem {
         Action::new(action_name, 1, parameters)
     }
 
-    pub fn get_actions(state: &State) -> Vec<Action> {
+    pub fn get_actions(state: &State) -> Vec<FooSailingAction> {
         let mut actions = Vec::new();
-        for boat in &state.boats {
-            actions.push(Self::get_north_east_action(boat));
-            actions.push(Self::get_north_west_action(boat));
-            actions.push(Self::get_est_action(boat));
-            actions.push(Self::get_west_action(boat));
-            actions.push(Self::get_south_west_action(boat));
-            actions.push(Self::get_south_east_action(boat));
-            actions.push(Self::get_south_action(boat));
+        for (b, boat) in state.boats.iter().enumerate() {
+            actions.push(FooSailingAction::NorthEast(b));
+            actions.push(FooSailingAction::NorthWest(b));
+            actions.push(FooSailingAction::Est(b));
+            actions.push(FooSailingAction::West(b));
+            actions.push(FooSailingAction::SouthWest(b));
+            actions.push(FooSailingAction::SouthEast(b));
+            actions.push(FooSailingAction::South(b));
             if (boat.v + ordered_float::OrderedFloat(1.0)) <= ordered_float::OrderedFloat(3.0) {
-                actions.push(Self::get_accelerate_action(boat));
+                actions.push(FooSailingAction::Accelerate(b));
             }
             if (boat.v - ordered_float::OrderedFloat(1.0)) >= ordered_float::OrderedFloat(1.0) {
-                actions.push(Self::get_decelerate_action(boat));
+                actions.push(FooSailingAction::Decelerate(b));
             }
 
-            for person in &state.persons {
+            for (p, person) in state.persons.iter().enumerate() {
                 if !person.saved {
                     if boat.x + boat.y >= person.d
                         && boat.y - boat.x >= person.d
@@ -167,7 +188,7 @@ impl FooSailingProblem {
                         && boat.y - boat.x <= person.d + ordered_float::OrderedFloat(25.0)
                         && boat.v <= ordered_float::OrderedFloat(1.0)
                     {
-                        actions.push(Self::get_save_person_action(boat, person));
+                        actions.push(FooSailingAction::SavePerson { boat: b, person: p });
                     }
                 }
             }
@@ -175,70 +196,46 @@ impl FooSailingProblem {
 
         actions
     }
-    pub fn apply_north_east_action(state: &State, action: &Action) -> State {
+    pub fn apply_north_east_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
         let v_val = new_state.boats[boat_index].v;
         new_state.boats[boat_index].x += v_val * ordered_float::OrderedFloat(1.5);
         new_state.boats[boat_index].y += v_val * ordered_float::OrderedFloat(1.5);
 
         new_state
     }
-    pub fn apply_north_west_action(state: &State, action: &Action) -> State {
+    pub fn apply_north_west_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
         let v_val = new_state.boats[boat_index].v;
         new_state.boats[boat_index].x += v_val * ordered_float::OrderedFloat(1.5);
         new_state.boats[boat_index].y -= v_val * ordered_float::OrderedFloat(1.5);
 
         new_state
     }
-    pub fn apply_est_action(state: &State, action: &Action) -> State {
+    pub fn apply_est_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
         let v_val = new_state.boats[boat_index].v;
         new_state.boats[boat_index].x += v_val * ordered_float::OrderedFloat(3.0);
 
         new_state
     }
-    pub fn apply_west_action(state: &State, action: &Action) -> State {
+    pub fn apply_west_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
         let v_val = new_state.boats[boat_index].v;
         new_state.boats[boat_index].x -= v_val * ordered_float::OrderedFloat(3.0);
 
         new_state
     }
-    pub fn apply_south_west_action(state: &State, action: &Action) -> State {
+    pub fn apply_south_west_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
         let v_val = new_state.boats[boat_index].v;
         new_state.boats[boat_index].x += v_val * ordered_float::OrderedFloat(2.0);
         new_state.boats[boat_index].y -= v_val * ordered_float::OrderedFloat(2.0);
 
         new_state
     }
-    pub fn apply_south_east_action(state: &State, action: &Action) -> State {
+    pub fn apply_south_east_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
         let v_val = new_state.boats[boat_index].v;
 
         new_state.boats[boat_index].x -= v_val * ordered_float::OrderedFloat(2.0);
@@ -246,46 +243,30 @@ impl FooSailingProblem {
 
         new_state
     }
-    pub fn apply_south_action(state: &State, action: &Action) -> State {
+    pub fn apply_south_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
         let v_val = new_state.boats[boat_index].v;
         new_state.boats[boat_index].y -= v_val * ordered_float::OrderedFloat(2.0);
 
         new_state
     }
-    pub fn apply_accelerate_action(state: &State, action: &Action) -> State {
+    pub fn apply_accelerate_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
 
         new_state.boats[boat_index].v += ordered_float::OrderedFloat(1.0);
 
         new_state
     }
-    pub fn apply_decelerate_action(state: &State, action: &Action) -> State {
+    pub fn apply_decelerate_action(state: &State, boat_index: usize) -> State {
         let mut new_state = state.clone();
-        let boat_index = match action.parameters.get("boat") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid boat index."),
-        };
 
         new_state.boats[boat_index].v -= ordered_float::OrderedFloat(1.0);
 
         new_state
     }
 
-    pub fn apply_save_person_action(state: &State, action: &Action) -> State {
+    pub fn apply_save_person_action(state: &State, person_index: usize) -> State {
         let mut new_state = state.clone();
-        let person_index = match action.parameters.get("person") {
-            Some(Value::Int(i)) => *i as usize, // Dereference and cast to usize.
-            _ => panic!("Action parameters do not contain a valid person index."),
-        };
         new_state.persons[person_index].saved = true;
 
         new_state
@@ -295,48 +276,49 @@ impl FooSailingProblem {
 impl Problem for FooSailingProblem {
     type State = State;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    type Action = FooSailingAction;
+    type Cost = i32;
+
+    fn get_possible_actions(&self, state: &State) -> Vec<FooSailingAction> {
         Self::get_actions(state)
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        // Start by cloning the current state.
-        let mut new_state = state.clone();
-
-        // Process the "sail" parameter if present.
-        if let Some(Value::Text(direction)) = action.parameters.get("sail") {
-            match direction.as_str() {
-                "north_east" => new_state = Self::apply_north_east_action(state, action),
-                "north_west" => new_state = Self::apply_north_west_action(state, action),
-                "est" => new_state = Self::apply_est_action(state, action),
-                "west" => new_state = Self::apply_west_action(state, action),
-                "south_west" => new_state = Self::apply_south_west_action(state, action),
-                "south_east" => new_state = Self::apply_south_east_action(state, action),
-                "south" => new_state = Self::apply_south_action(state, action),
-                _ => {} // Unknown sail direction: ignore or log if needed.
+    fn apply_action(&self, state: &State, action: &FooSailingAction) -> State {
+        match *action {
+            FooSailingAction::NorthEast(boat) => Self::apply_north_east_action(state, boat),
+            FooSailingAction::NorthWest(boat) => Self::apply_north_west_action(state, boat),
+            FooSailingAction::Est(boat) => Self::apply_est_action(state, boat),
+            FooSailingAction::West(boat) => Self::apply_west_action(state, boat),
+            FooSailingAction::SouthWest(boat) => Self::apply_south_west_action(state, boat),
+            FooSailingAction::SouthEast(boat) => Self::apply_south_east_action(state, boat),
+            FooSailingAction::South(boat) => Self::apply_south_action(state, boat),
+            FooSailingAction::Accelerate(boat) => Self::apply_accelerate_action(state, boat),
+            FooSailingAction::Decelerate(boat) => Self::apply_decelerate_action(state, boat),
+            FooSailingAction::SavePerson { person, .. } => {
+                Self::apply_save_person_action(state, person)
             }
         }
+    }
 
-        // Process the "decelerate" parameter if present.
-        if let Some(Value::Text(param)) = action.parameters.get("decelerate") {
-            if param == "decelerate" {
-                new_state = Self::apply_decelerate_action(state, action);
-            }
-        }
+    fn action_cost(&self, _state: &State, _action: &FooSailingAction) -> i32 {
+        1
+    }
 
-        // Process the "accelerate" parameter if present.
-        if let Some(Value::Text(param)) = action.parameters.get("accelerate") {
-            if param == "accelerate" {
-                new_state = Self::apply_accelerate_action(state, action);
+    fn describe_action(&self, state: &State, action: &FooSailingAction) -> Action {
+        match *action {
+            FooSailingAction::NorthEast(boat) => Self::get_north_east_action(&state.boats[boat]),
+            FooSailingAction::NorthWest(boat) => Self::get_north_west_action(&state.boats[boat]),
+            FooSailingAction::Est(boat) => Self::get_est_action(&state.boats[boat]),
+            FooSailingAction::West(boat) => Self::get_west_action(&state.boats[boat]),
+            FooSailingAction::SouthWest(boat) => Self::get_south_west_action(&state.boats[boat]),
+            FooSailingAction::SouthEast(boat) => Self::get_south_east_action(&state.boats[boat]),
+            FooSailingAction::South(boat) => Self::get_south_action(&state.boats[boat]),
+            FooSailingAction::Accelerate(boat) => Self::get_accelerate_action(&state.boats[boat]),
+            FooSailingAction::Decelerate(boat) => Self::get_decelerate_action(&state.boats[boat]),
+            FooSailingAction::SavePerson { boat, person } => {
+                Self::get_save_person_action(&state.boats[boat], &state.persons[person])
             }
         }
-
-        // Process the "save_person" parameter if present.
-        if let Some(Value::Text(_)) = action.parameters.get("save_person") {
-            new_state = Self::apply_save_person_action(state, action);
-        }
-
-        new_state
     }
 
     fn is_goal_state(&self, state: &State) -> bool {
@@ -378,3 +360,44 @@ impl Problem for FooSailingProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for FooSailingProblem {
+    /// Position and speed of every boat, then one flag per person.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        for boat in &state.boats {
+            variables.push(Variable::real(format!("x_boat{}", boat.index), None, None));
+            variables.push(Variable::real(format!("y_boat{}", boat.index), None, None));
+            variables.push(Variable::real(
+                format!("v_boat{}", boat.index),
+                Some(boat.v.into_inner().min(1.0)),
+                Some(boat.v.into_inner().max(3.0)),
+            ));
+        }
+        for person in &state.persons {
+            variables.push(Variable::boolean(format!("saved_person{}", person.index)));
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        for boat in &state.boats {
+            values.push(Value::OrderedFloat64(boat.x));
+            values.push(Value::OrderedFloat64(boat.y));
+            values.push(Value::OrderedFloat64(boat.v));
+        }
+        values.extend(state.persons.iter().map(|p| Value::Bool(p.saved)));
+        values
+    }
+
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let persons_start = 3 * state.boats.len();
+        FactoredGoal {
+            conditions: (0..state.persons.len())
+                .map(|i| NumericCondition::equals(persons_start + i, 1.0))
+                .collect(),
+            exact: true,
+        }
+    }
+}
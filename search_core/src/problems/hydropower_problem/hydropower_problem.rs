@@ -1,10 +1,11 @@
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct State {
     pub current_time: i32,
     pub funds: i32, // current funds
@@ -14,6 +15,16 @@ pub struct State {
 
 impl StateTrait for State {}
 
+/// Actions of the hydropower domain, all taking place at the current time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HydropowerAction {
+    AdvanceTime,
+    PumpWater,
+    Generate,
+}
+
+impl ActionTrait for HydropowerAction {}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HydropowerProblem {
     pub goal_funds: i32, // goal funds
@@ -74,13 +85,13 @@ impl HydropowerProblem {
         }
     }
 
-    pub fn apply_advance_time_action(state: &State, _action: &Action) -> State {
+    pub fn apply_advance_time_action(state: &State) -> State {
         let mut new_state = state.clone();
         new_state.current_time += 1;
         new_state
     }
 
-    pub fn apply_pump_water_action(&self, state: &State, _action: &Action) -> State {
+    pub fn apply_pump_water_action(&self, state: &State) -> State {
         let t = state.current_time as usize;
         let mut new_state = state.clone();
         let current_demand = self.demand.get(t).unwrap();
@@ -90,7 +101,7 @@ impl HydropowerProblem {
         new_state
     }
 
-    pub fn apply_generate_action(&self, state: &State, _action: &Action) -> State {
+    pub fn apply_generate_action(&self, state: &State) -> State {
         let t = state.current_time as usize;
         let mut new_state = state.clone();
         let current_demand = self.demand.get(t).unwrap();
@@ -103,39 +114,48 @@ impl HydropowerProblem {
 
 impl Problem for HydropowerProblem {
     type State = State;
+    type Action = HydropowerAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<HydropowerAction> {
         let mut actions = Vec::new();
+        let t = state.current_time as usize;
 
-        if let Some(action) = self.possible_advance_time_actions(state) {
-            actions.push(action);
+        if t < self.before.len() {
+            actions.push(HydropowerAction::AdvanceTime);
         }
 
-        if state.stored_capacity > 0 {
-            if let Some(action) = self.possible_pump_water_actions(state) {
-                actions.push(action);
+        if t < self.demand.len() {
+            if state.stored_capacity > 0 {
+                actions.push(HydropowerAction::PumpWater);
             }
-        }
-
-        if state.stored_units > 0 {
-            if let Some(action) = self.possible_generate_actions(state) {
-                actions.push(action);
+            if state.stored_units > 0 {
+                actions.push(HydropowerAction::Generate);
             }
         }
 
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("advance_time") {
-            Self::apply_advance_time_action(state, action)
-        } else if action.name.starts_with("pump_water_up") {
-            self.apply_pump_water_action(state, action)
-        } else if action.name.starts_with("generate") {
-            self.apply_generate_action(state, action)
-        } else {
-            panic!("Unknown action");
+    fn apply_action(&self, state: &State, action: &HydropowerAction) -> State {
+        match action {
+            HydropowerAction::AdvanceTime => Self::apply_advance_time_action(state),
+            HydropowerAction::PumpWater => self.apply_pump_water_action(state),
+            HydropowerAction::Generate => self.apply_generate_action(state),
+        }
+    }
+
+    fn action_cost(&self, _state: &State, _action: &HydropowerAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &HydropowerAction) -> Action {
+        match action {
+            HydropowerAction::AdvanceTime => self.possible_advance_time_actions(state),
+            HydropowerAction::PumpWater => self.possible_pump_water_actions(state),
+            HydropowerAction::Generate => self.possible_generate_actions(state),
         }
+        .expect("Action not applicable at the current time")
     }
 
     fn is_goal_state(&self, state: &State) -> bool {
@@ -1,5 +1,9 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::problems::factored_problem::{float_value, FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::pareto::MultiObjectiveProblem;
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::from_reader;
 use serde_json::Value as JsonValue;
@@ -132,12 +136,38 @@ impl Goal {
     }
 }
 
+/// Actions of the market trader domain; camels, markets and goods are referred to by their
+/// position in the state's lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MarketTraderAction {
+    Travel {
+        camel: usize,
+        from: usize,
+        to: usize,
+    },
+    Buy {
+        camel: usize,
+        goods: usize,
+        market: usize,
+    },
+    Sell {
+        camel: usize,
+        goods: usize,
+        market: usize,
+    },
+    Upgrade {
+        camel: usize,
+    },
+}
+
+impl ActionTrait for MarketTraderAction {}
+
 pub struct MarketTraderProblem {
     pub goal: Goal,
 }
 
 impl MarketTraderProblem {
-    pub fn possible_travel_actions(state: &State) -> Vec<Action> {
+    pub fn possible_travel_actions(state: &State) -> Vec<MarketTraderAction> {
         let mut actions = Vec::new();
 
         // Ensure required data exists in the state
@@ -145,30 +175,20 @@ impl MarketTraderProblem {
             return actions; // No cash, no travel
         }
 
-        for camel in &state.camels {
-            for from in &state.markets {
-                for to in &state.markets {
+        for (c, camel) in state.camels.iter().enumerate() {
+            for (f, from) in state.markets.iter().enumerate() {
+                for (t, to) in state.markets.iter().enumerate() {
                     if from != to
                         && state.can_drive(from, to)
                         && state.resources.get_cash()
                             >= state.drive_cost(from, to).unwrap_or(f64::INFINITY)
                         && state.is_camel_at(camel, from)
                     {
-                        if let Some(_cost) = state.drive_cost(from, to) {
-                            let mut parameters = HashMap::new();
-                            parameters.insert("camel".to_string(), Value::Text(camel.clone()));
-                            parameters.insert("from".to_string(), Value::Text(from.clone()));
-                            parameters.insert("to".to_string(), Value::Text(to.clone()));
-
-                            // Format the action name as "travel_{from}_{to}_{camel}"
-                            let action_name = format!("travel_{}_{}_{}", from, to, camel);
-
-                            actions.push(Action::new(
-                                action_name,
-                                0, // Cost scaled using the state scaling logic
-                                parameters,
-                            ));
-                        }
+                        actions.push(MarketTraderAction::Travel {
+                            camel: c,
+                            from: f,
+                            to: t,
+                        });
                     }
                 }
             }
@@ -177,7 +197,7 @@ impl MarketTraderProblem {
         actions
     }
 
-    pub fn possible_buy_actions(state: &State) -> Vec<Action> {
+    pub fn possible_buy_actions(state: &State) -> Vec<MarketTraderAction> {
         let mut actions = Vec::new();
 
         // Ensure there is enough cash and capacity to consider buying
@@ -185,38 +205,20 @@ impl MarketTraderProblem {
             return actions;
         }
 
-        for camel in &state.camels {
-            for market in &state.markets {
+        for (c, camel) in state.camels.iter().enumerate() {
+            for (m, market) in state.markets.iter().enumerate() {
                 if state.is_camel_at(camel, market) {
-                    for goods in &state.goods {
+                    for (g, goods) in state.goods.iter().enumerate() {
                         if let Some(price) = state.price(goods, market) {
                             let total_cost = price + 7.0;
                             if state.resources.get_cash() >= total_cost {
                                 if let Some(on_sale) = state.on_sale(goods, market) {
                                     if on_sale > 0 {
-                                        let mut parameters = HashMap::new();
-                                        parameters.insert(
-                                            "camel".to_string(),
-                                            Value::Text(camel.clone()),
-                                        );
-                                        parameters.insert(
-                                            "goods".to_string(),
-                                            Value::Text(goods.clone()),
-                                        );
-                                        parameters.insert(
-                                            "market".to_string(),
-                                            Value::Text(market.clone()),
-                                        );
-
-                                        // Format the action name as "buy_{item}_{market}_{camel}"
-                                        let action_name =
-                                            format!("buy_{}_{}_{}", goods, market, camel);
-
-                                        actions.push(Action::new(
-                                            action_name,
-                                            0, // Use scaling for cost
-                                            parameters,
-                                        ));
+                                        actions.push(MarketTraderAction::Buy {
+                                            camel: c,
+                                            goods: g,
+                                            market: m,
+                                        });
                                     }
                                 }
                             }
@@ -229,54 +231,31 @@ impl MarketTraderProblem {
         actions
     }
 
-    pub fn possible_upgrade_actions(state: &State) -> Vec<Action> {
-        let mut actions = Vec::new();
-
+    pub fn possible_upgrade_actions(state: &State) -> Vec<MarketTraderAction> {
         // Check if there is enough cash for an upgrade
         if state.resources.get_cash() < 57.0 {
-            return actions;
+            return Vec::new();
         }
 
-        for camel in &state.camels {
-            let mut parameters = HashMap::new();
-            parameters.insert("camel".to_string(), Value::Text(camel.clone()));
-
-            // Format the action name as "upgrade_{camel}"
-            let action_name = format!("upgrade_{}", camel);
-
-            actions.push(Action::new(
-                action_name,
-                0, // Fixed cost scaled for the upgrade action
-                parameters,
-            ));
-        }
-
-        actions
+        (0..state.camels.len())
+            .map(|camel| MarketTraderAction::Upgrade { camel })
+            .collect()
     }
 
-    pub fn possible_sell_actions(state: &State) -> Vec<Action> {
+    pub fn possible_sell_actions(state: &State) -> Vec<MarketTraderAction> {
         let mut actions = Vec::new();
 
-        for camel in &state.camels {
-            for market in &state.markets {
+        for (c, camel) in state.camels.iter().enumerate() {
+            for (m, market) in state.markets.iter().enumerate() {
                 if state.is_camel_at(camel, market) {
-                    for item in &state.goods {
+                    for (g, item) in state.goods.iter().enumerate() {
                         if let Some(bought_quantity) = state.bought.get(item) {
                             if *bought_quantity >= 1 {
-                                let mut parameters = HashMap::new();
-                                parameters.insert("camel".to_string(), Value::Text(camel.clone()));
-                                parameters.insert("goods".to_string(), Value::Text(item.clone()));
-                                parameters
-                                    .insert("market".to_string(), Value::Text(market.clone()));
-
-                                // Format the action name as "sell_{item}_{market}_{camel}"
-                                let action_name = format!("sell_{}_{}_{}", item, market, camel);
-
-                                actions.push(Action::new(
-                                    action_name,
-                                    0, // No specific cost for the sell action
-                                    parameters,
-                                ));
+                                actions.push(MarketTraderAction::Sell {
+                                    camel: c,
+                                    goods: g,
+                                    market: m,
+                                });
                             }
                         }
                     }
@@ -287,99 +266,130 @@ impl MarketTraderProblem {
         actions
     }
 
+    /// Display form of an action; all actions of this domain have cost 0.
+    pub fn action_description(state: &State, action: &MarketTraderAction) -> Action {
+        let mut parameters = HashMap::new();
+        let action_name = match *action {
+            MarketTraderAction::Travel { camel, from, to } => {
+                let (camel, from, to) = (
+                    &state.camels[camel],
+                    &state.markets[from],
+                    &state.markets[to],
+                );
+                parameters.insert("camel".to_string(), Value::Text(camel.clone()));
+                parameters.insert("from".to_string(), Value::Text(from.clone()));
+                parameters.insert("to".to_string(), Value::Text(to.clone()));
+                format!("travel_{}_{}_{}", from, to, camel)
+            }
+            MarketTraderAction::Buy {
+                camel,
+                goods,
+                market,
+            }
+            | MarketTraderAction::Sell {
+                camel,
+                goods,
+                market,
+            } => {
+                let (camel, goods, market) = (
+                    &state.camels[camel],
+                    &state.goods[goods],
+                    &state.markets[market],
+                );
+                parameters.insert("camel".to_string(), Value::Text(camel.clone()));
+                parameters.insert("goods".to_string(), Value::Text(goods.clone()));
+                parameters.insert("market".to_string(), Value::Text(market.clone()));
+                let kind = match action {
+                    MarketTraderAction::Buy { .. } => "buy",
+                    _ => "sell",
+                };
+                format!("{}_{}_{}_{}", kind, goods, market, camel)
+            }
+            MarketTraderAction::Upgrade { camel } => {
+                let camel = &state.camels[camel];
+                parameters.insert("camel".to_string(), Value::Text(camel.clone()));
+                format!("upgrade_{}", camel)
+            }
+        };
+        Action::new(action_name, 0, parameters)
+    }
+
     //////////////////////////------------------------------------------
 
-    pub fn apply_travel_action(state: &State, action: &Action) -> State {
+    pub fn apply_travel_action(state: &State, camel: usize, from: usize, to: usize) -> State {
         let mut new_state = state.clone();
+        let (camel, from, to) = (
+            &state.camels[camel],
+            &state.markets[from],
+            &state.markets[to],
+        );
+
+        // Update cash by decreasing it by the drive cost
+        if let Some(drive_cost) = state.drive_cost(from, to) {
+            new_state
+                .resources
+                .set_cash(new_state.resources.get_cash() - drive_cost);
+        }
 
-        // Retrieve parameters from the action
-        if let (Some(Value::Text(from)), Some(Value::Text(to)), Some(Value::Text(camel))) = (
-            action.parameters.get("from"),
-            action.parameters.get("to"),
-            action.parameters.get("camel"),
-        ) {
-            // Update cash by decreasing it by the drive cost
-            if let Some(drive_cost) = state.drive_cost(from, to) {
-                new_state
-                    .resources
-                    .set_cash(new_state.resources.get_cash() - drive_cost);
-            }
-
-            // Update the camel's location
-            if let Some(location) = new_state.location.get_mut(camel) {
-                *location = to.clone();
-            }
+        // Update the camel's location
+        if let Some(location) = new_state.location.get_mut(camel) {
+            *location = to.clone();
         }
 
         new_state
     }
 
-    pub fn apply_buy_action(state: &State, action: &Action) -> State {
+    pub fn apply_buy_action(state: &State, goods: usize, market: usize) -> State {
         let mut new_state = state.clone();
+        let (goods, market) = (&state.goods[goods], &state.markets[market]);
 
-        // Retrieve parameters from the action
-        if let (Some(Value::Text(_camel)), Some(Value::Text(goods)), Some(Value::Text(market))) = (
-            action.parameters.get("camel"),
-            action.parameters.get("goods"),
-            action.parameters.get("market"),
-        ) {
-            // Decrease capacity by 1
-            new_state.resources.capacity -= 1;
-
-            // Update the bought quantity for the goods
-            *new_state.bought.entry(goods.clone()).or_insert(0) += 1;
-
-            // Decrease cash by the price of the goods at the market
-            if let Some(price) = state.price(goods, market) {
-                new_state
-                    .resources
-                    .set_cash(new_state.resources.get_cash() - price);
-            }
+        // Decrease capacity by 1
+        new_state.resources.capacity -= 1;
+
+        // Update the bought quantity for the goods
+        *new_state.bought.entry(goods.clone()).or_insert(0) += 1;
+
+        // Decrease cash by the price of the goods at the market
+        if let Some(price) = state.price(goods, market) {
+            new_state
+                .resources
+                .set_cash(new_state.resources.get_cash() - price);
         }
 
         new_state
     }
 
-    pub fn apply_upgrade_action(state: &State, action: &Action) -> State {
+    pub fn apply_upgrade_action(state: &State) -> State {
         let mut new_state = state.clone();
 
-        // Retrieve parameters from the action
-        if let Some(Value::Text(_camel)) = action.parameters.get("camel") {
-            // Increase capacity by 20
-            new_state.resources.capacity += 20;
+        // Increase capacity by 20
+        new_state.resources.capacity += 20;
 
-            // Decrease cash by 50
-            new_state
-                .resources
-                .set_cash(new_state.resources.get_cash() - 50.0);
-        }
+        // Decrease cash by 50
+        new_state
+            .resources
+            .set_cash(new_state.resources.get_cash() - 50.0);
 
         new_state
     }
 
-    pub fn apply_sell_action(state: &State, action: &Action) -> State {
+    pub fn apply_sell_action(state: &State, goods: usize, market: usize) -> State {
         let mut new_state = state.clone();
+        let (goods, market) = (&state.goods[goods], &state.markets[market]);
 
-        // Retrieve parameters from the action
-        if let (Some(Value::Text(_camel)), Some(Value::Text(goods)), Some(Value::Text(market))) = (
-            action.parameters.get("camel"),
-            action.parameters.get("goods"),
-            action.parameters.get("market"),
-        ) {
-            // Increase capacity by 1
-            new_state.resources.capacity += 1;
-
-            // Decrease the bought quantity of the goods item by 1
-            if let Some(bought_quantity) = new_state.bought.get_mut(goods) {
-                *bought_quantity -= 1;
-            }
+        // Increase capacity by 1
+        new_state.resources.capacity += 1;
 
-            // Increase cash by the price of the goods at the market
-            if let Some(price) = state.price(goods, market) {
-                new_state
-                    .resources
-                    .set_cash(new_state.resources.get_cash() + price);
-            }
+        // Decrease the bought quantity of the goods item by 1
+        if let Some(bought_quantity) = new_state.bought.get_mut(goods) {
+            *bought_quantity -= 1;
+        }
+
+        // Increase cash by the price of the goods at the market
+        if let Some(price) = state.price(goods, market) {
+            new_state
+                .resources
+                .set_cash(new_state.resources.get_cash() + price);
         }
 
         new_state
@@ -419,7 +429,7 @@ impl MarketTraderProblem {
 }
 
 impl Problem for MarketTraderProblem {
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<MarketTraderAction> {
         let mut actions = Vec::new();
         // Collect possible actions from each helper function
         actions.extend(Self::possible_travel_actions(state));
@@ -430,21 +440,29 @@ impl Problem for MarketTraderProblem {
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        // Parse the action name to determine which apply function to call
-        if action.name.starts_with("travel_") {
-            Self::apply_travel_action(state, action)
-        } else if action.name.starts_with("buy_") {
-            Self::apply_buy_action(state, action)
-        } else if action.name.starts_with("sell_") {
-            Self::apply_sell_action(state, action)
-        } else if action.name.starts_with("upgrade_") {
-            Self::apply_upgrade_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    fn apply_action(&self, state: &State, action: &MarketTraderAction) -> State {
+        match *action {
+            MarketTraderAction::Travel { camel, from, to } => {
+                Self::apply_travel_action(state, camel, from, to)
+            }
+            MarketTraderAction::Buy { goods, market, .. } => {
+                Self::apply_buy_action(state, goods, market)
+            }
+            MarketTraderAction::Sell { goods, market, .. } => {
+                Self::apply_sell_action(state, goods, market)
+            }
+            MarketTraderAction::Upgrade { .. } => Self::apply_upgrade_action(state),
         }
     }
 
+    fn action_cost(&self, _state: &State, _action: &MarketTraderAction) -> i32 {
+        0
+    }
+
+    fn describe_action(&self, state: &State, action: &MarketTraderAction) -> Action {
+        Self::action_description(state, action)
+    }
+
     fn is_goal_state(&self, state: &State) -> bool {
         // Retrieve the goal cash threshold from the goal structure
         if let Some(atomic_goal) = self.goal.conditions.iter().find(|g| g.parameter == "cash") {
@@ -621,4 +639,102 @@ impl Problem for MarketTraderProblem {
     }
 
     type State = State;
+    type Action = MarketTraderAction;
+    type Cost = i32;
+}
+
+impl FactoredProblem for MarketTraderProblem {
+    /// A location flag per camel and market, the amount bought per good, the capacity and the cash.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        for camel in &state.camels {
+            for market in &state.markets {
+                variables.push(Variable::boolean(format!("at_{}_{}", camel, market)));
+            }
+        }
+        for goods in &state.goods {
+            let bought = state.bought(goods).unwrap_or(0);
+            variables.push(Variable::integer(
+                format!("bought_{}", goods),
+                Some(bought.min(0)),
+                None,
+            ));
+        }
+        variables.push(Variable::integer("capacity".to_string(), None, None));
+        variables.push(Variable::real(
+            "cash".to_string(),
+            Some(state.cash().min(0.0)),
+            None,
+        ));
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        for camel in &state.camels {
+            for market in &state.markets {
+                values.push(Value::Bool(state.is_camel_at(camel, market)));
+            }
+        }
+        for goods in &state.goods {
+            values.push(Value::Int64(state.bought(goods).unwrap_or(0)));
+        }
+        values.push(Value::Int64(state.resources.capacity));
+        values.push(float_value(state.cash()));
+        values
+    }
+
+    /// `cash >= goal`; without a usable cash condition the goal is never reached.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let cash = state.camels.len() * state.markets.len() + state.goods.len() + 1;
+        let threshold = self
+            .goal
+            .conditions
+            .iter()
+            .find(|g| g.parameter == "cash")
+            .and_then(|g| match &g.value {
+                Value::Int(goal_cash) => Some(*goal_cash as f64),
+                Value::Text(goal_cash_str) => goal_cash_str.parse::<i64>().ok().map(|c| c as f64),
+                _ => None,
+            });
+        match threshold {
+            Some(threshold) => FactoredGoal {
+                conditions: vec![NumericCondition::new(
+                    LinearExpression::variable(cash),
+                    Comparison::Ge,
+                    &LinearExpression::constant(threshold),
+                )],
+                exact: true,
+            },
+            None => FactoredGoal::unreachable(),
+        }
+    }
+}
+
+impl MultiObjectiveProblem for MarketTraderProblem {
+    /// Fuel is the drive cost of travelling, spending the cash paid for goods and upgrades;
+    /// a trader can save fuel by buying at the nearest markets, or cash by driving further.
+    fn objectives(&self) -> Vec<&'static str> {
+        vec!["fuel", "spending"]
+    }
+
+    fn objective_costs(
+        &self,
+        state: &State,
+        action: &MarketTraderAction,
+        successor: &State,
+    ) -> Vec<f64> {
+        match *action {
+            MarketTraderAction::Travel { from, to, .. } => vec![
+                state
+                    .drive_cost(&state.markets[from], &state.markets[to])
+                    .unwrap_or(0.0),
+                0.0,
+            ],
+            MarketTraderAction::Buy { .. } | MarketTraderAction::Upgrade { .. } => {
+                vec![0.0, state.cash() - successor.cash()]
+            }
+            MarketTraderAction::Sell { .. } => vec![0.0, 0.0],
+        }
+    }
 }
@@ -1,17 +1,25 @@
 pub mod block_grouping_problem;
 pub mod compute_number_problem;
 pub mod counters_problem;
+pub mod delivery_problem;
+pub mod drone_problem;
+pub mod expedition_problem;
 pub mod ext_plant_watering_problem;
+pub mod factored_problem;
 pub mod farm_problem;
 pub mod farmland_problem;
 pub mod fo_counters_problem;
 pub mod fo_farmland_problem;
 pub mod fo_sailing_problem;
+pub mod hydropower_problem;
 pub mod market_trader_problem;
 pub mod mprime_problem;
 pub mod path_ways_metric_problem;
 pub mod problem;
 pub mod red_car_problem;
+pub mod rover_problem;
+pub mod sailing_problem;
+pub mod settlersnumeric_problem;
 pub mod tpp_problem;
 pub mod zenotravel_fuel_problem;
 pub mod zenotravel_problem;
@@ -1,6 +1,9 @@
 // Domain-specific imports for planning problem traits and serialization
+use crate::heuristics::numeric_task::NumericCondition;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
@@ -118,6 +121,33 @@ impl Goal {
     }
 }
 
+/// Actions of the MPrime domain; pleasures, pains and foods are referred to by their
+/// position in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MPrimeAction {
+    OverCome {
+        pleasure: usize,
+        pain: usize,
+        food: usize,
+    },
+    Feast {
+        pleasure: usize,
+        food1: usize,
+        food2: usize,
+    },
+    Succumb {
+        pleasure: usize,
+        pain: usize,
+        food: usize,
+    },
+    Drink {
+        food1: usize,
+        food2: usize,
+    },
+}
+
+impl ActionTrait for MPrimeAction {}
+
 // Main problem struct defining:
 // - eats: compatibility map of which foods can lead to others
 // - goal: the Goal instance to achieve
@@ -134,22 +164,19 @@ impl MPrimeProblem {
     /// For each Pleasure with harmony >=1 that craves a food,
     /// and each Pain that also craves that same food,
     /// create an action to let the Pleasure overcome the Pain using that food.
-    pub fn get_over_come_actions(state: &State) -> Vec<Action> {
+    pub fn get_over_come_actions(state: &State) -> Vec<MPrimeAction> {
         let mut actions = Vec::new();
-        for pleasure in &state.pleasures {
+        for (i, pleasure) in state.pleasures.iter().enumerate() {
             if pleasure.harmony >= 1 {
-                for pain in &state.pains {
-                    for food in &state.foods {
+                for (j, pain) in state.pains.iter().enumerate() {
+                    for (k, food) in state.foods.iter().enumerate() {
                         if pleasure.craves.contains(&food.name) && pain.craves.contains(&food.name)
                         {
-                            let mut parameters = HashMap::new();
-                            let action_name =
-                                format!("over_come_{}_{}_{}", pleasure.name, pain.name, food.name);
-                            parameters
-                                .insert("pleasure".to_string(), Value::Text(pleasure.name.clone()));
-                            parameters.insert("pain".to_string(), Value::Text(pain.name.clone()));
-                            parameters.insert("food".to_string(), Value::Text(food.name.clone()));
-                            actions.push(Action::new(action_name, 1, parameters));
+                            actions.push(MPrimeAction::OverCome {
+                                pleasure: i,
+                                pain: j,
+                                food: k,
+                            });
                         }
                     }
                 }
@@ -162,20 +189,17 @@ impl MPrimeProblem {
     /// For each Pleasure and Pain pair,
     /// if Pleasure craves a food and Pain fears that Pleasure,
     /// create an action to let Pleasure succumb, increasing Pain's crave.
-    pub fn get_succumb_actions(state: &State) -> Vec<Action> {
+    pub fn get_succumb_actions(state: &State) -> Vec<MPrimeAction> {
         let mut actions = Vec::new();
-        for pleasure in &state.pleasures {
-            for pain in &state.pains {
-                for food in &state.foods {
+        for (i, pleasure) in state.pleasures.iter().enumerate() {
+            for (j, pain) in state.pains.iter().enumerate() {
+                for (k, food) in state.foods.iter().enumerate() {
                     if pleasure.craves.contains(&food.name) && pain.fears.contains(&pleasure.name) {
-                        let mut parameters = HashMap::new();
-                        let action_name =
-                            format!("succumb_{}_{}_{}", pleasure.name, pain.name, food.name);
-                        parameters
-                            .insert("pleasure".to_string(), Value::Text(pleasure.name.clone()));
-                        parameters.insert("pain".to_string(), Value::Text(pain.name.clone()));
-                        parameters.insert("food".to_string(), Value::Text(food.name.clone()));
-                        actions.push(Action::new(action_name, 1, parameters));
+                        actions.push(MPrimeAction::Succumb {
+                            pleasure: i,
+                            pain: j,
+                            food: k,
+                        });
                     }
                 }
             }
@@ -187,28 +211,19 @@ impl MPrimeProblem {
     /// For each Pleasure that craves food1 and food1 has availability,
     /// and for each food2 that food1 can eat (per `eats` map),
     /// create an action to feast: consume one unit of food1 and gain food2 craving.
-    pub fn get_feast_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_feast_actions(&self, state: &State) -> Vec<MPrimeAction> {
         let mut actions = Vec::new();
-        for pleasure in &state.pleasures {
-            for food1 in &state.foods {
+        for (i, pleasure) in state.pleasures.iter().enumerate() {
+            for (j, food1) in state.foods.iter().enumerate() {
                 if pleasure.craves.contains(&food1.name) && food1.locale >= 1 {
                     if let Some(eats_vec) = self.eats.get(&food1.name) {
-                        for food2 in &state.foods {
+                        for (k, food2) in state.foods.iter().enumerate() {
                             if eats_vec.contains(&food2.name) {
-                                let mut parameters = HashMap::new();
-                                let action_name = format!(
-                                    "feast_{}_{},{}",
-                                    pleasure.name, food1.name, food2.name
-                                );
-                                parameters.insert(
-                                    "pleasure".to_string(),
-                                    Value::Text(pleasure.name.clone()),
-                                );
-                                parameters
-                                    .insert("food1".to_string(), Value::Text(food1.name.clone()));
-                                parameters
-                                    .insert("food2".to_string(), Value::Text(food2.name.clone()));
-                                actions.push(Action::new(action_name, 1, parameters));
+                                actions.push(MPrimeAction::Feast {
+                                    pleasure: i,
+                                    food1: j,
+                                    food2: k,
+                                });
                             }
                         }
                     }
@@ -221,17 +236,13 @@ impl MPrimeProblem {
     /// Generate all "drink" actions:
     /// For each food1 with at least one unit,
     /// allow transferring one unit from food1 to any other food2.
-    pub fn get_drink_actions(state: &State) -> Vec<Action> {
+    pub fn get_drink_actions(state: &State) -> Vec<MPrimeAction> {
         let mut actions = Vec::new();
-        for food1 in &state.foods {
+        for (i, food1) in state.foods.iter().enumerate() {
             if food1.locale >= 1 {
-                for food2 in &state.foods {
+                for (j, food2) in state.foods.iter().enumerate() {
                     if food1.name != food2.name {
-                        let mut parameters = HashMap::new();
-                        let action_name = format!("drink_{}_{}", food1.name, food2.name);
-                        parameters.insert("food1".to_string(), Value::Text(food1.name.clone()));
-                        parameters.insert("food2".to_string(), Value::Text(food2.name.clone()));
-                        actions.push(Action::new(action_name, 1, parameters));
+                        actions.push(MPrimeAction::Drink { food1: i, food2: j });
                     }
                 }
             }
@@ -239,31 +250,66 @@ impl MPrimeProblem {
         actions
     }
 
+    /// Display form of an action, with the names of its objects as parameters.
+    pub fn action_description(state: &State, action: &MPrimeAction) -> Action {
+        let mut parameters = HashMap::new();
+        let action_name = match *action {
+            MPrimeAction::OverCome {
+                pleasure,
+                pain,
+                food,
+            }
+            | MPrimeAction::Succumb {
+                pleasure,
+                pain,
+                food,
+            } => {
+                let (pleasure, pain, food) = (
+                    &state.pleasures[pleasure].name,
+                    &state.pains[pain].name,
+                    &state.foods[food].name,
+                );
+                parameters.insert("pleasure".to_string(), Value::Text(pleasure.clone()));
+                parameters.insert("pain".to_string(), Value::Text(pain.clone()));
+                parameters.insert("food".to_string(), Value::Text(food.clone()));
+                let kind = match action {
+                    MPrimeAction::OverCome { .. } => "over_come",
+                    _ => "succumb",
+                };
+                format!("{}_{}_{}_{}", kind, pleasure, pain, food)
+            }
+            MPrimeAction::Feast {
+                pleasure,
+                food1,
+                food2,
+            } => {
+                let (pleasure, food1, food2) = (
+                    &state.pleasures[pleasure].name,
+                    &state.foods[food1].name,
+                    &state.foods[food2].name,
+                );
+                parameters.insert("pleasure".to_string(), Value::Text(pleasure.clone()));
+                parameters.insert("food1".to_string(), Value::Text(food1.clone()));
+                parameters.insert("food2".to_string(), Value::Text(food2.clone()));
+                format!("feast_{}_{},{}", pleasure, food1, food2)
+            }
+            MPrimeAction::Drink { food1, food2 } => {
+                let (food1, food2) = (&state.foods[food1].name, &state.foods[food2].name);
+                parameters.insert("food1".to_string(), Value::Text(food1.clone()));
+                parameters.insert("food2".to_string(), Value::Text(food2.clone()));
+                format!("drink_{}_{}", food1, food2)
+            }
+        };
+        Action::new(action_name, 1, parameters)
+    }
+
     /// Applies a "drink" action to a cloned state:
     /// - Decrement `locale` of food1 by 1
     /// - Increment `locale` of food2 by 1
-    pub fn apply_drink_action(state: &State, action: &Action) -> State {
+    pub fn apply_drink_action(state: &State, food1: usize, food2: usize) -> State {
         let mut new_state = state.clone();
-        let food1_name = match action.parameters.get("food1") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Missing or invalid 'food1' parameter"),
-        };
-        let food2_name = match action.parameters.get("food2") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Missing or invalid 'food2' parameter"),
-        };
-        let idx1 = new_state
-            .foods
-            .iter()
-            .position(|f| f.name == *food1_name)
-            .expect("Food1 not found");
-        let idx2 = new_state
-            .foods
-            .iter()
-            .position(|f| f.name == *food2_name)
-            .expect("Food2 not found");
-        new_state.foods[idx1].locale -= 1;
-        new_state.foods[idx2].locale += 1;
+        new_state.foods[food1].locale -= 1;
+        new_state.foods[food2].locale += 1;
         new_state
     }
 
@@ -271,32 +317,12 @@ impl MPrimeProblem {
     /// - Increase the pleasure's harmony by 1
     /// - Add the food to the pain's craves
     /// - Remove the pleasure from the pain's fears
-    pub fn apply_succumb_action(state: &State, action: &Action) -> State {
+    pub fn apply_succumb_action(state: &State, pleasure: usize, pain: usize, food: usize) -> State {
         let mut new_state = state.clone();
-        let pain_name = match action.parameters.get("pain") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'pain' parameter"),
-        };
-        let food_name = match action.parameters.get("food") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'food' parameter"),
-        };
-        let pleasure_name = match action.parameters.get("pleasure") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'pleasure' parameter"),
-        };
-        let pleasure = new_state
-            .pleasures
-            .iter_mut()
-            .find(|p| p.name == *pleasure_name)
-            .expect("Pleasure not found");
-        pleasure.harmony += 1;
-        let pain = new_state
-            .pains
-            .iter_mut()
-            .find(|p| p.name == *pain_name)
-            .expect("Pain not found");
-        pain.craves.push(food_name.clone());
+        let pleasure_name = &state.pleasures[pleasure].name;
+        new_state.pleasures[pleasure].harmony += 1;
+        let pain = &mut new_state.pains[pain];
+        pain.craves.push(state.foods[food].name.clone());
         pain.fears.retain(|f| f != pleasure_name);
         new_state
     }
@@ -305,33 +331,13 @@ impl MPrimeProblem {
     /// - Decrement `locale` of food1
     /// - Remove food1 from pleasure's `craves`
     /// - Add food2 to pleasure's `craves`
-    pub fn apply_feast_action(state: &State, action: &Action) -> State {
+    pub fn apply_feast_action(state: &State, pleasure: usize, food1: usize, food2: usize) -> State {
         let mut new_state = state.clone();
-        let food1_name = match action.parameters.get("food1") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'food1' parameter"),
-        };
-        let food2_name = match action.parameters.get("food2") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'food2' parameter"),
-        };
-        let pleasure_name = match action.parameters.get("pleasure") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'pleasure' parameter"),
-        };
-        let pleasure = new_state
-            .pleasures
-            .iter_mut()
-            .find(|p| p.name == *pleasure_name)
-            .expect("Pleasure not found");
-        let food1 = new_state
-            .foods
-            .iter_mut()
-            .find(|f| f.name == *food1_name)
-            .expect("Food1 not found");
-        food1.locale -= 1;
+        let food1_name = &state.foods[food1].name;
+        new_state.foods[food1].locale -= 1;
+        let pleasure = &mut new_state.pleasures[pleasure];
         pleasure.craves.retain(|c| c != food1_name);
-        pleasure.craves.push(food2_name.clone());
+        pleasure.craves.push(state.foods[food2].name.clone());
         new_state
     }
 
@@ -339,33 +345,18 @@ impl MPrimeProblem {
     /// - Decrease pleasure's harmony by 1
     /// - Remove food from pain's `craves`
     /// - Add pleasure to pain's `fears`
-    pub fn apply_over_come_action(state: &State, action: &Action) -> State {
+    pub fn apply_over_come_action(
+        state: &State,
+        pleasure: usize,
+        pain: usize,
+        food: usize,
+    ) -> State {
         let mut new_state = state.clone();
-        let pain_name = match action.parameters.get("pain") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'pain' param"),
-        };
-        let food_name = match action.parameters.get("food") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'food' param"),
-        };
-        let pleasure_name = match action.parameters.get("pleasure") {
-            Some(Value::Text(n)) => n,
-            _ => panic!("Missing or invalid 'pleasure' param"),
-        };
-        let pleasure = new_state
-            .pleasures
-            .iter_mut()
-            .find(|p| p.name == *pleasure_name)
-            .expect("Pleasure not found");
-        let pain = new_state
-            .pains
-            .iter_mut()
-            .find(|p| p.name == *pain_name)
-            .expect("Pain not found");
-        pleasure.harmony -= 1;
+        let food_name = &state.foods[food].name;
+        new_state.pleasures[pleasure].harmony -= 1;
+        let pain = &mut new_state.pains[pain];
         pain.craves.retain(|c| c != food_name);
-        pain.fears.push(pleasure_name.clone());
+        pain.fears.push(state.pleasures[pleasure].name.clone());
         new_state
     }
 }
@@ -373,8 +364,11 @@ impl MPrimeProblem {
 impl Problem for MPrimeProblem {
     type State = State;
 
+    type Action = MPrimeAction;
+    type Cost = i32;
+
     /// Returns the full set of applicable actions in `state`
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<MPrimeAction> {
         let mut actions = Vec::new();
         actions.extend(Self::get_over_come_actions(state));
         actions.extend(self.get_feast_actions(state));
@@ -383,21 +377,36 @@ impl Problem for MPrimeProblem {
         actions
     }
 
-    /// Dispatches to the correct apply_* function based on action name prefix
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("drink_") {
-            Self::apply_drink_action(state, action)
-        } else if action.name.starts_with("feast_") {
-            Self::apply_feast_action(state, action)
-        } else if action.name.starts_with("succumb_") {
-            Self::apply_succumb_action(state, action)
-        } else if action.name.starts_with("over_come_") {
-            Self::apply_over_come_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    /// Dispatches to the correct apply_* function
+    fn apply_action(&self, state: &State, action: &MPrimeAction) -> State {
+        match *action {
+            MPrimeAction::Drink { food1, food2 } => Self::apply_drink_action(state, food1, food2),
+            MPrimeAction::Feast {
+                pleasure,
+                food1,
+                food2,
+            } => Self::apply_feast_action(state, pleasure, food1, food2),
+            MPrimeAction::Succumb {
+                pleasure,
+                pain,
+                food,
+            } => Self::apply_succumb_action(state, pleasure, pain, food),
+            MPrimeAction::OverCome {
+                pleasure,
+                pain,
+                food,
+            } => Self::apply_over_come_action(state, pleasure, pain, food),
         }
     }
 
+    fn action_cost(&self, _state: &State, _action: &MPrimeAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &MPrimeAction) -> Action {
+        Self::action_description(state, action)
+    }
+
     /// Checks whether `state` satisfies the problem goal
     fn is_goal_state(&self, state: &State) -> bool {
         self.goal.is_goal_state(state)
@@ -421,3 +430,110 @@ impl Problem for MPrimeProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for MPrimeProblem {
+    /// Harmony of every pleasure and pain, the locale of every food, and one flag per
+    /// craving (emotion, food) and per fear (pain, pleasure).
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let total_locale: i32 = state.foods.iter().map(|f| f.locale).sum();
+        let mut variables = Vec::new();
+        for pleasure in &state.pleasures {
+            variables.push(Variable::integer(
+                format!("harmony_{}", pleasure.name),
+                Some(pleasure.harmony.min(0) as i64),
+                None,
+            ));
+        }
+        for pain in &state.pains {
+            variables.push(Variable::integer(
+                format!("harmony_{}", pain.name),
+                Some(pain.harmony as i64),
+                Some(pain.harmony as i64),
+            ));
+        }
+        for food in &state.foods {
+            variables.push(Variable::integer(
+                format!("locale_{}", food.name),
+                Some(food.locale.min(0) as i64),
+                Some(total_locale as i64),
+            ));
+        }
+        let emotions = state
+            .pleasures
+            .iter()
+            .map(|p| &p.name)
+            .chain(state.pains.iter().map(|p| &p.name));
+        for emotion in emotions {
+            for food in &state.foods {
+                variables.push(Variable::boolean(format!(
+                    "craves_{}_{}",
+                    emotion, food.name
+                )));
+            }
+        }
+        for pain in &state.pains {
+            for pleasure in &state.pleasures {
+                variables.push(Variable::boolean(format!(
+                    "fears_{}_{}",
+                    pain.name, pleasure.name
+                )));
+            }
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        values.extend(state.pleasures.iter().map(|p| Value::Int(p.harmony)));
+        values.extend(state.pains.iter().map(|p| Value::Int(p.harmony)));
+        values.extend(state.foods.iter().map(|f| Value::Int(f.locale)));
+        let cravings = state
+            .pleasures
+            .iter()
+            .map(|p| &p.craves)
+            .chain(state.pains.iter().map(|p| &p.craves));
+        for craves in cravings {
+            for food in &state.foods {
+                values.push(Value::Bool(craves.contains(&food.name)));
+            }
+        }
+        for pain in &state.pains {
+            for pleasure in &state.pleasures {
+                values.push(Value::Bool(pain.fears.contains(&pleasure.name)));
+            }
+        }
+        values
+    }
+
+    /// Every goal emotion must crave its food; pleasures are looked up before pains.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let emotions: Vec<&String> = state
+            .pleasures
+            .iter()
+            .map(|p| &p.name)
+            .chain(state.pains.iter().map(|p| &p.name))
+            .collect();
+        let cravings_start = emotions.len() + state.foods.len();
+        let conditions = self
+            .goal
+            .conditions
+            .iter()
+            .map(|condition| {
+                let emotion = emotions
+                    .iter()
+                    .position(|e| **e == condition.emotion)
+                    .unwrap_or_else(|| panic!("Emotion {} not found", condition.emotion));
+                let food = state
+                    .foods
+                    .iter()
+                    .position(|f| f.name == condition.food)
+                    .unwrap_or_else(|| panic!("Food {} not found", condition.food));
+                NumericCondition::equals(cravings_start + emotion * state.foods.len() + food, 1.0)
+            })
+            .collect();
+        FactoredGoal {
+            conditions,
+            exact: true,
+        }
+    }
+}
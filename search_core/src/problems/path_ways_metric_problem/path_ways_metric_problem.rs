@@ -1,5 +1,8 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::fs;
@@ -116,6 +119,20 @@ pub struct Condition {
     amount_condition: i32,   // required total amount for this condition
 }
 
+/// Actions of the pathways domain. Simples are referred to by their position in the state,
+/// reactions by their position in the problem's reaction lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PathWaysMetricAction {
+    Choose(usize),
+    Initialize(usize),
+    Associate(usize),
+    AssociateWithCatalyze(usize),
+    SelfAssociateWithCatalyze(usize),
+    Synthesize(usize),
+}
+
+impl ActionTrait for PathWaysMetricAction {}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PathWaysMetricProblem {
     pub goal: Goal,                                      // goal definition
@@ -265,26 +282,26 @@ impl PathWaysMetricProblem {
     }
 
     // Gather choose and initialize actions for all simples in the state
-    pub fn get_choose_initialize_simple_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_choose_initialize_simple_actions(&self, state: &State) -> Vec<PathWaysMetricAction> {
         let mut actions = Vec::new();
 
-        for simple in &state.simples {
+        for (i, simple) in state.simples.iter().enumerate() {
             if simple.possible {
                 // only possible simples can be chosen
-                actions.push(Self::get_choose_simple_action(simple));
+                actions.push(PathWaysMetricAction::Choose(i));
             }
             if simple.chosen {
                 // chosen simples can be initialized
-                actions.push(Self::get_initialize_simple_action(simple));
+                actions.push(PathWaysMetricAction::Initialize(i));
             }
         }
         actions
     }
 
     // Collect all valid association actions given the current state
-    pub fn get_associate_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_associate_actions(&self, state: &State) -> Vec<PathWaysMetricAction> {
         let mut actions = Vec::new();
-        for reaction in &self.association_reactions {
+        for (i, reaction) in self.association_reactions.iter().enumerate() {
             // Look up reactant simples by name
             let mol1_opt = state
                 .simples
@@ -300,7 +317,7 @@ impl PathWaysMetricProblem {
                 if mol1.available >= reaction.need_molecule_1
                     && mol2.available >= reaction.need_molecule_2
                 {
-                    actions.push(Self::get_associate_action(reaction));
+                    actions.push(PathWaysMetricAction::Associate(i));
                 }
             }
         }
@@ -308,9 +325,9 @@ impl PathWaysMetricProblem {
     }
 
     // Collect all valid catalyzed association actions
-    pub fn get_associate_with_catalyze_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_associate_with_catalyze_actions(&self, state: &State) -> Vec<PathWaysMetricAction> {
         let mut actions = Vec::new();
-        for reaction in &self.catalyzed_association_reactions {
+        for (i, reaction) in self.catalyzed_association_reactions.iter().enumerate() {
             let mol1_opt = state
                 .simples
                 .iter()
@@ -324,7 +341,7 @@ impl PathWaysMetricProblem {
                 if mol1.available >= reaction.need_molecule_1
                     && mol2.available >= reaction.need_molecule_2
                 {
-                    actions.push(Self::get_associate_with_catalyze_action(reaction));
+                    actions.push(PathWaysMetricAction::AssociateWithCatalyze(i));
                 }
             }
         }
@@ -332,9 +349,12 @@ impl PathWaysMetricProblem {
     }
 
     // Collect all valid self-associate with catalyze actions
-    pub fn get_self_associate_with_catalyze_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_self_associate_with_catalyze_actions(
+        &self,
+        state: &State,
+    ) -> Vec<PathWaysMetricAction> {
         let mut actions = Vec::new();
-        for reaction in &self.catalyzed_self_association_reactions {
+        for (i, reaction) in self.catalyzed_self_association_reactions.iter().enumerate() {
             let mol1_opt = state
                 .simples
                 .iter()
@@ -342,7 +362,7 @@ impl PathWaysMetricProblem {
 
             if let Some(mol1) = mol1_opt {
                 if mol1.available >= reaction.need_molecule_1 {
-                    actions.push(Self::get_self_associate_with_catalyze_action(reaction));
+                    actions.push(PathWaysMetricAction::SelfAssociateWithCatalyze(i));
                 }
             }
         }
@@ -350,9 +370,9 @@ impl PathWaysMetricProblem {
     }
 
     // Collect all valid synthesis actions
-    pub fn get_synthesize_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_synthesize_actions(&self, state: &State) -> Vec<PathWaysMetricAction> {
         let mut actions = Vec::new();
-        for reaction in &self.synthesis_reactions {
+        for (i, reaction) in self.synthesis_reactions.iter().enumerate() {
             let mol1_opt = state
                 .simples
                 .iter()
@@ -360,7 +380,7 @@ impl PathWaysMetricProblem {
 
             if let Some(mol1) = mol1_opt {
                 if mol1.available >= reaction.need_molecule_1 {
-                    actions.push(Self::get_synthesize_action(reaction));
+                    actions.push(PathWaysMetricAction::Synthesize(i));
                 }
             }
         }
@@ -368,198 +388,116 @@ impl PathWaysMetricProblem {
     }
 
     // Apply a choose action: mark simple as chosen and increment num_subs
-    pub fn apply_choose_action(state: &State, action: &Action) -> State {
+    pub fn apply_choose_action(state: &State, simple: usize) -> State {
         let mut new_state = state.clone();
-        let simple_name = match action.parameters.get("simple_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for simple."),
-        };
-        if let Some(simple) = new_state
-            .simples
-            .iter_mut()
-            .find(|s| s.name == *simple_name)
-        {
-            simple.chosen = true; // mark as chosen
-            simple.possible = false; // no longer possible
-            new_state.num_subs += 1; // track substitution count
-        } else {
-            panic!("Simple with name {} not found", simple_name);
-        }
+        new_state.simples[simple].chosen = true; // mark as chosen
+        new_state.simples[simple].possible = false; // no longer possible
+        new_state.num_subs += 1; // track substitution count
         new_state
     }
 
     // Apply initialize action: increase availability of a simple
-    pub fn apply_initialize_action(state: &State, action: &Action) -> State {
+    pub fn apply_initialize_action(state: &State, simple: usize) -> State {
         let mut new_state = state.clone();
-        let simple_name = match action.parameters.get("simple_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for simple."),
-        };
-        if let Some(simple) = new_state
-            .simples
-            .iter_mut()
-            .find(|s| s.name == *simple_name)
-        {
-            simple.available += 1; // increment availability
-        } else {
-            panic!("Simple with name {} not found", simple_name);
-        }
+        new_state.simples[simple].available += 1; // increment availability
         new_state
     }
 
     // Apply association action: consume reactants, produce complex
-    pub fn apply_associate_action(state: &State, action: &Action) -> State {
+    pub fn apply_associate_action(state: &State, reaction: &AssociationReaction) -> State {
         let mut new_state = state.clone();
-        let simple_1_name = match action.parameters.get("molecule_1_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for simple_1."),
-        };
-        let simple_2_name = match action.parameters.get("molecule_2_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for simple_2."),
-        };
-        let complex_name = match action.parameters.get("molecule_3_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid name for complex."),
-        };
-        let simple_1_need = match action.parameters.get("need_molecule_1") {
-            Some(Value::Int(need)) => *need,
-            _ => panic!("Action.parameters do not contain a valid need for simple_1."),
-        };
-        let simple_2_need = match action.parameters.get("need_molecule_2") {
-            Some(Value::Int(need)) => *need,
-            _ => panic!("Action.parameters do not contain a valid need for simple_2."),
-        };
-        let complex_prod = match action.parameters.get("prod") {
-            Some(Value::Int(prod)) => *prod,
-            _ => panic!("Action.parameters do not contain a valid prod for complex."),
-        };
         // find indices of reactant simples
         let idx1 = new_state
             .simples
             .iter()
-            .position(|s| s.name == *simple_1_name)
-            .expect(&format!("Simple with name {} not found", simple_1_name));
+            .position(|s| s.name == reaction.molecule_1_name)
+            .unwrap_or_else(|| panic!("Simple with name {} not found", reaction.molecule_1_name));
         let idx2 = new_state
             .simples
             .iter()
-            .position(|s| s.name == *simple_2_name)
-            .expect(&format!("Simple with name {} not found", simple_2_name));
+            .position(|s| s.name == reaction.molecule_2_name)
+            .unwrap_or_else(|| panic!("Simple with name {} not found", reaction.molecule_2_name));
         // apply consumption and production
         if let Some(complex) = new_state
             .complexes
             .iter_mut()
-            .find(|c| c.name == *complex_name)
+            .find(|c| c.name == reaction.molecule_3_name)
         {
-            new_state.simples[idx1].available -= simple_1_need; // consume reactant1
-            new_state.simples[idx2].available -= simple_2_need; // consume reactant2
-            complex.available += complex_prod; // produce complex
+            new_state.simples[idx1].available -= reaction.need_molecule_1; // consume reactant1
+            new_state.simples[idx2].available -= reaction.need_molecule_2; // consume reactant2
+            complex.available += reaction.prod; // produce complex
         } else {
-            panic!("Complex with name {} not found", complex_name);
+            panic!("Complex with name {} not found", reaction.molecule_3_name);
         }
 
         new_state
     }
 
     // Apply catalyzed association: consume reactant, produce complex
-    pub fn apply_associate_with_catalyze_action(state: &State, action: &Action) -> State {
+    pub fn apply_associate_with_catalyze_action(
+        state: &State,
+        reaction: &CatalyzedAssociationReaction,
+    ) -> State {
         let mut new_state = state.clone();
-        let simple_1_name = match action.parameters.get("molecule_1_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action.parameters do not contain a valid name for simple_1."),
-        };
-        let complex_name = match action.parameters.get("molecule_3_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action.parameters do not contain a valid name for complex."),
-        };
-        let simple_1_need = match action.parameters.get("need_molecule_1") {
-            Some(Value::Int(need)) => *need,
-            _ => panic!("Action.parameters do not contain a valid need for simple_1."),
-        };
-        let complex_prod = match action.parameters.get("prod") {
-            Some(Value::Int(prod)) => *prod,
-            _ => panic!("Action.parameters do not contain a valid prod for complex."),
-        };
         if let (Some(simple_1), Some(complex)) = (
             new_state
                 .simples
                 .iter_mut()
-                .find(|s| s.name == *simple_1_name),
+                .find(|s| s.name == reaction.molecule_1_name),
             new_state
                 .complexes
                 .iter_mut()
-                .find(|c| c.name == *complex_name),
+                .find(|c| c.name == reaction.molecule_3_name),
         ) {
-            simple_1.available -= simple_1_need; // consume reactant for catalysis
-            complex.available += complex_prod; // produce complex
+            simple_1.available -= reaction.need_molecule_1; // consume reactant for catalysis
+            complex.available += reaction.prod; // produce complex
         } else {
             panic!(
                 "Simple_1 with name {} or complex with name {} not found",
-                simple_1_name, complex_name
+                reaction.molecule_1_name, reaction.molecule_3_name
             );
         }
         new_state
     }
 
     // Apply self-associate catalyzed reaction: consume reactant, produce complex
-    pub fn apply_self_associate_with_catalyze_action(state: &State, action: &Action) -> State {
+    pub fn apply_self_associate_with_catalyze_action(
+        state: &State,
+        reaction: &CatalyzedSelfAssociationReaction,
+    ) -> State {
         let mut new_state = state.clone();
-        let simple_1_name = match action.parameters.get("molecule_1_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action.parameters do not contain a valid name for simple_1."),
-        };
-        let complex_name = match action.parameters.get("molecule_2_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action.parameters do not contain a valid name for complex."),
-        };
-        let simple_1_need = match action.parameters.get("need_molecule_1") {
-            Some(Value::Int(need)) => *need,
-            _ => panic!("Action.parameters do not contain a valid need for simple_1."),
-        };
-        let complex_prod = match action.parameters.get("prod") {
-            Some(Value::Int(prod)) => *prod,
-            _ => panic!("Action.parameters do not contain a valid prod for complex."),
-        };
         if let (Some(simple_1), Some(complex)) = (
             new_state
                 .simples
                 .iter_mut()
-                .find(|s| s.name == *simple_1_name),
+                .find(|s| s.name == reaction.molecule_1_name),
             new_state
                 .complexes
                 .iter_mut()
-                .find(|c| c.name == *complex_name),
+                .find(|c| c.name == reaction.molecule_2_name),
         ) {
-            simple_1.available -= simple_1_need; // consume reactant
-            complex.available += complex_prod; // produce complex
+            simple_1.available -= reaction.need_molecule_1; // consume reactant
+            complex.available += reaction.prod; // produce complex
         } else {
             panic!(
                 "Simple_1 with name {} or complex with name {} not found",
-                simple_1_name, complex_name
+                reaction.molecule_1_name, reaction.molecule_2_name
             );
         }
         new_state
     }
 
     // Apply synthesis action: produce simple molecule
-    pub fn apply_synthesize_action(state: &State, action: &Action) -> State {
+    pub fn apply_synthesize_action(state: &State, reaction: &SynthesisReaction) -> State {
         let mut new_state = state.clone();
-        let simple_name = match action.parameters.get("molecule_2_name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action.parameters do not contain a valid name for simple."),
-        };
-        let simple_prod = match action.parameters.get("prod") {
-            Some(Value::Int(prod)) => *prod,
-            _ => panic!("Action.parameters do not contain a valid prod for simple."),
-        };
         if let Some(simple) = new_state
             .simples
             .iter_mut()
-            .find(|s| s.name == *simple_name)
+            .find(|s| s.name == reaction.molecule_2_name)
         {
-            simple.available += simple_prod; // increase availability
+            simple.available += reaction.prod; // increase availability
         } else {
-            panic!("Simple with name {} not found", simple_name);
+            panic!("Simple with name {} not found", reaction.molecule_2_name);
         }
         new_state
     }
@@ -568,8 +506,11 @@ impl PathWaysMetricProblem {
 impl Problem for PathWaysMetricProblem {
     type State = State;
 
+    type Action = PathWaysMetricAction;
+    type Cost = i32;
+
     // Gather all possible actions from current state
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<PathWaysMetricAction> {
         let mut actions = Vec::new();
         actions.extend(self.get_choose_initialize_simple_actions(state));
         actions.extend(self.get_associate_actions(state));
@@ -580,21 +521,59 @@ impl Problem for PathWaysMetricProblem {
     }
 
     // Apply a given action by delegating to the correct apply_* method
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("synthesize_") {
-            Self::apply_synthesize_action(state, action)
-        } else if action.name.starts_with("self_associate_with_catalyze_") {
-            Self::apply_self_associate_with_catalyze_action(state, action)
-        } else if action.name.starts_with("associate_with_catalyze_") {
-            Self::apply_associate_with_catalyze_action(state, action)
-        } else if action.name.starts_with("associate_") {
-            Self::apply_associate_action(state, action)
-        } else if action.name.starts_with("initialize_") {
-            Self::apply_initialize_action(state, action)
-        } else if action.name.starts_with("choose_") {
-            Self::apply_choose_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    fn apply_action(&self, state: &State, action: &PathWaysMetricAction) -> State {
+        match *action {
+            PathWaysMetricAction::Choose(simple) => Self::apply_choose_action(state, simple),
+            PathWaysMetricAction::Initialize(simple) => {
+                Self::apply_initialize_action(state, simple)
+            }
+            PathWaysMetricAction::Associate(i) => {
+                Self::apply_associate_action(state, &self.association_reactions[i])
+            }
+            PathWaysMetricAction::AssociateWithCatalyze(i) => {
+                Self::apply_associate_with_catalyze_action(
+                    state,
+                    &self.catalyzed_association_reactions[i],
+                )
+            }
+            PathWaysMetricAction::SelfAssociateWithCatalyze(i) => {
+                Self::apply_self_associate_with_catalyze_action(
+                    state,
+                    &self.catalyzed_self_association_reactions[i],
+                )
+            }
+            PathWaysMetricAction::Synthesize(i) => {
+                Self::apply_synthesize_action(state, &self.synthesis_reactions[i])
+            }
+        }
+    }
+
+    fn action_cost(&self, _state: &State, _action: &PathWaysMetricAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &PathWaysMetricAction) -> Action {
+        match *action {
+            PathWaysMetricAction::Choose(simple) => {
+                Self::get_choose_simple_action(&state.simples[simple])
+            }
+            PathWaysMetricAction::Initialize(simple) => {
+                Self::get_initialize_simple_action(&state.simples[simple])
+            }
+            PathWaysMetricAction::Associate(i) => {
+                Self::get_associate_action(&self.association_reactions[i])
+            }
+            PathWaysMetricAction::AssociateWithCatalyze(i) => {
+                Self::get_associate_with_catalyze_action(&self.catalyzed_association_reactions[i])
+            }
+            PathWaysMetricAction::SelfAssociateWithCatalyze(i) => {
+                Self::get_self_associate_with_catalyze_action(
+                    &self.catalyzed_self_association_reactions[i],
+                )
+            }
+            PathWaysMetricAction::Synthesize(i) => {
+                Self::get_synthesize_action(&self.synthesis_reactions[i])
+            }
         }
     }
 
@@ -624,3 +603,82 @@ impl Problem for PathWaysMetricProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for PathWaysMetricProblem {
+    /// Chosen/possible flags and availability of every simple, availability of every
+    /// complex, and the number of substitutions.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        for simple in &state.simples {
+            variables.push(Variable::boolean(format!("chosen_{}", simple.name)));
+            variables.push(Variable::boolean(format!("possible_{}", simple.name)));
+            variables.push(Variable::integer(
+                format!("available_{}", simple.name),
+                Some(simple.available.min(0) as i64),
+                None,
+            ));
+        }
+        for complex in &state.complexes {
+            variables.push(Variable::integer(
+                format!("available_{}", complex.name),
+                Some(complex.available.min(0) as i64),
+                None,
+            ));
+        }
+        variables.push(Variable::integer(
+            "num_subs".to_string(),
+            Some(state.num_subs as i64),
+            None,
+        ));
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        for simple in &state.simples {
+            values.extend([
+                Value::Bool(simple.chosen),
+                Value::Bool(simple.possible),
+                Value::Int(simple.available),
+            ]);
+        }
+        values.extend(state.complexes.iter().map(|c| Value::Int(c.available)));
+        values.push(Value::Int(state.num_subs));
+        values
+    }
+
+    /// The summed availability of the two molecules of every condition reaches its amount.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let complexes_start = 3 * state.simples.len();
+        // Like `Goal::is_goal_state`, a molecule counts once as a simple and once as a complex.
+        let add_molecule = |mut expression: LinearExpression, name: &String| {
+            if let Some(i) = state.simples.iter().position(|s| s.name == *name) {
+                expression = expression.term(1.0, 3 * i + 2);
+            }
+            if let Some(i) = state.complexes.iter().position(|c| c.name == *name) {
+                expression = expression.term(1.0, complexes_start + i);
+            }
+            expression
+        };
+        let conditions = self
+            .goal
+            .conditions
+            .iter()
+            .map(|c| {
+                let sum = add_molecule(
+                    add_molecule(LinearExpression::default(), &c.molecule_1_name),
+                    &c.molecule_2_name,
+                );
+                NumericCondition::new(
+                    sum,
+                    Comparison::Ge,
+                    &LinearExpression::constant(c.amount_condition as f64),
+                )
+            })
+            .collect();
+        FactoredGoal {
+            conditions,
+            exact: true,
+        }
+    }
+}
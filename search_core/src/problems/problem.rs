@@ -1,11 +1,42 @@
-use crate::search::{action::Action, state::StateTrait};
+use crate::search::{
+    action::{Action, ActionTrait},
+    cost::CostTrait,
+    state::StateTrait,
+};
 
 pub trait Problem {
     type State: StateTrait; // Associated type for State
+    type Action: ActionTrait; // Associated type for the domain's actions
+    type Cost: CostTrait; // Cost type of actions and plans, e.g. i32
 
-    fn get_possible_actions(&self, state: &Self::State) -> Vec<Action>;
-    fn apply_action(&self, state: &Self::State, action: &Action) -> Self::State;
+    fn get_possible_actions(&self, state: &Self::State) -> Vec<Self::Action>;
+    fn apply_action(&self, state: &Self::State, action: &Self::Action) -> Self::State;
+    /// Cost of applying `action` in `state`.
+    fn action_cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost;
+    /// Named form of `action` as applied in `state`, used for output.
+    fn describe_action(&self, state: &Self::State, action: &Self::Action) -> Action;
     fn is_goal_state(&self, state: &Self::State) -> bool;
     fn heuristic(&self, state: &Self::State) -> f64;
+
+    /// Actions among `actions`, those applicable in `state`, that look promising, used by
+    /// enforced hill-climbing and by GBFS with a preferred queue. None by default.
+    fn preferred_actions(
+        &self,
+        _state: &Self::State,
+        _actions: &[Self::Action],
+    ) -> Vec<Self::Action> {
+        Vec::new()
+    }
     fn load_state_from_json(json_path: &str) -> (Self::State, Self);
+
+    /// Named form of a plan, replayed from `state`.
+    fn describe_plan(&self, state: &Self::State, plan: &[Self::Action]) -> Vec<Action> {
+        let mut state = state.clone();
+        let mut described = Vec::with_capacity(plan.len());
+        for action in plan {
+            described.push(self.describe_action(&state, action));
+            state = self.apply_action(&state, action);
+        }
+        described
+    }
 }
@@ -1,5 +1,8 @@
+use crate::heuristics::numeric_task::NumericCondition;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::BTreeMap;
@@ -226,94 +229,96 @@ impl Grid {
     }
 }
 
+/// Direction in which a vehicle is moved one cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        }
+    }
+}
+
+/// Moves one vehicle, referred to by its position in the state's list of its kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RedCarAction {
+    HorizontalCar(usize, Direction),
+    VerticalCar(usize, Direction),
+    HorizontalTruck(usize, Direction),
+    VerticalTruck(usize, Direction),
+}
+
+impl ActionTrait for RedCarAction {}
+
 // Main problem definition for the red‐car puzzle
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RedCarProblem {}
 
 impl RedCarProblem {
     /// Generate left/right moves for each horizontal car
-    pub fn get_possible_horizontal_cars_actions(state: &State) -> Vec<Action> {
+    pub fn get_possible_horizontal_cars_actions(state: &State) -> Vec<RedCarAction> {
         let mut actions = Vec::new();
-        for car in &state.horizontalcars {
-            if car.can_move("right", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_right", car.name);
-                params.insert("vehicle".into(), Value::Text(car.name.clone()));
-                params.insert("move".into(), Value::Text("right".into()));
-                actions.push(Action::new(name, 1, params));
-            }
-            if car.can_move("left", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_left", car.name);
-                params.insert("vehicle".into(), Value::Text(car.name.clone()));
-                params.insert("move".into(), Value::Text("left".into()));
-                actions.push(Action::new(name, 1, params));
+        for (i, car) in state.horizontalcars.iter().enumerate() {
+            for direction in [Direction::Right, Direction::Left] {
+                if car.can_move(direction.as_str(), &state.grid) {
+                    actions.push(RedCarAction::HorizontalCar(i, direction));
+                }
             }
         }
         actions
     }
 
     /// Generate up/down moves for each vertical car
-    pub fn get_possible_vertical_cars_actions(state: &State) -> Vec<Action> {
+    pub fn get_possible_vertical_cars_actions(state: &State) -> Vec<RedCarAction> {
         let mut actions = Vec::new();
-        for car in &state.verticalcars {
-            if car.can_move("up", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_up", car.name);
-                params.insert("vehicle".into(), Value::Text(car.name.clone()));
-                params.insert("move".into(), Value::Text("up".into()));
-                actions.push(Action::new(name, 1, params));
-            }
-            if car.can_move("down", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_down", car.name);
-                params.insert("vehicle".into(), Value::Text(car.name.clone()));
-                params.insert("move".into(), Value::Text("down".into()));
-                actions.push(Action::new(name, 1, params));
+        for (i, car) in state.verticalcars.iter().enumerate() {
+            for direction in [Direction::Up, Direction::Down] {
+                if car.can_move(direction.as_str(), &state.grid) {
+                    actions.push(RedCarAction::VerticalCar(i, direction));
+                }
             }
         }
         actions
     }
 
     /// Generate left/right moves for each horizontal truck
-    pub fn get_possible_horizontal_trucks_actions(state: &State) -> Vec<Action> {
+    pub fn get_possible_horizontal_trucks_actions(state: &State) -> Vec<RedCarAction> {
         let mut actions = Vec::new();
-        for truck in &state.horizontaltrucks {
-            if truck.can_move("right", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_right", truck.name);
-                params.insert("vehicle".into(), Value::Text(truck.name.clone()));
-                params.insert("move".into(), Value::Text("right".into()));
-                actions.push(Action::new(name, 1, params));
-            }
-            if truck.can_move("left", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_left", truck.name);
-                params.insert("vehicle".into(), Value::Text(truck.name.clone()));
-                params.insert("move".into(), Value::Text("left".into()));
-                actions.push(Action::new(name, 1, params));
+        for (i, truck) in state.horizontaltrucks.iter().enumerate() {
+            for direction in [Direction::Right, Direction::Left] {
+                if truck.can_move(direction.as_str(), &state.grid) {
+                    actions.push(RedCarAction::HorizontalTruck(i, direction));
+                }
             }
         }
         actions
     }
 
     /// Generate up/down moves for each vertical truck
-    pub fn get_possible_vertical_trucks_actions(state: &State) -> Vec<Action> {
+    pub fn get_possible_vertical_trucks_actions(state: &State) -> Vec<RedCarAction> {
         let mut actions = Vec::new();
-        for truck in &state.verticaltrucks {
-            if truck.can_move("up", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_up", truck.name);
-                params.insert("vehicle".into(), Value::Text(truck.name.clone()));
-                params.insert("move".into(), Value::Text("up".into()));
-                actions.push(Action::new(name, 1, params));
-            }
-            if truck.can_move("down", &state.grid) {
-                let mut params = std::collections::HashMap::new();
-                let name = format!("{}_move_down", truck.name);
-                params.insert("vehicle".into(), Value::Text(truck.name.clone()));
-                params.insert("move".into(), Value::Text("down".into()));
-                actions.push(Action::new(name, 1, params));
+        for (i, truck) in state.verticaltrucks.iter().enumerate() {
+            for direction in [Direction::Up, Direction::Down] {
+                if truck.can_move(direction.as_str(), &state.grid) {
+                    actions.push(RedCarAction::VerticalTruck(i, direction));
+                }
             }
         }
         actions
@@ -323,8 +328,11 @@ impl RedCarProblem {
 impl Problem for RedCarProblem {
     type State = State;
 
+    type Action = RedCarAction;
+    type Cost = i32;
+
     /// Combine all possible move actions into one list
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<RedCarAction> {
         let mut acts = Vec::new();
         acts.extend(Self::get_possible_horizontal_cars_actions(state));
         acts.extend(Self::get_possible_vertical_cars_actions(state));
@@ -334,50 +342,55 @@ impl Problem for RedCarProblem {
     }
 
     /// Apply a chosen action: move the matching vehicle and update grid
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        let dir = action
-            .parameters
-            .get("move")
-            .and_then(|v| match v {
-                Value::Text(s) => Some(s.as_str()),
-                _ => None,
-            })
-            .expect("Missing move");
-        let veh = action
-            .parameters
-            .get("vehicle")
-            .and_then(|v| match v {
-                Value::Text(s) => Some(s.as_str()),
-                _ => None,
-            })
-            .expect("Missing vehicle");
+    fn apply_action(&self, state: &State, action: &RedCarAction) -> State {
         let mut ns = state.clone();
-        if let Some(v) = ns.horizontalcars.iter_mut().find(|v| v.name == veh) {
-            let old = v.get_positions();
-            v.apply_action(dir);
-            let new = v.get_positions();
-            ns.grid.update(old, new, veh);
-        } else if let Some(v) = ns.verticalcars.iter_mut().find(|v| v.name == veh) {
-            let old = v.get_positions();
-            v.apply_action(dir);
-            let new = v.get_positions();
-            ns.grid.update(old, new, veh);
-        } else if let Some(v) = ns.horizontaltrucks.iter_mut().find(|v| v.name == veh) {
-            let old = v.get_positions();
-            v.apply_action(dir);
-            let new = v.get_positions();
-            ns.grid.update(old, new, veh);
-        } else if let Some(v) = ns.verticaltrucks.iter_mut().find(|v| v.name == veh) {
-            let old = v.get_positions();
-            v.apply_action(dir);
-            let new = v.get_positions();
-            ns.grid.update(old, new, veh);
-        } else {
-            panic!("Unknown vehicle {}", veh);
-        }
+        let (old, new, veh) = match *action {
+            RedCarAction::HorizontalCar(i, dir) => {
+                let v = &mut ns.horizontalcars[i];
+                let old = v.get_positions();
+                v.apply_action(dir.as_str());
+                (old, v.get_positions(), v.name.clone())
+            }
+            RedCarAction::VerticalCar(i, dir) => {
+                let v = &mut ns.verticalcars[i];
+                let old = v.get_positions();
+                v.apply_action(dir.as_str());
+                (old, v.get_positions(), v.name.clone())
+            }
+            RedCarAction::HorizontalTruck(i, dir) => {
+                let v = &mut ns.horizontaltrucks[i];
+                let old = v.get_positions();
+                v.apply_action(dir.as_str());
+                (old, v.get_positions(), v.name.clone())
+            }
+            RedCarAction::VerticalTruck(i, dir) => {
+                let v = &mut ns.verticaltrucks[i];
+                let old = v.get_positions();
+                v.apply_action(dir.as_str());
+                (old, v.get_positions(), v.name.clone())
+            }
+        };
+        ns.grid.update(old, new, &veh);
         ns
     }
 
+    fn action_cost(&self, _state: &State, _action: &RedCarAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &RedCarAction) -> Action {
+        let (veh, dir) = match *action {
+            RedCarAction::HorizontalCar(i, dir) => (&state.horizontalcars[i].name, dir),
+            RedCarAction::VerticalCar(i, dir) => (&state.verticalcars[i].name, dir),
+            RedCarAction::HorizontalTruck(i, dir) => (&state.horizontaltrucks[i].name, dir),
+            RedCarAction::VerticalTruck(i, dir) => (&state.verticaltrucks[i].name, dir),
+        };
+        let mut params = std::collections::HashMap::new();
+        params.insert("vehicle".into(), Value::Text(veh.clone()));
+        params.insert("move".into(), Value::Text(dir.as_str().into()));
+        Action::new(format!("{}_move_{}", veh, dir.as_str()), 1, params)
+    }
+
     /// Goal: the red-car must reach the right edge on row 2
     fn is_goal_state(&self, state: &State) -> bool {
         for car in &state.horizontalcars {
@@ -402,4 +415,40 @@ impl Problem for RedCarProblem {
             serde_json::from_value(pr.clone()).expect("Failed to deserialize problem");
         (state, problem)
     }
-}
\ No newline at end of file
+}
+impl FactoredProblem for RedCarProblem {
+    /// One variable per vehicle, in the order horizontal cars, vertical cars, horizontal
+    /// trucks, vertical trucks: the coordinate along which it slides.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let slide = |axis: &str, name: &String, size: i32, length: i32| {
+            Variable::integer(format!("{}_{}", axis, name), Some(0), Some((size - length) as i64))
+        };
+        let (cols, rows) = (state.grid.col_size, state.grid.row_size);
+        let mut variables = Vec::new();
+        variables.extend(state.horizontalcars.iter().map(|v| slide("x", &v.name, cols, 2)));
+        variables.extend(state.verticalcars.iter().map(|v| slide("y", &v.name, rows, 2)));
+        variables.extend(state.horizontaltrucks.iter().map(|v| slide("x", &v.name, cols, 3)));
+        variables.extend(state.verticaltrucks.iter().map(|v| slide("y", &v.name, rows, 3)));
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        values.extend(state.horizontalcars.iter().map(|v| Value::Int(v.x)));
+        values.extend(state.verticalcars.iter().map(|v| Value::Int(v.y)));
+        values.extend(state.horizontaltrucks.iter().map(|v| Value::Int(v.x)));
+        values.extend(state.verticaltrucks.iter().map(|v| Value::Int(v.y)));
+        values
+    }
+
+    /// The red car (a horizontal car) reaches the right edge; unreachable off row 2.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        match state.horizontalcars.iter().position(|c| c.name == "red-car") {
+            Some(i) if state.horizontalcars[i].y == 2 => FactoredGoal {
+                conditions: vec![NumericCondition::equals(i, (state.grid.col_size - 2) as f64)],
+                exact: true,
+            },
+            _ => FactoredGoal::unreachable(),
+        }
+    }
+}
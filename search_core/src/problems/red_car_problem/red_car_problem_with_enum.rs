@@ -1,5 +1,10 @@
+use crate::heuristics::numeric_task::NumericCondition;
+use crate::heuristics::pattern_database::ProjectableProblem;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::problems::red_car_problem::red_car_problem::Direction;
+use crate::search::action::{Action, ActionTrait};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::{BTreeMap, HashMap};
@@ -208,25 +213,29 @@ impl StateTrait for State {}
 
 // --- Red Car Problem Implementation ---
 
+/// Moves the vehicle at the given position in the state one cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RedCarAction {
+    pub vehicle: usize,
+    pub direction: Direction,
+}
+
+impl ActionTrait for RedCarAction {}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RedCarProblem {}
 
 impl RedCarProblem {
     /// Generate all valid moves (up/down/left/right) for every vehicle
-    pub fn get_possible_actions(state: &State) -> Vec<Action> {
-        let directions = ["left", "right", "up", "down"];
+    pub fn get_possible_actions(state: &State) -> Vec<RedCarAction> {
         let mut actions = Vec::new();
-        for vehicle in &state.vehicles {
-            for &dir in &directions {
-                if vehicle.can_move(dir, &state.grid) {
-                    let mut params = HashMap::new();
-                    let name = format!("{}_move_{}", vehicle.get_name(), dir);
-                    params.insert(
-                        "vehicle".to_string(),
-                        Value::Text(vehicle.get_name().to_string()),
-                    );
-                    params.insert("move".to_string(), Value::Text(dir.to_string()));
-                    actions.push(Action::new(name, 1, params));
+        for (i, vehicle) in state.vehicles.iter().enumerate() {
+            for direction in Direction::ALL {
+                if vehicle.can_move(direction.as_str(), &state.grid) {
+                    actions.push(RedCarAction {
+                        vehicle: i,
+                        direction,
+                    });
                 }
             }
         }
@@ -236,34 +245,38 @@ impl RedCarProblem {
 
 impl Problem for RedCarProblem {
     type State = State;
+    type Action = RedCarAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<RedCarAction> {
         Self::get_possible_actions(state)
     }
 
     /// Applies the chosen action by moving the corresponding vehicle and updating the grid
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        let dir = match action.parameters.get("move") {
-            Some(Value::Text(d)) => d.as_str(),
-            _ => panic!("Missing 'move' parameter"),
-        };
-        let veh_name = match action.parameters.get("vehicle") {
-            Some(Value::Text(v)) => v.as_str(),
-            _ => panic!("Missing 'vehicle' parameter"),
-        };
+    fn apply_action(&self, state: &State, action: &RedCarAction) -> State {
         let mut new_state = state.clone();
-        for vehicle in new_state.vehicles.iter_mut() {
-            if vehicle.get_name() == veh_name {
-                let old_pos = vehicle.get_positions();
-                vehicle.apply_action(dir);
-                let new_pos = vehicle.get_positions();
-                new_state.grid.update(old_pos, new_pos, veh_name);
-                break;
-            }
-        }
+        let vehicle = &mut new_state.vehicles[action.vehicle];
+        let old_pos = vehicle.get_positions();
+        vehicle.apply_action(action.direction.as_str());
+        let new_pos = vehicle.get_positions();
+        let veh_name = vehicle.get_name().to_string();
+        new_state.grid.update(old_pos, new_pos, &veh_name);
         new_state
     }
 
+    fn action_cost(&self, _state: &State, _action: &RedCarAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &RedCarAction) -> Action {
+        let vehicle = state.vehicles[action.vehicle].get_name();
+        let dir = action.direction.as_str();
+        let mut params = HashMap::new();
+        params.insert("vehicle".to_string(), Value::Text(vehicle.to_string()));
+        params.insert("move".to_string(), Value::Text(dir.to_string()));
+        Action::new(format!("{}_move_{}", vehicle, dir), 1, params)
+    }
+
     /// Goal check: red-car must end at right edge on row 2
     fn is_goal_state(&self, state: &State) -> bool {
         for v in &state.vehicles {
@@ -293,3 +306,80 @@ impl Problem for RedCarProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for RedCarProblem {
+    /// One variable per vehicle: the coordinate along which it slides (x for horizontal
+    /// vehicles, y for vertical ones); the other coordinate never changes.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        state
+            .vehicles
+            .iter()
+            .map(|vehicle| {
+                let (axis, size, length) = match vehicle {
+                    Vehicle::HorizontalCar(_) => ("x", state.grid.col_size, 2),
+                    Vehicle::VerticalCar(_) => ("y", state.grid.row_size, 2),
+                    Vehicle::HorizontalTruck(_) => ("x", state.grid.col_size, 3),
+                    Vehicle::VerticalTruck(_) => ("y", state.grid.row_size, 3),
+                };
+                Variable::integer(
+                    format!("{}_{}", axis, vehicle.get_name()),
+                    Some(0),
+                    Some((size - length) as i64),
+                )
+            })
+            .collect()
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        state
+            .vehicles
+            .iter()
+            .map(|vehicle| match vehicle {
+                Vehicle::HorizontalCar(car) => Value::Int(car.x),
+                Vehicle::VerticalCar(car) => Value::Int(car.y),
+                Vehicle::HorizontalTruck(truck) => Value::Int(truck.x),
+                Vehicle::VerticalTruck(truck) => Value::Int(truck.y),
+            })
+            .collect()
+    }
+
+    /// The red car reaches the right edge of row 2; unreachable if its fixed coordinate
+    /// already rules that out.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let col = state.grid.col_size - 2;
+        let red_car = state
+            .vehicles
+            .iter()
+            .position(|v| v.get_name() == "red-car");
+        let target = red_car.and_then(|i| match &state.vehicles[i] {
+            Vehicle::HorizontalCar(car) if car.y == 2 => Some((i, col)),
+            Vehicle::HorizontalTruck(truck) if truck.y == 2 => Some((i, col)),
+            Vehicle::VerticalCar(car) if car.x == col => Some((i, 2)),
+            Vehicle::VerticalTruck(truck) if truck.x == col => Some((i, 2)),
+            _ => None,
+        });
+        match target {
+            Some((i, value)) => FactoredGoal {
+                conditions: vec![NumericCondition::equals(i, value as f64)],
+                exact: true,
+            },
+            None => FactoredGoal::unreachable(),
+        }
+    }
+}
+
+impl ProjectableProblem for RedCarProblem {
+    fn variable_groups(&self, state: &State) -> Vec<Vec<usize>> {
+        (0..state.vehicles.len()).map(|i| vec![i]).collect()
+    }
+
+    /// Keeps the selected vehicles; the cells of the others become free.
+    fn project(&self, state: &State, groups: &[usize]) -> (Self, State) {
+        let vehicles: Vec<Vehicle> = groups.iter().map(|&i| state.vehicles[i].clone()).collect();
+        let mut grid = Grid::new(state.grid.row_size, state.grid.col_size);
+        for vehicle in &vehicles {
+            grid.update(Vec::new(), vehicle.get_positions(), vehicle.get_name());
+        }
+        (RedCarProblem {}, State { grid, vehicles })
+    }
+}
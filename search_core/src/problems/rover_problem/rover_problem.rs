@@ -1,12 +1,16 @@
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::soft_goals::{GoalEntry, SoftGoalProblem};
+use crate::search::state_codec::{BitReader, BitWriter, PackedProblem, StateCodec, SymbolTable};
+use crate::search::{state::StateTrait, state::Value};
+use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Store {
     pub id: String,
     pub rover_id: String, // The rover that owns this store
@@ -14,7 +18,7 @@ pub struct Store {
     pub full: bool, // when the store has a sample in it
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Camera {
     pub id: String,
     pub rover_id: String, // The rover that owns this camera
@@ -23,7 +27,7 @@ pub struct Camera {
     pub calibrated_objective: Option<String>, // The objective that this camera is calibrated to
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Rover {
     pub id: String,
     pub location: String,
@@ -35,7 +39,7 @@ pub struct Rover {
     pub store: Store,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Waypoint {
     pub id: String,
     pub has_soil_sample: bool, // Matches PDDL's (at_soil_sample ?w - waypoint)
@@ -43,13 +47,13 @@ pub struct Waypoint {
     pub in_sun: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Objective {
     pub id: String,
     pub visible_from: Vec<String>, // Waypoint IDs
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Lander {
     // Lander is a waypoint that can be communicated with
     pub id: String,
@@ -57,23 +61,91 @@ pub struct Lander {
     pub channel_free: bool,
 }
 
-// State contains only dynamic elements that change during search
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+// State contains only dynamic elements that change during search; maps and sets are ordered
+// so that equal states hash equally
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct State {
-    pub rovers: HashMap<String, Rover>, // Rover ID -> Rover
-    pub cameras: HashMap<String, Camera>, // Camera ID -> Camera
-    pub waypoints: HashMap<String, Waypoint>, // Waypoint ID -> Waypoint
-    pub landers: HashMap<String, Lander>,  // Lander ID -> Lander
-    pub soil_analysis: HashMap<String, HashSet<String>>, // Tracks which rover has analyzed soil at which waypoint
-    pub rock_analysis: HashMap<String, HashSet<String>>, // Tracks which rover has analyzed rock at which waypoint
-    pub images: HashMap<String, HashMap<String, HashSet<String>>>,  // Maps rover -> objective -> set of image modes captured
-    pub communicated_soil_data: HashSet<String>, // Tracks which soil data has been communicated
-    pub communicated_rock_data: HashSet<String>, // Tracks which rock data has been communicated
-    pub communicated_image_data: HashMap<String, HashSet<String>>, // Maps rover -> set of image modes communicated
-    pub recharges: i32,  // Number of recharge actions taken
+    pub rovers: BTreeMap<String, Rover>, // Rover ID -> Rover
+    pub cameras: BTreeMap<String, Camera>, // Camera ID -> Camera
+    pub waypoints: BTreeMap<String, Waypoint>, // Waypoint ID -> Waypoint
+    pub landers: BTreeMap<String, Lander>,  // Lander ID -> Lander
+    pub soil_analysis: BTreeMap<String, BTreeSet<String>>, // Tracks which rover has analyzed soil at which waypoint
+    pub rock_analysis: BTreeMap<String, BTreeSet<String>>, // Tracks which rover has analyzed rock at which waypoint
+    pub images: BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,  // Maps rover -> objective -> set of image modes captured
+    pub communicated_soil_data: BTreeSet<String>, // Tracks which soil data has been communicated
+    pub communicated_rock_data: BTreeSet<String>, // Tracks which rock data has been communicated
+    pub communicated_image_data: BTreeMap<String, BTreeSet<String>>, // Maps rover -> set of image modes communicated
 }
 impl StateTrait for State {}
 
+/// Actions of the rover domain. Every object is referred to by its position in the
+/// corresponding fixed collection: rovers, cameras, waypoints (including navigation targets
+/// and the waypoints of analysed samples) and landers in the state, objectives in the
+/// problem, and modes in `MODES`, so that an action names the same operator in every state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoverAction {
+    Navigate {
+        rover: usize,
+        to: usize,
+    },
+    Recharge(usize),
+    SampleSoil {
+        rover: usize,
+        waypoint: usize,
+    },
+    SampleRock {
+        rover: usize,
+        waypoint: usize,
+    },
+    Drop(usize),
+    Calibrate {
+        rover: usize,
+        camera: usize,
+    },
+    TakeImage {
+        rover: usize,
+        camera: usize,
+        mode: usize,
+    },
+    CommunicateSoilData {
+        rover: usize,
+        sample: usize,
+        lander: usize,
+    },
+    CommunicateRockData {
+        rover: usize,
+        sample: usize,
+        lander: usize,
+    },
+    CommunicateImageData {
+        rover: usize,
+        objective: usize,
+        mode: usize,
+        lander: usize,
+    },
+}
+
+impl ActionTrait for RoverAction {}
+
+/// Camera modes of the domain, in the order their ids refer to.
+pub const MODES: [&str; 3] = ["colour", "high_res", "low_res"];
+
+fn mode_id(mode: &str) -> usize {
+    MODES
+        .iter()
+        .position(|&m| m == mode)
+        .unwrap_or_else(|| panic!("Unknown camera mode {}", mode))
+}
+
+impl RoverAction {
+    /// Increment of the PDDL `recharges` metric. States do not count recharges, so that equal
+    /// configurations reached along different paths are the same state; the count starts at
+    /// zero, so summing this over a plan gives its final value.
+    pub fn recharges(&self) -> i32 {
+        matches!(self, RoverAction::Recharge(_)) as i32
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GoalCondition {
     SoilDataCommunicated { waypoint: String },
@@ -96,11 +168,27 @@ impl GoalCondition {
                 .map_or(false, |modes| modes.contains(mode)),
         }
     }
+
+    pub fn name(&self) -> String {
+        match self {
+            GoalCondition::SoilDataCommunicated { waypoint } => {
+                format!("communicated_soil_data_{}", waypoint)
+            }
+            GoalCondition::RockDataCommunicated { waypoint } => {
+                format!("communicated_rock_data_{}", waypoint)
+            }
+            GoalCondition::ImageDataCommunicated { objective, mode } => {
+                format!("communicated_image_data_{}_{}", objective, mode)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Goal {
     pub conditions: Vec<GoalCondition>,
+    #[serde(default)]
+    pub utilities: Vec<Option<OrderedFloat<f64>>>, // Utility of each condition, hard if missing
 }
 
 impl Goal {
@@ -113,12 +201,59 @@ impl Goal {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RoverProblem {
     pub goal: Goal,
-    pub objectives: HashMap<String, Objective>, // Static data
-    pub can_traverse: HashMap<String, HashMap<String, Vec<String>>>, // Static data
-    pub visible: HashMap<String, Vec<String>>,  // Static data
+    pub objectives: BTreeMap<String, Objective>, // Static data
+    pub can_traverse: BTreeMap<String, BTreeMap<String, Vec<String>>>, // Static data
+    pub visible: BTreeMap<String, Vec<String>>,  // Static data
+    // Ids of the objects that actions refer to by position, filled from the initial state
+    // by `index_objects`
+    #[serde(skip)]
+    pub rover_ids: Vec<String>,
+    #[serde(skip)]
+    pub camera_ids: Vec<String>,
+    #[serde(skip)]
+    pub waypoint_ids: Vec<String>,
+    #[serde(skip)]
+    pub lander_ids: Vec<String>,
+    #[serde(skip)]
+    pub objective_ids: Vec<String>,
+    #[serde(skip)]
+    pub waypoint_positions: HashMap<String, usize>, // Waypoint ID -> position in `waypoint_ids`
+    #[serde(skip)]
+    pub objective_positions: HashMap<String, usize>, // Objective ID -> position in `objective_ids`
+}
+
+/// Position of every id in `ids`.
+fn positions(ids: &[String]) -> HashMap<String, usize> {
+    ids.iter()
+        .enumerate()
+        .map(|(index, id)| (id.clone(), index))
+        .collect()
 }
 
 impl RoverProblem {
+    /// Builds the tables that map action arguments to object ids and back.
+    pub fn index_objects(&mut self, state: &State) {
+        self.rover_ids = state.rovers.keys().cloned().collect();
+        self.camera_ids = state.cameras.keys().cloned().collect();
+        self.waypoint_ids = state.waypoints.keys().cloned().collect();
+        self.lander_ids = state.landers.keys().cloned().collect();
+        self.objective_ids = self.objectives.keys().cloned().collect();
+        self.waypoint_positions = positions(&self.waypoint_ids);
+        self.objective_positions = positions(&self.objective_ids);
+    }
+
+    fn rover<'a>(&self, state: &'a State, index: usize) -> &'a Rover {
+        &state.rovers[&self.rover_ids[index]]
+    }
+
+    fn camera<'a>(&self, state: &'a State, index: usize) -> &'a Camera {
+        &state.cameras[&self.camera_ids[index]]
+    }
+
+    fn lander<'a>(&self, state: &'a State, index: usize) -> &'a Lander {
+        &state.landers[&self.lander_ids[index]]
+    }
+
     // Action generator methods
     pub fn get_navigate_action(rover: &Rover, from: &str, to: &str) -> Action {
         let mut parameters = HashMap::new();
@@ -310,14 +445,13 @@ impl RoverProblem {
     }
 
     // Get all possible actions for the current state
-    pub fn get_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_actions(&self, state: &State) -> Vec<RoverAction> {
         let mut actions = Vec::new();
 
         // Navigate actions
-        for (rover_id, rover) in &state.rovers {
+        for (r, (rover_id, rover)) in state.rovers.iter().enumerate() {
             if rover.available {
                 let current_waypoint = &rover.location;
-
                 if let Some(traversable_from_current) = self
                     .can_traverse
                     .get(rover_id)
@@ -332,11 +466,10 @@ impl RoverProblem {
                             })
                             && rover.energy >= 8
                         {
-                            actions.push(Self::get_navigate_action(
-                                rover,
-                                current_waypoint,
-                                to_waypoint,
-                            ));
+                            actions.push(RoverAction::Navigate {
+                                rover: r,
+                                to: self.waypoint_positions[to_waypoint],
+                            });
                         }
                     }
                 }
@@ -344,26 +477,26 @@ impl RoverProblem {
         }
 
         // Recharge actions
-        for rover in state.rovers.values() {
+        for (r, rover) in state.rovers.values().enumerate() {
             let waypoint = &rover.location;
             if state.waypoints.get(waypoint).map_or(false, |w| w.in_sun) && rover.energy <= 80 {
-                actions.push(Self::get_recharge_action(rover, waypoint));
+                actions.push(RoverAction::Recharge(r));
             }
         }
 
         // Sample soil actions
         for rover in state.rovers.values() {
             if rover.equipped_for_soil_analysis && rover.energy >= 3 {
-                let waypoint = &rover.location;
-                if state
+                if let Some((waypoint, _)) = state
                     .waypoints
-                    .get(waypoint)
-                    .map_or(false, |w| w.has_soil_sample)
+                    .iter()
+                    .enumerate()
+                    .find(|(_, (id, w))| **id == rover.location && w.has_soil_sample)
                 {
-                    for rover in state.rovers.values() {
+                    for (r, rover) in state.rovers.values().enumerate() {
                         let store = &rover.store;
                         if store.rover_id == rover.id && store.empty {
-                            actions.push(Self::get_sample_soil_action(rover, store, waypoint));
+                            actions.push(RoverAction::SampleSoil { rover: r, waypoint });
                         }
                     }
                 }
@@ -373,16 +506,16 @@ impl RoverProblem {
         // Sample rock actions
         for rover in state.rovers.values() {
             if rover.equipped_for_rock_analysis && rover.energy >= 5 {
-                let waypoint = &rover.location;
-                if state
+                if let Some((waypoint, _)) = state
                     .waypoints
-                    .get(waypoint)
-                    .map_or(false, |w| w.has_rock_sample)
+                    .iter()
+                    .enumerate()
+                    .find(|(_, (id, w))| **id == rover.location && w.has_rock_sample)
                 {
-                    for rover in state.rovers.values() {
+                    for (r, rover) in state.rovers.values().enumerate() {
                         let store = &rover.store;
                         if store.rover_id == rover.id && store.empty {
-                            actions.push(Self::get_sample_rock_action(rover, store, waypoint));
+                            actions.push(RoverAction::SampleRock { rover: r, waypoint });
                         }
                     }
                 }
@@ -390,51 +523,51 @@ impl RoverProblem {
         }
 
         // Drop actions
-        for rover in state.rovers.values() {
+        for (r, rover) in state.rovers.values().enumerate() {
             let store = &rover.store;
-                if store.rover_id == rover.id && store.full {
-                    actions.push(Self::get_drop_action(rover, store));
-                }
+            if store.rover_id == rover.id && store.full {
+                actions.push(RoverAction::Drop(r));
             }
-        
-
-        
-            // Calibrate actions
-            for rover in state.rovers.values() {
-                if rover.equipped_for_imaging && rover.energy >= 2 {
-                    let waypoint: &String = &rover.location;
-
-                    for camera in state.cameras.values() {
-                        if camera.rover_id == rover.id && camera.calibrated_objective.is_none() {
-                            let objective_id: &String = &camera.calibration_target;
+        }
 
-                            if let Some(objective) = self.objectives.get(objective_id) {
-                                if objective.visible_from.contains(waypoint) {
-                                    actions.push(Self::get_calibrate_action(
-                                        rover, camera, objective_id, waypoint,
-                                    ));
-                                }
+        // Calibrate actions
+        for (r, rover) in state.rovers.values().enumerate() {
+            if rover.equipped_for_imaging && rover.energy >= 2 {
+                let waypoint: &String = &rover.location;
+
+                for (c, camera) in state.cameras.values().enumerate() {
+                    if camera.rover_id == rover.id && camera.calibrated_objective.is_none() {
+                        let objective_id: &String = &camera.calibration_target;
+
+                        if let Some(objective) = self.objectives.get(objective_id) {
+                            if objective.visible_from.contains(waypoint) {
+                                actions.push(RoverAction::Calibrate {
+                                    rover: r,
+                                    camera: c,
+                                });
                             }
                         }
                     }
                 }
             }
+        }
 
-            // Take image actions
-            for rover in state.rovers.values() {
-                if rover.equipped_for_imaging && rover.energy >= 1 {
-                    let waypoint = &rover.location;
-
-                    for camera in state.cameras.values() {
-                        if camera.rover_id == rover.id {
-                            if let Some(obj_id) = &camera.calibrated_objective {
-                                if let Some(objective) = self.objectives.get(obj_id) {
-                                    if objective.visible_from.contains(waypoint) {
-                                        for mode in &camera.supported_modes {
-                                            actions.push(Self::get_take_image_action(
-                                                rover, waypoint, obj_id, camera, mode,
-                                            ));
-                                        }
+        // Take image actions
+        for (r, rover) in state.rovers.values().enumerate() {
+            if rover.equipped_for_imaging && rover.energy >= 1 {
+                let waypoint = &rover.location;
+
+                for (c, camera) in state.cameras.values().enumerate() {
+                    if camera.rover_id == rover.id {
+                        if let Some(obj_id) = &camera.calibrated_objective {
+                            if let Some(objective) = self.objectives.get(obj_id) {
+                                if objective.visible_from.contains(waypoint) {
+                                    for mode in &camera.supported_modes {
+                                        actions.push(RoverAction::TakeImage {
+                                            rover: r,
+                                            camera: c,
+                                            mode: mode_id(mode),
+                                        });
                                     }
                                 }
                             }
@@ -442,150 +575,119 @@ impl RoverProblem {
                     }
                 }
             }
+        }
 
-                    
-
-            // Communicate soil data actions
-            for rover in state.rovers.values() {
-                if rover.available && rover.energy >= 4 {
-                    let rover_waypoint = &rover.location;
-
-                    if let Some(soil_analyzed) = state.soil_analysis.get(&rover.id) {
-                        for sample_waypoint in soil_analyzed {
-                            // Check if this soil data has already been communicated
-                            if !state.communicated_soil_data.contains(sample_waypoint) {
-                                for lander in state.landers.values() {
-                                    let lander_waypoint = &lander.location;
-
-                                    if self
-                                        .visible
-                                        .get(rover_waypoint)
-                                        .map_or(false, |visible| visible.contains(lander_waypoint))
-                                        && lander.channel_free
-                                    {
-                                        actions.push(Self::get_communicate_soil_data_action(
-                                            rover,
-                                            lander,
-                                            sample_waypoint,
-                                            rover_waypoint,
-                                            lander_waypoint,
-                                        ));
-                                    }
-                                }
+        // Communicate soil data actions
+        for (r, rover) in state.rovers.values().enumerate() {
+            if rover.available && rover.energy >= 4 {
+                if let Some(soil_analyzed) = state.soil_analysis.get(&rover.id) {
+                    for sample_waypoint in soil_analyzed {
+                        // Check if this soil data has already been communicated
+                        if !state.communicated_soil_data.contains(sample_waypoint) {
+                            for lander in self.communicating_landers(state, rover) {
+                                actions.push(RoverAction::CommunicateSoilData {
+                                    rover: r,
+                                    sample: self.waypoint_positions[sample_waypoint],
+                                    lander,
+                                });
                             }
                         }
                     }
                 }
             }
+        }
 
-
-            // Communicate rock data actions
-            for rover in state.rovers.values() {
-                if rover.available && rover.energy >= 4 {
-                    let rover_waypoint = &rover.location;
-
-                    if let Some(rock_analyzed) = state.rock_analysis.get(&rover.id) {
-                        for sample_waypoint in rock_analyzed {
-                            // Check if this rock data has already been communicated
-                            if !state.communicated_rock_data.contains(sample_waypoint) {
-                                for lander in state.landers.values() {
-                                    let lander_waypoint = &lander.location;
-
-                                    if self
-                                        .visible
-                                        .get(rover_waypoint)
-                                        .map_or(false, |visible| visible.contains(lander_waypoint))
-                                        && lander.channel_free
-                                    {
-                                        actions.push(Self::get_communicate_rock_data_action(
-                                            rover,
-                                            lander,
-                                            sample_waypoint,
-                                            rover_waypoint,
-                                            lander_waypoint,
-                                        ));
-                                    }
-                                }
+        // Communicate rock data actions
+        for (r, rover) in state.rovers.values().enumerate() {
+            if rover.available && rover.energy >= 4 {
+                if let Some(rock_analyzed) = state.rock_analysis.get(&rover.id) {
+                    for sample_waypoint in rock_analyzed {
+                        // Check if this rock data has already been communicated
+                        if !state.communicated_rock_data.contains(sample_waypoint) {
+                            for lander in self.communicating_landers(state, rover) {
+                                actions.push(RoverAction::CommunicateRockData {
+                                    rover: r,
+                                    sample: self.waypoint_positions[sample_waypoint],
+                                    lander,
+                                });
                             }
                         }
                     }
                 }
             }
+        }
 
-
-            // Communicate image data actions
-            for rover in state.rovers.values() {
-                if rover.available && rover.energy >= 6 {
-                    let rover_waypoint = &rover.location;
-
-                    if let Some(images) = state.images.get(&rover.id) {
-                        for (objective_id, modes) in images {
-                            for mode in modes {
-                                // Check if this image data has already been communicated
-                                if !state.communicated_image_data
-                                    .get(objective_id)
-                                    .map_or(false, |communicated_modes| communicated_modes.contains(mode))
-                                {
-                                    for lander in state.landers.values() {
-                                        let lander_waypoint = &lander.location;
-
-                                        if self
-                                            .visible
-                                            .get(rover_waypoint)
-                                            .map_or(false, |visible| visible.contains(lander_waypoint))
-                                            && lander.channel_free
-                                        {
-                                            actions.push(Self::get_communicate_image_data_action(
-                                                rover,
-                                                lander,
-                                                objective_id,
-                                                mode,
-                                                rover_waypoint,
-                                                lander_waypoint,
-                                            ));
-                                        }
-                                    }
+        // Communicate image data actions
+        for (r, rover) in state.rovers.values().enumerate() {
+            if rover.available && rover.energy >= 6 {
+                if let Some(images) = state.images.get(&rover.id) {
+                    for (objective_id, modes) in images {
+                        for mode in modes {
+                            // Check if this image data has already been communicated
+                            if !state
+                                .communicated_image_data
+                                .get(objective_id)
+                                .map_or(false, |communicated_modes| {
+                                    communicated_modes.contains(mode)
+                                })
+                            {
+                                for lander in self.communicating_landers(state, rover) {
+                                    actions.push(RoverAction::CommunicateImageData {
+                                        rover: r,
+                                        objective: self.objective_positions[objective_id],
+                                        mode: mode_id(mode),
+                                        lander,
+                                    });
                                 }
                             }
                         }
                     }
                 }
             }
-
+        }
 
         actions
     }
 
-    // Action application methods
-    pub fn apply_navigate_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
+    /// Positions of the landers with a free channel visible from the rover's waypoint.
+    fn communicating_landers(&self, state: &State, rover: &Rover) -> Vec<usize> {
+        state
+            .landers
+            .values()
+            .enumerate()
+            .filter(|(_, lander)| {
+                self.visible
+                    .get(&rover.location)
+                    .map_or(false, |visible| visible.contains(&lander.location))
+                    && lander.channel_free
+            })
+            .map(|(l, _)| l)
+            .collect()
+    }
 
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
 
-        let to_waypoint = match action.parameters.get("to") {
-            Some(Value::Text(waypoint)) => waypoint,
-            _ => panic!("Invalid destination waypoint in action"),
-        };
+    fn calibrated_objective(camera: &Camera) -> &String {
+        camera
+            .calibrated_objective
+            .as_ref()
+            .expect("Camera is not calibrated")
+    }
+
+    // Action application methods
+    pub fn apply_navigate_action(state: &State, rover_id: &str, to_waypoint: &str) -> State {
+        let mut new_state = state.clone();
 
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
-            rover.location = to_waypoint.clone();
+            rover.location = to_waypoint.to_string();
             rover.energy -= 8;
         }
 
         new_state
     }
 
-    pub fn apply_recharge_action(state: &State, action: &Action) -> State {
+    pub fn apply_recharge_action(state: &State, rover_id: &str) -> State {
         let mut new_state = state.clone();
 
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
-
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
             rover.energy += 20;
             if rover.energy > 100 {
@@ -593,31 +695,20 @@ impl RoverProblem {
             }
         }
 
-        new_state.recharges += 1;
         new_state
     }
 
-    pub fn apply_sample_soil_action(state: &State, action: &Action) -> State {
+    pub fn apply_sample_soil_action(
+        state: &State,
+        rover_id: &str,
+        store_id: &str,
+        waypoint_id: &str,
+    ) -> State {
         let mut new_state = state.clone();
 
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
-
-        let store_id = match action.parameters.get("store") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid store ID in action"),
-        };
-
-        let waypoint_id = match action.parameters.get("waypoint") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid waypoint ID in action"),
-        };
-
         // Update the store - mark as not empty and full
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
-            if rover.store.id == *store_id {
+            if rover.store.id == store_id {
                 rover.store.empty = false;
                 rover.store.full = true;
             }
@@ -636,35 +727,25 @@ impl RoverProblem {
             // Record that this rover has analyzed soil at this waypoint
             new_state
                 .soil_analysis
-                .entry(rover_id.clone())
-                .or_insert_with(HashSet::new)
-                .insert(waypoint_id.clone());
+                .entry(rover_id.to_string())
+                .or_insert_with(BTreeSet::new)
+                .insert(waypoint_id.to_string());
         }
 
         new_state
     }
 
-    pub fn apply_sample_rock_action(state: &State, action: &Action) -> State {
+    pub fn apply_sample_rock_action(
+        state: &State,
+        rover_id: &str,
+        store_id: &str,
+        waypoint_id: &str,
+    ) -> State {
         let mut new_state = state.clone();
 
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
-
-        let store_id = match action.parameters.get("store") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid store ID in action"),
-        };
-
-        let waypoint_id = match action.parameters.get("waypoint") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid waypoint ID in action"),
-        };
-
         // Update the store - mark as not empty and full
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
-            if rover.store.id == *store_id {
+            if rover.store.id == store_id {
                 rover.store.empty = false;
                 rover.store.full = true;
             }
@@ -683,29 +764,19 @@ impl RoverProblem {
             // Record that this rover has analyzed rock at this waypoint
             new_state
                 .rock_analysis
-                .entry(rover_id.clone())
-                .or_insert_with(HashSet::new)
-                .insert(waypoint_id.clone());
+                .entry(rover_id.to_string())
+                .or_insert_with(BTreeSet::new)
+                .insert(waypoint_id.to_string());
         }
 
         new_state
     }
 
-    pub fn apply_drop_action(state: &State, action: &Action) -> State {
+    pub fn apply_drop_action(state: &State, rover_id: &str, store_id: &str) -> State {
         let mut new_state = state.clone();
 
-        let store_id = match action.parameters.get("store") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid store ID in action"),
-        };
-
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
-
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
-            if rover.store.id == *store_id {
+            if rover.store.id == store_id {
                 rover.store.empty = true;
                 rover.store.full = false;
             }
@@ -715,25 +786,17 @@ impl RoverProblem {
         new_state
     }
 
-    pub fn apply_calibrate_action(state: &State, action: &Action) -> State {
+    pub fn apply_calibrate_action(
+        state: &State,
+        rover_id: &str,
+        camera_id: &str,
+        objective_id: &str,
+    ) -> State {
         let mut new_state = state.clone();
-    
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
-        let camera_id = match action.parameters.get("camera") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid camera ID in action"),
-        };
-        let objective_id = match action.parameters.get("objective") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid objective ID in action"),
-        };
-    
+
         // Update camera calibration only if it's the intended calibration target
         if let Some(camera) = new_state.cameras.get_mut(camera_id) {
-            if camera.calibration_target == *objective_id {
+            if camera.calibration_target == objective_id {
                 camera.calibrated_objective = Some(objective_id.to_string());
             }
         }
@@ -747,58 +810,40 @@ impl RoverProblem {
     }
         
 
-    pub fn apply_take_image_action(state: &State, action: &Action) -> State {
-    let mut new_state = state.clone();
-
-    let rover_id = match action.parameters.get("rover") {
-        Some(Value::Text(id)) => id,
-        _ => panic!("Invalid rover ID in action"),
-    };
-    let camera_id = match action.parameters.get("camera") {
-        Some(Value::Text(id)) => id,
-        _ => panic!("Invalid camera ID in action"),
-    };
-    let objective_id = match action.parameters.get("objective") {
-        Some(Value::Text(id)) => id,
-        _ => panic!("Invalid objective ID in action"),
-    };
-    let mode = match action.parameters.get("mode") {
-        Some(Value::Text(id)) => id,
-        _ => panic!("Invalid mode in action"),
-    };
-
-    if let Some(camera) = new_state.cameras.get_mut(camera_id) {
-        camera.calibrated_objective = None; // Clear calibration after image
-    }
-
-    if let Some(rover) = new_state.rovers.get_mut(rover_id) {
-        rover.energy -= 1;
-
-        new_state
-            .images
-            .entry(rover_id.clone())
-            .or_insert_with(HashMap::new)
-            .entry(objective_id.clone())
-            .or_insert_with(HashSet::new)
-            .insert(mode.clone());
-    }
+    pub fn apply_take_image_action(
+        state: &State,
+        rover_id: &str,
+        camera_id: &str,
+        objective_id: &str,
+        mode: &str,
+    ) -> State {
+        let mut new_state = state.clone();
 
-    new_state
-}
+        if let Some(camera) = new_state.cameras.get_mut(camera_id) {
+            camera.calibrated_objective = None; // Clear calibration after image
+        }
 
+        if let Some(rover) = new_state.rovers.get_mut(rover_id) {
+            rover.energy -= 1;
 
-    pub fn apply_communicate_soil_data_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
+            new_state
+                .images
+                .entry(rover_id.to_string())
+                .or_insert_with(BTreeMap::new)
+                .entry(objective_id.to_string())
+                .or_insert_with(BTreeSet::new)
+                .insert(mode.to_string());
+        }
 
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
+        new_state
+    }
 
-        let sample_waypoint = match action.parameters.get("sample_waypoint") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid sample waypoint ID in action"),
-        };
+    pub fn apply_communicate_soil_data_action(
+        state: &State,
+        rover_id: &str,
+        sample_waypoint: &str,
+    ) -> State {
+        let mut new_state = state.clone();
 
         // Update the rover's energy
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
@@ -808,24 +853,18 @@ impl RoverProblem {
         // Record that this soil data has been communicated
         new_state
             .communicated_soil_data
-            .insert(sample_waypoint.clone());
+            .insert(sample_waypoint.to_string());
 
         new_state
     }
 
-    pub fn apply_communicate_rock_data_action(state: &State, action: &Action) -> State {
+    pub fn apply_communicate_rock_data_action(
+        state: &State,
+        rover_id: &str,
+        sample_waypoint: &str,
+    ) -> State {
         let mut new_state = state.clone();
 
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
-
-        let sample_waypoint = match action.parameters.get("sample_waypoint") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid sample waypoint ID in action"),
-        };
-
         // Update the rover's energy
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
             rover.energy -= 4;
@@ -834,29 +873,19 @@ impl RoverProblem {
         // Record that this rock data has been communicated
         new_state
             .communicated_rock_data
-            .insert(sample_waypoint.clone());
+            .insert(sample_waypoint.to_string());
 
         new_state
     }
 
-    pub fn apply_communicate_image_data_action(state: &State, action: &Action) -> State {
+    pub fn apply_communicate_image_data_action(
+        state: &State,
+        rover_id: &str,
+        objective_id: &str,
+        mode: &str,
+    ) -> State {
         let mut new_state = state.clone();
 
-        let rover_id = match action.parameters.get("rover") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid rover ID in action"),
-        };
-
-        let objective_id = match action.parameters.get("objective") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid objective ID in action"),
-        };
-
-        let mode = match action.parameters.get("mode") {
-            Some(Value::Text(mode)) => mode,
-            _ => panic!("Invalid mode in action"),
-        };
-
         // Update the rover's energy
         if let Some(rover) = new_state.rovers.get_mut(rover_id) {
             rover.energy -= 6;
@@ -865,9 +894,9 @@ impl RoverProblem {
         // Record that this image data has been communicated
         new_state
             .communicated_image_data
-            .entry(objective_id.clone())
-            .or_insert_with(HashSet::new)
-            .insert(mode.clone());
+            .entry(objective_id.to_string())
+            .or_insert_with(BTreeSet::new)
+            .insert(mode.to_string());
 
         new_state
     }
@@ -877,34 +906,195 @@ impl RoverProblem {
 
 impl Problem for RoverProblem {
     type State = State;
+    type Action = RoverAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<RoverAction> {
         self.get_actions(state)
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("navigate_") {
-            Self::apply_navigate_action(state, action)
-        } else if action.name.starts_with("recharge_") {
-            Self::apply_recharge_action(state, action)
-        } else if action.name.starts_with("sample_soil_") {
-            Self::apply_sample_soil_action(state, action)
-        } else if action.name.starts_with("sample_rock_") {
-            Self::apply_sample_rock_action(state, action)
-        } else if action.name.starts_with("drop_") {
-            Self::apply_drop_action(state, action)
-        } else if action.name.starts_with("calibrate_") {
-            Self::apply_calibrate_action(state, action)
-        } else if action.name.starts_with("take_image_") {
-            Self::apply_take_image_action(state, action)
-        } else if action.name.starts_with("communicate_soil_data_") {
-            Self::apply_communicate_soil_data_action(state, action)
-        } else if action.name.starts_with("communicate_rock_data_") {
-            Self::apply_communicate_rock_data_action(state, action)
-        } else if action.name.starts_with("communicate_image_data_") {
-            Self::apply_communicate_image_data_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    fn apply_action(&self, state: &State, action: &RoverAction) -> State {
+        match *action {
+            RoverAction::Navigate { rover, to } => {
+                let rover = self.rover(state, rover);
+                Self::apply_navigate_action(state, &rover.id, &self.waypoint_ids[to])
+            }
+            RoverAction::Recharge(rover) => {
+                Self::apply_recharge_action(state, &self.rover(state, rover).id)
+            }
+            RoverAction::SampleSoil { rover, waypoint } => {
+                let rover = self.rover(state, rover);
+                let waypoint = &self.waypoint_ids[waypoint];
+                Self::apply_sample_soil_action(state, &rover.id, &rover.store.id, waypoint)
+            }
+            RoverAction::SampleRock { rover, waypoint } => {
+                let rover = self.rover(state, rover);
+                let waypoint = &self.waypoint_ids[waypoint];
+                Self::apply_sample_rock_action(state, &rover.id, &rover.store.id, waypoint)
+            }
+            RoverAction::Drop(rover) => {
+                let rover = self.rover(state, rover);
+                Self::apply_drop_action(state, &rover.id, &rover.store.id)
+            }
+            RoverAction::Calibrate { rover, camera } => {
+                let rover = self.rover(state, rover);
+                let camera = self.camera(state, camera);
+                Self::apply_calibrate_action(
+                    state,
+                    &rover.id,
+                    &camera.id,
+                    &camera.calibration_target,
+                )
+            }
+            RoverAction::TakeImage {
+                rover,
+                camera,
+                mode,
+            } => {
+                let rover = self.rover(state, rover);
+                let camera = self.camera(state, camera);
+                Self::apply_take_image_action(
+                    state,
+                    &rover.id,
+                    &camera.id,
+                    Self::calibrated_objective(camera),
+                    MODES[mode],
+                )
+            }
+            RoverAction::CommunicateSoilData { rover, sample, .. } => {
+                let rover = self.rover(state, rover);
+                let sample_waypoint = &self.waypoint_ids[sample];
+                Self::apply_communicate_soil_data_action(state, &rover.id, sample_waypoint)
+            }
+            RoverAction::CommunicateRockData { rover, sample, .. } => {
+                let rover = self.rover(state, rover);
+                let sample_waypoint = &self.waypoint_ids[sample];
+                Self::apply_communicate_rock_data_action(state, &rover.id, sample_waypoint)
+            }
+            RoverAction::CommunicateImageData {
+                rover,
+                objective,
+                mode,
+                ..
+            } => {
+                let rover = self.rover(state, rover);
+                let objective_id = &self.objective_ids[objective];
+                Self::apply_communicate_image_data_action(state, &rover.id, objective_id, MODES[mode])
+            }
+        }
+    }
+
+    fn action_cost(&self, _state: &State, action: &RoverAction) -> i32 {
+        match action {
+            RoverAction::Navigate { .. } => 8,
+            RoverAction::Recharge(_) => 1,
+            RoverAction::SampleSoil { .. } => 3,
+            RoverAction::SampleRock { .. } => 5,
+            RoverAction::Drop(_) => 1,
+            RoverAction::Calibrate { .. } => 2,
+            RoverAction::TakeImage { .. } => 1,
+            RoverAction::CommunicateSoilData { .. } => 4,
+            RoverAction::CommunicateRockData { .. } => 4,
+            RoverAction::CommunicateImageData { .. } => 6,
+        }
+    }
+
+    fn describe_action(&self, state: &State, action: &RoverAction) -> Action {
+        match *action {
+            RoverAction::Navigate { rover, to } => {
+                let rover = self.rover(state, rover);
+                Self::get_navigate_action(rover, &rover.location, &self.waypoint_ids[to])
+            }
+            RoverAction::Recharge(rover) => {
+                let rover = self.rover(state, rover);
+                Self::get_recharge_action(rover, &rover.location)
+            }
+            RoverAction::SampleSoil { rover, waypoint } => {
+                let rover = self.rover(state, rover);
+                let waypoint = &self.waypoint_ids[waypoint];
+                Self::get_sample_soil_action(rover, &rover.store, waypoint)
+            }
+            RoverAction::SampleRock { rover, waypoint } => {
+                let rover = self.rover(state, rover);
+                let waypoint = &self.waypoint_ids[waypoint];
+                Self::get_sample_rock_action(rover, &rover.store, waypoint)
+            }
+            RoverAction::Drop(rover) => {
+                let rover = self.rover(state, rover);
+                Self::get_drop_action(rover, &rover.store)
+            }
+            RoverAction::Calibrate { rover, camera } => {
+                let rover = self.rover(state, rover);
+                let camera = self.camera(state, camera);
+                Self::get_calibrate_action(
+                    rover,
+                    camera,
+                    &camera.calibration_target,
+                    &rover.location,
+                )
+            }
+            RoverAction::TakeImage {
+                rover,
+                camera,
+                mode,
+            } => {
+                let rover = self.rover(state, rover);
+                let camera = self.camera(state, camera);
+                Self::get_take_image_action(
+                    rover,
+                    &rover.location,
+                    Self::calibrated_objective(camera),
+                    camera,
+                    MODES[mode],
+                )
+            }
+            RoverAction::CommunicateSoilData {
+                rover,
+                sample,
+                lander,
+            } => {
+                let rover = self.rover(state, rover);
+                let lander = self.lander(state, lander);
+                Self::get_communicate_soil_data_action(
+                    rover,
+                    lander,
+                    &self.waypoint_ids[sample],
+                    &rover.location,
+                    &lander.location,
+                )
+            }
+            RoverAction::CommunicateRockData {
+                rover,
+                sample,
+                lander,
+            } => {
+                let rover = self.rover(state, rover);
+                let lander = self.lander(state, lander);
+                Self::get_communicate_rock_data_action(
+                    rover,
+                    lander,
+                    &self.waypoint_ids[sample],
+                    &rover.location,
+                    &lander.location,
+                )
+            }
+            RoverAction::CommunicateImageData {
+                rover,
+                objective,
+                mode,
+                lander,
+            } => {
+                let rover = self.rover(state, rover);
+                let lander = self.lander(state, lander);
+                Self::get_communicate_image_data_action(
+                    rover,
+                    lander,
+                    &self.objective_ids[objective],
+                    MODES[mode],
+                    &rover.location,
+                    &lander.location,
+                )
+            }
         }
     }
 
@@ -935,9 +1125,220 @@ impl Problem for RoverProblem {
         let state: State = serde_json::from_value(state_value.clone())
             .expect("Failed to deserialize state");
         
-        let problem: RoverProblem = serde_json::from_value(problem_value.clone())
+        let mut problem: RoverProblem = serde_json::from_value(problem_value.clone())
             .expect("Failed to deserialize problem");
+        problem.index_objects(&state);
     
         (state, problem)
     }
 }
+
+/// Bit-packed rover states: names are stored as positions in symbol tables, and the
+/// fields no action changes are copied from the template state when decoding.
+pub struct RoverStateCodec {
+    template: State,
+    rovers: SymbolTable,
+    waypoints: SymbolTable,
+    objectives: SymbolTable,
+    modes: SymbolTable,
+}
+
+impl RoverStateCodec {
+    pub fn new(problem: &RoverProblem, template: &State) -> Self {
+        let rovers = SymbolTable::new(
+            template
+                .rovers
+                .keys()
+                .chain(template.soil_analysis.keys())
+                .chain(template.rock_analysis.keys())
+                .chain(template.images.keys()),
+        );
+        let waypoints = SymbolTable::new(
+            template
+                .waypoints
+                .keys()
+                .chain(template.rovers.values().map(|r| &r.location))
+                .chain(template.landers.values().map(|l| &l.location))
+                .chain(
+                    problem
+                        .can_traverse
+                        .values()
+                        .flat_map(|m| m.values().flatten()),
+                )
+                .chain(template.soil_analysis.values().flatten())
+                .chain(template.rock_analysis.values().flatten())
+                .chain(&template.communicated_soil_data)
+                .chain(&template.communicated_rock_data),
+        );
+        let objectives = SymbolTable::new(
+            problem
+                .objectives
+                .keys()
+                .chain(template.cameras.values().map(|c| &c.calibration_target))
+                .chain(
+                    template
+                        .cameras
+                        .values()
+                        .flat_map(|c| &c.calibrated_objective),
+                )
+                .chain(template.images.values().flat_map(|i| i.keys()))
+                .chain(template.communicated_image_data.keys()),
+        );
+        let modes = SymbolTable::new(
+            template
+                .cameras
+                .values()
+                .flat_map(|c| &c.supported_modes)
+                .chain(template.images.values().flat_map(|i| i.values().flatten()))
+                .chain(template.communicated_image_data.values().flatten()),
+        );
+        RoverStateCodec {
+            template: template.clone(),
+            rovers,
+            waypoints,
+            objectives,
+            modes,
+        }
+    }
+
+    fn write_analysis(
+        &self,
+        writer: &mut BitWriter,
+        analysis: &BTreeMap<String, BTreeSet<String>>,
+    ) {
+        self.rovers.check_keys(analysis);
+        for rover in self.rovers.names() {
+            writer.write_bool(analysis.contains_key(rover));
+            if let Some(waypoints) = analysis.get(rover) {
+                self.waypoints.write_set(writer, waypoints);
+            }
+        }
+    }
+
+    fn read_analysis(&self, reader: &mut BitReader) -> BTreeMap<String, BTreeSet<String>> {
+        let mut analysis = BTreeMap::new();
+        for rover in self.rovers.names() {
+            if reader.read_bool() {
+                analysis.insert(rover.clone(), self.waypoints.read_set(reader));
+            }
+        }
+        analysis
+    }
+
+    fn write_images(&self, writer: &mut BitWriter, images: &BTreeMap<String, BTreeSet<String>>) {
+        self.objectives.check_keys(images);
+        for objective in self.objectives.names() {
+            writer.write_bool(images.contains_key(objective));
+            if let Some(modes) = images.get(objective) {
+                self.modes.write_set(writer, modes);
+            }
+        }
+    }
+
+    fn read_images(&self, reader: &mut BitReader) -> BTreeMap<String, BTreeSet<String>> {
+        let mut images = BTreeMap::new();
+        for objective in self.objectives.names() {
+            if reader.read_bool() {
+                images.insert(objective.clone(), self.modes.read_set(reader));
+            }
+        }
+        images
+    }
+}
+
+impl StateCodec<State> for RoverStateCodec {
+    fn encode(&self, state: &State, out: &mut Vec<u8>) {
+        let mut writer = BitWriter::new(out);
+        for rover in state.rovers.values() {
+            self.waypoints.write(&mut writer, &rover.location);
+            writer.write_int(rover.energy as i64);
+            writer.write_bool(rover.store.empty);
+            writer.write_bool(rover.store.full);
+        }
+        for camera in state.cameras.values() {
+            writer.write_bool(camera.calibrated_objective.is_some());
+            if let Some(objective) = &camera.calibrated_objective {
+                self.objectives.write(&mut writer, objective);
+            }
+        }
+        for waypoint in state.waypoints.values() {
+            writer.write_bool(waypoint.has_soil_sample);
+            writer.write_bool(waypoint.has_rock_sample);
+        }
+        self.write_analysis(&mut writer, &state.soil_analysis);
+        self.write_analysis(&mut writer, &state.rock_analysis);
+        self.rovers.check_keys(&state.images);
+        for rover in self.rovers.names() {
+            writer.write_bool(state.images.contains_key(rover));
+            if let Some(images) = state.images.get(rover) {
+                self.write_images(&mut writer, images);
+            }
+        }
+        self.waypoints
+            .write_set(&mut writer, &state.communicated_soil_data);
+        self.waypoints
+            .write_set(&mut writer, &state.communicated_rock_data);
+        self.write_images(&mut writer, &state.communicated_image_data);
+    }
+
+    fn decode(&self, bytes: &[u8]) -> State {
+        let mut reader = BitReader::new(bytes);
+        let mut state = self.template.clone();
+        for rover in state.rovers.values_mut() {
+            rover.location = self.waypoints.read(&mut reader);
+            rover.energy = reader.read_int() as i32;
+            rover.store.empty = reader.read_bool();
+            rover.store.full = reader.read_bool();
+        }
+        for camera in state.cameras.values_mut() {
+            camera.calibrated_objective = if reader.read_bool() {
+                Some(self.objectives.read(&mut reader))
+            } else {
+                None
+            };
+        }
+        for waypoint in state.waypoints.values_mut() {
+            waypoint.has_soil_sample = reader.read_bool();
+            waypoint.has_rock_sample = reader.read_bool();
+        }
+        state.soil_analysis = self.read_analysis(&mut reader);
+        state.rock_analysis = self.read_analysis(&mut reader);
+        state.images = BTreeMap::new();
+        for rover in self.rovers.names() {
+            if reader.read_bool() {
+                state
+                    .images
+                    .insert(rover.clone(), self.read_images(&mut reader));
+            }
+        }
+        state.communicated_soil_data = self.waypoints.read_set(&mut reader);
+        state.communicated_rock_data = self.waypoints.read_set(&mut reader);
+        state.communicated_image_data = self.read_images(&mut reader);
+        state
+    }
+}
+
+impl PackedProblem for RoverProblem {
+    type Codec = RoverStateCodec;
+
+    fn state_codec(&self, state: &State) -> RoverStateCodec {
+        RoverStateCodec::new(self, state)
+    }
+}
+
+/// The goal conditions, soft when `Goal::utilities` gives them a utility.
+impl SoftGoalProblem for RoverProblem {
+    fn goal_list(&self) -> Vec<GoalEntry> {
+        let utilities = self.goal.utilities.iter().map(|u| u.map(OrderedFloat::into_inner));
+        self.goal
+            .conditions
+            .iter()
+            .zip(utilities.chain(std::iter::repeat(None)))
+            .map(|(cond, utility)| GoalEntry::new(cond.name(), utility))
+            .collect()
+    }
+
+    fn goals_achieved(&self, state: &State) -> Vec<bool> {
+        self.goal.conditions.iter().map(|cond| cond.is_satisfied(state)).collect()
+    }
+}
@@ -1,5 +1,9 @@
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::symmetry::{
+    canonical_order, groups_by_signature, ObjectGroup, SymmetricProblem,
+};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
@@ -7,14 +11,14 @@ use ordered_float::OrderedFloat;
 use std::fs;
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Boat {
     pub x: OrderedFloat<f64>, // x-coordinate
     pub y: OrderedFloat<f64>, // y-coordinate
     pub index: i32, // index of the boat
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Person {
     pub d: OrderedFloat<f64>, 
     pub saved: bool, 
@@ -22,7 +26,7 @@ pub struct Person {
 }
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct State {
     pub boats: Vec<Boat>,
     pub persons: Vec<Person>,
@@ -30,13 +34,21 @@ pub struct State {
 
 impl StateTrait for State {}
 
-impl PartialEq for State {
-    fn eq(&self, other: &Self) -> bool {
-        self.boats == other.boats && self.persons == other.persons
-    }
+/// Actions of the sailing domain; boats and persons are referred to by their position in
+/// the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SailingAction {
+    NorthEast(usize),
+    NorthWest(usize),
+    East(usize),
+    West(usize),
+    SouthWest(usize),
+    SouthEast(usize),
+    South(usize),
+    SavePerson { boat: usize, person: usize },
 }
 
-impl Eq for State {}
+impl ActionTrait for SailingAction {}
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SailingProblem {}
@@ -103,160 +115,122 @@ impl SailingProblem {
         }
     }
 
-    fn apply_go_north_east(state: &State, action: &Action) -> State {
-        let boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
+    fn apply_go_north_east(state: &State, boat: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(boat) = new_state.boats.iter_mut().find(|b| b.index == boat_index) {
-            boat.x += OrderedFloat(1.5);
-            boat.y += OrderedFloat(1.5);
-        }
+        let boat = &mut new_state.boats[boat];
+        boat.x += OrderedFloat(1.5);
+        boat.y += OrderedFloat(1.5);
         new_state
     }
 
-    fn apply_go_north_west(state: &State, action: &Action) -> State {
-        let boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
+    fn apply_go_north_west(state: &State, boat: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(boat) = new_state.boats.iter_mut().find(|b| b.index == boat_index) {
-            boat.x -= OrderedFloat(1.5);
-            boat.y += OrderedFloat(1.5);
-        }
+        let boat = &mut new_state.boats[boat];
+        boat.x -= OrderedFloat(1.5);
+        boat.y += OrderedFloat(1.5);
         new_state
     }
 
-    fn apply_go_east(state: &State, action: &Action) -> State {
-        let boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
+    fn apply_go_east(state: &State, boat: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(boat) = new_state.boats.iter_mut().find(|b| b.index == boat_index) {
-            boat.x += OrderedFloat(3.0);
-        }
+        let boat = &mut new_state.boats[boat];
+        boat.x += OrderedFloat(3.0);
         new_state
     }
 
-    fn apply_go_west(state: &State, action: &Action) -> State {
-        let boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
+    fn apply_go_west(state: &State, boat: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(boat) = new_state.boats.iter_mut().find(|b| b.index == boat_index) {
-            boat.x -= OrderedFloat(3.0);
-        }
+        let boat = &mut new_state.boats[boat];
+        boat.x -= OrderedFloat(3.0);
         new_state
     }
 
-    fn apply_go_south_west(state: &State, action: &Action) -> State {
-        let boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
+    fn apply_go_south_west(state: &State, boat: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(boat) = new_state.boats.iter_mut().find(|b| b.index == boat_index) {
-            boat.x -= OrderedFloat(2.0);
-            boat.y -= OrderedFloat(2.0);
-        }
+        let boat = &mut new_state.boats[boat];
+        boat.x -= OrderedFloat(2.0);
+        boat.y -= OrderedFloat(2.0);
         new_state
     }
 
-    fn apply_go_south_east(state: &State, action: &Action) -> State {
-        let boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
+    fn apply_go_south_east(state: &State, boat: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(boat) = new_state.boats.iter_mut().find(|b| b.index == boat_index) {
-            boat.x += OrderedFloat(2.0);
-            boat.y -= OrderedFloat(2.0);
-        }
+        let boat = &mut new_state.boats[boat];
+        boat.x += OrderedFloat(2.0);
+        boat.y -= OrderedFloat(2.0);
         new_state
     }
 
-    fn apply_go_south(state: &State, action: &Action) -> State {
-        let boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
+    fn apply_go_south(state: &State, boat: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(boat) = new_state.boats.iter_mut().find(|b| b.index == boat_index) {
-            boat.y -= OrderedFloat(2.0);
-        }
+        let boat = &mut new_state.boats[boat];
+        boat.y -= OrderedFloat(2.0);
         new_state
     }
 
-    fn apply_save_person(state: &State, action: &Action) -> State {
-        let _boat_index = match action.parameters.get("boat").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected boat index to be an integer"),
-        };
-        let person_index = match action.parameters.get("person").unwrap() {
-            Value::Int(index) => *index,
-            _ => panic!("Expected person index to be an integer"),
-        };
+    fn apply_save_person(state: &State, person: usize) -> State {
         let mut new_state = state.clone();
-        if let Some(person) = new_state
-            .persons
-            .iter_mut()
-            .find(|p| p.index == person_index)
-        {
-            person.saved = true;
-        }
+        new_state.persons[person].saved = true;
         new_state
     }
 }
 
 impl Problem for SailingProblem {
     type State = State;
+    type Action = SailingAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<SailingAction> {
         let mut actions = Vec::new();
 
-        for boat in &state.boats {
-            actions.push(Self::go_north_east_action(boat));
-            actions.push(Self::go_north_west_action(boat));
-            actions.push(Self::go_east_action(boat));
-            actions.push(Self::go_west_action(boat));
-            actions.push(Self::go_south_west_action(boat));
-            actions.push(Self::go_south_east_action(boat));
-            actions.push(Self::go_south_action(boat));
-
-            for person in &state.persons {
-                if !person.saved {
-                    if let Some(action) = Self::save_person_action(boat, person) {
-                        actions.push(action);
-                    }
+        for (b, boat) in state.boats.iter().enumerate() {
+            actions.push(SailingAction::NorthEast(b));
+            actions.push(SailingAction::NorthWest(b));
+            actions.push(SailingAction::East(b));
+            actions.push(SailingAction::West(b));
+            actions.push(SailingAction::SouthWest(b));
+            actions.push(SailingAction::SouthEast(b));
+            actions.push(SailingAction::South(b));
+
+            for (p, person) in state.persons.iter().enumerate() {
+                if !person.saved && Self::save_person_action(boat, person).is_some() {
+                    actions.push(SailingAction::SavePerson { boat: b, person: p });
                 }
             }
         }
         actions
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("go_north_east") {
-            Self::apply_go_north_east(state, action)
-        } else if action.name.starts_with("go_north_west") {
-            Self::apply_go_north_west(state, action)
-        } else if action.name.starts_with("go_east") {
-            Self::apply_go_east(state, action)
-        } else if action.name.starts_with("go_west") {
-            Self::apply_go_west(state, action)
-        } else if action.name.starts_with("go_south_west") {
-            Self::apply_go_south_west(state, action)
-        } else if action.name.starts_with("go_south_east") {
-            Self::apply_go_south_east(state, action)
-        } else if action.name.starts_with("go_south") {
-            Self::apply_go_south(state, action)
-        } else if action.name.starts_with("save_person") {
-            Self::apply_save_person(state, action)
-        } else {
-            panic!("Unknown action");
+    fn apply_action(&self, state: &State, action: &SailingAction) -> State {
+        match *action {
+            SailingAction::NorthEast(boat) => Self::apply_go_north_east(state, boat),
+            SailingAction::NorthWest(boat) => Self::apply_go_north_west(state, boat),
+            SailingAction::East(boat) => Self::apply_go_east(state, boat),
+            SailingAction::West(boat) => Self::apply_go_west(state, boat),
+            SailingAction::SouthWest(boat) => Self::apply_go_south_west(state, boat),
+            SailingAction::SouthEast(boat) => Self::apply_go_south_east(state, boat),
+            SailingAction::South(boat) => Self::apply_go_south(state, boat),
+            SailingAction::SavePerson { person, .. } => Self::apply_save_person(state, person),
+        }
+    }
+
+    fn action_cost(&self, _state: &State, _action: &SailingAction) -> i32 {
+        1
+    }
+
+    fn describe_action(&self, state: &State, action: &SailingAction) -> Action {
+        match *action {
+            SailingAction::NorthEast(boat) => Self::go_north_east_action(&state.boats[boat]),
+            SailingAction::NorthWest(boat) => Self::go_north_west_action(&state.boats[boat]),
+            SailingAction::East(boat) => Self::go_east_action(&state.boats[boat]),
+            SailingAction::West(boat) => Self::go_west_action(&state.boats[boat]),
+            SailingAction::SouthWest(boat) => Self::go_south_west_action(&state.boats[boat]),
+            SailingAction::SouthEast(boat) => Self::go_south_east_action(&state.boats[boat]),
+            SailingAction::South(boat) => Self::go_south_action(&state.boats[boat]),
+            SailingAction::SavePerson { boat, person } => {
+                Self::save_person_action(&state.boats[boat], &state.persons[person])
+                    .expect("Person out of reach of the boat")
+            }
         }
     }
 
@@ -291,3 +265,32 @@ impl Problem for SailingProblem {
         0.0
     }
 }
+
+impl SymmetricProblem for SailingProblem {
+    /// All boats, which move alike, and persons at the same distance.
+    fn symmetry_groups(&self, state: &State) -> Vec<ObjectGroup> {
+        let mut groups = groups_by_signature("boat", state.boats.iter().map(|_| ()));
+        groups.extend(groups_by_signature("person", state.persons.iter().map(|p| p.d)));
+        groups
+    }
+
+    /// Boat positions and rescue flags are sorted within each group.
+    fn canonical_state(&self, state: &State, groups: &[ObjectGroup]) -> State {
+        let mut canonical = state.clone();
+        for group in groups {
+            if group.kind == "boat" {
+                let order = canonical_order(group, |b| (state.boats[b].x, state.boats[b].y));
+                for (&slot, &b) in group.objects.iter().zip(&order) {
+                    canonical.boats[slot].x = state.boats[b].x;
+                    canonical.boats[slot].y = state.boats[b].y;
+                }
+            } else {
+                let order = canonical_order(group, |p| state.persons[p].saved);
+                for (&slot, &p) in group.objects.iter().zip(&order) {
+                    canonical.persons[slot].saved = state.persons[p].saved;
+                }
+            }
+        }
+        canonical
+    }
+}
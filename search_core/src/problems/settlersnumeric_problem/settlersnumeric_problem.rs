@@ -1,15 +1,18 @@
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::pareto::MultiObjectiveProblem;
+use crate::search::state_codec::{BitReader, BitWriter, PackedProblem, StateCodec, SymbolTable};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use serde_json::Value as JsonValue;
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Place {
     pub id: String, // Unique identifier for the place
-    pub available_resources: HashMap<String, i32>, // Resource inventory at this location
+    pub available_resources: BTreeMap<String, i32>, // Resource inventory at this location
     pub carts: i32, // Number of carts present
     pub housing: i32, // Number of housing units
     pub has_cabin: bool, // Whether a cabin has been built
@@ -26,30 +29,141 @@ pub struct Place {
     pub is_by_coast: bool, // True if this place is adjacent to the sea
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Vehicle {
     pub id: String, // Unique identifier for the vehicle
     pub is_train: bool, // True if the vehicle is a train
     pub is_ship: bool, // True if the vehicle is a ship
     pub space_in: i32, // Remaining capacity in the vehicle
-    pub available_resources: HashMap<String, i32>, // Resources currently carried
+    pub available_resources: BTreeMap<String, i32>, // Resources currently carried
     pub location: String, // Place ID where the vehicle is located
     pub potential: bool, // True if the vehicle is not yet built
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Metric fluents are not part of the state, see `SettlersMetrics`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct State {
-    pub places: HashMap<String, Place>, // All known places in the world
-    pub vehicles: HashMap<String, Vehicle>, // All vehicles and their current state
-    pub labour: i32, // Total labour expended so far
-    pub resource_use: i32, // Total resource use so far
-    pub pollution: i32, // Pollution level accumulated
-    pub connections_by_rail: HashMap<String, Vec<String>>, // Rail connectivity between places
+    pub places: BTreeMap<String, Place>, // All known places in the world
+    pub vehicles: BTreeMap<String, Vehicle>, // All vehicles and their current state
+    pub connections_by_rail: BTreeMap<String, Vec<String>>, // Rail connectivity between places
 }
 
 
 impl StateTrait for State {}
 
+/// Actions of the settlers domain. Places, including move targets, and vehicles are referred
+/// to by their position in the state and resources by their position in `RESOURCES`, so that
+/// an action names the same operator in every state. A rail is built towards the place named
+/// by the given goal condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SettlersNumericAction {
+    Load {
+        vehicle: usize,
+        resource: usize,
+    },
+    Unload {
+        vehicle: usize,
+        resource: usize,
+    },
+    MoveTrain {
+        vehicle: usize,
+        to: usize,
+    },
+    MoveShip {
+        vehicle: usize,
+        to: usize,
+    },
+    MoveEmptyCart {
+        from: usize,
+        to: usize,
+    },
+    MoveLadenCart {
+        from: usize,
+        to: usize,
+        resource: usize,
+    },
+    BuildRail(usize),
+    BuildTrain {
+        vehicle: usize,
+        place: usize,
+    },
+    BuildShip {
+        vehicle: usize,
+        place: usize,
+    },
+    BuildCabin(usize),
+    BuildDocks(usize),
+    BuildQuarry(usize),
+    BuildSawmill(usize),
+    BuildHouse(usize),
+    BuildCart(usize),
+    BuildIronworks(usize),
+    BuildCoalStack(usize),
+    BuildMine(usize),
+    BuildWharf(usize),
+    FellTimber(usize),
+    BurnCoal(usize),
+    SawWood(usize),
+    MakeIron(usize),
+    MineOre(usize),
+    BreakStone(usize),
+}
+
+impl ActionTrait for SettlersNumericAction {}
+
+/// Resources of the domain, in the order their ids refer to.
+pub const RESOURCES: [&str; 6] = ["coal", "iron", "ore", "stone", "timber", "wood"];
+
+fn resource_id(resource: &str) -> usize {
+    RESOURCES
+        .iter()
+        .position(|&r| r == resource)
+        .unwrap_or_else(|| panic!("Unknown resource {}", resource))
+}
+
+/// Increments of the PDDL metric fluents `labour`, `resource-use` and `pollution`. States do
+/// not accumulate them, so that equal configurations reached along different paths are the
+/// same state; every instance starts them at zero, so summing the increments of a plan gives
+/// their final values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SettlersMetrics {
+    pub labour: i32,
+    pub resource_use: i32,
+    pub pollution: i32,
+}
+
+impl SettlersNumericAction {
+    /// Metric increments of the action, as in the domain's effects.
+    pub fn metrics(&self) -> SettlersMetrics {
+        let (labour, resource_use, pollution) = match self {
+            SettlersNumericAction::Load { .. } | SettlersNumericAction::Unload { .. } => (1, 0, 0),
+            SettlersNumericAction::MoveTrain { .. } => (0, 0, 1),
+            SettlersNumericAction::MoveShip { .. } => (0, 0, 2),
+            SettlersNumericAction::MoveEmptyCart { .. }
+            | SettlersNumericAction::MoveLadenCart { .. } => (2, 0, 0),
+            SettlersNumericAction::BuildRail(_)
+            | SettlersNumericAction::BuildTrain { .. }
+            | SettlersNumericAction::BuildDocks(_)
+            | SettlersNumericAction::BuildQuarry(_)
+            | SettlersNumericAction::BuildSawmill(_)
+            | SettlersNumericAction::BuildCoalStack(_)
+            | SettlersNumericAction::BuildWharf(_) => (2, 0, 0),
+            SettlersNumericAction::BuildShip { .. }
+            | SettlersNumericAction::BuildIronworks(_)
+            | SettlersNumericAction::BuildMine(_) => (3, 0, 0),
+            SettlersNumericAction::BuildCabin(_)
+            | SettlersNumericAction::BuildCart(_)
+            | SettlersNumericAction::FellTimber(_) => (1, 0, 0),
+            SettlersNumericAction::BuildHouse(_) | SettlersNumericAction::SawWood(_) => (0, 0, 0),
+            SettlersNumericAction::BurnCoal(_) => (0, 0, 1),
+            SettlersNumericAction::MakeIron(_) => (0, 0, 2),
+            SettlersNumericAction::MineOre(_) => (1, 2, 0),
+            SettlersNumericAction::BreakStone(_) => (1, 1, 0),
+        };
+        SettlersMetrics { labour, resource_use, pollution }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Goal {
     pub conditions: Vec<Condition>,
@@ -144,11 +258,31 @@ impl Condition {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SettlersNumericProblem {
     pub goal: Goal,
-    pub connections_by_land: HashMap<String, Vec<String>>, // Place ID -> Connected Places
-    pub connections_by_sea: HashMap<String, Vec<String>>,  // Place ID -> Connected Places
+    pub connections_by_land: BTreeMap<String, Vec<String>>, // Place ID -> Connected Places
+    pub connections_by_sea: BTreeMap<String, Vec<String>>,  // Place ID -> Connected Places
+    // Ids of the places and vehicles that actions refer to by position, filled from the
+    // initial state by `index_objects`
+    #[serde(skip)]
+    pub place_ids: Vec<String>,
+    #[serde(skip)]
+    pub vehicle_ids: Vec<String>,
+    #[serde(skip)]
+    pub place_positions: HashMap<String, usize>, // Place ID -> position in `place_ids`
 }
 
 impl SettlersNumericProblem {
+    /// Builds the tables that map action arguments to the ids of places and vehicles.
+    pub fn index_objects(&mut self, state: &State) {
+        self.place_ids = state.places.keys().cloned().collect();
+        self.vehicle_ids = state.vehicles.keys().cloned().collect();
+        self.place_positions = self
+            .place_ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (id.clone(), index))
+            .collect();
+    }
+
     /// Generates a `load` action.
     pub fn get_load_action(vehicle: &Vehicle, place: &Place, resource: &str) -> Action {
         let mut parameters = HashMap::new();
@@ -332,7 +466,7 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         Action::new(format!("break_stone_{}", place.id), 1, parameters)
     }
 
-    pub fn get_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_actions(&self, state: &State) -> Vec<SettlersNumericAction> {
         let mut actions = Vec::new();
         
         // Check what resources/conditions are needed for the goal
@@ -341,9 +475,10 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         let mut need_rail_connection = false;
         let mut rail_from = String::new();
         let mut rail_to = String::new();
+        let mut rail_condition = 0;
         let mut need_resources = HashMap::new();
         
-        for condition in &self.goal.conditions {
+        for (c, condition) in self.goal.conditions.iter().enumerate() {
             if !condition.attribute.is_empty() && condition.attribute == "housing" {
                 need_housing = true;
                 need_resources.insert("wood".to_string(), true);
@@ -358,13 +493,14 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
                     need_rail_connection = true;
                     rail_from = condition.from.clone();
                     rail_to = condition.to.clone();
+                    rail_condition = c;
                     need_resources.insert("wood".to_string(), true);
                     need_resources.insert("iron".to_string(), true);
                 }
             }
             
             if !condition.resource.is_empty() {
-                need_resources.insert(condition.resource.clone(), true);
+                need_resources.insert(condition.resource.to_string(), true);
             }
         }
         
@@ -372,21 +508,21 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         
         // 1. If we need housing, generate actions to build houses
         if need_housing {
-            for place in state.places.values() {
+            for (p, place) in state.places.values().enumerate() {
                 if place.available_resources.get("wood").unwrap_or(&0) >= &1
                     && place.available_resources.get("stone").unwrap_or(&0) >= &1
                 {
-                    actions.push(Self::get_build_house_action(place));
+                    actions.push(SettlersNumericAction::BuildHouse(p));
                 }
             }
         }
         
         // 2. If we need sawmills, generate actions to build sawmills
         if need_sawmill {
-            for place in state.places.values() {
+            for (p, place) in state.places.values().enumerate() {
                 if place.is_woodland && !place.has_sawmill 
                     && place.available_resources.get("timber").unwrap_or(&0) >= &2 {
-                    actions.push(Self::get_build_sawmill_action(place));
+                    actions.push(SettlersNumericAction::BuildSawmill(p));
                 }
             }
         }
@@ -398,30 +534,30 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
                     && from_place.available_resources.get("iron").unwrap_or(&0) >= &1
                     && !state.connections_by_rail.get(&rail_from).unwrap_or(&vec![]).contains(&rail_to)
                 {
-                    actions.push(Self::get_build_rail_action(from_place, &rail_to));
+                    actions.push(SettlersNumericAction::BuildRail(rail_condition));
                 }
             }
         }
         
         // 4. Generate resource gathering actions only for needed resources
-        for place in state.places.values() {
+        for (p, place) in state.places.values().enumerate() {
             // Only generate timber-related actions if we need timber or wood
             if need_resources.contains_key("timber") || need_resources.contains_key("wood") {
                 // B: Don't build more than one cabin per woodland
                 if place.is_woodland && !place.has_cabin && 
                    state.places.values().filter(|p| p.has_cabin).count() < 2 {
-                    actions.push(Self::get_build_cabin_action(place));
+                    actions.push(SettlersNumericAction::BuildCabin(p));
                 }
                 
                 // C: Don't fell more timber than needed
                 if place.has_cabin && 
                    state.places.values().map(|p| 
                        p.available_resources.get("timber").unwrap_or(&0)).sum::<i32>() < 5 {
-                    actions.push(Self::get_fell_timber_action(place));
+                    actions.push(SettlersNumericAction::FellTimber(p));
                 }
                 
                 if place.has_sawmill && place.available_resources.get("timber").unwrap_or(&0) >= &1 {
-                    actions.push(Self::get_saw_wood_action(place));
+                    actions.push(SettlersNumericAction::SawWood(p));
                 }
             }
             
@@ -430,63 +566,67 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
                 // B: Don't build more than one quarry
                 if place.is_mountain && !place.has_quarry && 
                    state.places.values().filter(|p| p.has_quarry).count() < 2 {
-                    actions.push(Self::get_build_quarry_action(place));
+                    actions.push(SettlersNumericAction::BuildQuarry(p));
                 }
                 
                 // C: Don't break more stone than needed
                 if place.has_quarry && 
                    state.places.values().map(|p| 
                        p.available_resources.get("stone").unwrap_or(&0)).sum::<i32>() < 3 {
-                    actions.push(Self::get_break_stone_action(place));
+                    actions.push(SettlersNumericAction::BreakStone(p));
                 }
             }
             
             // Only generate iron-related actions if we need iron
             if need_resources.contains_key("iron") {
                 if place.is_metalliferous && !place.has_mine {
-                    actions.push(Self::get_build_mine_action(place));
+                    actions.push(SettlersNumericAction::BuildMine(p));
                 }
                 
                 // C: Don't mine more ore than needed
                 if place.has_mine && 
                    state.places.values().map(|p| 
                        p.available_resources.get("ore").unwrap_or(&0)).sum::<i32>() < 3 {
-                    actions.push(Self::get_mine_ore_action(place));
+                    actions.push(SettlersNumericAction::MineOre(p));
                 }
                 
                 if place.available_resources.get("timber").unwrap_or(&0) >= &1 && !place.has_coal_stack {
-                    actions.push(Self::get_build_coal_stack_action(place));
+                    actions.push(SettlersNumericAction::BuildCoalStack(p));
                 }
                 
                 // C: Don't burn more coal than needed
                 if place.has_coal_stack && place.available_resources.get("timber").unwrap_or(&0) >= &1 &&
                    state.places.values().map(|p| 
                        p.available_resources.get("coal").unwrap_or(&0)).sum::<i32>() < 4 {
-                    actions.push(Self::get_burn_coal_action(place));
+                    actions.push(SettlersNumericAction::BurnCoal(p));
                 }
                 
                 if place.has_ironworks
                     && place.available_resources.get("ore").unwrap_or(&0) >= &1
                     && place.available_resources.get("coal").unwrap_or(&0) >= &2
                 {
-                    actions.push(Self::get_make_iron_action(place));
+                    actions.push(SettlersNumericAction::MakeIron(p));
                 }
             }
         }
         
         // 5. Generate transport actions only for needed resources
-        for (from_id, from_place) in &state.places {
+        for (from, (from_id, from_place)) in state.places.iter().enumerate() {
             if from_place.carts > 0 {
                 if let Some(connected_places) = self.connections_by_land.get(from_id) {
                     for to_id in connected_places {
-                        if let Some(to_place) = state.places.get(to_id) {
+                        if let Some(&to) = self.place_positions.get(to_id) {
                             // Move empty cart
-                            actions.push(Self::get_move_empty_cart_action(from_place, to_place));
+                            actions.push(SettlersNumericAction::MoveEmptyCart { from, to });
                             
                             // Move laden cart only for needed resources
                             for (resource, &amount) in &from_place.available_resources {
                                 if amount > 0 && need_resources.contains_key(resource) {
-                                    actions.push(Self::get_move_laden_cart_action(from_place, to_place, resource));
+                                    actions.push(SettlersNumericAction::MoveLadenCart {
+                                        from,
+                                        to,
+                                        resource: resource_id(resource),
+                                    });
                                 }
                             }
                         }
@@ -497,11 +637,11 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         
         // Add cart building actions if we need to move resources
         if !need_resources.is_empty() {
-            for place in state.places.values() {
+            for (p, place) in state.places.values().enumerate() {
                 // B: Limit the number of carts to what's needed
                 if place.available_resources.get("timber").unwrap_or(&0) >= &1 && 
                    state.places.values().map(|p| p.carts).sum::<i32>() < 3 {
-                    actions.push(Self::get_build_cart_action(place));
+                    actions.push(SettlersNumericAction::BuildCart(p));
                 }
             }
         }
@@ -510,129 +650,108 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
     }
     
 
+    fn place_id(&self, place: usize) -> &String {
+        &self.place_ids[place]
+    }
+
+    fn place<'a>(&self, state: &'a State, place: usize) -> &'a Place {
+        &state.places[&self.place_ids[place]]
+    }
+
+    fn vehicle<'a>(&self, state: &'a State, vehicle: usize) -> &'a Vehicle {
+        &state.vehicles[&self.vehicle_ids[vehicle]]
+    }
+
     /// Applies a `load` action to the state.
-    pub fn apply_load_action(state: &State, action: &Action) -> State {
+    pub fn apply_load_action(
+        state: &State,
+        vehicle_id: &str,
+        place_id: &str,
+        resource: &str,
+    ) -> State {
         let mut new_state = state.clone();
-        let vehicle_id = match action.parameters.get("vehicle") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid vehicle ID in action"),
-        };
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
-        let resource = match action.parameters.get("resource") {
-            Some(Value::Text(res)) => res,
-            _ => panic!("Invalid resource in action"),
-        };
 
         let vehicle = new_state.vehicles.get_mut(vehicle_id).unwrap();
         let place = new_state.places.get_mut(place_id).unwrap();
 
       
         *place.available_resources.get_mut(resource).unwrap() -= 1;
-        *vehicle.available_resources.entry(resource.clone()).or_insert(0) += 1;
+        *vehicle
+            .available_resources
+            .entry(resource.to_string())
+            .or_insert(0) += 1;
         vehicle.space_in -= 1;
-        new_state.labour += 1;
     
 
         new_state
     }
 
     /// Applies an `unload` action to the state.
-    pub fn apply_unload_action(state: &State, action: &Action) -> State {
+    pub fn apply_unload_action(
+        state: &State,
+        vehicle_id: &str,
+        place_id: &str,
+        resource: &str,
+    ) -> State {
         let mut new_state = state.clone();
-        let vehicle_id = match action.parameters.get("vehicle") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid vehicle ID in action"),
-        };
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
-        let resource = match action.parameters.get("resource") {
-            Some(Value::Text(res)) => res,
-            _ => panic!("Invalid resource in action"),
-        };
 
         let vehicle = new_state.vehicles.get_mut(vehicle_id).unwrap();
         let place = new_state.places.get_mut(place_id).unwrap();
 
         
         *vehicle.available_resources.get_mut(resource).unwrap() -= 1;
-        *place.available_resources.entry(resource.clone()).or_insert(0) += 1;
+        *place
+            .available_resources
+            .entry(resource.to_string())
+            .or_insert(0) += 1;
         vehicle.space_in += 1;
-        new_state.labour += 1;
     
 
         new_state
     }
 
     /// Applies a `build_docks` action to the state.
-    pub fn apply_build_docks_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_docks_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         *place.available_resources.get_mut("stone").unwrap() -= 2;
         *place.available_resources.get_mut("wood").unwrap() -= 2;
         place.has_docks = true;
-        new_state.labour += 2;
 
         new_state
     }
 
     /// Applies a `build_quarry` action to the state.
-    pub fn apply_build_quarry_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_quarry_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         // Apply effects directly
         place.has_quarry = true;
-        new_state.labour += 2;
 
         new_state
     }
 
     /// Applies a `build_sawmill` action to the state.
-    pub fn apply_build_sawmill_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_sawmill_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
     
         if place.available_resources.get("timber").unwrap_or(&0) >= &2 {
             *place.available_resources.get_mut("timber").unwrap() -= 2;
             place.has_sawmill = true;
-            new_state.labour += 2;
         }
     
         new_state
     }
     
 
-    pub fn apply_move_empty_cart_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_empty_cart_action(state: &State, from: &str, to: &str) -> State {
         let mut new_state = state.clone();
-        let from = match action.parameters.get("from") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid from place ID in action"),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid to place ID in action"),
-        };
     
         // Get the cart count from the "from" place
         let from_carts = new_state.places.get(from).unwrap().carts;
@@ -642,27 +761,19 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
             new_state.places.get_mut(from).unwrap().carts -= 1;
             // Update the "to" place
             new_state.places.get_mut(to).unwrap().carts += 1;
-            new_state.labour += 2;
         }
     
         new_state
     }
     
 
-    pub fn apply_move_laden_cart_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_laden_cart_action(
+        state: &State,
+        from: &str,
+        to: &str,
+        resource: &str,
+    ) -> State {
         let mut new_state = state.clone();
-        let from = match action.parameters.get("from") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid from place ID in action"),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid to place ID in action"),
-        };
-        let resource = match action.parameters.get("resource") {
-            Some(Value::Text(res)) => res,
-            _ => panic!("Invalid resource in action"),
-        };
     
         // Get the cart count and resource amount from the "from" place
         let from_place = new_state.places.get(from).unwrap();
@@ -678,9 +789,10 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
             // Update the "to" place
             let to_place = new_state.places.get_mut(to).unwrap();
             to_place.carts += 1;
-            *to_place.available_resources.entry(resource.clone()).or_insert(0) += 1;
-            
-            new_state.labour += 2;
+            *to_place
+                .available_resources
+                .entry(resource.to_string())
+                .or_insert(0) += 1;
         }
     
         new_state
@@ -688,12 +800,8 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
     
     
     
-    pub fn apply_build_house_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_house_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
     
@@ -705,97 +813,63 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
     }
 
 
-    pub fn apply_mine_ore_action(state: &State, action: &Action) -> State {
+    pub fn apply_mine_ore_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
     
         if place.has_mine {
             *place.available_resources.entry("ore".to_string()).or_insert(0) += 1;
-            new_state.labour += 1;
-            new_state.resource_use += 2; // Increment resource_use by 2 as per PDDL
         }
     
         new_state
     }
 
-    pub fn apply_build_coal_stack_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_coal_stack_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         *place.available_resources.get_mut("timber").unwrap() -= 1;
         place.has_coal_stack = true;
-        new_state.labour += 2;
 
         new_state
     }
 
-    pub fn apply_fell_timber_action(state: &State, action: &Action) -> State {
+    pub fn apply_fell_timber_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         *place.available_resources.entry("timber".to_string()).or_insert(0) += 1;
-        new_state.labour += 1;
 
         new_state
     }
 
-    pub fn apply_burn_coal_action(state: &State, action: &Action) -> State {
+    pub fn apply_burn_coal_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         *place.available_resources.get_mut("timber").unwrap() -= 1;
         *place.available_resources.entry("coal".to_string()).or_insert(0) += 1;
-        new_state.pollution += 1;
     
         new_state
     }
 
-    pub fn apply_build_mine_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_mine_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         *place.available_resources.get_mut("wood").unwrap() -= 2;
         place.has_mine = true;
-        new_state.labour += 3;
 
         new_state
     }
 
-    pub fn apply_build_train_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_train_action(state: &State, place_id: &str, vehicle_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
-        let vehicle_id = match action.parameters.get("vehicle") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid vehicle ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
         let vehicle = new_state.vehicles.get_mut(vehicle_id).unwrap();
@@ -804,7 +878,7 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         *place.available_resources.get_mut("iron").unwrap() -= 2;
         vehicle.is_train = true;
         vehicle.potential = false;
-        vehicle.location = place_id.clone(); // Set the location
+        vehicle.location = place_id.to_string(); // Set the location
         vehicle.space_in = 5;
         
         // Initialize resources to 0
@@ -815,101 +889,70 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         vehicle.available_resources.insert("iron".to_string(), 0);
         vehicle.available_resources.insert("ore".to_string(), 0);
         
-        new_state.labour += 2;
     
         new_state
     }
     
     
-    pub fn apply_make_iron_action(state: &State, action: &Action) -> State {
+    pub fn apply_make_iron_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         *place.available_resources.get_mut("ore").unwrap() -= 1;
         *place.available_resources.get_mut("coal").unwrap() -= 2;
         *place.available_resources.entry("iron".to_string()).or_insert(0) += 1;
-        new_state.pollution += 2;
 
         new_state
     }
 
-    pub fn apply_move_train_action(state: &State, action: &Action) -> State {
+    pub fn apply_move_train_action(state: &State, vehicle_id: &str, from: &str, to: &str) -> State {
         let mut new_state = state.clone();
-        let vehicle_id = match action.parameters.get("vehicle") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid vehicle ID in action"),
-        };
-        let from = match action.parameters.get("from") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid from place ID in action"),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid to place ID in action"),
-        };
     
         let vehicle = new_state.vehicles.get_mut(vehicle_id).unwrap();
         
         // Apply effects directly if preconditions are met
         if vehicle.is_train
-            && vehicle.location == *from
+            && vehicle.location == from
             && vehicle.available_resources.get("coal").unwrap_or(&0) >= &1
-            && new_state.connections_by_rail.get(from).unwrap_or(&vec![]).contains(to)
+            && new_state
+                .connections_by_rail
+                .get(from)
+                .map_or(false, |places| places.iter().any(|p| p == to))
         {
             *vehicle.available_resources.get_mut("coal").unwrap() -= 1;
-            vehicle.location = to.clone();
-            new_state.pollution += 1;
+            vehicle.location = to.to_string();
         }
     
         new_state
     }
     
 
-    pub fn apply_build_wharf_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_wharf_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
         *place.available_resources.get_mut("stone").unwrap() -= 2;
         *place.available_resources.get_mut("iron").unwrap() -= 2;
         place.has_wharf = true;
-        new_state.labour += 2;
 
         new_state
     }
 
-    pub fn apply_break_stone_action(state: &State, action: &Action) -> State {
+    pub fn apply_break_stone_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
     
         *place.available_resources.entry("stone".to_string()).or_insert(0) += 1;
-        new_state.labour += 1;
-        new_state.resource_use += 1;
     
         new_state
     }
     
 
-    pub fn apply_saw_wood_action(state: &State, action: &Action) -> State {
+    pub fn apply_saw_wood_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
 
         let place = new_state.places.get_mut(place_id).unwrap();
 
@@ -919,18 +962,9 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         new_state
     }
 
-    pub fn apply_build_rail_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_rail_action(state: &State, from: &str, to: &str) -> State {
         let mut new_state = state.clone();
-        
-        let from = match action.parameters.get("from") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid from place ID in action"),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid to place ID in action"),
-        };
-    
+
         let from_place = new_state.places.get_mut(from).unwrap();
     
         // Apply effects directly
@@ -940,89 +974,66 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
         // Update the connections_by_rail in the state
         new_state
             .connections_by_rail
-            .entry(from.clone())
+            .entry(from.to_string())
             .or_insert_with(Vec::new)
-            .push(to.clone());
+            .push(to.to_string());
             
-        new_state.labour += 2;
     
         new_state
     }
     
 
 
-    pub fn apply_build_ironworks_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_ironworks_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
     
         *place.available_resources.get_mut("stone").unwrap() -= 2;
         *place.available_resources.get_mut("wood").unwrap() -= 2;
         place.has_ironworks = true;
-        new_state.labour += 3;
     
         new_state
     }
 
-    pub fn apply_build_cabin_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_cabin_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
     
         place.has_cabin = true;
-        new_state.labour += 1;
     
         new_state
     }
     
-    pub fn apply_move_ship_action(state: &State, action: &Action, problem: &SettlersNumericProblem) -> State {
+    pub fn apply_move_ship_action(
+        state: &State,
+        vehicle_id: &str,
+        from: &str,
+        to: &str,
+        problem: &SettlersNumericProblem,
+    ) -> State {
         let mut new_state = state.clone();
-        let vehicle_id = match action.parameters.get("vehicle") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid vehicle ID in action"),
-        };
-        let from = match action.parameters.get("from") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid from place ID in action"),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid to place ID in action"),
-        };
 
         let vehicle = new_state.vehicles.get_mut(vehicle_id).unwrap();
 
         if vehicle.is_ship 
-            && vehicle.location == *from
-            && vehicle.available_resources.get("coal").unwrap_or(&0) >= &2 
-            && problem.connections_by_sea.get(from).unwrap_or(&vec![]).contains(to)
+            && vehicle.location == from
+            && vehicle.available_resources.get("coal").unwrap_or(&0) >= &2
+            && problem
+                .connections_by_sea
+                .get(from)
+                .map_or(false, |places| places.iter().any(|p| p == to))
         {
             *vehicle.available_resources.get_mut("coal").unwrap() -= 2;
-            vehicle.location = to.clone();
-            new_state.pollution += 2;
+            vehicle.location = to.to_string();
         }
 
         new_state
     }
     
-    pub fn apply_build_ship_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_ship_action(state: &State, vehicle_id: &str, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let vehicle_id = match action.parameters.get("vehicle") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid vehicle ID in action"),
-        };
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
         let vehicle = new_state.vehicles.get_mut(vehicle_id).unwrap();
@@ -1033,7 +1044,7 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
             *place.available_resources.get_mut("iron").unwrap() -= 4;
             vehicle.is_ship = true;
             vehicle.potential = false;
-            vehicle.location = place_id.clone();
+            vehicle.location = place_id.to_string();
             vehicle.space_in = 10;
             
             // Initialize resources to 0
@@ -1044,24 +1055,18 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
             vehicle.available_resources.insert("iron".to_string(), 0);
             vehicle.available_resources.insert("ore".to_string(), 0);
             
-            new_state.labour += 3;
         }
     
         new_state
     }
 
-    pub fn apply_build_cart_action(state: &State, action: &Action) -> State {
+    pub fn apply_build_cart_action(state: &State, place_id: &str) -> State {
         let mut new_state = state.clone();
-        let place_id = match action.parameters.get("place") {
-            Some(Value::Text(id)) => id,
-            _ => panic!("Invalid place ID in action"),
-        };
     
         let place = new_state.places.get_mut(place_id).unwrap();
     
         *place.available_resources.get_mut("timber").unwrap() -= 1;
         place.carts += 1;
-        new_state.labour += 1;
     
         new_state
     }
@@ -1069,67 +1074,210 @@ pub fn get_move_ship_action(vehicle: &Vehicle, from: &str, to: &str) -> Action {
 }
 impl Problem for SettlersNumericProblem {
     type State = State;
+    type Action = SettlersNumericAction;
+    type Cost = i32;
 
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<SettlersNumericAction> {
         self.get_actions(state)
     }
 
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("load_") {
-            Self::apply_load_action(state, action)
-        } else if action.name.starts_with("unload_") {
-            Self::apply_unload_action(state, action)
-        } else if action.name.starts_with("move_train_") {
-            Self::apply_move_train_action(state, action)
-        } else if action.name.starts_with("move_empty_cart_") {
-            Self::apply_move_empty_cart_action(state, action)
-        } else if action.name.starts_with("move_laden_cart_") {
-            Self::apply_move_laden_cart_action(state, action)
-        } else if action.name.starts_with("build_coal_stack_") {
-            Self::apply_build_coal_stack_action(state, action)
-        } else if action.name.starts_with("fell_timber_") {
-            Self::apply_fell_timber_action(state, action)
-        } else if action.name.starts_with("burn_coal_") {
-            Self::apply_burn_coal_action(state, action)
-        } else if action.name.starts_with("build_docks_") {
-            Self::apply_build_docks_action(state, action)
-        } else if action.name.starts_with("build_house_") {
-            Self::apply_build_house_action(state, action)
-        } else if action.name.starts_with("build_quarry_") {
-            Self::apply_build_quarry_action(state, action)
-        } else if action.name.starts_with("build_sawmill_") {
-            Self::apply_build_sawmill_action(state, action)
-        } else if action.name.starts_with("build_wharf_") {
-            Self::apply_build_wharf_action(state, action)
-        } else if action.name.starts_with("build_mine_") {
-            Self::apply_build_mine_action(state, action)
-        } else if action.name.starts_with("build_train_") {
-            Self::apply_build_train_action(state, action)
-        } else if action.name.starts_with("make_iron_") {
-            Self::apply_make_iron_action(state, action)
-        } else if action.name.starts_with("break_stone_") {
-            Self::apply_break_stone_action(state, action)
-        } else if action.name.starts_with("saw_wood_") {
-            Self::apply_saw_wood_action(state, action)
-        } else if action.name.starts_with("build_rail_") {
-            Self::apply_build_rail_action(state, action)
-        } else if action.name.starts_with("build_ironworks_") {
-            Self::apply_build_ironworks_action(state, action)
-        } else if action.name.starts_with("build_cabin_") {
-            Self::apply_build_cabin_action(state, action)
-        } else if action.name.starts_with("move_ship_") {
-            Self::apply_move_ship_action(state, action, self)
-        } else if action.name.starts_with("build_ship_") {
-            Self::apply_build_ship_action(state, action)
-        } else if action.name.starts_with("mine_ore_") {
-            Self::apply_mine_ore_action(state, action)
-        } else if action.name.starts_with("build_cart_") {
-            Self::apply_build_cart_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    fn apply_action(&self, state: &State, action: &SettlersNumericAction) -> State {
+        match *action {
+            SettlersNumericAction::Load { vehicle, resource } => {
+                let vehicle = self.vehicle(state, vehicle);
+                let place = &state.places[&vehicle.location];
+                Self::apply_load_action(state, &vehicle.id, &place.id, RESOURCES[resource])
+            }
+            SettlersNumericAction::Unload { vehicle, resource } => {
+                let vehicle = self.vehicle(state, vehicle);
+                Self::apply_unload_action(state, &vehicle.id, &vehicle.location, RESOURCES[resource])
+            }
+            SettlersNumericAction::MoveTrain { vehicle, to } => {
+                let vehicle = self.vehicle(state, vehicle);
+                let to = self.place_id(to);
+                Self::apply_move_train_action(state, &vehicle.id, &vehicle.location, to)
+            }
+            SettlersNumericAction::MoveShip { vehicle, to } => {
+                let vehicle = self.vehicle(state, vehicle);
+                let to = self.place_id(to);
+                Self::apply_move_ship_action(state, &vehicle.id, &vehicle.location, to, self)
+            }
+            SettlersNumericAction::MoveEmptyCart { from, to } => {
+                let (from, to) = (self.place_id(from), self.place_id(to));
+                Self::apply_move_empty_cart_action(state, from, to)
+            }
+            SettlersNumericAction::MoveLadenCart { from, to, resource } => {
+                let (from, to) = (self.place_id(from), self.place_id(to));
+                Self::apply_move_laden_cart_action(state, from, to, RESOURCES[resource])
+            }
+            SettlersNumericAction::BuildRail(condition) => {
+                let condition = &self.goal.conditions[condition];
+                Self::apply_build_rail_action(state, &condition.from, &condition.to)
+            }
+            SettlersNumericAction::BuildTrain { vehicle, place } => {
+                let vehicle = &self.vehicle_ids[vehicle];
+                Self::apply_build_train_action(state, self.place_id(place), vehicle)
+            }
+            SettlersNumericAction::BuildShip { vehicle, place } => {
+                let vehicle = &self.vehicle_ids[vehicle];
+                Self::apply_build_ship_action(state, vehicle, self.place_id(place))
+            }
+            SettlersNumericAction::BuildCabin(place) => {
+                Self::apply_build_cabin_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildDocks(place) => {
+                Self::apply_build_docks_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildQuarry(place) => {
+                Self::apply_build_quarry_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildSawmill(place) => {
+                Self::apply_build_sawmill_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildHouse(place) => {
+                Self::apply_build_house_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildCart(place) => {
+                Self::apply_build_cart_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildIronworks(place) => {
+                Self::apply_build_ironworks_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildCoalStack(place) => {
+                Self::apply_build_coal_stack_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildMine(place) => {
+                Self::apply_build_mine_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BuildWharf(place) => {
+                Self::apply_build_wharf_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::FellTimber(place) => {
+                Self::apply_fell_timber_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BurnCoal(place) => {
+                Self::apply_burn_coal_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::SawWood(place) => {
+                Self::apply_saw_wood_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::MakeIron(place) => {
+                Self::apply_make_iron_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::MineOre(place) => {
+                Self::apply_mine_ore_action(state, self.place_id(place))
+            }
+            SettlersNumericAction::BreakStone(place) => {
+                Self::apply_break_stone_action(state, self.place_id(place))
+            }
+        }
+    }
+
+    fn action_cost(&self, _state: &State, action: &SettlersNumericAction) -> i32 {
+        match action {
+            SettlersNumericAction::MoveEmptyCart { .. }
+            | SettlersNumericAction::MoveLadenCart { .. }
+            | SettlersNumericAction::MoveShip { .. }
+            | SettlersNumericAction::MineOre(_) => 2,
+            SettlersNumericAction::BuildShip { .. } => 3,
+            _ => 1,
         }
     }
-    
+
+    fn describe_action(&self, state: &State, action: &SettlersNumericAction) -> Action {
+        match *action {
+            SettlersNumericAction::Load { vehicle, resource } => {
+                let vehicle = self.vehicle(state, vehicle);
+                let place = &state.places[&vehicle.location];
+                Self::get_load_action(vehicle, place, RESOURCES[resource])
+            }
+            SettlersNumericAction::Unload { vehicle, resource } => {
+                let vehicle = self.vehicle(state, vehicle);
+                let place = &state.places[&vehicle.location];
+                Self::get_unload_action(vehicle, place, RESOURCES[resource])
+            }
+            SettlersNumericAction::MoveTrain { vehicle, to } => {
+                let vehicle = self.vehicle(state, vehicle);
+                Self::get_move_train_action(vehicle, &vehicle.location, self.place_id(to))
+            }
+            SettlersNumericAction::MoveShip { vehicle, to } => {
+                let vehicle = self.vehicle(state, vehicle);
+                Self::get_move_ship_action(vehicle, &vehicle.location, self.place_id(to))
+            }
+            SettlersNumericAction::MoveEmptyCart { from, to } => Self::get_move_empty_cart_action(
+                self.place(state, from),
+                self.place(state, to),
+            ),
+            SettlersNumericAction::MoveLadenCart { from, to, resource } => {
+                Self::get_move_laden_cart_action(
+                    self.place(state, from),
+                    self.place(state, to),
+                    RESOURCES[resource],
+                )
+            }
+            SettlersNumericAction::BuildRail(condition) => {
+                let condition = &self.goal.conditions[condition];
+                Self::get_build_rail_action(&state.places[&condition.from], &condition.to)
+            }
+            SettlersNumericAction::BuildTrain { vehicle, place } => Self::get_build_train_action(
+                self.vehicle(state, vehicle),
+                self.place(state, place),
+            ),
+            SettlersNumericAction::BuildShip { vehicle, place } => Self::get_build_ship_action(
+                self.vehicle(state, vehicle),
+                self.place(state, place),
+            ),
+            SettlersNumericAction::BuildCabin(place) => {
+                Self::get_build_cabin_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildDocks(place) => {
+                Self::get_build_docks_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildQuarry(place) => {
+                Self::get_build_quarry_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildSawmill(place) => {
+                Self::get_build_sawmill_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildHouse(place) => {
+                Self::get_build_house_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildCart(place) => {
+                Self::get_build_cart_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildIronworks(place) => {
+                Self::get_build_ironworks_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildCoalStack(place) => {
+                Self::get_build_coal_stack_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildMine(place) => {
+                Self::get_build_mine_action(self.place(state, place))
+            }
+            SettlersNumericAction::BuildWharf(place) => {
+                Self::get_build_wharf_action(self.place(state, place))
+            }
+            SettlersNumericAction::FellTimber(place) => {
+                Self::get_fell_timber_action(self.place(state, place))
+            }
+            SettlersNumericAction::BurnCoal(place) => {
+                Self::get_burn_coal_action(self.place(state, place))
+            }
+            SettlersNumericAction::SawWood(place) => {
+                Self::get_saw_wood_action(self.place(state, place))
+            }
+            SettlersNumericAction::MakeIron(place) => {
+                Self::get_make_iron_action(self.place(state, place))
+            }
+            SettlersNumericAction::MineOre(place) => {
+                Self::get_mine_ore_action(self.place(state, place))
+            }
+            SettlersNumericAction::BreakStone(place) => {
+                Self::get_break_stone_action(self.place(state, place))
+            }
+        }
+    }
+
     fn is_goal_state(&self, state: &State) -> bool {
         self.goal.is_goal_state(state)
     }
@@ -1156,9 +1304,180 @@ impl Problem for SettlersNumericProblem {
         // Deserialize each part into the corresponding struct.
         let state: State =
             serde_json::from_value(state_value.clone()).expect("Failed to deserialize state");
-        let problem: SettlersNumericProblem =
+        let mut problem: SettlersNumericProblem =
             serde_json::from_value(problem_value.clone()).expect("Failed to deserialize problem");
+        problem.index_objects(&state);
 
         (state, problem)
     }
 }
+
+/// Bit-packed settlers states. Places and resources are stored as positions in symbol
+/// tables, including the empty location of vehicles not built yet; place terrain and ids
+/// are copied from the template state when decoding.
+pub struct SettlersStateCodec {
+    template: State,
+    places: SymbolTable,
+    resources: SymbolTable,
+}
+
+impl SettlersStateCodec {
+    pub fn new(problem: &SettlersNumericProblem, template: &State) -> Self {
+        let produced = RESOURCES.map(String::from);
+        let places = SymbolTable::new(
+            template
+                .places
+                .keys()
+                .chain(template.vehicles.values().map(|v| &v.location))
+                .chain(template.connections_by_rail.keys())
+                .chain(template.connections_by_rail.values().flatten())
+                .chain(problem.connections_by_land.values().flatten())
+                .chain(problem.connections_by_sea.values().flatten())
+                .chain(
+                    problem
+                        .goal
+                        .conditions
+                        .iter()
+                        .flat_map(|c| [&c.from, &c.to]),
+                ),
+        );
+        let resources = SymbolTable::new(
+            template
+                .places
+                .values()
+                .flat_map(|p| p.available_resources.keys())
+                .chain(
+                    template
+                        .vehicles
+                        .values()
+                        .flat_map(|v| v.available_resources.keys()),
+                )
+                .chain(&produced),
+        );
+        SettlersStateCodec {
+            template: template.clone(),
+            places,
+            resources,
+        }
+    }
+
+    fn write_resources(&self, writer: &mut BitWriter, resources: &BTreeMap<String, i32>) {
+        self.resources.check_keys(resources);
+        for resource in self.resources.names() {
+            writer.write_bool(resources.contains_key(resource));
+            if let Some(&amount) = resources.get(resource) {
+                writer.write_int(amount as i64);
+            }
+        }
+    }
+
+    fn read_resources(&self, reader: &mut BitReader) -> BTreeMap<String, i32> {
+        let mut resources = BTreeMap::new();
+        for resource in self.resources.names() {
+            if reader.read_bool() {
+                resources.insert(resource.clone(), reader.read_int() as i32);
+            }
+        }
+        resources
+    }
+}
+
+impl StateCodec<State> for SettlersStateCodec {
+    fn encode(&self, state: &State, out: &mut Vec<u8>) {
+        let mut writer = BitWriter::new(out);
+        for place in state.places.values() {
+            self.write_resources(&mut writer, &place.available_resources);
+            writer.write_int(place.carts as i64);
+            writer.write_int(place.housing as i64);
+            for built in [
+                place.has_cabin,
+                place.has_coal_stack,
+                place.has_quarry,
+                place.has_mine,
+                place.has_sawmill,
+                place.has_ironworks,
+                place.has_docks,
+                place.has_wharf,
+            ] {
+                writer.write_bool(built);
+            }
+        }
+        for vehicle in state.vehicles.values() {
+            writer.write_bool(vehicle.is_train);
+            writer.write_bool(vehicle.is_ship);
+            writer.write_bool(vehicle.potential);
+            writer.write_int(vehicle.space_in as i64);
+            self.write_resources(&mut writer, &vehicle.available_resources);
+            self.places.write(&mut writer, &vehicle.location);
+        }
+        self.places.check_keys(&state.connections_by_rail);
+        for place in self.places.names() {
+            writer.write_bool(state.connections_by_rail.contains_key(place));
+            if let Some(connections) = state.connections_by_rail.get(place) {
+                writer.write_int(connections.len() as i64);
+                for to in connections {
+                    self.places.write(&mut writer, to);
+                }
+            }
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> State {
+        let mut reader = BitReader::new(bytes);
+        let mut state = self.template.clone();
+        for place in state.places.values_mut() {
+            place.available_resources = self.read_resources(&mut reader);
+            place.carts = reader.read_int() as i32;
+            place.housing = reader.read_int() as i32;
+            for built in [
+                &mut place.has_cabin,
+                &mut place.has_coal_stack,
+                &mut place.has_quarry,
+                &mut place.has_mine,
+                &mut place.has_sawmill,
+                &mut place.has_ironworks,
+                &mut place.has_docks,
+                &mut place.has_wharf,
+            ] {
+                *built = reader.read_bool();
+            }
+        }
+        for vehicle in state.vehicles.values_mut() {
+            vehicle.is_train = reader.read_bool();
+            vehicle.is_ship = reader.read_bool();
+            vehicle.potential = reader.read_bool();
+            vehicle.space_in = reader.read_int() as i32;
+            vehicle.available_resources = self.read_resources(&mut reader);
+            vehicle.location = self.places.read(&mut reader);
+        }
+        state.connections_by_rail = BTreeMap::new();
+        for place in self.places.names() {
+            if reader.read_bool() {
+                let count = reader.read_int() as usize;
+                let connections = (0..count).map(|_| self.places.read(&mut reader)).collect();
+                state.connections_by_rail.insert(place.clone(), connections);
+            }
+        }
+        state
+    }
+}
+
+impl PackedProblem for SettlersNumericProblem {
+    type Codec = SettlersStateCodec;
+
+    fn state_codec(&self, state: &State) -> SettlersStateCodec {
+        SettlersStateCodec::new(self, state)
+    }
+}
+
+impl MultiObjectiveProblem for SettlersNumericProblem {
+    /// The metric fluents of the domain, see `SettlersNumericAction::metrics`.
+    fn objectives(&self) -> Vec<&'static str> {
+        vec!["labour", "resource_use", "pollution"]
+    }
+
+    fn objective_costs(&self, _state: &State, action: &SettlersNumericAction, _successor: &State) -> Vec<f64> {
+        let metrics = action.metrics();
+        vec![metrics.labour as f64, metrics.resource_use as f64, metrics.pollution as f64]
+    }
+}
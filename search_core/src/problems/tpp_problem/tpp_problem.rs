@@ -1,9 +1,14 @@
+use crate::heuristics::numeric_task::{Comparison, LinearExpression, NumericCondition};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::cost::FixedPoint;
+use crate::search::soft_goals::{GoalEntry, SoftGoalProblem};
+use crate::search::{state::StateTrait, state::Value};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 
 // State holds positions of trucks, available markets, and purchase history
@@ -42,31 +47,42 @@ pub struct MarketItem {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Goal {
     pub goal_requests: HashMap<String, i32>, // map: ItemID -> total requested quantity
+    #[serde(default)]
+    pub utilities: HashMap<String, OrderedFloat<f64>>, // map: ItemID -> utility of its request, if soft
 }
 
+// Actions of the TPP domain. Trucks and markets are referred to by their position in the
+// state, a drive's destination by its position in the locations of `distances`, and a bought
+// item by its position in the market's items, so that an action names the same operator in
+// every state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TppAction {
+    Drive { truck: usize, to: usize },
+    Buy { market: usize, item: usize },
+}
+
+impl ActionTrait for TppAction {}
+
 // The TppProblem encapsulates travel distances and purchase goals
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TppProblem {
-    pub distances: HashMap<String, Vec<(String, OrderedFloat<f64>)>>, // map: location -> [(neighbor, travel_cost)]
+    pub distances: BTreeMap<String, Vec<(String, OrderedFloat<f64>)>>, // map: location -> [(neighbor, travel_cost)]
     pub goal: Goal,                                                   // purchase goals for items
 }
 
 impl TppProblem {
     /// Generate drive actions for each truck to move between connected locations
-    pub fn get_drive_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_drive_actions(&self, state: &State) -> Vec<TppAction> {
         let mut actions = Vec::new();
-        for truck in &state.trucks {
+        for (i, truck) in state.trucks.iter().enumerate() {
             if let Some(pairs) = self.distances.get(&truck.location) {
-                for (to, cost) in pairs {
-                    let mut parameters = HashMap::new();
-                    let action_name =
-                        format!("drive_{}_from{}_to{}", truck.name, truck.location, to);
-                    parameters.insert("name".to_string(), Value::Text(truck.name.clone()));
-                    parameters.insert("from".to_string(), Value::Text(truck.location.clone()));
-                    parameters.insert("to".to_string(), Value::Text(to.clone()));
-                    let cost_i32 = (cost.into_inner().round()) as i32;
-                    // travel cost converted to integer
-                    actions.push(Action::new(action_name, cost_i32, parameters));
+                for (to, _) in pairs {
+                    let to = self
+                        .distances
+                        .keys()
+                        .position(|location| location == to)
+                        .expect("Unknown drive destination");
+                    actions.push(TppAction::Drive { truck: i, to });
                 }
             }
         }
@@ -74,36 +90,15 @@ impl TppProblem {
     }
 
     /// Generate buy actions for trucks at markets where they can purchase needed items
-    pub fn get_buy_actions(&self, state: &State) -> Vec<Action> {
+    pub fn get_buy_actions(&self, state: &State) -> Vec<TppAction> {
         let mut actions = Vec::new();
 
         for truck in &state.trucks {
-            for market in &state.markets {
+            for (m, market) in state.markets.iter().enumerate() {
                 if truck.location == market.location {
-                    for (item_id, market_item) in &market.items {
-                        let on_sale = market_item.on_sale; // available quantity
-                        let item_cost = market_item.price; // price per unit
-                        let requested = *self.goal.goal_requests.get(item_id).unwrap_or(&0); // goal quantity
-                        let already_bought = *state.items_bought.get(item_id).unwrap_or(&0); // purchased so far
-                        if on_sale > 0 && requested > already_bought {
-                            let from = &market.location; // market location
-                            let mut parameters = HashMap::new();
-                            parameters.insert("item_id".to_string(), Value::Text(item_id.clone()));
-                            parameters.insert("from".to_string(), Value::Text(from.clone()));
-                            let remaining_needed = requested - already_bought; // amount still needed
-                            let amount_to_buy = if on_sale > remaining_needed {
-                                remaining_needed
-                            } else {
-                                on_sale
-                            }; // buy up to remaining need or available
-                               // compute action cost: units * price
-                            let action_cost = (amount_to_buy as f64) * item_cost.into_inner();
-                            let action_name =
-                                format!("bought_{}_from{}_amount{}", item_id, from, amount_to_buy);
-                            parameters
-                                .insert("amount_to_buy".to_string(), Value::Int(amount_to_buy));
-                            let cost_i32 = action_cost.round() as i32; // round to integer
-                            actions.push(Action::new(action_name, cost_i32, parameters));
+                    for item in 0..market.items.len() {
+                        if self.amount_to_buy(state, m, item).1 > 0 {
+                            actions.push(TppAction::Buy { market: m, item });
                         }
                     }
                 }
@@ -113,52 +108,56 @@ impl TppProblem {
         actions
     }
 
-    /// Apply a drive action, updating the truck's location
-    pub fn apply_drive_action(state: &State, action: &Action) -> State {
-        let mut new_state = state.clone();
-        let truck_name = match action.parameters.get("name") {
-            Some(Value::Text(name)) => name,
-            _ => panic!("Action parameters do not contain a valid truck name."),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Text(to)) => to,
-            _ => panic!("Action parameters do not contain a valid market location."),
-        };
+    /// Destination and travel cost of a drive action
+    fn drive_destination(
+        &self,
+        state: &State,
+        truck: usize,
+        to: usize,
+    ) -> &(String, OrderedFloat<f64>) {
+        let to = self.distances.keys().nth(to).expect("Invalid drive destination index");
+        self.distances[&state.trucks[truck].location]
+            .iter()
+            .find(|(location, _)| location == to)
+            .expect("No road to the destination")
+    }
 
-        if let Some(truck) = new_state.trucks.iter_mut().find(|t| t.name == *truck_name) {
-            truck.location = to.to_string(); // update location
-        } else {
-            panic!("Truck with name {} not found.", truck_name);
-        }
+    /// Item bought by a buy action, with the amount bought and its price per unit:
+    /// up to the remaining need, limited by what is on sale (0 if nothing is to be bought)
+    fn amount_to_buy<'a>(
+        &self,
+        state: &'a State,
+        market: usize,
+        item: usize,
+    ) -> (&'a String, i32, OrderedFloat<f64>) {
+        let (item_id, market_item) = state.markets[market]
+            .items
+            .iter()
+            .nth(item)
+            .expect("Invalid market item index");
+        let on_sale = market_item.on_sale; // available quantity
+        let requested = *self.goal.goal_requests.get(item_id).unwrap_or(&0); // goal quantity
+        let already_bought = *state.items_bought.get(item_id).unwrap_or(&0); // purchased so far
+        let remaining_needed = requested - already_bought; // amount still needed
+        let amount_to_buy = on_sale.min(remaining_needed).max(0);
+        (item_id, amount_to_buy, market_item.price)
+    }
 
+    /// Apply a drive action, updating the truck's location
+    pub fn apply_drive_action(&self, state: &State, truck: usize, to: usize) -> State {
+        let mut new_state = state.clone();
+        let (to, _) = self.drive_destination(state, truck, to);
+        new_state.trucks[truck].location = to.to_string(); // update location
         new_state
     }
 
     /// Apply a buy action, reducing market stock and increasing purchased count
-    pub fn apply_buy_action(state: &State, action: &Action) -> State {
+    pub fn apply_buy_action(&self, state: &State, market: usize, item: usize) -> State {
         let mut new_state = state.clone();
-        let market_index = match action.parameters.get("from") {
-            Some(Value::Text(name)) => name.parse::<usize>().expect("Invalid market index format"),
-            _ => panic!("Action parameters do not contain a valid market location."),
-        };
-        let item_id = match action.parameters.get("item_id") {
-            Some(Value::Text(item_id)) => item_id,
-            _ => panic!("Action parameters do not contain a valid item index."),
-        };
+        let (item_id, amount_to_buy, _) = self.amount_to_buy(state, market, item);
 
-        let amount_to_buy = match action.parameters.get("amount_to_buy") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Action parameters do not contain a valid amount to buy."),
-        };
-
-        if let Some(market) = new_state.markets.get_mut(market_index) {
-            if let Some(market_item) = market.items.get_mut(item_id) {
-                market_item.on_sale -= amount_to_buy; // decrement market stock
-            } else {
-                panic!("Market does not sell item {}.", item_id);
-            }
-        } else {
-            panic!("Invalid market index {}.", market_index);
+        if let Some(market_item) = new_state.markets[market].items.get_mut(item_id) {
+            market_item.on_sale -= amount_to_buy; // decrement market stock
         }
 
         if let Some(count) = new_state.items_bought.get_mut(item_id) {
@@ -175,25 +174,62 @@ impl TppProblem {
 impl Problem for TppProblem {
     type State = State;
 
+    type Action = TppAction;
+
+    type Cost = FixedPoint<6>; // Distances and prices are decimals
+
     /// Gather all possible drive and buy actions
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<TppAction> {
         let mut actions = Vec::new();
         actions.extend(self.get_drive_actions(state));
         actions.extend(self.get_buy_actions(state));
         actions
     }
 
-    /// Dispatch to appropriate apply_* based on action name prefix
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("bought_") {
-            Self::apply_buy_action(state, action)
-        } else if action.name.starts_with("drive_") {
-            Self::apply_drive_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    /// Dispatch to the appropriate apply_* method
+    fn apply_action(&self, state: &State, action: &TppAction) -> State {
+        match *action {
+            TppAction::Buy { market, item } => self.apply_buy_action(state, market, item),
+            TppAction::Drive { truck, to } => self.apply_drive_action(state, truck, to),
+        }
+    }
+
+    /// Drives cost their distance, purchases the units bought times the price
+    fn action_cost(&self, state: &State, action: &TppAction) -> FixedPoint<6> {
+        match *action {
+            TppAction::Drive { truck, to } => {
+                FixedPoint::from_f64(self.drive_destination(state, truck, to).1.into_inner())
+            }
+            TppAction::Buy { market, item } => {
+                let (_, amount_to_buy, price) = self.amount_to_buy(state, market, item);
+                FixedPoint::from_f64(price.into_inner()).times(amount_to_buy as i64)
+            }
         }
     }
 
+    fn describe_action(&self, state: &State, action: &TppAction) -> Action {
+        let mut parameters = HashMap::new();
+        let action_name = match *action {
+            TppAction::Drive { truck: t, to } => {
+                let truck = &state.trucks[t];
+                let (to, _) = self.drive_destination(state, t, to);
+                parameters.insert("name".to_string(), Value::Text(truck.name.clone()));
+                parameters.insert("from".to_string(), Value::Text(truck.location.clone()));
+                parameters.insert("to".to_string(), Value::Text(to.clone()));
+                format!("drive_{}_from{}_to{}", truck.name, truck.location, to)
+            }
+            TppAction::Buy { market, item } => {
+                let (item_id, amount_to_buy, _) = self.amount_to_buy(state, market, item);
+                let from = &state.markets[market].location;
+                parameters.insert("item_id".to_string(), Value::Text(item_id.clone()));
+                parameters.insert("from".to_string(), Value::Text(from.clone()));
+                parameters.insert("amount_to_buy".to_string(), Value::Int(amount_to_buy));
+                format!("bought_{}_from{}_amount{}", item_id, from, amount_to_buy)
+            }
+        };
+        Action::new(action_name, self.action_cost(state, action), parameters)
+    }
+
     /// Check goal: all trucks at depot (-1) and all requests fulfilled
     fn is_goal_state(&self, state: &State) -> bool {
         for truck in &state.trucks {
@@ -231,3 +267,125 @@ impl Problem for TppProblem {
         (state, problem)
     }
 }
+
+impl TppProblem {
+    /// Ids of the requested items, in sorted order.
+    fn requested_items(&self) -> Vec<&String> {
+        let mut items: Vec<_> = self.goal.goal_requests.keys().collect();
+        items.sort();
+        items
+    }
+
+    /// Every location mentioned by the distance table, plus the depot, in sorted order.
+    fn locations(&self) -> Vec<String> {
+        let mut locations: BTreeSet<String> = self.distances.keys().cloned().collect();
+        for pairs in self.distances.values() {
+            locations.extend(pairs.iter().map(|(to, _)| to.clone()));
+        }
+        locations.insert("-1".to_string());
+        locations.into_iter().collect()
+    }
+}
+
+impl FactoredProblem for TppProblem {
+    /// A location flag per truck and location, the stock of every market item, and the
+    /// amount bought of every item.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let locations = self.locations();
+        let mut variables = Vec::new();
+        for truck in &state.trucks {
+            for location in &locations {
+                variables.push(Variable::boolean(format!("at_{}_{}", truck.name, location)));
+            }
+        }
+        for (m, market) in state.markets.iter().enumerate() {
+            for (item_id, item) in &market.items {
+                variables.push(Variable::integer(
+                    format!("on_sale_{}_market{}", item_id, m),
+                    Some(item.on_sale.min(0) as i64),
+                    Some(item.on_sale as i64),
+                ));
+            }
+        }
+        for (item_id, &bought) in &state.items_bought {
+            let requested = *self.goal.goal_requests.get(item_id).unwrap_or(&0);
+            variables.push(Variable::integer(
+                format!("bought_{}", item_id),
+                Some(bought as i64),
+                Some(bought.max(requested) as i64),
+            ));
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let locations = self.locations();
+        let mut values = Vec::new();
+        for truck in &state.trucks {
+            for location in &locations {
+                values.push(Value::Bool(truck.location == *location));
+            }
+        }
+        for market in &state.markets {
+            values.extend(market.items.values().map(|item| Value::Int(item.on_sale)));
+        }
+        values.extend(state.items_bought.values().map(|&b| Value::Int(b)));
+        values
+    }
+
+    /// Every truck back at the depot and every request fulfilled.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let locations = self.locations();
+        let depot = locations
+            .iter()
+            .position(|l| l == "-1")
+            .expect("depot is always a location");
+        let bought_start = state.trucks.len() * locations.len()
+            + state.markets.iter().map(|m| m.items.len()).sum::<usize>();
+        let mut conditions: Vec<NumericCondition> = (0..state.trucks.len())
+            .map(|t| NumericCondition::equals(t * locations.len() + depot, 1.0))
+            .collect();
+        for (item_id, &request) in &self.goal.goal_requests {
+            let owned = match state.items_bought.keys().position(|i| i == item_id) {
+                Some(i) => LinearExpression::variable(bought_start + i),
+                None => LinearExpression::constant(0.0),
+            };
+            conditions.push(NumericCondition::new(
+                owned,
+                Comparison::Ge,
+                &LinearExpression::constant(request as f64),
+            ));
+        }
+        FactoredGoal {
+            conditions,
+            exact: true,
+        }
+    }
+}
+
+impl SoftGoalProblem for TppProblem {
+    /// One goal per requested item, by item id; a request without a utility is hard.
+    fn goal_list(&self) -> Vec<GoalEntry> {
+        self.requested_items()
+            .into_iter()
+            .map(|item_id| {
+                let utility = self.goal.utilities.get(item_id).map(|u| u.into_inner());
+                GoalEntry::new(item_id.clone(), utility)
+            })
+            .collect()
+    }
+
+    fn goals_achieved(&self, state: &State) -> Vec<bool> {
+        self.requested_items()
+            .into_iter()
+            .map(|item_id| {
+                state.items_bought.get(item_id).unwrap_or(&0) >= &self.goal.goal_requests[item_id]
+            })
+            .collect()
+    }
+
+    /// Trucks back at the depot
+    fn final_conditions_met(&self, state: &State) -> bool {
+        state.trucks.iter().all(|truck| truck.location == "-1")
+    }
+}
@@ -1,4 +1,7 @@
+use crate::heuristics::numeric_task::NumericCondition;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
+use crate::problems::zenotravel_problem::zenotravel_problem::ZenoTravelAction;
 use crate::search::{action::Action, state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -51,35 +54,25 @@ pub struct ZenoTravelFuelProblem {
 }
 
 impl ZenoTravelFuelProblem {
-    /// Create refuel actions for every airplane (refill to capacity)
-    pub fn get_refuel_actions(state: &State) -> Vec<Action> {
-        let mut actions = Vec::new();
-        for airplane in &state.airplanes {
-            let mut parameters = HashMap::new();
-            let action_name = format!("refuel_airplane{}", airplane.index);
-            parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-            // cost fixed at 1 for refueling
-            actions.push(Action::new(action_name, 1, parameters));
-        }
-        actions
+    /// Create refuel actions for every airplane (refill fuel to capacity)
+    pub fn get_refuel_actions(state: &State) -> Vec<ZenoTravelAction> {
+        (0..state.airplanes.len())
+            .map(ZenoTravelAction::Refuel)
+            .collect()
     }
 
-    /// Generate actions for boarding persons who are on the ground
-    pub fn get_possible_board_persons(state: &State) -> Vec<Action> {
+    /// Generate boarding actions for persons on the ground
+    pub fn get_possible_board_persons(state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
         for (j, person) in state.persons.iter().enumerate() {
-            // only persons on ground (on_airplane == -1)
             if person.on_airplane == -1 {
-                for airplane in &state.airplanes {
-                    // eligible if airplane at same city as person
+                // only if on ground
+                for (i, airplane) in state.airplanes.iter().enumerate() {
                     if airplane.location == person.location {
-                        let mut parameters = HashMap::new();
-                        let action_name =
-                            format!("board_person{}_to_airplane{}", j, airplane.index);
-                        parameters.insert("person".to_string(), Value::Int(j as i32));
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        // cost fixed at 1 for boarding
-                        actions.push(Action::new(action_name, 1, parameters));
+                        actions.push(ZenoTravelAction::Board {
+                            person: j,
+                            airplane: i,
+                        });
                     }
                 }
             }
@@ -87,26 +80,18 @@ impl ZenoTravelFuelProblem {
         actions
     }
 
-    /// Generate actions for debarking persons who are onboard
-    pub fn get_possible_debark_persons(state: &State) -> Vec<Action> {
+    /// Generate debark actions for persons onboard
+    pub fn get_possible_debark_persons(state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
         for (j, person) in state.persons.iter().enumerate() {
-            // only persons onboard (on_airplane != -1)
             if person.on_airplane != -1 {
-                for airplane in &state.airplanes {
-                    // eligible if on matching airplane
+                // only if onboard
+                for (i, airplane) in state.airplanes.iter().enumerate() {
                     if airplane.index == person.on_airplane {
-                        let mut parameters = HashMap::new();
-                        let action_name =
-                            format!("debark_person{}_from_airplane{}", j, airplane.index);
-                        parameters.insert("person".to_string(), Value::Int(j as i32));
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        parameters.insert(
-                            "airplane_location".to_string(),
-                            Value::Int(airplane.location),
-                        );
-                        // cost fixed at 1 for debarking
-                        actions.push(Action::new(action_name, 1, parameters));
+                        actions.push(ZenoTravelAction::Debark {
+                            person: j,
+                            airplane: i,
+                        });
                     }
                 }
             }
@@ -114,39 +99,22 @@ impl ZenoTravelFuelProblem {
         actions
     }
 
-    /// Generate flight actions (slow and fast) for each airplane to reachable cities
-    pub fn get_possible_airplane_flys(&self, state: &State) -> Vec<Action> {
+    /// Generate flight actions (slow and fast) to every city reachable with the fuel left
+    pub fn get_possible_airplane_flys(&self, state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
-        for airplane in &state.airplanes {
+        for (i, airplane) in state.airplanes.iter().enumerate() {
             if let Some(pairs) = self.distances.get(&airplane.location.to_string()) {
-                for (city, distance) in pairs {
-                    // slow flight if enough fuel
+                for &(city, distance) in pairs {
+                    let to = city as usize;
+                    // slow flight option
                     if airplane.fuel >= airplane.slow_burn * distance {
-                        let mut parameters = HashMap::new();
-                        let action_name = format!(
-                            "fly_slow_airplane{}_from{}_to_city{}",
-                            airplane.index, airplane.location, city
-                        );
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        parameters.insert("to".to_string(), Value::Int(*city));
-                        parameters.insert("distance".to_string(), Value::Int(*distance));
-                        let cost = airplane.slow_burn * distance; // fuel cost
-                        actions.push(Action::new(action_name, cost, parameters));
+                        actions.push(ZenoTravelAction::FlySlow { airplane: i, to });
                     }
-                    // fast flight if fuel and zoom limit allow
+                    // fast flight option
                     if airplane.fuel >= airplane.fast_burn * distance
                         && airplane.onboard <= airplane.zoom_limit
                     {
-                        let mut parameters = HashMap::new();
-                        let action_name = format!(
-                            "fly_fast_airplane{}_from{}_to_city{}",
-                            airplane.index, airplane.location, city
-                        );
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        parameters.insert("to".to_string(), Value::Int(*city));
-                        parameters.insert("distance".to_string(), Value::Int(*distance));
-                        let cost = airplane.fast_burn * distance; // fuel cost
-                        actions.push(Action::new(action_name, cost, parameters));
+                        actions.push(ZenoTravelAction::FlyFast { airplane: i, to });
                     }
                 }
             }
@@ -154,100 +122,140 @@ impl ZenoTravelFuelProblem {
         actions
     }
 
+    /// Destination city and distance of a flight to city `to`
+    fn flight(&self, state: &State, airplane: usize, to: usize) -> (i32, i32) {
+        *self.distances[&state.airplanes[airplane].location.to_string()]
+            .iter()
+            .find(|&&(city, _)| city as usize == to)
+            .expect("No flight to the city")
+    }
+
+    /// Cost of a flight: the fuel it burns
+    fn fly_cost(&self, airplane: &Airplane, distance: i32, fast: bool) -> i32 {
+        if fast {
+            airplane.fast_burn * distance
+        } else {
+            airplane.slow_burn * distance
+        }
+    }
+
+    /// Display form of an action, with the indices and cities it involves as parameters
+    pub fn action_description(&self, state: &State, action: &ZenoTravelAction) -> Action {
+        let mut parameters = HashMap::new();
+        let action_name = match *action {
+            ZenoTravelAction::Refuel(airplane) => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                format!("refuel_airplane{}", airplane.index)
+            }
+            ZenoTravelAction::Board { person, airplane } => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("person".to_string(), Value::Int(person as i32));
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                format!("board_person{}_to_airplane{}", person, airplane.index)
+            }
+            ZenoTravelAction::Debark { person, airplane } => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("person".to_string(), Value::Int(person as i32));
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                parameters.insert(
+                    "airplane_location".to_string(),
+                    Value::Int(airplane.location),
+                );
+                format!("debark_person{}_from_airplane{}", person, airplane.index)
+            }
+            ZenoTravelAction::FlySlow { airplane: a, to }
+            | ZenoTravelAction::FlyFast { airplane: a, to } => {
+                let (city, distance) = self.flight(state, a, to);
+                let airplane = &state.airplanes[a];
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                parameters.insert("to".to_string(), Value::Int(city));
+                parameters.insert("distance".to_string(), Value::Int(distance));
+                let speed = match action {
+                    ZenoTravelAction::FlyFast { .. } => "fast",
+                    _ => "slow",
+                };
+                format!(
+                    "fly_{}_airplane{}_from{}_to_city{}",
+                    speed, airplane.index, airplane.location, city
+                )
+            }
+        };
+        Action::new(
+            action_name,
+            self.fly_or_unit_cost(state, action),
+            parameters,
+        )
+    }
+
+    /// Flights cost `fly_cost`, every other action 1
+    fn fly_or_unit_cost(&self, state: &State, action: &ZenoTravelAction) -> i32 {
+        match *action {
+            ZenoTravelAction::FlySlow { airplane, to } => self.fly_cost(
+                &state.airplanes[airplane],
+                self.flight(state, airplane, to).1,
+                false,
+            ),
+            ZenoTravelAction::FlyFast { airplane, to } => self.fly_cost(
+                &state.airplanes[airplane],
+                self.flight(state, airplane, to).1,
+                true,
+            ),
+            _ => 1,
+        }
+    }
+
     /// Apply refuel: refill airplane fuel to capacity
-    pub fn apply_refuel_action(state: &State, action: &Action) -> State {
+    pub fn apply_refuel_action(state: &State, airplane: usize) -> State {
         let mut new_state = state.clone();
-        let idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane index parameter."),
-        };
-        new_state.airplanes[idx].fuel = new_state.airplanes[idx].capacity; // refill
+        new_state.airplanes[airplane].fuel = new_state.airplanes[airplane].capacity; // refill
         new_state
     }
 
-    /// Apply boarding: update person and airplane onboard count
-    pub fn apply_board_action(state: &State, action: &Action) -> State {
+    /// Apply boarding: update person's on_airplane and airplane onboard count
+    pub fn apply_board_action(state: &State, person: usize, airplane: usize) -> State {
         let mut new_state = state.clone();
-        let plane_idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane index parameter."),
-        };
-        let person_idx = match action.parameters.get("person") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid person index parameter."),
-        };
-        new_state.persons[person_idx].on_airplane = plane_idx as i32;
-        new_state.persons[person_idx].location = -1; // now in air
-        new_state.airplanes[plane_idx].onboard += 1; // increment onboard
+        new_state.persons[person].on_airplane = state.airplanes[airplane].index;
+        new_state.persons[person].location = -1; // now in-air
+        new_state.airplanes[airplane].onboard += 1; // increment onboard
         new_state
     }
 
-    /// Apply debark: update person and airplane onboard count
-    pub fn apply_debark_action(state: &State, action: &Action) -> State {
+    /// Apply debark: update person's location and airplane onboard count
+    pub fn apply_debark_action(state: &State, person: usize, airplane: usize) -> State {
         let mut new_state = state.clone();
-        let plane_idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane index parameter."),
-        };
-        let person_idx = match action.parameters.get("person") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid person index parameter."),
-        };
-        let loc = match action.parameters.get("airplane_location") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid location parameter."),
-        };
-        new_state.persons[person_idx].on_airplane = -1; // now on ground
-        new_state.persons[person_idx].location = loc; // update city
-        new_state.airplanes[plane_idx].onboard -= 1; // decrement onboard
+        new_state.persons[person].on_airplane = -1; // now on-ground
+        new_state.persons[person].location = state.airplanes[airplane].location; // set city
+        new_state.airplanes[airplane].onboard -= 1; // decrement onboard
         new_state
     }
 
-    /// Apply fast flight: move airplane and deduct fast fuel consumption
-    pub fn apply_fast_fly_action(state: &State, action: &Action) -> State {
+    /// Apply fast flight: update location and deduct fast fuel
+    pub fn apply_fast_fly_action(&self, state: &State, airplane: usize, to: usize) -> State {
         let mut new_state = state.clone();
-        let dist = match action.parameters.get("distance") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid distance parameter."),
-        };
-        let idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane parameter."),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid destination parameter."),
-        };
-        new_state.airplanes[idx].location = to; // move
-        new_state.airplanes[idx].fuel -= new_state.airplanes[idx].fast_burn * dist; // deduct fuel
+        let (city, dist) = self.flight(state, airplane, to);
+        new_state.airplanes[airplane].location = city;
+        new_state.airplanes[airplane].fuel -= new_state.airplanes[airplane].fast_burn * dist;
         new_state
     }
 
-    /// Apply slow flight: move airplane and deduct slow fuel consumption
-    pub fn apply_slow_fly_action(state: &State, action: &Action) -> State {
+    /// Apply slow flight: update location and deduct slow fuel
+    pub fn apply_slow_fly_action(&self, state: &State, airplane: usize, to: usize) -> State {
         let mut new_state = state.clone();
-        let dist = match action.parameters.get("distance") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid distance parameter."),
-        };
-        let idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane parameter."),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid destination parameter."),
-        };
-        new_state.airplanes[idx].location = to; // move
-        new_state.airplanes[idx].fuel -= new_state.airplanes[idx].slow_burn * dist; // deduct fuel
+        let (city, dist) = self.flight(state, airplane, to);
+        new_state.airplanes[airplane].location = city;
+        new_state.airplanes[airplane].fuel -= new_state.airplanes[airplane].slow_burn * dist;
         new_state
     }
 }
 
 impl Problem for ZenoTravelFuelProblem {
     type State = State;
-    /// Collect all possible actions in current state
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    type Action = ZenoTravelAction;
+    type Cost = i32;
+
+    /// Collect all possible actions in the current state
+    fn get_possible_actions(&self, state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
         actions.extend(Self::get_refuel_actions(state));
         actions.extend(self.get_possible_airplane_flys(state));
@@ -255,22 +263,34 @@ impl Problem for ZenoTravelFuelProblem {
         actions.extend(Self::get_possible_debark_persons(state));
         actions
     }
-    /// Dispatch apply_* based on action name prefix
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("refuel_") {
-            Self::apply_refuel_action(state, action)
-        } else if action.name.starts_with("board_") {
-            Self::apply_board_action(state, action)
-        } else if action.name.starts_with("debark_") {
-            Self::apply_debark_action(state, action)
-        } else if action.name.starts_with("fly_fast_") {
-            Self::apply_fast_fly_action(state, action)
-        } else if action.name.starts_with("fly_slow_") {
-            Self::apply_slow_fly_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+
+    /// Dispatch to the matching apply_* method
+    fn apply_action(&self, state: &State, action: &ZenoTravelAction) -> State {
+        match *action {
+            ZenoTravelAction::Refuel(airplane) => Self::apply_refuel_action(state, airplane),
+            ZenoTravelAction::Board { person, airplane } => {
+                Self::apply_board_action(state, person, airplane)
+            }
+            ZenoTravelAction::Debark { person, airplane } => {
+                Self::apply_debark_action(state, person, airplane)
+            }
+            ZenoTravelAction::FlyFast { airplane, to } => {
+                self.apply_fast_fly_action(state, airplane, to)
+            }
+            ZenoTravelAction::FlySlow { airplane, to } => {
+                self.apply_slow_fly_action(state, airplane, to)
+            }
         }
     }
+
+    fn action_cost(&self, state: &State, action: &ZenoTravelAction) -> i32 {
+        self.fly_or_unit_cost(state, action)
+    }
+
+    fn describe_action(&self, state: &State, action: &ZenoTravelAction) -> Action {
+        self.action_description(state, action)
+    }
+
     /// Check if all airplanes and persons meet goal positions
     fn is_goal_state(&self, state: &State) -> bool {
         // airplane location goals
@@ -304,3 +324,76 @@ impl Problem for ZenoTravelFuelProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for ZenoTravelFuelProblem {
+    /// Per airplane: city, fuel and onboard count. Per person: city and airplane, each -1
+    /// when not applicable.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let last_city = (self.num_cities - 1) as i64;
+        let last_airplane = state.airplanes.iter().map(|a| a.index).max().unwrap_or(-1) as i64;
+        let mut variables = Vec::new();
+        for airplane in &state.airplanes {
+            variables.push(Variable::integer(
+                format!("airplane{}_location", airplane.index),
+                Some(0),
+                Some(last_city),
+            ));
+            variables.push(Variable::integer(
+                format!("airplane{}_fuel", airplane.index),
+                Some(airplane.fuel.min(0) as i64),
+                Some(airplane.fuel.max(airplane.capacity) as i64),
+            ));
+            variables.push(Variable::integer(
+                format!("airplane{}_onboard", airplane.index),
+                Some(0),
+                Some(state.persons.len() as i64),
+            ));
+        }
+        for p in 0..state.persons.len() {
+            variables.push(Variable::integer(
+                format!("person{}_location", p),
+                Some(-1),
+                Some(last_city),
+            ));
+            variables.push(Variable::integer(
+                format!("person{}_on_airplane", p),
+                Some(-1),
+                Some(last_airplane),
+            ));
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        for airplane in &state.airplanes {
+            values.extend([
+                Value::Int(airplane.location),
+                Value::Int(airplane.fuel),
+                Value::Int(airplane.onboard),
+            ]);
+        }
+        for person in &state.persons {
+            values.extend([Value::Int(person.location), Value::Int(person.on_airplane)]);
+        }
+        values
+    }
+
+    /// Airplanes at their cities; persons at their cities and off every airplane.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let persons_start = 3 * state.airplanes.len();
+        let mut conditions = Vec::new();
+        for &(a, city) in &self.goal.airplanes {
+            conditions.push(NumericCondition::equals(3 * a as usize, city as f64));
+        }
+        for &(p, city) in &self.goal.persons {
+            let person = persons_start + 2 * p as usize;
+            conditions.push(NumericCondition::equals(person, city as f64));
+            conditions.push(NumericCondition::equals(person + 1, -1.0));
+        }
+        FactoredGoal {
+            conditions,
+            exact: true,
+        }
+    }
+}
@@ -1,5 +1,15 @@
+use crate::heuristics::numeric_task::{
+    Comparison, LinearExpression, NumericAction, NumericCondition, NumericEffect, NumericProblem,
+    NumericTask,
+};
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::search::action::{Action, ActionTrait};
+use crate::search::stubborn_sets::{Footprint, InterferenceProblem};
+use crate::search::symmetry::{
+    canonical_order, groups_by_signature, ObjectGroup, SymmetricProblem,
+};
+use crate::search::{state::StateTrait, state::Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
@@ -51,6 +61,20 @@ pub struct MinimizeCondition {
     pub time: i32, // weight for total travel time
 }
 
+// Actions of the ZenoTravel domains. Airplanes and persons are referred to by their position
+// in the state, flight destinations by their city index, so that an action names the same
+// operator in every state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ZenoTravelAction {
+    Refuel(usize),
+    Board { person: usize, airplane: usize },
+    Debark { person: usize, airplane: usize },
+    FlySlow { airplane: usize, to: usize },
+    FlyFast { airplane: usize, to: usize },
+}
+
+impl ActionTrait for ZenoTravelAction {}
+
 // Problem definition including goals, map distances, and cost weights
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ZenoTravelProblem {
@@ -62,33 +86,24 @@ pub struct ZenoTravelProblem {
 
 impl ZenoTravelProblem {
     /// Create refuel actions for every airplane (refill fuel to capacity)
-    pub fn get_refuel_actions(state: &State) -> Vec<Action> {
-        let mut actions = Vec::new();
-        for airplane in &state.airplanes {
-            let mut parameters = HashMap::new();
-            let action_name = format!("refuel_airplane{}", airplane.index);
-            parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-            // fixed cost of 1 for refueling
-            actions.push(Action::new(action_name, 1, parameters));
-        }
-        actions
+    pub fn get_refuel_actions(state: &State) -> Vec<ZenoTravelAction> {
+        (0..state.airplanes.len())
+            .map(ZenoTravelAction::Refuel)
+            .collect()
     }
 
     /// Generate boarding actions for persons on the ground
-    pub fn get_possible_board_persons(state: &State) -> Vec<Action> {
+    pub fn get_possible_board_persons(state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
         for (j, person) in state.persons.iter().enumerate() {
             if person.on_airplane == -1 {
                 // only if on ground
-                for airplane in &state.airplanes {
+                for (i, airplane) in state.airplanes.iter().enumerate() {
                     if airplane.location == person.location {
-                        let mut parameters = HashMap::new();
-                        let action_name =
-                            format!("board_person{}_to_airplane{}", j, airplane.index);
-                        parameters.insert("person".to_string(), Value::Int(j as i32));
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        // fixed cost of 1 for boarding
-                        actions.push(Action::new(action_name, 1, parameters));
+                        actions.push(ZenoTravelAction::Board {
+                            person: j,
+                            airplane: i,
+                        });
                     }
                 }
             }
@@ -97,24 +112,17 @@ impl ZenoTravelProblem {
     }
 
     /// Generate debark actions for persons onboard
-    pub fn get_possible_debark_persons(state: &State) -> Vec<Action> {
+    pub fn get_possible_debark_persons(state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
         for (j, person) in state.persons.iter().enumerate() {
             if person.on_airplane != -1 {
                 // only if onboard
-                for airplane in &state.airplanes {
+                for (i, airplane) in state.airplanes.iter().enumerate() {
                     if airplane.index == person.on_airplane {
-                        let mut parameters = HashMap::new();
-                        let action_name =
-                            format!("debark_person{}_from_airplane{}", j, airplane.index);
-                        parameters.insert("person".to_string(), Value::Int(j as i32));
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        parameters.insert(
-                            "airplane_location".to_string(),
-                            Value::Int(airplane.location),
-                        );
-                        // fixed cost of 1 for debarking
-                        actions.push(Action::new(action_name, 1, parameters));
+                        actions.push(ZenoTravelAction::Debark {
+                            person: j,
+                            airplane: i,
+                        });
                     }
                 }
             }
@@ -122,45 +130,22 @@ impl ZenoTravelProblem {
         actions
     }
 
-    /// Generate flight actions (slow and fast) with cost = fuel_weight*fuel + time_weight*time
-    pub fn get_possible_airplane_flys(&self, state: &State) -> Vec<Action> {
+    /// Generate flight actions (slow and fast) to every city reachable with the fuel left
+    pub fn get_possible_airplane_flys(&self, state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
-        for airplane in &state.airplanes {
+        for (i, airplane) in state.airplanes.iter().enumerate() {
             if let Some(pairs) = self.distances.get(&airplane.location.to_string()) {
-                for (city, distance) in pairs {
+                for &(city, distance) in pairs {
+                    let to = city as usize;
                     // slow flight option
                     if airplane.fuel >= airplane.slow_burn * distance {
-                        let mut parameters = HashMap::new();
-                        let action_name = format!(
-                            "fly_slow_airplane{}_from{}_to_city{}",
-                            airplane.index, airplane.location, city
-                        );
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        parameters.insert("to".to_string(), Value::Int(*city));
-                        parameters.insert("distance".to_string(), Value::Int(*distance));
-                        let total_fuel = airplane.slow_burn * distance;
-                        let total_time = distance / airplane.slow_speed;
-                        let cost =
-                            self.minimize.fuel * total_fuel + self.minimize.time * total_time;
-                        actions.push(Action::new(action_name, cost, parameters));
+                        actions.push(ZenoTravelAction::FlySlow { airplane: i, to });
                     }
                     // fast flight option
                     if airplane.fuel >= airplane.fast_burn * distance
                         && airplane.onboard <= airplane.zoom_limit
                     {
-                        let mut parameters = HashMap::new();
-                        let action_name = format!(
-                            "fly_fast_airplane{}_from{}_to_city{}",
-                            airplane.index, airplane.location, city
-                        );
-                        parameters.insert("airplane".to_string(), Value::Int(airplane.index));
-                        parameters.insert("to".to_string(), Value::Int(*city));
-                        parameters.insert("distance".to_string(), Value::Int(*distance));
-                        let total_fuel = airplane.fast_burn * distance;
-                        let total_time = distance / airplane.fast_speed;
-                        let cost =
-                            self.minimize.fuel * total_fuel + self.minimize.time * total_time;
-                        actions.push(Action::new(action_name, cost, parameters));
+                        actions.push(ZenoTravelAction::FlyFast { airplane: i, to });
                     }
                 }
             }
@@ -168,101 +153,141 @@ impl ZenoTravelProblem {
         actions
     }
 
+    /// Destination city and distance of a flight to city `to`
+    fn flight(&self, state: &State, airplane: usize, to: usize) -> (i32, i32) {
+        *self.distances[&state.airplanes[airplane].location.to_string()]
+            .iter()
+            .find(|&&(city, _)| city as usize == to)
+            .expect("No flight to the city")
+    }
+
+    /// Cost of a flight: fuel_weight * fuel + time_weight * time
+    fn fly_cost(&self, airplane: &Airplane, distance: i32, fast: bool) -> i32 {
+        let (burn, speed) = if fast {
+            (airplane.fast_burn, airplane.fast_speed)
+        } else {
+            (airplane.slow_burn, airplane.slow_speed)
+        };
+        self.minimize.fuel * burn * distance + self.minimize.time * (distance / speed)
+    }
+
+    /// Display form of an action, with the indices and cities it involves as parameters
+    pub fn action_description(&self, state: &State, action: &ZenoTravelAction) -> Action {
+        let mut parameters = HashMap::new();
+        let action_name = match *action {
+            ZenoTravelAction::Refuel(airplane) => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                format!("refuel_airplane{}", airplane.index)
+            }
+            ZenoTravelAction::Board { person, airplane } => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("person".to_string(), Value::Int(person as i32));
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                format!("board_person{}_to_airplane{}", person, airplane.index)
+            }
+            ZenoTravelAction::Debark { person, airplane } => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("person".to_string(), Value::Int(person as i32));
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                parameters.insert(
+                    "airplane_location".to_string(),
+                    Value::Int(airplane.location),
+                );
+                format!("debark_person{}_from_airplane{}", person, airplane.index)
+            }
+            ZenoTravelAction::FlySlow { airplane: a, to }
+            | ZenoTravelAction::FlyFast { airplane: a, to } => {
+                let (city, distance) = self.flight(state, a, to);
+                let airplane = &state.airplanes[a];
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                parameters.insert("to".to_string(), Value::Int(city));
+                parameters.insert("distance".to_string(), Value::Int(distance));
+                let speed = match action {
+                    ZenoTravelAction::FlyFast { .. } => "fast",
+                    _ => "slow",
+                };
+                format!(
+                    "fly_{}_airplane{}_from{}_to_city{}",
+                    speed, airplane.index, airplane.location, city
+                )
+            }
+        };
+        Action::new(
+            action_name,
+            self.fly_or_unit_cost(state, action),
+            parameters,
+        )
+    }
+
+    /// Flights cost `fly_cost`, every other action 1
+    fn fly_or_unit_cost(&self, state: &State, action: &ZenoTravelAction) -> i32 {
+        match *action {
+            ZenoTravelAction::FlySlow { airplane, to } => self.fly_cost(
+                &state.airplanes[airplane],
+                self.flight(state, airplane, to).1,
+                false,
+            ),
+            ZenoTravelAction::FlyFast { airplane, to } => self.fly_cost(
+                &state.airplanes[airplane],
+                self.flight(state, airplane, to).1,
+                true,
+            ),
+            _ => 1,
+        }
+    }
+
     /// Apply refuel: refill airplane fuel to capacity
-    pub fn apply_refuel_action(state: &State, action: &Action) -> State {
+    pub fn apply_refuel_action(state: &State, airplane: usize) -> State {
         let mut new_state = state.clone();
-        let idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane index parameter."),
-        };
-        new_state.airplanes[idx].fuel = new_state.airplanes[idx].capacity; // refill
+        new_state.airplanes[airplane].fuel = new_state.airplanes[airplane].capacity; // refill
         new_state
     }
 
     /// Apply boarding: update person's on_airplane and airplane onboard count
-    pub fn apply_board_action(state: &State, action: &Action) -> State {
+    pub fn apply_board_action(state: &State, person: usize, airplane: usize) -> State {
         let mut new_state = state.clone();
-        let plane_idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane index parameter."),
-        };
-        let person_idx = match action.parameters.get("person") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid person index parameter."),
-        };
-        new_state.persons[person_idx].on_airplane = plane_idx as i32;
-        new_state.persons[person_idx].location = -1; // now in-air
-        new_state.airplanes[plane_idx].onboard += 1; // increment onboard
+        new_state.persons[person].on_airplane = state.airplanes[airplane].index;
+        new_state.persons[person].location = -1; // now in-air
+        new_state.airplanes[airplane].onboard += 1; // increment onboard
         new_state
     }
 
     /// Apply debark: update person's location and airplane onboard count
-    pub fn apply_debark_action(state: &State, action: &Action) -> State {
+    pub fn apply_debark_action(state: &State, person: usize, airplane: usize) -> State {
         let mut new_state = state.clone();
-        let plane_idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane index parameter."),
-        };
-        let person_idx = match action.parameters.get("person") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid person index parameter."),
-        };
-        let loc = match action.parameters.get("airplane_location") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid location parameter."),
-        };
-        new_state.persons[person_idx].on_airplane = -1; // now on-ground
-        new_state.persons[person_idx].location = loc; // set city
-        new_state.airplanes[plane_idx].onboard -= 1; // decrement onboard
+        new_state.persons[person].on_airplane = -1; // now on-ground
+        new_state.persons[person].location = state.airplanes[airplane].location; // set city
+        new_state.airplanes[airplane].onboard -= 1; // decrement onboard
         new_state
     }
 
     /// Apply fast flight: update location and deduct fast fuel
-    pub fn apply_fast_fly_action(state: &State, action: &Action) -> State {
+    pub fn apply_fast_fly_action(&self, state: &State, airplane: usize, to: usize) -> State {
         let mut new_state = state.clone();
-        let dist = match action.parameters.get("distance") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid distance parameter."),
-        };
-        let idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane parameter."),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid destination parameter."),
-        };
-        new_state.airplanes[idx].location = to;
-        new_state.airplanes[idx].fuel -= new_state.airplanes[idx].fast_burn * dist;
+        let (city, dist) = self.flight(state, airplane, to);
+        new_state.airplanes[airplane].location = city;
+        new_state.airplanes[airplane].fuel -= new_state.airplanes[airplane].fast_burn * dist;
         new_state
     }
 
     /// Apply slow flight: update location and deduct slow fuel
-    pub fn apply_slow_fly_action(state: &State, action: &Action) -> State {
+    pub fn apply_slow_fly_action(&self, state: &State, airplane: usize, to: usize) -> State {
         let mut new_state = state.clone();
-        let dist = match action.parameters.get("distance") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid distance parameter."),
-        };
-        let idx = match action.parameters.get("airplane") {
-            Some(Value::Int(i)) => *i as usize,
-            _ => panic!("Invalid airplane parameter."),
-        };
-        let to = match action.parameters.get("to") {
-            Some(Value::Int(i)) => *i,
-            _ => panic!("Invalid destination parameter."),
-        };
-        new_state.airplanes[idx].location = to;
-        new_state.airplanes[idx].fuel -= new_state.airplanes[idx].slow_burn * dist;
+        let (city, dist) = self.flight(state, airplane, to);
+        new_state.airplanes[airplane].location = city;
+        new_state.airplanes[airplane].fuel -= new_state.airplanes[airplane].slow_burn * dist;
         new_state
     }
 }
 
 impl Problem for ZenoTravelProblem {
     type State = State;
+    type Action = ZenoTravelAction;
+    type Cost = i32;
 
     /// Collect all possible actions in the current state
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
+    fn get_possible_actions(&self, state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
         actions.extend(Self::get_refuel_actions(state));
         actions.extend(self.get_possible_airplane_flys(state));
@@ -271,23 +296,33 @@ impl Problem for ZenoTravelProblem {
         actions
     }
 
-    /// Dispatch apply_* based on action name prefix
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        if action.name.starts_with("refuel_") {
-            Self::apply_refuel_action(state, action)
-        } else if action.name.starts_with("board_") {
-            Self::apply_board_action(state, action)
-        } else if action.name.starts_with("debark_") {
-            Self::apply_debark_action(state, action)
-        } else if action.name.starts_with("fly_fast_") {
-            Self::apply_fast_fly_action(state, action)
-        } else if action.name.starts_with("fly_slow_") {
-            Self::apply_slow_fly_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", action.name)
+    /// Dispatch to the matching apply_* method
+    fn apply_action(&self, state: &State, action: &ZenoTravelAction) -> State {
+        match *action {
+            ZenoTravelAction::Refuel(airplane) => Self::apply_refuel_action(state, airplane),
+            ZenoTravelAction::Board { person, airplane } => {
+                Self::apply_board_action(state, person, airplane)
+            }
+            ZenoTravelAction::Debark { person, airplane } => {
+                Self::apply_debark_action(state, person, airplane)
+            }
+            ZenoTravelAction::FlyFast { airplane, to } => {
+                self.apply_fast_fly_action(state, airplane, to)
+            }
+            ZenoTravelAction::FlySlow { airplane, to } => {
+                self.apply_slow_fly_action(state, airplane, to)
+            }
         }
     }
 
+    fn action_cost(&self, state: &State, action: &ZenoTravelAction) -> i32 {
+        self.fly_or_unit_cost(state, action)
+    }
+
+    fn describe_action(&self, state: &State, action: &ZenoTravelAction) -> Action {
+        self.action_description(state, action)
+    }
+
     /// Check if all airplanes and persons meet goal positions
     fn is_goal_state(&self, state: &State) -> bool {
         for &(idx, city) in &self.goal.airplanes {
@@ -321,3 +356,414 @@ impl Problem for ZenoTravelProblem {
         (state, problem)
     }
 }
+
+impl NumericProblem for ZenoTravelProblem {
+    /// Per airplane: one 0/1 variable per city, fuel and onboard count.
+    /// Per person: one 0/1 variable per city and one per airplane.
+    fn numeric_task(&self, state: &State) -> NumericTask<Self::Action> {
+        let cities = self.num_cities as usize;
+        let planes = state.airplanes.len();
+        let mut task = NumericTask::default();
+        for a in 0..planes {
+            for c in 0..cities {
+                task.add_variable(format!("airplane{}_at{}", a, c));
+            }
+            task.add_variable(format!("airplane{}_fuel", a));
+            task.add_variable(format!("airplane{}_onboard", a));
+        }
+        let plane_block = cities + 2;
+        for p in 0..state.persons.len() {
+            for c in 0..cities {
+                task.add_variable(format!("person{}_at{}", p, c));
+            }
+            for a in 0..planes {
+                task.add_variable(format!("person{}_in{}", p, a));
+            }
+        }
+        let plane_at = |a: usize, c: usize| a * plane_block + c;
+        let fuel = |a: usize| a * plane_block + cities;
+        let onboard = |a: usize| a * plane_block + cities + 1;
+        let person_at = |p: usize, c: usize| planes * plane_block + p * (cities + planes) + c;
+        let person_in =
+            |p: usize, a: usize| planes * plane_block + p * (cities + planes) + cities + a;
+
+        for (a, airplane) in state.airplanes.iter().enumerate() {
+            task.actions.push(NumericAction {
+                operator: ZenoTravelAction::Refuel(a),
+                cost: 1.0,
+                preconditions: Vec::new(),
+                effects: vec![NumericEffect::Assign(
+                    fuel(a),
+                    LinearExpression::constant(airplane.capacity as f64),
+                )],
+            });
+
+            for from in 0..cities {
+                for (city, distance) in self.distances.get(&from.to_string()).into_iter().flatten()
+                {
+                    let to = *city as usize;
+                    for (operator, burn, speed) in [
+                        (
+                            ZenoTravelAction::FlySlow { airplane: a, to },
+                            airplane.slow_burn,
+                            airplane.slow_speed,
+                        ),
+                        (
+                            ZenoTravelAction::FlyFast { airplane: a, to },
+                            airplane.fast_burn,
+                            airplane.fast_speed,
+                        ),
+                    ] {
+                        let total_fuel = burn * distance;
+                        let cost = self.minimize.fuel * total_fuel
+                            + self.minimize.time * (distance / speed);
+                        let mut preconditions = vec![
+                            NumericCondition::equals(plane_at(a, from), 1.0),
+                            NumericCondition::new(
+                                LinearExpression::variable(fuel(a)),
+                                Comparison::Ge,
+                                &LinearExpression::constant(total_fuel as f64),
+                            ),
+                        ];
+                        if matches!(operator, ZenoTravelAction::FlyFast { .. }) {
+                            preconditions.push(NumericCondition::new(
+                                LinearExpression::variable(onboard(a)),
+                                Comparison::Le,
+                                &LinearExpression::constant(airplane.zoom_limit as f64),
+                            ));
+                        }
+                        task.actions.push(NumericAction {
+                            operator,
+                            cost: cost as f64,
+                            preconditions,
+                            effects: vec![
+                                NumericEffect::Assign(
+                                    plane_at(a, from),
+                                    LinearExpression::constant(0.0),
+                                ),
+                                NumericEffect::Assign(
+                                    plane_at(a, to),
+                                    LinearExpression::constant(1.0),
+                                ),
+                                NumericEffect::Increase(
+                                    fuel(a),
+                                    LinearExpression::constant(-total_fuel as f64),
+                                ),
+                            ],
+                        });
+                    }
+                }
+            }
+
+            for p in 0..state.persons.len() {
+                for c in 0..cities {
+                    task.actions.push(NumericAction {
+                        operator: ZenoTravelAction::Board { person: p, airplane: a },
+                        cost: 1.0,
+                        preconditions: vec![
+                            NumericCondition::equals(person_at(p, c), 1.0),
+                            NumericCondition::equals(plane_at(a, c), 1.0),
+                        ],
+                        effects: vec![
+                            NumericEffect::Assign(person_at(p, c), LinearExpression::constant(0.0)),
+                            NumericEffect::Assign(person_in(p, a), LinearExpression::constant(1.0)),
+                            NumericEffect::Increase(onboard(a), LinearExpression::constant(1.0)),
+                        ],
+                    });
+                    task.actions.push(NumericAction {
+                        operator: ZenoTravelAction::Debark { person: p, airplane: a },
+                        cost: 1.0,
+                        preconditions: vec![
+                            NumericCondition::equals(person_in(p, a), 1.0),
+                            NumericCondition::equals(plane_at(a, c), 1.0),
+                        ],
+                        effects: vec![
+                            NumericEffect::Assign(person_in(p, a), LinearExpression::constant(0.0)),
+                            NumericEffect::Assign(person_at(p, c), LinearExpression::constant(1.0)),
+                            NumericEffect::Increase(onboard(a), LinearExpression::constant(-1.0)),
+                        ],
+                    });
+                }
+            }
+        }
+
+        for &(a, city) in &self.goal.airplanes {
+            task.goals.push(NumericCondition::equals(
+                plane_at(a as usize, city as usize),
+                1.0,
+            ));
+        }
+        for &(p, city) in &self.goal.persons {
+            task.goals.push(NumericCondition::equals(
+                person_at(p as usize, city as usize),
+                1.0,
+            ));
+        }
+        task
+    }
+
+    fn numeric_values(&self, state: &State) -> Vec<f64> {
+        let cities = self.num_cities;
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+        let mut values = Vec::new();
+        for airplane in &state.airplanes {
+            values.extend((0..cities).map(|c| flag(airplane.location == c)));
+            values.push(airplane.fuel as f64);
+            values.push(airplane.onboard as f64);
+        }
+        for person in &state.persons {
+            values.extend(
+                (0..cities).map(|c| flag(person.on_airplane == -1 && person.location == c)),
+            );
+            values.extend(
+                state
+                    .airplanes
+                    .iter()
+                    .map(|a| flag(person.on_airplane == a.index)),
+            );
+        }
+        values
+    }
+}
+
+impl FactoredProblem for ZenoTravelProblem {
+    /// Per airplane: city, fuel and onboard count. Per person: city and airplane, each -1
+    /// when not applicable.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let last_city = (self.num_cities - 1) as i64;
+        let last_airplane = state.airplanes.iter().map(|a| a.index).max().unwrap_or(-1) as i64;
+        let mut variables = Vec::new();
+        for airplane in &state.airplanes {
+            variables.push(Variable::integer(
+                format!("airplane{}_location", airplane.index),
+                Some(0),
+                Some(last_city),
+            ));
+            variables.push(Variable::integer(
+                format!("airplane{}_fuel", airplane.index),
+                Some(airplane.fuel.min(0) as i64),
+                Some(airplane.fuel.max(airplane.capacity) as i64),
+            ));
+            variables.push(Variable::integer(
+                format!("airplane{}_onboard", airplane.index),
+                Some(0),
+                Some(state.persons.len() as i64),
+            ));
+        }
+        for p in 0..state.persons.len() {
+            variables.push(Variable::integer(
+                format!("person{}_location", p),
+                Some(-1),
+                Some(last_city),
+            ));
+            variables.push(Variable::integer(
+                format!("person{}_on_airplane", p),
+                Some(-1),
+                Some(last_airplane),
+            ));
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        for airplane in &state.airplanes {
+            values.extend([
+                Value::Int(airplane.location),
+                Value::Int(airplane.fuel),
+                Value::Int(airplane.onboard),
+            ]);
+        }
+        for person in &state.persons {
+            values.extend([Value::Int(person.location), Value::Int(person.on_airplane)]);
+        }
+        values
+    }
+
+    /// Airplanes at their cities; persons at their cities and off every airplane.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let persons_start = 3 * state.airplanes.len();
+        let mut conditions = Vec::new();
+        for &(a, city) in &self.goal.airplanes {
+            conditions.push(NumericCondition::equals(3 * a as usize, city as f64));
+        }
+        for &(p, city) in &self.goal.persons {
+            let person = persons_start + 2 * p as usize;
+            conditions.push(NumericCondition::equals(person, city as f64));
+            conditions.push(NumericCondition::equals(person + 1, -1.0));
+        }
+        FactoredGoal {
+            conditions,
+            exact: true,
+        }
+    }
+}
+
+impl InterferenceProblem for ZenoTravelProblem {
+    /// Flights go to every city; those without a connection from the airplane's location
+    /// are never applicable.
+    fn all_actions(&self, state: &State) -> Vec<ZenoTravelAction> {
+        let mut actions = Vec::new();
+        for airplane in 0..state.airplanes.len() {
+            actions.push(ZenoTravelAction::Refuel(airplane));
+            for to in 0..self.num_cities as usize {
+                actions.push(ZenoTravelAction::FlySlow { airplane, to });
+                actions.push(ZenoTravelAction::FlyFast { airplane, to });
+            }
+            for person in 0..state.persons.len() {
+                actions.push(ZenoTravelAction::Board { person, airplane });
+                actions.push(ZenoTravelAction::Debark { person, airplane });
+            }
+        }
+        actions
+    }
+
+    /// Variables follow `FactoredProblem::variables`: location, fuel and onboard count of
+    /// each airplane, then location and airplane of each person.
+    fn footprint(&self, state: &State, action: &ZenoTravelAction) -> Footprint {
+        let person = |p: usize| 3 * state.airplanes.len() + 2 * p;
+        let (reads, writes) = match *action {
+            ZenoTravelAction::Refuel(a) => (vec![], vec![3 * a + 1]),
+            ZenoTravelAction::FlySlow { airplane: a, .. } => {
+                (vec![3 * a, 3 * a + 1], vec![3 * a, 3 * a + 1])
+            }
+            ZenoTravelAction::FlyFast { airplane: a, .. } => {
+                (vec![3 * a, 3 * a + 1, 3 * a + 2], vec![3 * a, 3 * a + 1])
+            }
+            ZenoTravelAction::Board {
+                person: p,
+                airplane: a,
+            }
+            | ZenoTravelAction::Debark {
+                person: p,
+                airplane: a,
+            } => (
+                vec![person(p), person(p) + 1, 3 * a, 3 * a + 2],
+                vec![person(p), person(p) + 1, 3 * a + 2],
+            ),
+        };
+        Footprint { reads, writes }
+    }
+
+    /// The variables of the first precondition that fails. A flight's fuel requirement
+    /// depends on where the airplane is, so its location may enable it too.
+    fn enabling_variables(&self, state: &State, action: &ZenoTravelAction) -> Vec<usize> {
+        let person = |p: usize| 3 * state.airplanes.len() + 2 * p;
+        match *action {
+            ZenoTravelAction::Refuel(_) => Vec::new(),
+            ZenoTravelAction::FlySlow { airplane: a, to }
+            | ZenoTravelAction::FlyFast { airplane: a, to } => {
+                let airplane = &state.airplanes[a];
+                let fast = matches!(action, ZenoTravelAction::FlyFast { .. });
+                let burn = if fast {
+                    airplane.fast_burn
+                } else {
+                    airplane.slow_burn
+                };
+                let flight = self
+                    .distances
+                    .get(&airplane.location.to_string())
+                    .and_then(|pairs| pairs.iter().find(|&&(city, _)| city as usize == to));
+                match flight {
+                    Some(&(_, distance)) => {
+                        if airplane.fuel < burn * distance {
+                            vec![3 * a, 3 * a + 1]
+                        } else {
+                            vec![3 * a + 2]
+                        }
+                    }
+                    _ => vec![3 * a],
+                }
+            }
+            ZenoTravelAction::Board {
+                person: p,
+                airplane: a,
+            } => {
+                if state.persons[p].on_airplane != -1 {
+                    vec![person(p) + 1]
+                } else {
+                    vec![person(p), 3 * a]
+                }
+            }
+            ZenoTravelAction::Debark { person: p, .. } => vec![person(p) + 1],
+        }
+    }
+}
+
+impl SymmetricProblem for ZenoTravelProblem {
+    /// Airplanes with the same burn rates, speeds, capacity, zoom limit and goal, and
+    /// persons with the same goal.
+    fn symmetry_groups(&self, state: &State) -> Vec<ObjectGroup> {
+        let goal = |goals: &[(i32, i32)], object: usize| {
+            goals
+                .iter()
+                .find(|&&(o, _)| o as usize == object)
+                .map(|&(_, city)| city)
+        };
+        let mut groups = groups_by_signature(
+            "airplane",
+            state.airplanes.iter().enumerate().map(|(a, airplane)| {
+                (
+                    airplane.slow_burn,
+                    airplane.slow_speed,
+                    airplane.fast_burn,
+                    airplane.fast_speed,
+                    airplane.capacity,
+                    airplane.zoom_limit,
+                    goal(&self.goal.airplanes, a),
+                )
+            }),
+        );
+        groups.extend(groups_by_signature(
+            "person",
+            (0..state.persons.len()).map(|p| goal(&self.goal.persons, p)),
+        ));
+        groups
+    }
+
+    /// Airplanes are ordered by location, fuel, onboard count and the groups of the persons
+    /// on board, and passengers follow their airplane. Persons are then ordered by location
+    /// and airplane.
+    fn canonical_state(&self, state: &State, groups: &[ObjectGroup]) -> State {
+        let person_class = |p: usize| {
+            groups
+                .iter()
+                .position(|g| g.kind == "person" && g.objects.contains(&p))
+                .map_or((1, p), |g| (0, g))
+        };
+        let mut canonical = state.clone();
+        let mut renamed = HashMap::new(); // Airplane index -> index of its new position
+        for group in groups.iter().filter(|g| g.kind == "airplane") {
+            let order = canonical_order(group, |a| {
+                let airplane = &state.airplanes[a];
+                let mut aboard: Vec<(usize, usize)> = (0..state.persons.len())
+                    .filter(|&p| state.persons[p].on_airplane == airplane.index)
+                    .map(person_class)
+                    .collect();
+                aboard.sort_unstable();
+                (airplane.location, airplane.fuel, airplane.onboard, aboard)
+            });
+            for (&slot, &a) in group.objects.iter().zip(&order) {
+                let (from, to) = (&state.airplanes[a], &mut canonical.airplanes[slot]);
+                to.location = from.location;
+                to.fuel = from.fuel;
+                to.onboard = from.onboard;
+                renamed.insert(from.index, to.index);
+            }
+        }
+        for person in &mut canonical.persons {
+            if let Some(&index) = renamed.get(&person.on_airplane) {
+                person.on_airplane = index;
+            }
+        }
+
+        let persons = canonical.persons.clone();
+        for group in groups.iter().filter(|g| g.kind == "person") {
+            let order = canonical_order(group, |p| (persons[p].location, persons[p].on_airplane));
+            for (&slot, &p) in group.objects.iter().zip(&order) {
+                canonical.persons[slot] = persons[p].clone();
+            }
+        }
+        canonical
+    }
+}
@@ -1,5 +1,11 @@
+use crate::heuristics::numeric_task::NumericCondition;
+use crate::problems::factored_problem::{FactoredGoal, FactoredProblem, Variable};
 use crate::problems::problem::Problem;
-use crate::search::{action::Action, state::StateTrait, state::Value};
+use crate::problems::zenotravel_problem::zenotravel_problem::ZenoTravelAction;
+use crate::search::pareto::MultiObjectiveProblem;
+use crate::search::{
+    action::Action, cost::CostTrait, cost::Rational, state::StateTrait, state::Value,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
@@ -53,32 +59,25 @@ pub struct ZenoTravelTimeProblem {
 }
 
 impl ZenoTravelTimeProblem {
-    /// Refuel actions: refill fuel to capacity for each airplane
-    pub fn get_refuel_actions(state: &State) -> Vec<Action> {
-        let mut actions = Vec::new();
-        for airplane in &state.airplanes {
-            let mut params = HashMap::new();
-            let name = format!("refuel_airplane{}", airplane.index);
-            params.insert("airplane".to_string(), Value::Int(airplane.index));
-            // cost = 1 for refueling
-            actions.push(Action::new(name, 1, params));
-        }
-        actions
+    /// Create refuel actions for every airplane (refill fuel to capacity)
+    pub fn get_refuel_actions(state: &State) -> Vec<ZenoTravelAction> {
+        (0..state.airplanes.len())
+            .map(ZenoTravelAction::Refuel)
+            .collect()
     }
 
-    /// Boarding actions: for each person on ground and matching airplane location
-    pub fn get_possible_board_persons(state: &State) -> Vec<Action> {
+    /// Generate boarding actions for persons on the ground
+    pub fn get_possible_board_persons(state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
-        for (i, person) in state.persons.iter().enumerate() {
+        for (j, person) in state.persons.iter().enumerate() {
             if person.on_airplane == -1 {
-                for airplane in &state.airplanes {
+                // only if on ground
+                for (i, airplane) in state.airplanes.iter().enumerate() {
                     if airplane.location == person.location {
-                        let mut params = HashMap::new();
-                        let name = format!("board_person{}_to_airplane{}", i, airplane.index);
-                        params.insert("person".to_string(), Value::Int(i as i32));
-                        params.insert("airplane".to_string(), Value::Int(airplane.index));
-                        // cost = 1 for boarding
-                        actions.push(Action::new(name, 1, params));
+                        actions.push(ZenoTravelAction::Board {
+                            person: j,
+                            airplane: i,
+                        });
                     }
                 }
             }
@@ -86,23 +85,18 @@ impl ZenoTravelTimeProblem {
         actions
     }
 
-    /// Debarking actions: for each person onboard matching airplane
-    pub fn get_possible_debark_persons(state: &State) -> Vec<Action> {
+    /// Generate debark actions for persons onboard
+    pub fn get_possible_debark_persons(state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
-        for (i, person) in state.persons.iter().enumerate() {
+        for (j, person) in state.persons.iter().enumerate() {
             if person.on_airplane != -1 {
-                for airplane in &state.airplanes {
+                // only if onboard
+                for (i, airplane) in state.airplanes.iter().enumerate() {
                     if airplane.index == person.on_airplane {
-                        let mut params = HashMap::new();
-                        let name = format!("debark_person{}_from_airplane{}", i, airplane.index);
-                        params.insert("person".to_string(), Value::Int(i as i32));
-                        params.insert("airplane".to_string(), Value::Int(airplane.index));
-                        params.insert(
-                            "airplane_location".to_string(),
-                            Value::Int(airplane.location),
-                        );
-                        // cost = 1 for debarking
-                        actions.push(Action::new(name, 1, params));
+                        actions.push(ZenoTravelAction::Debark {
+                            person: j,
+                            airplane: i,
+                        });
                     }
                 }
             }
@@ -110,39 +104,22 @@ impl ZenoTravelTimeProblem {
         actions
     }
 
-    /// Flight actions: cost = travel time (distance / speed)
-    pub fn get_possible_airplane_flys(&self, state: &State) -> Vec<Action> {
+    /// Generate flight actions (slow and fast) to every city reachable with the fuel left
+    pub fn get_possible_airplane_flys(&self, state: &State) -> Vec<ZenoTravelAction> {
         let mut actions = Vec::new();
-        for airplane in &state.airplanes {
-            if let Some(conns) = self.distances.get(&airplane.location.to_string()) {
-                for &(city, dist) in conns {
-                    // slow flight if fuel sufficient
-                    if airplane.fuel >= airplane.slow_burn * dist {
-                        let mut params = HashMap::new();
-                        let name = format!(
-                            "fly_slow_airplane{}_from{}_to_city{}",
-                            airplane.index, airplane.location, city
-                        );
-                        params.insert("airplane".to_string(), Value::Int(airplane.index));
-                        params.insert("to".to_string(), Value::Int(city));
-                        params.insert("distance".to_string(), Value::Int(dist));
-                        let time = dist / airplane.slow_speed; // travel time
-                        actions.push(Action::new(name, time, params));
+        for (i, airplane) in state.airplanes.iter().enumerate() {
+            if let Some(pairs) = self.distances.get(&airplane.location.to_string()) {
+                for &(city, distance) in pairs {
+                    let to = city as usize;
+                    // slow flight option
+                    if airplane.fuel >= airplane.slow_burn * distance {
+                        actions.push(ZenoTravelAction::FlySlow { airplane: i, to });
                     }
-                    // fast flight if onboard within limit and fuel sufficient
-                    if airplane.fuel >= airplane.fast_burn * dist
+                    // fast flight option
+                    if airplane.fuel >= airplane.fast_burn * distance
                         && airplane.onboard <= airplane.zoom_limit
                     {
-                        let mut params = HashMap::new();
-                        let name = format!(
-                            "fly_fast_airplane{}_from{}_to_city{}",
-                            airplane.index, airplane.location, city
-                        );
-                        params.insert("airplane".to_string(), Value::Int(airplane.index));
-                        params.insert("to".to_string(), Value::Int(city));
-                        params.insert("distance".to_string(), Value::Int(dist));
-                        let time = dist / airplane.fast_speed; // travel time
-                        actions.push(Action::new(name, time, params));
+                        actions.push(ZenoTravelAction::FlyFast { airplane: i, to });
                     }
                 }
             }
@@ -150,175 +127,176 @@ impl ZenoTravelTimeProblem {
         actions
     }
 
-    /// Apply refuel: refill fuel to capacity
-    pub fn apply_refuel_action(state: &State, action: &Action) -> State {
-        let mut ns = state.clone();
-        let idx = action
-            .parameters
-            .get("airplane")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i as usize),
-                _ => None,
-            })
-            .expect("Missing airplane idx");
-        ns.airplanes[idx].fuel = ns.airplanes[idx].capacity;
-        ns
+    /// Destination city and distance of a flight to city `to`
+    fn flight(&self, state: &State, airplane: usize, to: usize) -> (i32, i32) {
+        *self.distances[&state.airplanes[airplane].location.to_string()]
+            .iter()
+            .find(|&&(city, _)| city as usize == to)
+            .expect("No flight to the city")
+    }
+
+    /// Cost of a flight: its travel time (distance / speed), exactly
+    fn fly_cost(&self, airplane: &Airplane, distance: i32, fast: bool) -> Rational {
+        let speed = if fast {
+            airplane.fast_speed
+        } else {
+            airplane.slow_speed
+        };
+        Rational::new(distance as i64, speed as i64)
+    }
+
+    /// Display form of an action, with the indices and cities it involves as parameters
+    pub fn action_description(&self, state: &State, action: &ZenoTravelAction) -> Action {
+        let mut parameters = HashMap::new();
+        let action_name = match *action {
+            ZenoTravelAction::Refuel(airplane) => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                format!("refuel_airplane{}", airplane.index)
+            }
+            ZenoTravelAction::Board { person, airplane } => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("person".to_string(), Value::Int(person as i32));
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                format!("board_person{}_to_airplane{}", person, airplane.index)
+            }
+            ZenoTravelAction::Debark { person, airplane } => {
+                let airplane = &state.airplanes[airplane];
+                parameters.insert("person".to_string(), Value::Int(person as i32));
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                parameters.insert(
+                    "airplane_location".to_string(),
+                    Value::Int(airplane.location),
+                );
+                format!("debark_person{}_from_airplane{}", person, airplane.index)
+            }
+            ZenoTravelAction::FlySlow { airplane: a, to }
+            | ZenoTravelAction::FlyFast { airplane: a, to } => {
+                let (city, distance) = self.flight(state, a, to);
+                let airplane = &state.airplanes[a];
+                parameters.insert("airplane".to_string(), Value::Int(airplane.index));
+                parameters.insert("to".to_string(), Value::Int(city));
+                parameters.insert("distance".to_string(), Value::Int(distance));
+                let speed = match action {
+                    ZenoTravelAction::FlyFast { .. } => "fast",
+                    _ => "slow",
+                };
+                format!(
+                    "fly_{}_airplane{}_from{}_to_city{}",
+                    speed, airplane.index, airplane.location, city
+                )
+            }
+        };
+        Action::new(
+            action_name,
+            self.fly_or_unit_cost(state, action),
+            parameters,
+        )
+    }
+
+    /// Flights cost `fly_cost`, every other action 1
+    fn fly_or_unit_cost(&self, state: &State, action: &ZenoTravelAction) -> Rational {
+        match *action {
+            ZenoTravelAction::FlySlow { airplane, to } => self.fly_cost(
+                &state.airplanes[airplane],
+                self.flight(state, airplane, to).1,
+                false,
+            ),
+            ZenoTravelAction::FlyFast { airplane, to } => self.fly_cost(
+                &state.airplanes[airplane],
+                self.flight(state, airplane, to).1,
+                true,
+            ),
+            _ => Rational::from_integer(1),
+        }
     }
 
-    /// Apply boarding: update person.on_airplane and airplane.onboard
-    pub fn apply_board_action(state: &State, action: &Action) -> State {
-        let mut ns = state.clone();
-        let plane = action
-            .parameters
-            .get("airplane")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i as usize),
-                _ => None,
-            })
-            .unwrap();
-        let pers = action
-            .parameters
-            .get("person")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i as usize),
-                _ => None,
-            })
-            .unwrap();
-        ns.persons[pers].on_airplane = plane as i32;
-        ns.persons[pers].location = -1;
-        ns.airplanes[plane].onboard += 1;
-        ns
+    /// Apply refuel: refill airplane fuel to capacity
+    pub fn apply_refuel_action(state: &State, airplane: usize) -> State {
+        let mut new_state = state.clone();
+        new_state.airplanes[airplane].fuel = new_state.airplanes[airplane].capacity; // refill
+        new_state
     }
 
-    /// Apply debark: update person.location and airplane.onboard
-    pub fn apply_debark_action(state: &State, action: &Action) -> State {
-        let mut ns = state.clone();
-        let plane = action
-            .parameters
-            .get("airplane")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i as usize),
-                _ => None,
-            })
-            .unwrap();
-        let pers = action
-            .parameters
-            .get("person")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i as usize),
-                _ => None,
-            })
-            .unwrap();
-        let loc = action
-            .parameters
-            .get("airplane_location")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i),
-                _ => None,
-            })
-            .unwrap();
-        ns.persons[pers].on_airplane = -1;
-        ns.persons[pers].location = loc;
-        ns.airplanes[plane].onboard -= 1;
-        ns
+    /// Apply boarding: update person's on_airplane and airplane onboard count
+    pub fn apply_board_action(state: &State, person: usize, airplane: usize) -> State {
+        let mut new_state = state.clone();
+        new_state.persons[person].on_airplane = state.airplanes[airplane].index;
+        new_state.persons[person].location = -1; // now in-air
+        new_state.airplanes[airplane].onboard += 1; // increment onboard
+        new_state
     }
 
-    /// Apply fast flight: move airplane and deduct fast fuel
-    pub fn apply_fast_fly_action(state: &State, action: &Action) -> State {
-        let mut ns = state.clone();
-        let dist = action
-            .parameters
-            .get("distance")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i),
-                _ => None,
-            })
-            .unwrap();
-        let idx = action
-            .parameters
-            .get("airplane")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i as usize),
-                _ => None,
-            })
-            .unwrap();
-        let to = action
-            .parameters
-            .get("to")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i),
-                _ => None,
-            })
-            .unwrap();
-        ns.airplanes[idx].location = to;
-        ns.airplanes[idx].fuel -= ns.airplanes[idx].fast_burn * dist;
-        ns
+    /// Apply debark: update person's location and airplane onboard count
+    pub fn apply_debark_action(state: &State, person: usize, airplane: usize) -> State {
+        let mut new_state = state.clone();
+        new_state.persons[person].on_airplane = -1; // now on-ground
+        new_state.persons[person].location = state.airplanes[airplane].location; // set city
+        new_state.airplanes[airplane].onboard -= 1; // decrement onboard
+        new_state
     }
 
-    /// Apply slow flight: move airplane and deduct slow fuel
-    pub fn apply_slow_fly_action(state: &State, action: &Action) -> State {
-        let mut ns = state.clone();
-        let dist = action
-            .parameters
-            .get("distance")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i),
-                _ => None,
-            })
-            .unwrap();
-        let idx = action
-            .parameters
-            .get("airplane")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i as usize),
-                _ => None,
-            })
-            .unwrap();
-        let to = action
-            .parameters
-            .get("to")
-            .and_then(|v| match v {
-                Value::Int(i) => Some(*i),
-                _ => None,
-            })
-            .unwrap();
-        ns.airplanes[idx].location = to;
-        ns.airplanes[idx].fuel -= ns.airplanes[idx].slow_burn * dist;
-        ns
+    /// Apply fast flight: update location and deduct fast fuel
+    pub fn apply_fast_fly_action(&self, state: &State, airplane: usize, to: usize) -> State {
+        let mut new_state = state.clone();
+        let (city, dist) = self.flight(state, airplane, to);
+        new_state.airplanes[airplane].location = city;
+        new_state.airplanes[airplane].fuel -= new_state.airplanes[airplane].fast_burn * dist;
+        new_state
+    }
+
+    /// Apply slow flight: update location and deduct slow fuel
+    pub fn apply_slow_fly_action(&self, state: &State, airplane: usize, to: usize) -> State {
+        let mut new_state = state.clone();
+        let (city, dist) = self.flight(state, airplane, to);
+        new_state.airplanes[airplane].location = city;
+        new_state.airplanes[airplane].fuel -= new_state.airplanes[airplane].slow_burn * dist;
+        new_state
     }
 }
 
 impl Problem for ZenoTravelTimeProblem {
     type State = State;
+    type Action = ZenoTravelAction;
+    type Cost = Rational;
 
-    /// Gather all available actions for current state
-    fn get_possible_actions(&self, state: &State) -> Vec<Action> {
-        let mut acts = Vec::new();
-        acts.extend(Self::get_refuel_actions(state));
-        acts.extend(self.get_possible_airplane_flys(state));
-        acts.extend(Self::get_possible_board_persons(state));
-        acts.extend(Self::get_possible_debark_persons(state));
-        acts
+    /// Collect all possible actions in the current state
+    fn get_possible_actions(&self, state: &State) -> Vec<ZenoTravelAction> {
+        let mut actions = Vec::new();
+        actions.extend(Self::get_refuel_actions(state));
+        actions.extend(self.get_possible_airplane_flys(state));
+        actions.extend(Self::get_possible_board_persons(state));
+        actions.extend(Self::get_possible_debark_persons(state));
+        actions
     }
 
-    /// Dispatch to appropriate apply function based on action name
-    fn apply_action(&self, state: &State, action: &Action) -> State {
-        let name = &action.name;
-        if name.starts_with("refuel_") {
-            Self::apply_refuel_action(state, action)
-        } else if name.starts_with("board_") {
-            Self::apply_board_action(state, action)
-        } else if name.starts_with("debark_") {
-            Self::apply_debark_action(state, action)
-        } else if name.starts_with("fly_fast_") {
-            Self::apply_fast_fly_action(state, action)
-        } else if name.starts_with("fly_slow_") {
-            Self::apply_slow_fly_action(state, action)
-        } else {
-            panic!("Unknown action type: {}", name)
+    /// Dispatch to the matching apply_* method
+    fn apply_action(&self, state: &State, action: &ZenoTravelAction) -> State {
+        match *action {
+            ZenoTravelAction::Refuel(airplane) => Self::apply_refuel_action(state, airplane),
+            ZenoTravelAction::Board { person, airplane } => {
+                Self::apply_board_action(state, person, airplane)
+            }
+            ZenoTravelAction::Debark { person, airplane } => {
+                Self::apply_debark_action(state, person, airplane)
+            }
+            ZenoTravelAction::FlyFast { airplane, to } => {
+                self.apply_fast_fly_action(state, airplane, to)
+            }
+            ZenoTravelAction::FlySlow { airplane, to } => {
+                self.apply_slow_fly_action(state, airplane, to)
+            }
         }
     }
 
+    fn action_cost(&self, state: &State, action: &ZenoTravelAction) -> Rational {
+        self.fly_or_unit_cost(state, action)
+    }
+
+    fn describe_action(&self, state: &State, action: &ZenoTravelAction) -> Action {
+        self.action_description(state, action)
+    }
+
     /// Check if current state meets all goal requirements
     fn is_goal_state(&self, state: &State) -> bool {
         for &(ai, city) in &self.goal.airplanes {
@@ -351,3 +329,99 @@ impl Problem for ZenoTravelTimeProblem {
         (state, problem)
     }
 }
+
+impl FactoredProblem for ZenoTravelTimeProblem {
+    /// Per airplane: city, fuel and onboard count. Per person: city and airplane, each -1
+    /// when not applicable.
+    fn variables(&self, state: &State) -> Vec<Variable> {
+        let last_city = (self.num_cities - 1) as i64;
+        let last_airplane = state.airplanes.iter().map(|a| a.index).max().unwrap_or(-1) as i64;
+        let mut variables = Vec::new();
+        for airplane in &state.airplanes {
+            variables.push(Variable::integer(
+                format!("airplane{}_location", airplane.index),
+                Some(0),
+                Some(last_city),
+            ));
+            variables.push(Variable::integer(
+                format!("airplane{}_fuel", airplane.index),
+                Some(airplane.fuel.min(0) as i64),
+                Some(airplane.fuel.max(airplane.capacity) as i64),
+            ));
+            variables.push(Variable::integer(
+                format!("airplane{}_onboard", airplane.index),
+                Some(0),
+                Some(state.persons.len() as i64),
+            ));
+        }
+        for p in 0..state.persons.len() {
+            variables.push(Variable::integer(
+                format!("person{}_location", p),
+                Some(-1),
+                Some(last_city),
+            ));
+            variables.push(Variable::integer(
+                format!("person{}_on_airplane", p),
+                Some(-1),
+                Some(last_airplane),
+            ));
+        }
+        variables
+    }
+
+    fn values(&self, state: &State) -> Vec<Value> {
+        let mut values = Vec::new();
+        for airplane in &state.airplanes {
+            values.extend([
+                Value::Int(airplane.location),
+                Value::Int(airplane.fuel),
+                Value::Int(airplane.onboard),
+            ]);
+        }
+        for person in &state.persons {
+            values.extend([Value::Int(person.location), Value::Int(person.on_airplane)]);
+        }
+        values
+    }
+
+    /// Airplanes at their cities; persons at their cities and off every airplane.
+    fn goal(&self, state: &State) -> FactoredGoal {
+        let persons_start = 3 * state.airplanes.len();
+        let mut conditions = Vec::new();
+        for &(a, city) in &self.goal.airplanes {
+            conditions.push(NumericCondition::equals(3 * a as usize, city as f64));
+        }
+        for &(p, city) in &self.goal.persons {
+            let person = persons_start + 2 * p as usize;
+            conditions.push(NumericCondition::equals(person, city as f64));
+            conditions.push(NumericCondition::equals(person + 1, -1.0));
+        }
+        FactoredGoal {
+            conditions,
+            exact: true,
+        }
+    }
+}
+
+impl MultiObjectiveProblem for ZenoTravelTimeProblem {
+    /// Time is the action cost; fuel is what flights burn, refuelling being free.
+    fn objectives(&self) -> Vec<&'static str> {
+        vec!["time", "fuel"]
+    }
+
+    fn objective_costs(
+        &self,
+        state: &State,
+        action: &ZenoTravelAction,
+        successor: &State,
+    ) -> Vec<f64> {
+        let fuel = match *action {
+            ZenoTravelAction::FlySlow { airplane, .. }
+            | ZenoTravelAction::FlyFast { airplane, .. } => {
+                state.airplanes[airplane].fuel - successor.airplanes[airplane].fuel
+            }
+            _ => 0,
+        };
+        vec![self.action_cost(state, action).to_f64(), fuel as f64]
+    }
+}
@@ -1,18 +1,32 @@
+use super::cost::CostTrait;
 use super::state::Value;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Domain action type: a small value naming the operator and its objects by index, so that
+/// successor generation does not allocate. `Problem::describe_action` turns it into an
+/// `Action` for display. Actions are serialisable so that search checkpoints can store them.
+pub trait ActionTrait:
+    Debug + Clone + Copy + PartialEq + Eq + Hash + Serialize + for<'de> Deserialize<'de>
+{
+}
+
+/// Display and serialisation form of an action.
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Action {
     pub name: String,
-    pub cost: i32,
+    pub cost: f64, // The exact cost, in the problem's cost type, is `Problem::action_cost`
     pub parameters: HashMap<String, Value>,
 }
 
 impl Action {
-    pub fn new(name: String, cost: i32, parameters: HashMap<String, Value>) -> Self {
+    pub fn new(name: String, cost: impl CostTrait, parameters: HashMap<String, Value>) -> Self {
         Action {
             name,
-            cost,
+            cost: cost.to_f64(),
             parameters,
         }
     }
@@ -31,7 +45,7 @@ mod tests {
         let action = Action::new("move".to_string(), 5, parameters);
 
         assert_eq!(action.name, "move");
-        assert_eq!(action.cost, 5);
+        assert_eq!(action.cost, 5.0);
         assert_eq!(action.parameters.get("speed"), Some(&Value::Int(10)));
         assert_eq!(
             action.parameters.get("direction"),
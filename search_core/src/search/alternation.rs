@@ -0,0 +1,193 @@
+use crate::algorithms::gbfs::GBFSQueue;
+use crate::algorithms::priority_queue::PriorityQueue;
+use crate::problems::problem::Problem;
+use crate::search::search::state_hash;
+use crate::search::search_tree::SearchTree;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Expansions granted to a heuristic's queue each time it reaches a new best value.
+const PROGRESS_BOOST: usize = 1000;
+
+/// A heuristic with the name it is reported under.
+pub struct NamedHeuristic<'a, S> {
+    pub name: String,
+    pub evaluate: Box<dyn Fn(&S) -> f64 + 'a>,
+}
+
+impl<'a, S> NamedHeuristic<'a, S> {
+    pub fn new(name: &str, evaluate: impl Fn(&S) -> f64 + 'a) -> Self {
+        NamedHeuristic {
+            name: name.to_string(),
+            evaluate: Box::new(evaluate),
+        }
+    }
+}
+
+/// What one heuristic of an alternation search contributed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeuristicStats {
+    pub name: String,
+    pub expansions: usize,   // Nodes expanded from this heuristic's queue
+    pub improvements: usize, // Times this heuristic went below its best value so far
+    pub best_h: f64,         // Starts at the value of the initial state
+    pub evaluation_time: Duration,
+    pub found_goal: bool, // Whether the goal was generated by an expansion from its queue
+}
+
+/// Greedy best-first search with one open queue per heuristic, every node being queued
+/// in all of them. Expansions go round-robin through the queues, and a heuristic that
+/// reaches a new best value gets `PROGRESS_BOOST` extra turns for its queue. A node whose
+/// value is infinite for every heuristic is pruned as a dead end.
+///
+/// Returns the plan and the contribution of each heuristic, which is also printed.
+pub fn alternation_search<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    heuristics: &[NamedHeuristic<P::State>],
+) -> (Result<Vec<P::Action>, &'static str>, Vec<HeuristicStats>) {
+    assert!(
+        !heuristics.is_empty(),
+        "Alternation needs at least one heuristic"
+    );
+    let mut stats: Vec<HeuristicStats> = heuristics
+        .iter()
+        .map(|heuristic| {
+            let start = Instant::now();
+            let best_h = (heuristic.evaluate)(initial_state);
+            HeuristicStats {
+                name: heuristic.name.clone(),
+                expansions: 0,
+                improvements: 0,
+                best_h,
+                evaluation_time: start.elapsed(),
+                found_goal: false,
+            }
+        })
+        .collect();
+    if problem.is_goal_state(initial_state) {
+        return (Ok(Vec::new()), stats);
+    }
+
+    let mut tree = SearchTree::new(initial_state.clone());
+    let mut closed = HashSet::from([state_hash(initial_state)]);
+    let mut expanded = HashSet::new();
+    let mut queues: Vec<GBFSQueue> = heuristics.iter().map(|_| GBFSQueue::new()).collect();
+    let mut boosts = vec![0usize; heuristics.len()];
+    for (queue, s) in queues.iter_mut().zip(&stats) {
+        queue.insert(0, 0, s.best_h);
+    }
+    let (mut nodes, mut uniq) = (0, 0);
+    let mut turn = 0;
+    let mut values = vec![0.0; heuristics.len()];
+
+    let result = 'search: loop {
+        // The queues take turns, boosted queues before the others, and empty ones are
+        // skipped.
+        let order: Vec<usize> = (0..queues.len())
+            .map(|offset| (turn + offset) % queues.len())
+            .collect();
+        turn = (turn + 1) % queues.len();
+        let boosted: Vec<usize> = order.iter().copied().filter(|&k| boosts[k] > 0).collect();
+        let chosen = boosted
+            .iter()
+            .chain(&order)
+            .find_map(|&k| queues[k].pop().map(|cur_idx| (k, cur_idx)));
+        let Some((k, cur_idx)) = chosen else {
+            break Err("No solution found");
+        };
+        boosts[k] = boosts[k].saturating_sub(1);
+        if !expanded.insert(cur_idx) {
+            continue;
+        }
+        stats[k].expansions += 1;
+
+        let successors = tree.expand_node(
+            cur_idx,
+            |state| problem.get_possible_actions(state),
+            |state, action| problem.apply_action(state, action),
+            |state, action| problem.action_cost(state, action),
+        );
+        for succ_idx in successors {
+            nodes += 1;
+            let state = tree.get_state(succ_idx).unwrap();
+            if !closed.insert(state_hash(state)) {
+                continue;
+            }
+            uniq += 1;
+            if problem.is_goal_state(state) {
+                stats[k].found_goal = true;
+                break 'search Ok(tree.trace_actions(succ_idx));
+            }
+
+            for (j, heuristic) in heuristics.iter().enumerate() {
+                let start = Instant::now();
+                values[j] = (heuristic.evaluate)(state);
+                stats[j].evaluation_time += start.elapsed();
+                if values[j] < stats[j].best_h {
+                    stats[j].best_h = values[j];
+                    stats[j].improvements += 1;
+                    boosts[j] += PROGRESS_BOOST;
+                }
+            }
+            if values.iter().all(|h| h.is_infinite()) {
+                continue;
+            }
+            let cost = tree.get_node(succ_idx).unwrap().cost;
+            for (queue, &h) in queues.iter_mut().zip(&values) {
+                queue.insert(succ_idx, cost, h);
+            }
+        }
+    };
+
+    println!("nodes: {nodes}, unique: {uniq}");
+    for s in &stats {
+        println!(
+            "  {}: expansions: {}, improvements: {}, best h: {}, evaluation time: {:?}{}",
+            s.name,
+            s.expansions,
+            s.improvements,
+            s.best_h,
+            s.evaluation_time,
+            if s.found_goal { ", found the goal" } else { "" }
+        );
+    }
+    (result, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::numeric_heuristic::{NumericHeuristic, NumericHeuristicKind};
+    use crate::heuristics::numeric_task::NumericProblem;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_alternation_reports_every_heuristic() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let goal_count = NumericHeuristic::new(
+            problem.numeric_task(&state),
+            NumericHeuristicKind::GoalCount,
+        );
+        let heuristics = [
+            NamedHeuristic::new("domain", |s| problem.heuristic(s)),
+            NamedHeuristic::new("goal_count", |s| {
+                goal_count.evaluate(&problem.numeric_values(s))
+            }),
+        ];
+
+        let (result, stats) = alternation_search(&problem, &state, &heuristics);
+        check_plan(&problem, &state, &result.unwrap());
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats.iter().filter(|s| s.found_goal).count(), 1);
+        assert!(stats.iter().all(|s| s.expansions > 0));
+        // Progress is measured from the initial state: the constant domain heuristic
+        // never improves, goal counting does.
+        assert_eq!((stats[0].improvements, stats[0].best_h), (0, 0.0));
+        assert!(stats[1].improvements > 0);
+        assert!(stats[1].best_h < (heuristics[1].evaluate)(&state));
+    }
+}
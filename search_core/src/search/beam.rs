@@ -0,0 +1,385 @@
+use crate::problems::problem::Problem;
+use crate::search::rng::Rng;
+use crate::search::search::state_hash;
+use ordered_float::OrderedFloat;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeamMode {
+    Layered,   // Expands the whole beam, then keeps the best successors as the next beam
+    BestFirst, // Greedy best-first search whose open list is capped at the beam width
+}
+
+#[derive(Debug, Clone)]
+pub struct BeamConfig {
+    pub width: usize,
+    pub mode: BeamMode,
+    pub max_depth: usize, // Layers (or expansions, in best-first mode) before giving up
+}
+
+impl Default for BeamConfig {
+    fn default() -> Self {
+        BeamConfig {
+            width: 100,
+            mode: BeamMode::Layered,
+            max_depth: 10_000,
+        }
+    }
+}
+
+/// Layered beam search that samples its beams, restarted with a wider beam after a
+/// failure.
+#[derive(Debug, Clone)]
+pub struct StochasticBeamConfig {
+    pub initial_width: usize,
+    pub max_width: usize, // The width doubles at every restart up to this one
+    pub max_depth: usize,
+    pub temperature: f64, // Successors weigh exp(-(h - h_min) / temperature); 0 keeps the best
+    pub seed: u64,
+}
+
+impl Default for StochasticBeamConfig {
+    fn default() -> Self {
+        StochasticBeamConfig {
+            initial_width: 10,
+            max_width: 10_000,
+            max_depth: 10_000,
+            temperature: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Parent pointers of the nodes that entered the beam; the states themselves are only
+/// kept while they are in the beam.
+struct Trail<A> {
+    parents: Vec<Option<(usize, A)>>,
+}
+
+impl<A: Copy> Trail<A> {
+    fn new() -> Self {
+        Trail {
+            parents: vec![None],
+        }
+    }
+
+    fn add(&mut self, parent: usize, action: A) -> usize {
+        self.parents.push(Some((parent, action)));
+        self.parents.len() - 1
+    }
+
+    fn plan(&self, mut node: usize) -> Vec<A> {
+        let mut plan = Vec::new();
+        while let Some((parent, action)) = self.parents[node] {
+            plan.push(action);
+            node = parent;
+        }
+        plan.reverse();
+        plan
+    }
+}
+
+/// A successor waiting to enter the beam.
+struct Candidate<S, A> {
+    parent: usize,
+    action: A,
+    state: S,
+    hash: u64,
+    h: f64,
+}
+
+enum Expansion<S, A> {
+    Successors(Vec<Candidate<S, A>>), // Those that never entered the beam, dead ends dropped
+    Goal(Vec<A>),                     // Plan to a goal among the successors
+}
+
+/// Expands every state of the beam.
+fn expand_beam<P, H>(
+    problem: &P,
+    beam: &[(usize, P::State)],
+    seen: &HashSet<u64>,
+    trail: &Trail<P::Action>,
+    heuristic: &H,
+    nodes: &mut usize,
+) -> Expansion<P::State, P::Action>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    let mut layer = HashSet::new();
+    let mut candidates = Vec::new();
+    for (node, state) in beam {
+        for action in problem.get_possible_actions(state) {
+            *nodes += 1;
+            let successor = problem.apply_action(state, &action);
+            let hash = state_hash(&successor);
+            if seen.contains(&hash) || !layer.insert(hash) {
+                continue;
+            }
+            if problem.is_goal_state(&successor) {
+                let mut plan = trail.plan(*node);
+                plan.push(action);
+                return Expansion::Goal(plan);
+            }
+            let h = heuristic(&successor);
+            if h.is_finite() {
+                candidates.push(Candidate {
+                    parent: *node,
+                    action,
+                    state: successor,
+                    hash,
+                    h,
+                });
+            }
+        }
+    }
+    Expansion::Successors(candidates)
+}
+
+/// Beam search: incomplete, but its memory is bounded by the beam width times the depth,
+/// plus a hash for every state that entered the beam, used to drop duplicates.
+pub fn beam_search<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &BeamConfig,
+    heuristic: H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    match config.mode {
+        BeamMode::Layered => layered_beam(
+            problem,
+            initial_state,
+            config.width,
+            config.max_depth,
+            &heuristic,
+            keep_best,
+        ),
+        BeamMode::BestFirst => best_first_beam(problem, initial_state, config, &heuristic),
+    }
+}
+
+/// Layered beam search restarted with a doubled width until it succeeds or the maximum
+/// width fails too. Each beam is sampled from the successors of the previous one, the
+/// better ones being more likely to be kept.
+pub fn stochastic_beam_search<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &StochasticBeamConfig,
+    heuristic: H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    assert!(
+        config.temperature >= 0.0,
+        "The temperature of a stochastic beam must be non-negative"
+    );
+    let mut rng = Rng::new(config.seed);
+    let mut width = config.initial_width;
+    loop {
+        let result = layered_beam(
+            problem,
+            initial_state,
+            width,
+            config.max_depth,
+            &heuristic,
+            |candidates, width| sample(candidates, width, config.temperature, &mut rng),
+        );
+        if result.is_ok() || width >= config.max_width {
+            return result;
+        }
+        width = (width * 2).min(config.max_width);
+    }
+}
+
+/// Keeps the `width` candidates of lowest heuristic value.
+fn keep_best<S, A>(candidates: &mut Vec<Candidate<S, A>>, width: usize) {
+    candidates.sort_by_key(|c| OrderedFloat(c.h));
+    candidates.truncate(width);
+}
+
+/// Keeps `width` candidates sampled without replacement, with weights
+/// exp(-(h - h_min) / temperature) (Efraimidis and Spirakis). At temperature 0, the
+/// limit of the weights, the best candidates are kept.
+fn sample<S, A>(
+    candidates: &mut Vec<Candidate<S, A>>,
+    width: usize,
+    temperature: f64,
+    rng: &mut Rng,
+) {
+    if candidates.len() <= width {
+        return;
+    }
+    if temperature == 0.0 {
+        keep_best(candidates, width);
+        return;
+    }
+    let h_min = candidates.iter().map(|c| c.h).fold(f64::INFINITY, f64::min);
+    let mut keyed: Vec<(OrderedFloat<f64>, Candidate<S, A>)> = candidates
+        .drain(..)
+        .map(|c| {
+            let weight = (-(c.h - h_min) / temperature).exp();
+            (OrderedFloat(rng.next_f64().ln() / weight), c)
+        })
+        .collect();
+    keyed.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+    candidates.extend(keyed.into_iter().take(width).map(|(_, c)| c));
+}
+
+fn layered_beam<P, H, F>(
+    problem: &P,
+    initial_state: &P::State,
+    width: usize,
+    max_depth: usize,
+    heuristic: &H,
+    mut select: F,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+    F: FnMut(&mut Vec<Candidate<P::State, P::Action>>, usize),
+{
+    if problem.is_goal_state(initial_state) {
+        return Ok(Vec::new());
+    }
+    let mut trail = Trail::new();
+    let mut seen = HashSet::from([state_hash(initial_state)]);
+    let mut beam = vec![(0, initial_state.clone())];
+    let mut nodes = 0;
+
+    for depth in 1..=max_depth {
+        let expanded = expand_beam(problem, &beam, &seen, &trail, heuristic, &mut nodes);
+        let mut candidates = match expanded {
+            Expansion::Successors(candidates) => candidates,
+            Expansion::Goal(plan) => {
+                println!("nodes: {nodes}, layers: {depth}, width: {width}");
+                return Ok(plan);
+            }
+        };
+        if candidates.is_empty() {
+            break;
+        }
+        select(&mut candidates, width);
+        beam = candidates
+            .into_iter()
+            .map(|c| {
+                seen.insert(c.hash);
+                (trail.add(c.parent, c.action), c.state)
+            })
+            .collect();
+    }
+    println!("nodes: {nodes}, width: {width}");
+    Err("No solution found within the beam")
+}
+
+/// Greedy best-first search that forgets the worst open node whenever the open list
+/// outgrows the beam width. `max_depth` caps the number of expansions.
+fn best_first_beam<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &BeamConfig,
+    heuristic: &H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    if problem.is_goal_state(initial_state) {
+        return Ok(Vec::new());
+    }
+    let mut trail = Trail::new();
+    let mut seen = HashSet::from([state_hash(initial_state)]);
+    let mut open = BTreeSet::from([(OrderedFloat(heuristic(initial_state)), 0)]);
+    let mut states = HashMap::from([(0, initial_state.clone())]);
+    let mut nodes = 0;
+
+    for _ in 0..config.max_depth {
+        let Some((_, node)) = open.pop_first() else {
+            break;
+        };
+        let state = states.remove(&node).unwrap();
+        for action in problem.get_possible_actions(&state) {
+            nodes += 1;
+            let successor = problem.apply_action(&state, &action);
+            if !seen.insert(state_hash(&successor)) {
+                continue;
+            }
+            if problem.is_goal_state(&successor) {
+                println!("nodes: {nodes}, width: {}", config.width);
+                let mut plan = trail.plan(node);
+                plan.push(action);
+                return Ok(plan);
+            }
+            let h = heuristic(&successor);
+            if h.is_infinite() {
+                continue;
+            }
+            let child = trail.add(node, action);
+            open.insert((OrderedFloat(h), child));
+            states.insert(child, successor);
+            if open.len() > config.width {
+                let (_, worst) = open.pop_last().unwrap();
+                states.remove(&worst);
+            }
+        }
+    }
+    println!("nodes: {nodes}, width: {}", config.width);
+    Err("No solution found within the beam")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_beam_searches_find_valid_plans() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let heuristic = |s: &_| problem.heuristic(s);
+        for mode in [BeamMode::Layered, BeamMode::BestFirst] {
+            let config = BeamConfig {
+                width: 5,
+                mode,
+                ..BeamConfig::default()
+            };
+            let plan = beam_search(&problem, &state, &config, heuristic).unwrap();
+            check_plan(&problem, &state, &plan);
+        }
+
+        // The same seed gives the same plan.
+        let config = StochasticBeamConfig {
+            initial_width: 2,
+            seed: 3,
+            ..StochasticBeamConfig::default()
+        };
+        let plan = stochastic_beam_search(&problem, &state, &config, heuristic).unwrap();
+        check_plan(&problem, &state, &plan);
+        assert_eq!(
+            stochastic_beam_search(&problem, &state, &config, heuristic).unwrap(),
+            plan
+        );
+
+        // At temperature 0 the sampled beam is the layered beam of the same width.
+        let config = StochasticBeamConfig {
+            initial_width: 2,
+            max_width: 2,
+            temperature: 0.0,
+            ..StochasticBeamConfig::default()
+        };
+        let layered = BeamConfig {
+            width: 2,
+            ..BeamConfig::default()
+        };
+        assert_eq!(
+            stochastic_beam_search(&problem, &state, &config, heuristic),
+            beam_search(&problem, &state, &layered, heuristic)
+        );
+    }
+}
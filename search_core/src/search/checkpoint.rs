@@ -0,0 +1,290 @@
+use crate::algorithms::priority_queue::PriorityQueue;
+use crate::search::action::ActionTrait;
+use crate::search::cost::CostTrait;
+use crate::search::search_tree::SearchTree;
+use crate::search::state::StateTrait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Everything `keyed_search` keeps besides the tree: the open queue, the closed list of
+/// state keys, the node counters and the values reported to observers. The keys of
+/// `state_hash` come from `DefaultHasher::new`, whose keys are fixed, so a checkpoint can
+/// be resumed by any run of the same build.
+#[derive(Serialize, Deserialize)]
+pub struct SearchProgress<Q> {
+    pub queue: Q,
+    pub closed: HashSet<u64>,
+    pub nodes: usize,
+    pub uniq: usize,
+    pub h_values: Vec<f64>, // by node index, NaN for nodes that were never queued
+    pub best_h: f64,
+    pub f_layer: f64,
+}
+
+impl<Q: PriorityQueue> SearchProgress<Q> {
+    /// Progress of a search that has not started yet: only the root is open.
+    pub fn new(mut queue: Q) -> Self {
+        queue.insert(0, 0, f64::MAX);
+        SearchProgress {
+            queue,
+            closed: HashSet::new(),
+            nodes: 0,
+            uniq: 0,
+            h_values: Vec::new(),
+            best_h: f64::INFINITY,
+            f_layer: f64::NEG_INFINITY,
+        }
+    }
+}
+
+/// Called by `keyed_search` after every expansion with everything needed to resume it.
+/// `()` does nothing.
+pub trait SearchHook<S: StateTrait, A: ActionTrait, W: CostTrait, Q> {
+    /// Returns false to stop the search.
+    fn after_expansion(&mut self, tree: &SearchTree<S, A, W>, progress: &SearchProgress<Q>) -> bool;
+}
+
+impl<S: StateTrait, A: ActionTrait, W: CostTrait, Q> SearchHook<S, A, W, Q> for () {
+    fn after_expansion(&mut self, _: &SearchTree<S, A, W>, _: &SearchProgress<Q>) -> bool {
+        true
+    }
+}
+
+/// Identifies the search a checkpoint belongs to, so that it is never resumed on another
+/// problem or with another strategy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointHeader {
+    pub json_path: String,
+    pub search_strategy: String,
+}
+
+/// Writes search checkpoints every `interval`, and whenever the request flag is set, e.g.
+/// by a watchdog thread. Setting the interrupt flag, which `handle_signals` does on SIGINT
+/// and SIGTERM, writes a checkpoint and stops the search. Checkpoints are taken between
+/// two expansions.
+pub struct Checkpointer {
+    path: PathBuf,
+    header: CheckpointHeader,
+    interval: Option<Duration>,
+    last_saved: Instant,
+    requested: Arc<AtomicBool>,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl Checkpointer {
+    pub fn new(path: impl Into<PathBuf>, header: CheckpointHeader, interval: Option<Duration>) -> Self {
+        Checkpointer {
+            path: path.into(),
+            header,
+            interval,
+            last_saved: Instant::now(),
+            requested: Arc::new(AtomicBool::new(false)),
+            interrupted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flag that makes the search write a checkpoint after the current expansion.
+    pub fn request_flag(&self) -> Arc<AtomicBool> {
+        self.requested.clone()
+    }
+
+    /// Flag that makes the search write a checkpoint and stop after the current expansion.
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        self.interrupted.clone()
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// Sets the interrupt flag on SIGINT and SIGTERM. A second signal while the checkpoint
+    /// is pending terminates the process as usual.
+    pub fn handle_signals(&self) -> io::Result<()> {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+        use signal_hook::flag;
+        for signal in [SIGINT, SIGTERM] {
+            flag::register_conditional_shutdown(signal, 1, self.interrupted.clone())?;
+            flag::register(signal, self.interrupted.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Saves a checkpoint if one was requested, the search was interrupted or the interval
+    /// has elapsed. A failed save is reported and leaves the previous checkpoint in place;
+    /// the next one is attempted after another interval.
+    pub fn maybe_save<S, A, W, Q>(
+        &mut self,
+        tree: &SearchTree<S, A, W>,
+        progress: &SearchProgress<Q>,
+    ) where
+        S: StateTrait,
+        A: ActionTrait,
+        W: CostTrait,
+        Q: Serialize,
+    {
+        let due = self
+            .interval
+            .is_some_and(|interval| self.last_saved.elapsed() >= interval);
+        if self.requested.swap(false, Ordering::SeqCst) || self.is_interrupted() || due {
+            if let Err(e) = save_checkpoint(&self.path, &self.header, tree, progress) {
+                eprintln!(
+                    "Failed to save checkpoint to {}, keeping the previous one: {}",
+                    self.path.display(),
+                    e
+                );
+            }
+            self.last_saved = Instant::now();
+        }
+    }
+}
+
+impl<S, A, W, Q> SearchHook<S, A, W, Q> for Checkpointer
+where
+    S: StateTrait,
+    A: ActionTrait,
+    W: CostTrait,
+    Q: Serialize,
+{
+    fn after_expansion(&mut self, tree: &SearchTree<S, A, W>, progress: &SearchProgress<Q>) -> bool {
+        self.maybe_save(tree, progress);
+        !self.is_interrupted()
+    }
+}
+
+/// Writes the header, the tree and the progress to `path`, through a temporary file so
+/// that an interrupted write leaves the previous checkpoint intact.
+pub fn save_checkpoint<S, A, W, Q>(
+    path: &Path,
+    header: &CheckpointHeader,
+    tree: &SearchTree<S, A, W>,
+    progress: &SearchProgress<Q>,
+) -> Result<(), String>
+where
+    S: StateTrait,
+    A: ActionTrait,
+    W: CostTrait,
+    Q: Serialize,
+{
+    let temporary = path.with_extension("partial");
+    let file = File::create(&temporary).map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(file);
+    serde_cbor::to_writer(&mut writer, &(header, tree, progress)).map_err(|e| e.to_string())?;
+    writer.flush().map_err(|e| e.to_string())?;
+    fs::rename(&temporary, path).map_err(|e| e.to_string())
+}
+
+/// Contents of a checkpoint file besides its header: the tree and the progress of the search.
+pub type Checkpoint<S, A, W, Q> = (SearchTree<S, A, W>, SearchProgress<Q>);
+
+/// Loads the checkpoint at `path`, refusing one written for another search than `header`.
+pub fn load_checkpoint<S, A, W, Q>(
+    path: &Path,
+    header: &CheckpointHeader,
+) -> Result<Checkpoint<S, A, W, Q>, String>
+where
+    S: StateTrait,
+    A: ActionTrait,
+    W: CostTrait,
+    Q: DeserializeOwned,
+{
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let (saved, tree, progress): (CheckpointHeader, _, _) =
+        serde_cbor::from_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+    if saved != *header {
+        return Err(format!(
+            "{} was written for {} with {}, not {} with {}",
+            path.display(),
+            saved.json_path,
+            saved.search_strategy,
+            header.json_path,
+            header.search_strategy
+        ));
+    }
+    Ok((tree, progress))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::problem::Problem;
+    use crate::search::search::{generic_search, keyed_search, state_hash};
+
+    type Saved = Checkpoint<
+        <CountersProblem as Problem>::State,
+        <CountersProblem as Problem>::Action,
+        <CountersProblem as Problem>::Cost,
+        AStarQueue,
+    >;
+
+    #[test]
+    fn test_resumed_search_matches_uninterrupted_search() {
+        let json_path = "src/inputs/counters_problem/problems_json/pfile1.json";
+        let (state, problem) = CountersProblem::load_state_from_json(json_path);
+        let mut tree = SearchTree::new(state.clone());
+        let plan = generic_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |s| problem.heuristic(s),
+        );
+
+        // An interrupt raised up front stops the search after the first expansion.
+        let path = std::env::temp_dir()
+            .join(format!("counters_pfile1_{}.checkpoint", std::process::id()));
+        let header = CheckpointHeader {
+            json_path: json_path.to_string(),
+            search_strategy: "A*".to_string(),
+        };
+        let mut checkpointer = Checkpointer::new(&path, header.clone(), None);
+        checkpointer.interrupt_flag().store(true, Ordering::SeqCst);
+        let search = |tree: &mut SearchTree<_, _, _>,
+                      progress: &mut SearchProgress<AStarQueue>,
+                      checkpointer: &mut Checkpointer| {
+            keyed_search(
+                tree,
+                |s| problem.get_possible_actions(s),
+                |s, a| problem.apply_action(s, a),
+                |s, a| problem.action_cost(s, a),
+                |s| problem.is_goal_state(s),
+                progress,
+                |s| problem.heuristic(s),
+                &mut (),
+                state_hash,
+                checkpointer,
+            )
+        };
+        let mut first_tree = SearchTree::new(state);
+        let mut first_progress = SearchProgress::new(AStarQueue::new());
+        assert!(search(&mut first_tree, &mut first_progress, &mut checkpointer).is_err());
+
+        let other = CheckpointHeader {
+            search_strategy: "GBFS".to_string(),
+            ..header.clone()
+        };
+        let refused: Result<Saved, _> = load_checkpoint(&path, &other);
+        assert!(refused.is_err());
+
+        let (mut resumed_tree, mut resumed_progress): Saved = load_checkpoint(&path, &header).unwrap();
+        assert!(resumed_tree.nodes.len() < tree.nodes.len());
+        let mut checkpointer = Checkpointer::new(&path, header, None);
+        assert_eq!(search(&mut resumed_tree, &mut resumed_progress, &mut checkpointer), plan);
+        assert_eq!(resumed_tree.nodes.len(), tree.nodes.len());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+use crate::search::action::ActionTrait;
+use crate::search::cost::CostTrait;
+use crate::search::state_codec::StateCodec;
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+/// Parent of the root node.
+const NO_PARENT: u32 = u32::MAX;
+
+/// A node of the compact tree: the action leading to it is stored as its position among
+/// the parent's possible actions.
+#[derive(Debug, Clone, Copy)]
+pub struct CompactNode<W = i32> {
+    pub parent: u32,
+    pub operator: u32,
+    pub cost: W,
+}
+
+/// Search tree storing every state encoded by a `StateCodec` in one byte arena, and only
+/// parent, operator and cost per node. Children lists are kept only on request.
+pub struct CompactSearchTree<S, A, C: StateCodec<S>, W = i32> {
+    pub nodes: Vec<CompactNode<W>>,
+    arena: Vec<u8>,
+    ends: Vec<usize>, // end of each node's encoding in `arena`
+    children: Option<Vec<Vec<u32>>>,
+    codec: C,
+    marker: PhantomData<(S, A)>,
+}
+
+impl<S, A: ActionTrait, C: StateCodec<S>, W: CostTrait> CompactSearchTree<S, A, C, W> {
+    pub fn new(initial_state: &S, codec: C, keep_children: bool) -> Self {
+        let mut tree = CompactSearchTree {
+            nodes: Vec::new(),
+            arena: Vec::new(),
+            ends: Vec::new(),
+            children: keep_children.then(Vec::new),
+            codec,
+            marker: PhantomData,
+        };
+        let mut bytes = Vec::new();
+        tree.codec.encode(initial_state, &mut bytes);
+        tree.push(NO_PARENT, 0, W::default(), &bytes);
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Encoded state of a node.
+    pub fn state_bytes(&self, idx: usize) -> &[u8] {
+        let start = if idx == 0 { 0 } else { self.ends[idx - 1] };
+        &self.arena[start..self.ends[idx]]
+    }
+
+    pub fn get_state(&self, idx: usize) -> S {
+        self.codec.decode(self.state_bytes(idx))
+    }
+
+    pub fn parent(&self, idx: usize) -> Option<usize> {
+        match self.nodes[idx].parent {
+            NO_PARENT => None,
+            parent => Some(parent as usize),
+        }
+    }
+
+    pub fn children(&self, idx: usize) -> Option<&[u32]> {
+        self.children.as_ref().map(|c| c[idx].as_slice())
+    }
+
+    /// Adds a node whose state is already encoded; the caller decides beforehand whether
+    /// the successor is kept, so pruned successors are never stored.
+    pub fn add_node(&mut self, parent_idx: usize, operator: usize, cost: W, bytes: &[u8]) -> usize {
+        let idx = self.push(parent_idx as u32, operator as u32, cost, bytes);
+        if let Some(children) = &mut self.children {
+            children[parent_idx].push(idx as u32);
+        }
+        idx
+    }
+
+    fn push(&mut self, parent: u32, operator: u32, cost: W, bytes: &[u8]) -> usize {
+        let idx = self.nodes.len();
+        assert!(idx < NO_PARENT as usize, "Compact search tree is full");
+        self.nodes.push(CompactNode {
+            parent,
+            operator,
+            cost,
+        });
+        self.arena.extend_from_slice(bytes);
+        self.ends.push(self.arena.len());
+        if let Some(children) = &mut self.children {
+            children.push(Vec::new());
+        }
+        idx
+    }
+
+    /// Actions from the root to `node_index`, recovered by regenerating the actions of
+    /// every ancestor state.
+    pub fn trace_actions<F>(&self, node_index: usize, get_actions: F) -> Vec<A>
+    where
+        F: Fn(&S) -> Vec<A>,
+    {
+        let mut actions = Vec::new();
+        let mut current = node_index;
+        while let Some(parent) = self.parent(current) {
+            let operator = self.nodes[current].operator as usize;
+            actions.push(get_actions(&self.get_state(parent))[operator]);
+            current = parent;
+        }
+        actions.reverse();
+        actions
+    }
+
+    /// Approximate number of heap bytes held by the tree.
+    pub fn memory_usage(&self) -> usize {
+        let children = self.children.as_ref().map_or(0, |c| {
+            c.capacity() * size_of::<Vec<u32>>()
+                + c.iter()
+                    .map(|v| v.capacity() * size_of::<u32>())
+                    .sum::<usize>()
+        });
+        self.nodes.capacity() * size_of::<CompactNode<W>>()
+            + self.arena.capacity()
+            + self.ends.capacity() * size_of::<usize>()
+            + children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::problem::Problem;
+    use crate::problems::rover_problem::rover_problem::RoverProblem;
+    use crate::problems::settlersnumeric_problem::settlersnumeric_problem::SettlersNumericProblem;
+    use crate::search::rng::Rng;
+    use crate::search::observer::{SearchEvent, TraceRecorder};
+    use crate::search::search::{compact_search, observed_search};
+    use crate::search::search_tree::SearchTree;
+    use crate::search::state_codec::{CborCodec, PackedProblem};
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_compact_search_finds_optimal_plan() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let mut tree = SearchTree::new(state.clone());
+        let mut trace = TraceRecorder::in_memory();
+        let plan = observed_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |s| problem.heuristic(s),
+            &mut trace,
+        )
+        .unwrap();
+
+        let mut compact = CompactSearchTree::new(&state, CborCodec::new(), true);
+        let compact_plan = compact_search(
+            &mut compact,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |s| problem.heuristic(s),
+        )
+        .unwrap();
+
+        assert_eq!(
+            check_plan(&problem, &state, &compact_plan),
+            check_plan(&problem, &state, &plan)
+        );
+        assert!(compact.len() < tree.states.len());
+        // the compact tree holds the root and exactly the states the plain search kept
+        let kept = trace.events.iter().filter(|e| matches!(e, SearchEvent::Generated { .. })).count()
+            - trace.events.iter().filter(|e| matches!(e, SearchEvent::DuplicatePruned { .. })).count();
+        assert_eq!(compact.len(), kept + 1);
+        let root_children = compact.nodes.iter().filter(|n| n.parent == 0).count();
+        assert_eq!(compact.children(0).unwrap().len(), root_children);
+    }
+
+    fn check_round_trip<P: PackedProblem>(json_path: &str) {
+        let (mut state, problem) = P::load_state_from_json(json_path);
+        let codec = problem.state_codec(&state);
+        let mut rng = Rng::new(7);
+        for _ in 0..200 {
+            let (mut packed, mut cbor) = (Vec::new(), Vec::new());
+            codec.encode(&state, &mut packed);
+            CborCodec::new().encode(&state, &mut cbor);
+            assert_eq!(codec.decode(&packed), state);
+            assert!(packed.len() * 10 < cbor.len());
+
+            let actions = problem.get_possible_actions(&state);
+            match rng.choose(&actions) {
+                Some(action) => state = problem.apply_action(&state, action),
+                None => break,
+            }
+        }
+    }
+
+    #[test]
+    fn test_packed_states_round_trip() {
+        check_round_trip::<RoverProblem>("src/inputs/rover_problem/pfile5.json");
+        check_round_trip::<SettlersNumericProblem>(
+            "src/inputs/settlersnumeric_problem/pfile5.json",
+        );
+    }
+}
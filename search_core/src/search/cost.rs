@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub};
+
+/// Cost type of a domain's actions and plans: `i32` or `i64` for integer costs,
+/// `FixedPoint` for decimal ones and `Rational` for exact fractions. Costs are summed
+/// exactly along paths; only queue priorities go through `to_f64`.
+pub trait CostTrait:
+    Debug
+    + Display
+    + Default // Zero
+    + Copy
+    + Ord
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + AddAssign
+    + Sum
+    + Serialize
+    + for<'de> Deserialize<'de>
+{
+    fn to_f64(self) -> f64;
+}
+
+impl CostTrait for i32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl CostTrait for i64 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+/// Decimal with `DECIMALS` digits after the point, stored as an integer number of units.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct FixedPoint<const DECIMALS: u32>(pub i64);
+
+impl<const DECIMALS: u32> FixedPoint<DECIMALS> {
+    const UNIT: i64 = 10i64.pow(DECIMALS);
+
+    pub fn from_integer(value: i64) -> Self {
+        FixedPoint(value * Self::UNIT)
+    }
+
+    /// Nearest fixed-point value, exact for decimals with at most `DECIMALS` digits.
+    pub fn from_f64(value: f64) -> Self {
+        FixedPoint((value * Self::UNIT as f64).round() as i64)
+    }
+
+    pub fn times(self, factor: i64) -> Self {
+        FixedPoint(
+            self.0
+                .checked_mul(factor)
+                .expect("Fixed-point cost overflow"),
+        )
+    }
+}
+
+impl<const DECIMALS: u32> Add for FixedPoint<DECIMALS> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        FixedPoint(
+            self.0
+                .checked_add(other.0)
+                .expect("Fixed-point cost overflow"),
+        )
+    }
+}
+
+impl<const DECIMALS: u32> Sub for FixedPoint<DECIMALS> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        FixedPoint(
+            self.0
+                .checked_sub(other.0)
+                .expect("Fixed-point cost overflow"),
+        )
+    }
+}
+
+impl<const DECIMALS: u32> AddAssign for FixedPoint<DECIMALS> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const DECIMALS: u32> Sum for FixedPoint<DECIMALS> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// Shortest decimal form: "12", "12.5", "-0.25".
+impl<const DECIMALS: u32> Display for FixedPoint<DECIMALS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let (units, fraction) = (
+            self.0.unsigned_abs() / Self::UNIT as u64,
+            self.0.unsigned_abs() % Self::UNIT as u64,
+        );
+        if fraction == 0 {
+            return write!(f, "{sign}{units}");
+        }
+        let digits = format!("{:0width$}", fraction, width = DECIMALS as usize);
+        write!(f, "{sign}{units}.{}", digits.trim_end_matches('0'))
+    }
+}
+
+impl<const DECIMALS: u32> CostTrait for FixedPoint<DECIMALS> {
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::UNIT as f64
+    }
+}
+
+/// Exact fraction in lowest terms, with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rational {
+    numerator: i64,
+    denominator: i64,
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+impl Rational {
+    pub fn new(numerator: i64, denominator: i64) -> Self {
+        assert!(denominator != 0, "Rational cost with a zero denominator");
+        Self::reduced(numerator as i128, denominator as i128)
+    }
+
+    pub fn from_integer(value: i64) -> Self {
+        Rational {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+
+    pub fn numerator(self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(self) -> i64 {
+        self.denominator
+    }
+
+    fn reduced(numerator: i128, denominator: i128) -> Self {
+        let divisor = gcd(numerator, denominator).max(1) * denominator.signum();
+        let narrow = |x: i128| i64::try_from(x / divisor).expect("Rational cost overflow");
+        Rational {
+            numerator: narrow(numerator),
+            denominator: narrow(denominator),
+        }
+    }
+}
+
+impl Default for Rational {
+    fn default() -> Self {
+        Rational::from_integer(0)
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (a, b) = (self.numerator as i128, self.denominator as i128);
+        let (c, d) = (other.numerator as i128, other.denominator as i128);
+        Self::reduced(a * d + c * b, b * d)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + Rational {
+            numerator: -other.numerator,
+            denominator: other.denominator,
+        }
+    }
+}
+
+impl AddAssign for Rational {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for Rational {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numerator as i128 * other.denominator as i128)
+            .cmp(&(other.numerator as i128 * self.denominator as i128))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// "3" or "7/2".
+impl Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl CostTrait for Rational {
+    fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_costs_add_up_without_rounding() {
+        let thirds: Rational = (0..3).map(|_| Rational::new(1, 3)).sum();
+        assert_eq!(thirds, Rational::from_integer(1));
+        assert_eq!(Rational::new(6, -4).to_string(), "-3/2");
+        assert!(Rational::new(1, 3) < Rational::new(1, 2));
+        assert_eq!(
+            Rational::new(7, 2) - Rational::new(1, 2),
+            Rational::new(3, 1)
+        );
+
+        let tenths: FixedPoint<2> = (0..10).map(|_| FixedPoint::from_f64(0.1)).sum();
+        assert_eq!(tenths, FixedPoint::from_integer(1));
+        assert_eq!(tenths.to_string(), "1");
+        assert_eq!(FixedPoint::<3>::from_f64(-12.25).to_string(), "-12.25");
+        assert_eq!(FixedPoint::<2>::from_f64(0.05).times(3).to_string(), "0.15");
+    }
+}
@@ -0,0 +1,409 @@
+use crate::problems::problem::Problem;
+use crate::search::action::ActionTrait;
+use crate::search::cost::CostTrait;
+use crate::search::search_tree::SearchTree;
+use crate::search::state::StateTrait;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Dot,
+    Json,
+}
+
+impl ExportFormat {
+    /// DOT for `.dot` and `.gv` files, JSON otherwise.
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".dot") || path.ends_with(".gv") {
+            ExportFormat::Dot
+        } else {
+            ExportFormat::Json
+        }
+    }
+}
+
+/// Size caps and filters applied when exporting. Nodes on the solution path are always
+/// kept, so that it can be highlighted.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub max_nodes: Option<usize>, // Keep only the first nodes generated
+    pub max_depth: Option<usize>, // Keep only nodes this close to the root
+    pub expanded_only: bool,      // Drop nodes that were generated but never expanded
+    pub skip_duplicates: bool,    // Drop nodes pruned by the closed list
+    pub include_states: bool,     // Add the serialised state of every node (JSON only)
+}
+
+impl ExportOptions {
+    /// Parses options of the form `max_nodes=N`, `max_depth=N`, `expanded_only`,
+    /// `skip_duplicates` and `states`.
+    pub fn from_specs<'a>(specs: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut options = ExportOptions::default();
+        for spec in specs {
+            let (name, value) = spec.split_once('=').unwrap_or((spec, ""));
+            let number = || {
+                value
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Invalid export option: {}", spec))
+            };
+            match name {
+                "max_nodes" => options.max_nodes = Some(number()),
+                "max_depth" => options.max_depth = Some(number()),
+                "expanded_only" => options.expanded_only = true,
+                "skip_duplicates" => options.skip_duplicates = true,
+                "states" => options.include_states = true,
+                _ => panic!("Unknown export option: {}", spec),
+            }
+        }
+        options
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedNode {
+    pub id: usize,
+    pub g: f64,
+    pub h: Option<f64>,
+    pub expanded: Option<usize>, // Position in the expansion order
+    pub goal: bool,
+    pub on_solution: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedLink {
+    pub source: usize,
+    pub target: usize,
+    pub label: String,
+    pub cost: f64,
+    pub on_solution: bool,
+}
+
+/// A graph in node-link form, as read by d3 and networkx.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedGraph {
+    pub directed: bool,
+    pub nodes: Vec<ExportedNode>,
+    pub links: Vec<ExportedLink>,
+    pub truncated: bool, // Whether the size caps removed nodes
+}
+
+/// What the search knows about the nodes of its tree beyond the tree itself, as collected
+/// by the `TreeExporter` observer.
+#[derive(Debug, Clone, Default)]
+pub struct TreeAnnotations {
+    pub expansions: Vec<(usize, f64)>, // Expanded nodes in order, with their h
+    pub pruned: HashSet<usize>,
+    pub goal: Option<usize>,
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl ExportedGraph {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialise graph")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n    node [shape=circle];\n");
+        for node in &self.nodes {
+            let mut label = format!("{}\\ng={}", node.id, node.g);
+            if let Some(h) = node.h {
+                write!(label, " h={}", h).unwrap();
+            }
+            if let Some(order) = node.expanded {
+                write!(label, "\\n#{}", order).unwrap();
+            }
+            let mut attributes = format!("label=\"{}\"", label);
+            if node.expanded.is_some() {
+                attributes.push_str(", style=filled, fillcolor=lightgrey");
+            }
+            if node.goal {
+                attributes.push_str(", shape=doublecircle");
+            }
+            if node.on_solution {
+                attributes.push_str(", color=red, penwidth=2");
+            }
+            writeln!(dot, "    {} [{}];", node.id, attributes).unwrap();
+        }
+        for link in &self.links {
+            let mut attributes = format!("label=\"{}\"", escape(&link.label));
+            if link.on_solution {
+                attributes.push_str(", color=red, penwidth=2");
+            }
+            writeln!(
+                dot,
+                "    {} -> {} [{}];",
+                link.source, link.target, attributes
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn save(&self, path: &str, format: ExportFormat) -> Result<(), String> {
+        let text = match format {
+            ExportFormat::Dot => self.to_dot(),
+            ExportFormat::Json => self.to_json(),
+        };
+        fs::write(path, text).map_err(|e| e.to_string())
+    }
+}
+
+/// Exports the explored part of a search tree. Actions are labelled by `action_label`,
+/// called with the parent state.
+pub fn export_search_tree<S, A, W, L>(
+    tree: &SearchTree<S, A, W>,
+    annotations: &TreeAnnotations,
+    action_label: L,
+    options: &ExportOptions,
+) -> ExportedGraph
+where
+    S: StateTrait,
+    A: ActionTrait,
+    W: CostTrait,
+    L: Fn(&S, &A) -> String,
+{
+    let mut solution = HashSet::new();
+    let mut current = annotations.goal;
+    while let Some(node) = current {
+        solution.insert(node);
+        current = tree.nodes[node].parent;
+    }
+    let mut expansion: HashMap<usize, (usize, f64)> = HashMap::new();
+    for (order, &(node, h)) in annotations.expansions.iter().enumerate() {
+        expansion.insert(node, (order, h));
+    }
+
+    // Parents come before their children, so every filter keeps the kept nodes connected.
+    let mut depth = vec![0; tree.nodes.len()];
+    let mut kept = vec![false; tree.nodes.len()];
+    let mut graph = ExportedGraph {
+        directed: true,
+        nodes: Vec::new(),
+        links: Vec::new(),
+        truncated: false,
+    };
+    for (id, node) in tree.nodes.iter().enumerate() {
+        if let Some(parent) = node.parent {
+            depth[id] = depth[parent] + 1;
+        }
+        let keep = solution.contains(&id)
+            || (node.parent.is_none_or(|parent| kept[parent])
+                && (!options.expanded_only || expansion.contains_key(&id))
+                && !(options.skip_duplicates && annotations.pruned.contains(&id))
+                && options.max_depth.is_none_or(|max| depth[id] <= max));
+        if !keep {
+            continue;
+        }
+        if options
+            .max_nodes
+            .is_some_and(|max| graph.nodes.len() >= max)
+            && !solution.contains(&id)
+        {
+            graph.truncated = true;
+            continue;
+        }
+        kept[id] = true;
+
+        graph.nodes.push(ExportedNode {
+            id,
+            g: node.cost.to_f64(),
+            h: expansion.get(&id).map(|&(_, h)| h),
+            expanded: expansion.get(&id).map(|&(order, _)| order),
+            goal: annotations.goal == Some(id),
+            on_solution: solution.contains(&id),
+            state: options.include_states.then(|| {
+                serde_json::to_value(&tree.states[id]).expect("Failed to serialise state")
+            }),
+        });
+        if let (Some(parent), Some(action)) = (node.parent, &node.action) {
+            graph.links.push(ExportedLink {
+                source: parent,
+                target: id,
+                label: action_label(&tree.states[parent], action),
+                cost: (node.cost - tree.nodes[parent].cost).to_f64(),
+                on_solution: solution.contains(&id),
+            });
+        }
+    }
+    graph
+}
+
+/// Exports the graph of states reachable from `initial_state`, explored in order of cost
+/// with every transition as a link. Each node gets its cheapest cost g and its heuristic
+/// value; the cheapest path to a goal is highlighted. Meant for small instances: the
+/// exploration stops at `max_nodes` states.
+pub fn export_state_space<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    options: &ExportOptions,
+) -> ExportedGraph {
+    let mut ids: HashMap<P::State, usize> = HashMap::new();
+    let mut states = vec![initial_state.clone()];
+    let mut g = vec![Some(P::Cost::default())]; // None until reached
+    let mut depth = vec![0];
+    let mut parent: Vec<Option<(usize, usize)>> = vec![None]; // (parent, link index)
+    let mut expanded = vec![None];
+    ids.insert(initial_state.clone(), 0);
+
+    let mut graph = ExportedGraph {
+        directed: true,
+        nodes: Vec::new(),
+        links: Vec::new(),
+        truncated: false,
+    };
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((P::Cost::default(), 0)));
+    let mut order = 0;
+    let mut goal = None;
+    while let Some(Reverse((cost, id))) = open.pop() {
+        if expanded[id].is_some() || g[id].is_some_and(|best| cost > best) {
+            continue;
+        }
+        expanded[id] = Some(order);
+        order += 1;
+        let state = states[id].clone();
+        if problem.is_goal_state(&state) {
+            goal = goal.or(Some(id));
+        }
+        if options.max_depth.is_some_and(|max| depth[id] >= max) {
+            continue;
+        }
+        for action in problem.get_possible_actions(&state) {
+            let successor = problem.apply_action(&state, &action);
+            let action_cost = problem.action_cost(&state, &action);
+            let target = match ids.get(&successor) {
+                Some(&target) => target,
+                None if options.max_nodes.is_some_and(|max| states.len() >= max) => {
+                    graph.truncated = true;
+                    continue;
+                }
+                None => {
+                    ids.insert(successor.clone(), states.len());
+                    states.push(successor);
+                    g.push(None);
+                    depth.push(depth[id] + 1);
+                    parent.push(None);
+                    expanded.push(None);
+                    states.len() - 1
+                }
+            };
+            if g[target].is_none_or(|best| cost + action_cost < best) {
+                g[target] = Some(cost + action_cost);
+                parent[target] = Some((id, graph.links.len()));
+                open.push(Reverse((cost + action_cost, target)));
+            }
+            graph.links.push(ExportedLink {
+                source: id,
+                target,
+                label: problem.describe_action(&state, &action).name,
+                cost: action_cost.to_f64(),
+                on_solution: false,
+            });
+        }
+    }
+
+    let mut solution = HashSet::new();
+    let mut current = goal;
+    while let Some(id) = current {
+        solution.insert(id);
+        current = parent[id].map(|(source, link)| {
+            graph.links[link].on_solution = true;
+            source
+        });
+    }
+    graph.nodes = states
+        .iter()
+        .enumerate()
+        .map(|(id, state)| ExportedNode {
+            id,
+            g: g[id].map_or(f64::INFINITY, CostTrait::to_f64),
+            h: Some(problem.heuristic(state)),
+            expanded: expanded[id],
+            goal: problem.is_goal_state(state),
+            on_solution: solution.contains(&id),
+            state: options
+                .include_states
+                .then(|| serde_json::to_value(state).expect("Failed to serialise state")),
+        })
+        .collect();
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::search::observer::SearchObserver;
+    use crate::search::search::observed_search;
+
+    /// Collects the annotations the way `TreeExporter` does.
+    impl<S: StateTrait, A: ActionTrait, W: CostTrait> SearchObserver<S, A, W> for TreeAnnotations {
+        fn node_expanded(&mut self, _tree: &SearchTree<S, A, W>, node: usize, h: f64) {
+            self.expansions.push((node, h));
+        }
+
+        fn duplicate_pruned(&mut self, _tree: &SearchTree<S, A, W>, node: usize) {
+            self.pruned.insert(node);
+        }
+
+        fn solution_found(&mut self, _tree: &SearchTree<S, A, W>, node: usize) {
+            self.goal = Some(node);
+        }
+    }
+
+    #[test]
+    fn test_export_keeps_solution_within_caps() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let mut tree = SearchTree::new(state.clone());
+        let mut annotations = TreeAnnotations::default();
+        let plan = observed_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |s| problem.heuristic(s),
+            &mut annotations,
+        )
+        .unwrap();
+        let label = |s: &_, a: &_| problem.describe_action(s, a).name;
+        let names: Vec<_> = problem
+            .describe_plan(&state, &plan)
+            .into_iter()
+            .map(|action| action.name)
+            .collect();
+
+        let options = ExportOptions::from_specs(["max_nodes=5", "skip_duplicates"]);
+        let graph = export_search_tree(&tree, &annotations, label, &options);
+        assert!(graph.truncated);
+        assert!(graph.nodes.len() <= 5 + plan.len());
+        let solution: Vec<_> = graph.links.iter().filter(|l| l.on_solution).collect();
+        assert_eq!(solution.len(), plan.len());
+        for (link, name) in solution.iter().zip(&names) {
+            assert_eq!(&link.label, name);
+        }
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph"));
+        assert_eq!(dot.matches("color=red").count(), 2 * plan.len() + 1);
+
+        let options = ExportOptions::from_specs(["max_nodes=50"]);
+        let space = export_state_space(&problem, &state, &options);
+        assert_eq!(space.nodes.len(), 50);
+        assert!(space.links.iter().all(|link| link.target < 50));
+        let json: serde_json::Value = serde_json::from_str(&space.to_json()).unwrap();
+        assert_eq!(json["links"].as_array().unwrap().len(), space.links.len());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::problems::problem::Problem;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Location and memory limit of the disk-based breadth-first search.
+#[derive(Debug, Clone)]
+pub struct ExternalSearchConfig {
+    pub directory: PathBuf, // Each search writes to a fresh subdirectory, removed afterwards
+    pub run_size: usize,    // Successors sorted in memory before they are written as a run
+}
+
+impl Default for ExternalSearchConfig {
+    fn default() -> Self {
+        ExternalSearchConfig {
+            directory: std::env::temp_dir(),
+            run_size: 1_000_000,
+        }
+    }
+}
+
+/// Searches started by this process, so that concurrent searches get distinct directories.
+static SEARCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes length-prefixed records to a file.
+struct RecordWriter {
+    writer: BufWriter<File>,
+    count: usize,
+}
+
+impl RecordWriter {
+    fn create(path: &Path) -> io::Result<Self> {
+        Ok(RecordWriter {
+            writer: BufWriter::new(File::create(path)?),
+            count: 0,
+        })
+    }
+
+    fn write(&mut self, record: &[u8]) -> io::Result<()> {
+        self.writer
+            .write_all(&(record.len() as u32).to_le_bytes())?;
+        self.writer.write_all(record)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Flushes the file and returns the number of records written.
+    fn finish(mut self) -> io::Result<usize> {
+        self.writer.flush()?;
+        Ok(self.count)
+    }
+}
+
+struct RecordReader {
+    reader: BufReader<File>,
+}
+
+impl RecordReader {
+    fn open(path: &Path) -> io::Result<Self> {
+        Ok(RecordReader {
+            reader: BufReader::new(File::open(path)?),
+        })
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let mut length = [0; 4];
+        self.reader.read_exact(&mut length)?;
+        let mut record = vec![0; u32::from_le_bytes(length) as usize];
+        self.reader.read_exact(&mut record)?;
+        Ok(Some(record))
+    }
+}
+
+/// Sorted, duplicate-free union of several sorted run files.
+struct MergedRuns {
+    readers: Vec<RecordReader>,
+    heap: BinaryHeap<Reverse<(Vec<u8>, usize)>>,
+}
+
+impl MergedRuns {
+    fn open(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut merged = MergedRuns {
+            readers: Vec::new(),
+            heap: BinaryHeap::new(),
+        };
+        for path in paths {
+            let mut reader = RecordReader::open(path)?;
+            if let Some(record) = reader.next_record()? {
+                merged.heap.push(Reverse((record, merged.readers.len())));
+            }
+            merged.readers.push(reader);
+        }
+        Ok(merged)
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(Reverse((record, run))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        self.advance(run)?;
+        while let Some(Reverse((next, run))) = self.heap.peek() {
+            if *next != record {
+                break;
+            }
+            let run = *run;
+            self.heap.pop();
+            self.advance(run)?;
+        }
+        Ok(Some(record))
+    }
+
+    fn advance(&mut self, run: usize) -> io::Result<()> {
+        if let Some(record) = self.readers[run].next_record()? {
+            self.heap.push(Reverse((record, run)));
+        }
+        Ok(())
+    }
+}
+
+fn encode<S: serde::Serialize>(state: &S) -> Vec<u8> {
+    serde_cbor::to_vec(state).expect("Failed to serialise state")
+}
+
+fn decode<S: serde::de::DeserializeOwned>(bytes: &[u8]) -> S {
+    serde_cbor::from_slice(bytes).expect("Failed to deserialise state")
+}
+
+fn layer_path(directory: &Path, depth: usize) -> PathBuf {
+    directory.join(format!("layer_{}.bin", depth))
+}
+
+/// Breadth-first search keeping its layers on disk rather than in memory. Each layer is
+/// a sorted file of CBOR-serialised states; successors are sorted in bounded runs, merged,
+/// and subtracted from the sorted file of all states seen so far. The plan is rebuilt by
+/// scanning the layers backwards for a predecessor of each state, so no parent pointers
+/// are stored. States must serialise canonically, i.e. equal states to equal bytes.
+pub fn external_bfs<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &ExternalSearchConfig,
+) -> Result<Vec<P::Action>, String> {
+    let search = SEARCHES.fetch_add(1, Ordering::Relaxed);
+    let directory = config
+        .directory
+        .join(format!("external_bfs_{}_{}", std::process::id(), search));
+    fs::create_dir_all(&config.directory).map_err(|e| e.to_string())?;
+    // fails rather than reusing a directory left by someone else
+    fs::create_dir(&directory).map_err(|e| format!("{}: {}", directory.display(), e))?;
+    let result = search_layers(problem, initial_state, &directory, config.run_size)
+        .map_err(|e| e.to_string());
+    fs::remove_dir_all(&directory).map_err(|e| e.to_string())?;
+    result?.ok_or_else(|| "No solution found".to_string())
+}
+
+fn search_layers<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    directory: &Path,
+    run_size: usize,
+) -> io::Result<Option<Vec<P::Action>>> {
+    if problem.is_goal_state(initial_state) {
+        return Ok(Some(Vec::new()));
+    }
+    let root = encode(initial_state);
+    for path in [layer_path(directory, 0), directory.join("visited.bin")] {
+        let mut writer = RecordWriter::create(&path)?;
+        writer.write(&root)?;
+        writer.finish()?;
+    }
+    let (mut nodes, mut uniq) = (0, 1);
+
+    for depth in 0.. {
+        // Expand the layer into sorted runs of successors.
+        let mut runs = Vec::new();
+        let mut buffer: Vec<Vec<u8>> = Vec::new();
+        let mut layer = RecordReader::open(&layer_path(directory, depth))?;
+        while let Some(record) = layer.next_record()? {
+            let state: P::State = decode(&record);
+            for action in problem.get_possible_actions(&state) {
+                nodes += 1;
+                let successor = problem.apply_action(&state, &action);
+                if problem.is_goal_state(&successor) {
+                    println!("nodes: {nodes}, unique: {uniq}, layers: {}", depth + 1);
+                    let mut plan = backtrack(problem, directory, depth, record)?;
+                    plan.push(action);
+                    return Ok(Some(plan));
+                }
+                buffer.push(encode(&successor));
+                if buffer.len() >= run_size {
+                    runs.push(write_run(directory, runs.len(), &mut buffer)?);
+                }
+            }
+        }
+        runs.push(write_run(directory, runs.len(), &mut buffer)?);
+
+        let layer_size = merge_runs(directory, depth + 1, &runs)?;
+        for run in &runs {
+            fs::remove_file(run)?;
+        }
+        uniq += layer_size;
+        if layer_size == 0 {
+            break;
+        }
+    }
+    println!("nodes: {nodes}, unique: {uniq}");
+    Ok(None)
+}
+
+/// Sorts and deduplicates the buffer and writes it as a run file.
+fn write_run(directory: &Path, index: usize, buffer: &mut Vec<Vec<u8>>) -> io::Result<PathBuf> {
+    buffer.sort_unstable();
+    buffer.dedup();
+    let path = directory.join(format!("run_{}.bin", index));
+    let mut writer = RecordWriter::create(&path)?;
+    for record in buffer.drain(..) {
+        writer.write(&record)?;
+    }
+    writer.finish()?;
+    Ok(path)
+}
+
+/// Merges the runs into the layer at `depth`, dropping the states already seen, and adds
+/// the new states to the visited file. Returns the size of the layer.
+fn merge_runs(directory: &Path, depth: usize, runs: &[PathBuf]) -> io::Result<usize> {
+    let mut merged = MergedRuns::open(runs)?;
+    let mut visited = RecordReader::open(&directory.join("visited.bin"))?;
+    let mut next_visited = RecordWriter::create(&directory.join("visited_next.bin"))?;
+    let mut layer = RecordWriter::create(&layer_path(directory, depth))?;
+    let mut seen = visited.next_record()?;
+    while let Some(record) = merged.next_record()? {
+        while let Some(old) = seen.as_ref().filter(|old| **old < record) {
+            next_visited.write(old)?;
+            seen = visited.next_record()?;
+        }
+        if seen.as_ref() == Some(&record) {
+            continue;
+        }
+        next_visited.write(&record)?;
+        layer.write(&record)?;
+    }
+    while let Some(old) = seen {
+        next_visited.write(&old)?;
+        seen = visited.next_record()?;
+    }
+    next_visited.finish()?;
+    drop(visited);
+    fs::rename(
+        directory.join("visited_next.bin"),
+        directory.join("visited.bin"),
+    )?;
+    layer.finish()
+}
+
+/// Actions leading from the root to `target`, a state of layer `depth`.
+fn backtrack<P: Problem>(
+    problem: &P,
+    directory: &Path,
+    depth: usize,
+    mut target: Vec<u8>,
+) -> io::Result<Vec<P::Action>> {
+    let mut plan = Vec::new();
+    for layer_depth in (0..depth).rev() {
+        let mut layer = RecordReader::open(&layer_path(directory, layer_depth))?;
+        let mut found = false;
+        while let Some(record) = layer.next_record()? {
+            let state: P::State = decode(&record);
+            let action = problem
+                .get_possible_actions(&state)
+                .into_iter()
+                .find(|action| encode(&problem.apply_action(&state, action)) == target);
+            if let Some(action) = action {
+                plan.push(action);
+                target = record;
+                found = true;
+                break;
+            }
+        }
+        assert!(
+            found,
+            "Layer {} holds no predecessor of the state",
+            layer_depth
+        );
+    }
+    plan.reverse();
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::bfs::BfsQueue;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::red_car_problem::red_car_problem::RedCarProblem;
+    use crate::search::search::generic_search;
+    use crate::search::search_tree::SearchTree;
+    use crate::search::test_util::check_plan;
+
+    fn check_against_bfs<P: Problem>(json_path: &str, name: &str) {
+        let (state, problem) = P::load_state_from_json(json_path);
+        let mut tree = SearchTree::new(state.clone());
+        let plan = generic_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            BfsQueue::new(),
+            |_| 0.0,
+        )
+        .unwrap();
+
+        // the search must leave whatever else is in its directory alone
+        let config = ExternalSearchConfig {
+            directory: std::env::temp_dir().join(format!("external_bfs_test_{}", name)),
+            run_size: 16,
+        };
+        fs::create_dir_all(&config.directory).unwrap();
+        let keep = config.directory.join("keep.txt");
+        fs::write(&keep, "not the search's").unwrap();
+        let external_plan = external_bfs(&problem, &state, &config).unwrap();
+        let left: Vec<_> = fs::read_dir(&config.directory).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(left, vec![keep]);
+        fs::remove_dir_all(&config.directory).unwrap();
+        assert_eq!(external_plan.len(), plan.len());
+
+        check_plan(&problem, &state, &external_plan);
+    }
+
+    #[test]
+    fn test_external_bfs_matches_bfs() {
+        check_against_bfs::<CountersProblem>(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+            "counters",
+        );
+        check_against_bfs::<RedCarProblem>(
+            "src/inputs/red_car_problem/red_car/problems_json/pfile1.json",
+            "red_car",
+        );
+    }
+}
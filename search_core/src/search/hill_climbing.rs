@@ -0,0 +1,133 @@
+use crate::problems::problem::Problem;
+use crate::search::search::state_hash;
+use crate::search::search_tree::SearchTree;
+use std::collections::{HashSet, VecDeque};
+
+/// Enforced hill-climbing (Hoffmann & Nebel): from the current state, a breadth-first
+/// search looks for a state of strictly lower heuristic value, which becomes the new
+/// current state, until a goal is reached. Each breadth-first search first follows the
+/// preferred actions only, and all actions if that fails. States with an infinite
+/// heuristic value are dead ends and never followed.
+///
+/// Incomplete: it fails when no state of the last plateau improves on it, and callers
+/// usually fall back to a complete search then.
+pub fn enforced_hill_climbing<P, H, R>(
+    problem: &P,
+    initial_state: &P::State,
+    heuristic: H,
+    preferred: R,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+    R: Fn(&P::State, &[P::Action]) -> Vec<P::Action>,
+{
+    let mut plan = Vec::new();
+    let mut current = initial_state.clone();
+    let mut current_h = heuristic(&current);
+    let (mut nodes, mut plateaus) = (0, 0);
+
+    while !problem.is_goal_state(&current) {
+        if current_h.is_infinite() {
+            return Err("Enforced hill-climbing started from a dead end");
+        }
+        let improvement = [true, false].into_iter().find_map(|preferred_only| {
+            let preferred = preferred_only.then_some(&preferred);
+            improve(
+                problem, &current, current_h, &heuristic, preferred, &mut nodes,
+            )
+        });
+        let Some((state, h, actions)) = improvement else {
+            println!("nodes: {nodes}, plateaus: {plateaus}");
+            return Err("Enforced hill-climbing found no better state");
+        };
+        plateaus += 1;
+        plan.extend(actions);
+        current = state;
+        current_h = h;
+    }
+    println!("nodes: {nodes}, plateaus: {plateaus}");
+    Ok(plan)
+}
+
+/// Breadth-first search from `start` for a goal or a state with a heuristic value below
+/// `start_h`, following only the preferred actions if `preferred` is given. Returns the
+/// state, its heuristic value and the actions leading to it.
+fn improve<P, H, R>(
+    problem: &P,
+    start: &P::State,
+    start_h: f64,
+    heuristic: &H,
+    preferred: Option<&R>,
+    nodes: &mut usize,
+) -> Option<(P::State, f64, Vec<P::Action>)>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+    R: Fn(&P::State, &[P::Action]) -> Vec<P::Action>,
+{
+    let mut tree = SearchTree::new(start.clone());
+    let mut closed = HashSet::from([state_hash(start)]);
+    let mut open = VecDeque::from([0]);
+    let get_actions = |state: &P::State| {
+        let actions = problem.get_possible_actions(state);
+        match preferred {
+            Some(preferred) => preferred(state, &actions),
+            None => actions,
+        }
+    };
+
+    while let Some(cur_idx) = open.pop_front() {
+        let successors = tree.expand_node(
+            cur_idx,
+            get_actions,
+            |state, action| problem.apply_action(state, action),
+            |state, action| problem.action_cost(state, action),
+        );
+        for succ_idx in successors {
+            *nodes += 1;
+            let state = tree.get_state(succ_idx).unwrap();
+            if !closed.insert(state_hash(state)) {
+                continue;
+            }
+            let h = heuristic(state);
+            if problem.is_goal_state(state) || h < start_h {
+                return Some((state.clone(), h, tree.trace_actions(succ_idx)));
+            }
+            if h.is_finite() {
+                open.push_back(succ_idx);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::numeric_heuristic::{NumericHeuristic, NumericHeuristicKind};
+    use crate::heuristics::numeric_task::NumericProblem;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_enforced_hill_climbing_with_helpful_actions() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let h_ff = NumericHeuristic::new(problem.numeric_task(&state), NumericHeuristicKind::HFF);
+        let heuristic = |s: &_| h_ff.evaluate(&problem.numeric_values(s));
+        let helpful = |s: &_, actions: &[_]| {
+            let helpful = h_ff.helpful_actions(&problem.numeric_values(s));
+            assert!(!helpful.is_empty() || problem.is_goal_state(s));
+            actions
+                .iter()
+                .filter(|a| helpful.contains(a))
+                .copied()
+                .collect()
+        };
+
+        let plan = enforced_hill_climbing(&problem, &state, heuristic, helpful).unwrap();
+        check_plan(&problem, &state, &plan);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::problems::problem::Problem;
+use crate::search::rng::Rng;
+
+/// How rollouts choose their actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RolloutPolicy {
+    Random,
+    Greedy, // The successor of lowest heuristic value, ties broken at random
+}
+
+/// Reward of a rollout that did not reach a goal; reaching one is always worth 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardShaping {
+    GoalOnly,  // 0
+    Heuristic, // 0.5 / (1 + h) at the end of the rollout, 0 at a dead end
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsConfig {
+    pub max_iterations: usize,
+    pub rollout_depth: usize,
+    pub exploration: f64, // UCT exploration constant
+    pub policy: RolloutPolicy,
+    pub reward: RewardShaping,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            max_iterations: 10_000,
+            rollout_depth: 50,
+            exploration: std::f64::consts::SQRT_2,
+            policy: RolloutPolicy::Random,
+            reward: RewardShaping::Heuristic,
+            seed: 0,
+        }
+    }
+}
+
+struct MctsNode<S, A, C> {
+    parent: Option<usize>,
+    action: Option<A>,
+    state: S,
+    cost: C,
+    children: Vec<usize>,
+    untried: Vec<A>,
+    visits: u32,
+    total_reward: f64,
+}
+
+/// Monte Carlo tree search with UCT selection. Every iteration descends the tree by UCT,
+/// adds one child, runs a rollout from it and backs up the reward. A goal reached by the
+/// tree or a rollout gives a plan; the cheapest one is returned once the iterations run
+/// out.
+pub fn mcts_search<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &MctsConfig,
+    heuristic: H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    if problem.is_goal_state(initial_state) {
+        return Ok(Vec::new());
+    }
+    let mut rng = Rng::new(config.seed);
+    let mut nodes = vec![MctsNode {
+        parent: None,
+        action: None,
+        state: initial_state.clone(),
+        cost: P::Cost::default(),
+        children: Vec::new(),
+        untried: problem.get_possible_actions(initial_state),
+        visits: 0,
+        total_reward: 0.0,
+    }];
+    let mut best_plan: Option<(P::Cost, Vec<P::Action>)> = None;
+    let mut plans = 0;
+
+    for _ in 0..config.max_iterations {
+        // Selection: descend through fully expanded nodes.
+        let mut node = 0;
+        while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+            let parent_visits = (nodes[node].visits as f64).ln();
+            node = *nodes[node]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    let uct = |child: usize| {
+                        let child = &nodes[child];
+                        child.total_reward / child.visits as f64
+                            + config.exploration * (parent_visits / child.visits as f64).sqrt()
+                    };
+                    uct(a).total_cmp(&uct(b))
+                })
+                .unwrap();
+        }
+
+        // Expansion, unless the node is a goal or a dead end.
+        if !nodes[node].untried.is_empty() && !problem.is_goal_state(&nodes[node].state) {
+            let index = rng.gen_index(nodes[node].untried.len());
+            let action = nodes[node].untried.swap_remove(index);
+            let parent = &nodes[node];
+            let state = problem.apply_action(&parent.state, &action);
+            let cost = parent.cost + problem.action_cost(&parent.state, &action);
+            let untried = if problem.is_goal_state(&state) {
+                Vec::new()
+            } else {
+                problem.get_possible_actions(&state)
+            };
+            nodes.push(MctsNode {
+                parent: Some(node),
+                action: Some(action),
+                state,
+                cost,
+                children: Vec::new(),
+                untried,
+                visits: 0,
+                total_reward: 0.0,
+            });
+            let child = nodes.len() - 1;
+            nodes[node].children.push(child);
+            node = child;
+        }
+
+        // Rollout.
+        let mut state = nodes[node].state.clone();
+        let mut cost = nodes[node].cost;
+        let mut rollout = Vec::new();
+        let mut reached_goal = problem.is_goal_state(&state);
+        let mut dead_end = false;
+        while !reached_goal && rollout.len() < config.rollout_depth {
+            let actions = problem.get_possible_actions(&state);
+            let Some(action) = choose_action(
+                problem,
+                &state,
+                &actions,
+                config.policy,
+                &heuristic,
+                &mut rng,
+            ) else {
+                dead_end = true;
+                break;
+            };
+            cost += problem.action_cost(&state, &action);
+            state = problem.apply_action(&state, &action);
+            rollout.push(action);
+            reached_goal = problem.is_goal_state(&state);
+        }
+
+        let reward = if reached_goal {
+            plans += 1;
+            if best_plan
+                .as_ref()
+                .is_none_or(|(best_cost, _)| cost < *best_cost)
+            {
+                let mut plan = trace(&nodes, node);
+                plan.extend(rollout);
+                best_plan = Some((cost, plan));
+            }
+            1.0
+        } else {
+            match config.reward {
+                RewardShaping::GoalOnly => 0.0,
+                RewardShaping::Heuristic if dead_end => 0.0,
+                RewardShaping::Heuristic => {
+                    let h = heuristic(&state);
+                    if h.is_finite() {
+                        0.5 / (1.0 + h.max(0.0))
+                    } else {
+                        0.0
+                    }
+                }
+            }
+        };
+
+        // Backpropagation.
+        let mut current = Some(node);
+        while let Some(index) = current {
+            nodes[index].visits += 1;
+            nodes[index].total_reward += reward;
+            current = nodes[index].parent;
+        }
+    }
+
+    println!(
+        "iterations: {}, nodes: {}, plans: {}, best cost: {}",
+        config.max_iterations,
+        nodes.len(),
+        plans,
+        best_plan
+            .as_ref()
+            .map_or("none".to_string(), |(cost, _)| cost.to_string())
+    );
+    best_plan
+        .map(|(_, plan)| plan)
+        .ok_or("No solution found within the iterations")
+}
+
+fn trace<S, A: Copy, C>(nodes: &[MctsNode<S, A, C>], mut node: usize) -> Vec<A> {
+    let mut plan = Vec::new();
+    while let Some(action) = nodes[node].action {
+        plan.push(action);
+        node = nodes[node].parent.unwrap();
+    }
+    plan.reverse();
+    plan
+}
+
+fn choose_action<P, H>(
+    problem: &P,
+    state: &P::State,
+    actions: &[P::Action],
+    policy: RolloutPolicy,
+    heuristic: &H,
+    rng: &mut Rng,
+) -> Option<P::Action>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    match policy {
+        RolloutPolicy::Random => rng.choose(actions).copied(),
+        RolloutPolicy::Greedy => {
+            let values: Vec<f64> = actions
+                .iter()
+                .map(|action| heuristic(&problem.apply_action(state, action)))
+                .collect();
+            let best = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let best_actions: Vec<P::Action> = actions
+                .iter()
+                .zip(&values)
+                .filter(|(_, &h)| h == best)
+                .map(|(&action, _)| action)
+                .collect();
+            rng.choose(&best_actions).or(rng.choose(actions)).copied()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_mcts_returns_cheapest_plan_found() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let heuristic = |s: &_| problem.heuristic(s);
+        for policy in [RolloutPolicy::Random, RolloutPolicy::Greedy] {
+            let config = MctsConfig {
+                max_iterations: 2_000,
+                policy,
+                seed: 1,
+                ..MctsConfig::default()
+            };
+            let plan = mcts_search(&problem, &state, &config, heuristic).unwrap();
+            let cost = check_plan(&problem, &state, &plan);
+
+            // The first iterations of a longer run with the same seed are the same, so its
+            // plan can only be cheaper.
+            let longer = MctsConfig {
+                max_iterations: 4_000,
+                ..config
+            };
+            let better = mcts_search(&problem, &state, &longer, heuristic).unwrap();
+            assert!(check_plan(&problem, &state, &better) <= cost);
+        }
+    }
+}
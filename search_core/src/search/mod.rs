@@ -1,6 +1,28 @@
 pub mod action;
+pub mod alternation;
+pub mod beam;
+pub mod checkpoint;
+pub mod compact_tree;
+pub mod cost;
+pub mod export;
+pub mod external_bfs;
+pub mod hill_climbing;
+pub mod mcts;
 pub mod node;
+pub mod observer;
+pub mod pareto;
+pub mod preferred_search;
+pub mod random_walk;
+pub mod rng;
 pub mod search;
 pub mod search_tree;
+pub mod soft_goals;
 pub mod solve;
 pub mod state;
+pub mod state_codec;
+pub mod stubborn_sets;
+pub mod symmetry;
+#[cfg(test)]
+pub mod test_util;
+pub mod top_k;
+pub mod width;
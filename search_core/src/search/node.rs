@@ -1,16 +1,18 @@
+use crate::search::cost::CostTrait;
+use serde::{Deserialize, Serialize};
 // use std::cell::RefCell;
 // use std::rc::{Rc, Weak};
-use crate::search::{action::Action, state::StateTrait};
 
-pub struct Node {
+#[derive(Serialize, Deserialize)]
+pub struct Node<A, W = i32> {
     pub parent:   Option<usize>,
     pub children: Vec<usize>,
-    pub action:   Option<Action>,
-    pub cost:     i32,
+    pub action:   Option<A>,
+    pub cost:     W, // path cost from the root
 }
 
-impl Node {
+impl<A, W: CostTrait> Node<A, W> {
     pub fn new_root() -> Self {
-        Self { parent: None, children: Vec::new(), action: None, cost: 0 }
+        Self { parent: None, children: Vec::new(), action: None, cost: W::default() }
     }
 }
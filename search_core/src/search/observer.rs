@@ -0,0 +1,375 @@
+use crate::search::action::ActionTrait;
+use crate::search::cost::CostTrait;
+use crate::search::export::{export_search_tree, ExportFormat, ExportOptions, TreeAnnotations};
+use crate::search::search_tree::SearchTree;
+use crate::search::state::StateTrait;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::{Duration, Instant};
+
+/// Callbacks invoked by `observed_search`. All of them do nothing by default, so an
+/// observer only implements the events it cares about.
+pub trait SearchObserver<S: StateTrait, A: ActionTrait, W: CostTrait = i32> {
+    /// A successor was added to the tree, before the duplicate check.
+    fn node_generated(&mut self, _tree: &SearchTree<S, A, W>, _node: usize) {}
+
+    /// A node was taken from the open queue and is about to be expanded.
+    fn node_expanded(&mut self, _tree: &SearchTree<S, A, W>, _node: usize, _h: f64) {}
+
+    /// A generated node was dropped because its state had been seen before.
+    fn duplicate_pruned(&mut self, _tree: &SearchTree<S, A, W>, _node: usize) {}
+
+    /// A node with a lower heuristic value than any before was queued.
+    fn new_best_h(&mut self, _tree: &SearchTree<S, A, W>, _node: usize, _h: f64) {}
+
+    /// The first node with f = g + h above every earlier expansion was expanded.
+    fn new_f_layer(&mut self, _f: f64) {}
+
+    fn solution_found(&mut self, _tree: &SearchTree<S, A, W>, _node: usize) {}
+
+    /// The search ended, with or without a solution.
+    fn search_finished(&mut self, _tree: &SearchTree<S, A, W>) {}
+}
+
+impl<S: StateTrait, A: ActionTrait, W: CostTrait> SearchObserver<S, A, W> for () {}
+
+/// Several observers notified in order.
+pub struct Observers<'a, S, A, W = i32> {
+    pub observers: Vec<Box<dyn SearchObserver<S, A, W> + 'a>>,
+}
+
+impl<'a, S: StateTrait, A: ActionTrait, W: CostTrait> Observers<'a, S, A, W> {
+    /// Builds observers from command-line style specifications: `progress` or
+    /// `progress:SECONDS`, `trace:PATH` and `tree:PATH[:OPTION...]`, where the options
+    /// are those of `ExportOptions::from_specs`. Exported trees label their edges with
+    /// `action_label`.
+    pub fn from_specs<T: AsRef<str>>(
+        specs: &[T],
+        action_label: &'a dyn Fn(&S, &A) -> String,
+    ) -> Self {
+        let observers = specs
+            .iter()
+            .map(|spec| {
+                let spec = spec.as_ref();
+                let (name, argument) = spec.split_once(':').unwrap_or((spec, ""));
+                let observer: Box<dyn SearchObserver<S, A, W> + 'a> = match name {
+                    "progress" => {
+                        let seconds = if argument.is_empty() {
+                            10.0
+                        } else {
+                            argument
+                                .parse()
+                                .unwrap_or_else(|_| panic!("Invalid interval: {}", argument))
+                        };
+                        Box::new(ProgressLogger::new(Duration::from_secs_f64(seconds)))
+                    }
+                    "trace" if !argument.is_empty() => Box::new(TraceRecorder::new(argument)),
+                    "tree" if !argument.is_empty() => {
+                        let mut parts = argument.split(':');
+                        let path = parts.next().unwrap();
+                        let options = ExportOptions::from_specs(parts);
+                        Box::new(TreeExporter::new(path, action_label, options))
+                    }
+                    _ => panic!("Unknown observer: {}", spec),
+                };
+                observer
+            })
+            .collect();
+        Observers { observers }
+    }
+}
+
+impl<S: StateTrait, A: ActionTrait, W: CostTrait> SearchObserver<S, A, W>
+    for Observers<'_, S, A, W>
+{
+    fn node_generated(&mut self, tree: &SearchTree<S, A, W>, node: usize) {
+        for observer in &mut self.observers {
+            observer.node_generated(tree, node);
+        }
+    }
+
+    fn node_expanded(&mut self, tree: &SearchTree<S, A, W>, node: usize, h: f64) {
+        for observer in &mut self.observers {
+            observer.node_expanded(tree, node, h);
+        }
+    }
+
+    fn duplicate_pruned(&mut self, tree: &SearchTree<S, A, W>, node: usize) {
+        for observer in &mut self.observers {
+            observer.duplicate_pruned(tree, node);
+        }
+    }
+
+    fn new_best_h(&mut self, tree: &SearchTree<S, A, W>, node: usize, h: f64) {
+        for observer in &mut self.observers {
+            observer.new_best_h(tree, node, h);
+        }
+    }
+
+    fn new_f_layer(&mut self, f: f64) {
+        for observer in &mut self.observers {
+            observer.new_f_layer(f);
+        }
+    }
+
+    fn solution_found(&mut self, tree: &SearchTree<S, A, W>, node: usize) {
+        for observer in &mut self.observers {
+            observer.solution_found(tree, node);
+        }
+    }
+
+    fn search_finished(&mut self, tree: &SearchTree<S, A, W>) {
+        for observer in &mut self.observers {
+            observer.search_finished(tree);
+        }
+    }
+}
+
+/// Prints the search counters every `interval` and once more at the end.
+pub struct ProgressLogger {
+    interval: Duration,
+    start: Instant,
+    last_report: Instant,
+    generated: usize,
+    expanded: usize,
+    pruned: usize,
+    best_h: f64,
+    f_layer: f64,
+}
+
+impl ProgressLogger {
+    pub fn new(interval: Duration) -> Self {
+        ProgressLogger {
+            interval,
+            start: Instant::now(),
+            last_report: Instant::now(),
+            generated: 0,
+            expanded: 0,
+            pruned: 0,
+            best_h: f64::INFINITY,
+            f_layer: f64::NEG_INFINITY,
+        }
+    }
+
+    fn report(&mut self) {
+        println!(
+            "[{:.1}s] expanded: {}, generated: {}, pruned: {}, best h: {}, f: {}",
+            self.start.elapsed().as_secs_f64(),
+            self.expanded,
+            self.generated,
+            self.pruned,
+            self.best_h,
+            self.f_layer
+        );
+        self.last_report = Instant::now();
+    }
+}
+
+impl<S: StateTrait, A: ActionTrait, W: CostTrait> SearchObserver<S, A, W> for ProgressLogger {
+    fn node_generated(&mut self, _tree: &SearchTree<S, A, W>, _node: usize) {
+        self.generated += 1;
+    }
+
+    fn node_expanded(&mut self, _tree: &SearchTree<S, A, W>, _node: usize, _h: f64) {
+        self.expanded += 1;
+        if self.last_report.elapsed() >= self.interval {
+            self.report();
+        }
+    }
+
+    fn duplicate_pruned(&mut self, _tree: &SearchTree<S, A, W>, _node: usize) {
+        self.pruned += 1;
+    }
+
+    fn new_best_h(&mut self, _tree: &SearchTree<S, A, W>, _node: usize, h: f64) {
+        self.best_h = h;
+    }
+
+    fn new_f_layer(&mut self, f: f64) {
+        self.f_layer = f;
+    }
+
+    fn search_finished(&mut self, _tree: &SearchTree<S, A, W>) {
+        self.report();
+    }
+}
+
+/// One recorded search event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event")]
+pub enum SearchEvent {
+    Generated { node: usize, parent: usize },
+    Expanded { node: usize, g: f64, h: f64 },
+    DuplicatePruned { node: usize },
+    NewBestH { node: usize, h: f64 },
+    NewFLayer { f: f64 },
+    SolutionFound { node: usize },
+}
+
+/// Records every event in order, and writes them as a JSON array to `path`, if one is
+/// given, when the search ends.
+pub struct TraceRecorder {
+    path: Option<String>,
+    pub events: Vec<SearchEvent>,
+}
+
+impl TraceRecorder {
+    pub fn new(path: &str) -> Self {
+        TraceRecorder {
+            path: Some(path.to_string()),
+            events: Vec::new(),
+        }
+    }
+
+    /// A recorder that keeps the events in memory only.
+    pub fn in_memory() -> Self {
+        TraceRecorder {
+            path: None,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl<S: StateTrait, A: ActionTrait, W: CostTrait> SearchObserver<S, A, W> for TraceRecorder {
+    fn node_generated(&mut self, tree: &SearchTree<S, A, W>, node: usize) {
+        let parent = tree.nodes[node]
+            .parent
+            .expect("Generated node without parent");
+        self.events.push(SearchEvent::Generated { node, parent });
+    }
+
+    fn node_expanded(&mut self, tree: &SearchTree<S, A, W>, node: usize, h: f64) {
+        let g = tree.nodes[node].cost.to_f64();
+        self.events.push(SearchEvent::Expanded { node, g, h });
+    }
+
+    fn duplicate_pruned(&mut self, _tree: &SearchTree<S, A, W>, node: usize) {
+        self.events.push(SearchEvent::DuplicatePruned { node });
+    }
+
+    fn new_best_h(&mut self, _tree: &SearchTree<S, A, W>, node: usize, h: f64) {
+        self.events.push(SearchEvent::NewBestH { node, h });
+    }
+
+    fn new_f_layer(&mut self, f: f64) {
+        self.events.push(SearchEvent::NewFLayer { f });
+    }
+
+    fn solution_found(&mut self, _tree: &SearchTree<S, A, W>, node: usize) {
+        self.events.push(SearchEvent::SolutionFound { node });
+    }
+
+    fn search_finished(&mut self, _tree: &SearchTree<S, A, W>) {
+        if let Some(path) = &self.path {
+            let file = File::create(path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+            serde_json::to_writer(BufWriter::new(file), &self.events)
+                .expect("Failed to write search trace");
+        }
+    }
+}
+
+/// Writes the explored tree to `path` when the search ends, as Graphviz DOT if the path
+/// ends in `.dot` and as node-link JSON otherwise (see `export_search_tree`).
+pub struct TreeExporter<'a, S, A> {
+    path: String,
+    action_label: &'a dyn Fn(&S, &A) -> String,
+    options: ExportOptions,
+    annotations: TreeAnnotations,
+}
+
+impl<'a, S, A> TreeExporter<'a, S, A> {
+    pub fn new(
+        path: &str,
+        action_label: &'a dyn Fn(&S, &A) -> String,
+        options: ExportOptions,
+    ) -> Self {
+        TreeExporter {
+            path: path.to_string(),
+            action_label,
+            options,
+            annotations: TreeAnnotations::default(),
+        }
+    }
+}
+
+impl<S: StateTrait, A: ActionTrait, W: CostTrait> SearchObserver<S, A, W>
+    for TreeExporter<'_, S, A>
+{
+    fn node_expanded(&mut self, _tree: &SearchTree<S, A, W>, node: usize, h: f64) {
+        self.annotations.expansions.push((node, h));
+    }
+
+    fn duplicate_pruned(&mut self, _tree: &SearchTree<S, A, W>, node: usize) {
+        self.annotations.pruned.insert(node);
+    }
+
+    fn solution_found(&mut self, _tree: &SearchTree<S, A, W>, node: usize) {
+        self.annotations.goal = Some(node);
+    }
+
+    fn search_finished(&mut self, tree: &SearchTree<S, A, W>) {
+        let graph = export_search_tree(tree, &self.annotations, self.action_label, &self.options);
+        graph
+            .save(&self.path, ExportFormat::from_path(&self.path))
+            .unwrap_or_else(|e| panic!("{}: {}", self.path, e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::problem::Problem;
+    use crate::search::search::{generic_search, observed_search};
+
+    #[test]
+    fn test_trace_recorder_sees_every_event() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let mut tree = SearchTree::new(state.clone());
+        let plan = generic_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |s| problem.heuristic(s),
+        );
+
+        let mut recorder = TraceRecorder::in_memory();
+        let mut observed_tree = SearchTree::new(state);
+        let observed_plan = observed_search(
+            &mut observed_tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |s| problem.heuristic(s),
+            &mut recorder,
+        );
+        assert_eq!(observed_plan, plan);
+
+        // Successors after the goal are in the tree but were never looked at.
+        let count = |f: fn(&SearchEvent) -> bool| recorder.events.iter().filter(|e| f(e)).count();
+        let generated = count(|e| matches!(e, SearchEvent::Generated { .. }));
+        assert!(generated > 0 && generated < observed_tree.nodes.len());
+        assert!(count(|e| matches!(e, SearchEvent::DuplicatePruned { .. })) > 0);
+        let Some(&SearchEvent::SolutionFound { node }) = recorder.events.last() else {
+            panic!("The trace does not end with the solution");
+        };
+        assert_eq!(observed_tree.trace_actions(node), plan.unwrap());
+
+        let mut best_h = f64::INFINITY;
+        for event in &recorder.events {
+            if let SearchEvent::NewBestH { h, .. } = event {
+                assert!(*h < best_h);
+                best_h = *h;
+            }
+        }
+    }
+}
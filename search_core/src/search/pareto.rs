@@ -0,0 +1,341 @@
+use crate::problems::problem::Problem;
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Problems whose actions have a vector of costs, one per objective, all to be minimised.
+pub trait MultiObjectiveProblem: Problem {
+    /// Names of the objectives, in the order of the cost vectors.
+    fn objectives(&self) -> Vec<&'static str>;
+
+    /// Costs of applying `action` in `state`, which leads to `successor`. Every component
+    /// must be non-negative.
+    fn objective_costs(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+        successor: &Self::State,
+    ) -> Vec<f64>;
+
+    /// Lower bounds on the remaining cost of every objective. Zero by default.
+    fn objective_heuristics(&self, _state: &Self::State) -> Vec<f64> {
+        vec![0.0; self.objectives().len()]
+    }
+}
+
+/// True if `a` is at most `b` on every objective, `a` equal to `b` included.
+pub fn weakly_dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x <= y)
+}
+
+/// True if `a` is at most `b` on every objective and lower on one.
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    weakly_dominates(a, b) && a != b
+}
+
+#[derive(Debug, Clone)]
+pub struct ParetoConfig {
+    /// Upper bound on the total cost of each objective; missing entries are unbounded.
+    pub bounds: Vec<f64>,
+    /// Labels expanded before the search gives up with the plans found so far.
+    pub max_expansions: usize,
+}
+
+impl Default for ParetoConfig {
+    fn default() -> Self {
+        ParetoConfig {
+            bounds: Vec::new(),
+            max_expansions: 1_000_000,
+        }
+    }
+}
+
+/// A plan of the Pareto front with its total cost on every objective.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParetoPlan<A> {
+    pub actions: Vec<A>,
+    pub costs: Vec<f64>,
+}
+
+/// The plan of `plans` with the lowest weighted sum of costs, the first one on ties.
+pub fn weighted_choice<'a, A>(
+    plans: &'a [ParetoPlan<A>],
+    weights: &[f64],
+) -> Option<&'a ParetoPlan<A>> {
+    let weighted =
+        |plan: &ParetoPlan<A>| -> f64 { plan.costs.iter().zip(weights).map(|(c, w)| c * w).sum() };
+    plans
+        .iter()
+        .min_by(|a, b| weighted(a).total_cmp(&weighted(b)))
+}
+
+/// A path to a state with its cost vector. Labels dominated by a later one are closed
+/// without being expanded.
+struct Label<A> {
+    state: usize,
+    g: Vec<f64>,
+    parent: Option<usize>,
+    action: Option<A>,
+    open: bool,
+}
+
+fn trace<A: Copy>(labels: &[Label<A>], mut label: usize) -> Vec<A> {
+    let mut actions = Vec::new();
+    while let (Some(parent), Some(action)) = (labels[label].parent, labels[label].action) {
+        actions.push(action);
+        label = parent;
+    }
+    actions.reverse();
+    actions
+}
+
+/// NAMOA* (Mandow and Pérez de la Cruz): best-first search over labels, each state keeping
+/// its non-dominated cost vectors, with open labels taken in lexicographic order of
+/// f = g + h. Labels whose f is dominated by a plan already found, or that exceed
+/// `config.bounds`, are pruned. Goals are tested on expansion, so with admissible
+/// `objective_heuristics` the result is the whole Pareto front within the bounds, ordered
+/// by the first objective.
+pub fn pareto_search<P: MultiObjectiveProblem>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &ParetoConfig,
+) -> Result<Vec<ParetoPlan<P::Action>>, &'static str> {
+    let objectives = problem.objectives().len();
+    let bound = |i: usize| config.bounds.get(i).copied().unwrap_or(f64::INFINITY);
+    let within_bounds = |f: &[f64]| f.iter().enumerate().all(|(i, &x)| x <= bound(i));
+    let priority = |f: &[f64]| f.iter().map(|&x| OrderedFloat(x)).collect::<Vec<_>>();
+
+    let mut states = vec![initial_state.clone()];
+    let mut ids: HashMap<P::State, usize> = HashMap::from([(initial_state.clone(), 0)]);
+    // Non-dominated labels of every state, open and closed
+    let mut open_labels: Vec<Vec<usize>> = vec![vec![0]];
+    let mut closed_labels: Vec<Vec<usize>> = vec![Vec::new()];
+    let mut labels = vec![Label {
+        state: 0,
+        g: vec![0.0; objectives],
+        parent: None,
+        action: None,
+        open: true,
+    }];
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((
+        priority(&problem.objective_heuristics(initial_state)),
+        0,
+    )));
+    let mut plans: Vec<ParetoPlan<P::Action>> = Vec::new();
+    let mut expanded = 0;
+
+    while let Some(Reverse((f, label))) = heap.pop() {
+        if !labels[label].open {
+            continue;
+        }
+        labels[label].open = false;
+        let id = labels[label].state;
+        open_labels[id].retain(|&l| l != label);
+        let f: Vec<f64> = f.into_iter().map(OrderedFloat::into_inner).collect();
+        if plans.iter().any(|plan| weakly_dominates(&plan.costs, &f)) {
+            continue;
+        }
+        closed_labels[id].push(label);
+
+        let state = states[id].clone();
+        if problem.is_goal_state(&state) {
+            plans.push(ParetoPlan {
+                actions: trace(&labels, label),
+                costs: labels[label].g.clone(),
+            });
+            continue;
+        }
+        expanded += 1;
+        if expanded > config.max_expansions {
+            break;
+        }
+
+        for action in problem.get_possible_actions(&state) {
+            let successor = problem.apply_action(&state, &action);
+            let costs = problem.objective_costs(&state, &action, &successor);
+            assert!(
+                costs.len() == objectives && costs.iter().all(|&c| c >= 0.0),
+                "Invalid objective costs {:?} for {:?}",
+                costs,
+                action
+            );
+            let g: Vec<f64> = labels[label]
+                .g
+                .iter()
+                .zip(&costs)
+                .map(|(a, b)| a + b)
+                .collect();
+            let f: Vec<f64> = g
+                .iter()
+                .zip(problem.objective_heuristics(&successor))
+                .map(|(a, b)| a + b)
+                .collect();
+            if !within_bounds(&f) || plans.iter().any(|plan| weakly_dominates(&plan.costs, &f)) {
+                continue;
+            }
+
+            let next = match ids.get(&successor) {
+                Some(&next) => next,
+                None => {
+                    ids.insert(successor.clone(), states.len());
+                    states.push(successor);
+                    open_labels.push(Vec::new());
+                    closed_labels.push(Vec::new());
+                    states.len() - 1
+                }
+            };
+            let known = open_labels[next].iter().chain(&closed_labels[next]);
+            if known
+                .into_iter()
+                .any(|&l| weakly_dominates(&labels[l].g, &g))
+            {
+                continue;
+            }
+            for &l in &open_labels[next] {
+                if dominates(&g, &labels[l].g) {
+                    labels[l].open = false;
+                }
+            }
+            open_labels[next].retain(|&l| labels[l].open);
+            closed_labels[next].retain(|&l| !dominates(&g, &labels[l].g));
+
+            labels.push(Label {
+                state: next,
+                g,
+                parent: Some(label),
+                action: Some(action),
+                open: true,
+            });
+            open_labels[next].push(labels.len() - 1);
+            heap.push(Reverse((priority(&f), labels.len() - 1)));
+        }
+    }
+
+    println!(
+        "labels: {}, expanded: {}, states: {}, pareto plans: {}",
+        labels.len(),
+        expanded,
+        states.len(),
+        plans.len()
+    );
+    if plans.is_empty() {
+        Err("No solution found")
+    } else {
+        Ok(plans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::settlersnumeric_problem::settlersnumeric_problem::{
+        SettlersNumericAction, SettlersNumericProblem,
+    };
+    use crate::problems::zenotravel_time_problem::zenotravel_time_problem::ZenoTravelTimeProblem;
+    use crate::search::cost::{CostTrait, Rational};
+    use crate::search::search::generic_search;
+    use crate::search::search_tree::SearchTree;
+
+    #[test]
+    fn test_pareto_front_trades_time_against_fuel() {
+        let (mut state, problem) = ZenoTravelTimeProblem::load_state_from_json(
+            "src/inputs/zenotravel_time_problem/problems_json/pfile1.json",
+        );
+        // Enough fuel to fly fast, which pfile1 alone never allows
+        state.airplanes[0].capacity = 40000;
+        state.airplanes[0].fuel = 40000;
+        let front = pareto_search(&problem, &state, &ParetoConfig::default()).unwrap();
+        assert!(front.len() > 1);
+        for (i, plan) in front.iter().enumerate() {
+            let mut current = state.clone();
+            let mut costs = vec![0.0; 2];
+            for action in &plan.actions {
+                let next = problem.apply_action(&current, action);
+                let step = problem.objective_costs(&current, action, &next);
+                costs.iter_mut().zip(step).for_each(|(c, s)| *c += s);
+                current = next;
+            }
+            assert!(problem.is_goal_state(&current));
+            assert_eq!(costs, plan.costs);
+            assert!(front
+                .iter()
+                .all(|other| !dominates(&other.costs, &plan.costs)));
+            assert!(i == 0 || front[i - 1].costs[0] <= plan.costs[0]);
+        }
+
+        // The fastest plan of the front is as fast as the plan of A* on time.
+        let mut tree = SearchTree::new(state.clone());
+        let plan = generic_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |_| 0.0,
+        )
+        .unwrap();
+        let mut current = state.clone();
+        let mut time = Rational::default();
+        for action in &plan {
+            time += problem.action_cost(&current, action);
+            current = problem.apply_action(&current, action);
+        }
+        assert!((front[0].costs[0] - time.to_f64()).abs() < 1e-9);
+
+        // Weights pick the cheapest plan on their sum, bounds cut the front.
+        let frugal = weighted_choice(&front, &[0.0, 1.0]).unwrap();
+        assert_eq!(frugal, front.last().unwrap());
+        let config = ParetoConfig {
+            bounds: vec![f64::INFINITY, frugal.costs[1]],
+            ..ParetoConfig::default()
+        };
+        assert_eq!(
+            pareto_search(&problem, &state, &config).unwrap(),
+            vec![frugal.clone()]
+        );
+    }
+
+    #[test]
+    fn test_settlers_objectives_are_the_domain_metrics() {
+        use SettlersNumericAction::*;
+        // (labour, resource-use, pollution) increments of the PDDL operators
+        let increments = [
+            (Load { vehicle: 0, resource: 0 }, [1, 0, 0]),
+            (Unload { vehicle: 0, resource: 0 }, [1, 0, 0]),
+            (MoveTrain { vehicle: 0, to: 0 }, [0, 0, 1]),
+            (MoveShip { vehicle: 0, to: 0 }, [0, 0, 2]),
+            (MoveEmptyCart { from: 0, to: 0 }, [2, 0, 0]),
+            (MoveLadenCart { from: 0, to: 0, resource: 0 }, [2, 0, 0]),
+            (BuildRail(0), [2, 0, 0]),
+            (BuildTrain { vehicle: 0, place: 0 }, [2, 0, 0]),
+            (BuildShip { vehicle: 0, place: 0 }, [3, 0, 0]),
+            (BuildCabin(0), [1, 0, 0]),
+            (BuildDocks(0), [2, 0, 0]),
+            (BuildQuarry(0), [2, 0, 0]),
+            (BuildSawmill(0), [2, 0, 0]),
+            (BuildHouse(0), [0, 0, 0]),
+            (BuildCart(0), [1, 0, 0]),
+            (BuildIronworks(0), [3, 0, 0]),
+            (BuildCoalStack(0), [2, 0, 0]),
+            (BuildMine(0), [3, 0, 0]),
+            (BuildWharf(0), [2, 0, 0]),
+            (FellTimber(0), [1, 0, 0]),
+            (BurnCoal(0), [0, 0, 1]),
+            (SawWood(0), [0, 0, 0]),
+            (MakeIron(0), [0, 0, 2]),
+            (MineOre(0), [1, 2, 0]),
+            (BreakStone(0), [1, 1, 0]),
+        ];
+        let (state, problem) = SettlersNumericProblem::load_state_from_json(
+            "src/inputs/settlersnumeric_problem/pfile0.json",
+        );
+        assert_eq!(problem.objectives(), ["labour", "resource_use", "pollution"]);
+        for (action, expected) in increments {
+            let costs = problem.objective_costs(&state, &action, &state);
+            assert_eq!(costs, expected.map(f64::from), "{:?}", action);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::algorithms::gbfs::GBFSQueue;
+use crate::algorithms::priority_queue::PriorityQueue;
+use crate::problems::problem::Problem;
+use crate::search::checkpoint::SearchProgress;
+use crate::search::cost::CostTrait;
+use crate::search::search::{keyed_search, state_hash};
+use crate::search::search_tree::SearchTree;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+/// Expansions granted to the preferred queue each time the best heuristic value improves.
+const PREFERRED_BOOST: usize = 1000;
+
+/// Greedy best-first queue made of two queues: one holds every node, the other only the
+/// nodes marked in `preferred`. Pops alternate between the queues, and each improvement of
+/// the best heuristic value gives the preferred queue `PREFERRED_BOOST` extra turns (as in
+/// Fast Downward). A node in both queues is only popped once.
+pub struct DualQueue<'a> {
+    all: GBFSQueue,
+    preferred: GBFSQueue,
+    is_preferred: &'a RefCell<Vec<bool>>, // by node index
+    popped: HashSet<usize>,
+    best_h: f64,
+    preferred_turns: usize,
+    use_preferred: bool,
+}
+
+impl<'a> DualQueue<'a> {
+    pub fn new(is_preferred: &'a RefCell<Vec<bool>>) -> Self {
+        DualQueue {
+            all: GBFSQueue::new(),
+            preferred: GBFSQueue::new(),
+            is_preferred,
+            popped: HashSet::new(),
+            best_h: f64::INFINITY,
+            preferred_turns: 0,
+            use_preferred: false,
+        }
+    }
+}
+
+impl PriorityQueue for DualQueue<'_> {
+    fn insert<C: CostTrait>(&mut self, node_index: usize, cost: C, heuristic_value: f64) {
+        if heuristic_value < self.best_h {
+            self.best_h = heuristic_value;
+            self.preferred_turns += PREFERRED_BOOST;
+        }
+        self.all.insert(node_index, cost, heuristic_value);
+        if self.is_preferred.borrow().get(node_index).copied().unwrap_or(false) {
+            self.preferred.insert(node_index, cost, heuristic_value);
+        }
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        loop {
+            // Alternate between the queues, keeping to the preferred one while it is
+            // boosted, and take from the other one when the chosen queue is empty.
+            self.use_preferred = self.preferred_turns > 0 || !self.use_preferred;
+            self.preferred_turns = self.preferred_turns.saturating_sub(1);
+            let popped = if self.use_preferred {
+                self.preferred.pop().or_else(|| self.all.pop())
+            } else {
+                self.all.pop().or_else(|| self.preferred.pop())
+            }?;
+            if self.popped.insert(popped) {
+                return Some(popped);
+            }
+        }
+    }
+}
+
+/// Greedy best-first search with two open queues: one holds every generated node, the
+/// other only the nodes reached through a preferred action (see `DualQueue`). Complete,
+/// like plain GBFS.
+pub fn preferred_gbfs<P, H, R>(
+    problem: &P,
+    initial_state: &P::State,
+    heuristic: H,
+    preferred: R,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+    R: Fn(&P::State, &[P::Action]) -> Vec<P::Action>,
+{
+    if problem.is_goal_state(initial_state) {
+        return Ok(Vec::new());
+    }
+
+    // The tree applies the actions of a state right after listing them, and numbers the
+    // successors in that order, so the flag of each new node is pushed when it is created.
+    let is_preferred = RefCell::new(vec![false]);
+    let preferred_actions = RefCell::new(Vec::new());
+    let mut tree = SearchTree::new(initial_state.clone());
+    keyed_search(
+        &mut tree,
+        |state| {
+            let actions = problem.get_possible_actions(state);
+            *preferred_actions.borrow_mut() = preferred(state, &actions);
+            actions
+        },
+        |state, action| {
+            let flag = preferred_actions.borrow().contains(action);
+            is_preferred.borrow_mut().push(flag);
+            problem.apply_action(state, action)
+        },
+        |state, action| problem.action_cost(state, action),
+        |state| problem.is_goal_state(state),
+        &mut SearchProgress::new(DualQueue::new(&is_preferred)),
+        heuristic,
+        &mut (),
+        state_hash,
+        &mut (),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::red_car_problem::red_car_problem::RedCarProblem;
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_preferred_gbfs_finds_plans_with_any_preferences() {
+        let (state, problem) = RedCarProblem::load_state_from_json(
+            "src/inputs/red_car_problem/red_car/problems_json/pfile1.json",
+        );
+        // Preferring nothing, everything or an arbitrary half must not lose completeness.
+        let preferences: [fn(&_, &[_]) -> Vec<_>; 3] = [
+            |_, _| Vec::new(),
+            |_, actions| actions.to_vec(),
+            |_, actions| actions.iter().step_by(2).copied().collect(),
+        ];
+        for preferred in preferences {
+            let plan =
+                preferred_gbfs(&problem, &state, |s| problem.heuristic(s), preferred).unwrap();
+            check_plan(&problem, &state, &plan);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::problems::problem::Problem;
+use crate::search::rng::Rng;
+
+#[derive(Debug, Clone)]
+pub struct RandomWalkConfig {
+    pub walks_per_step: usize,    // Walks in each burst from the current state
+    pub walk_length: usize,       // Length of the walks after a jump that made progress
+    pub length_growth: f64,       // Factor applied to the walk length at every stalled step
+    pub max_stalled_steps: usize, // Steps without progress before restarting from the start
+    pub max_walks: usize,         // Total budget of walks
+    pub seed: u64,
+}
+
+impl Default for RandomWalkConfig {
+    fn default() -> Self {
+        RandomWalkConfig {
+            walks_per_step: 200,
+            walk_length: 10,
+            length_growth: 1.5,
+            max_stalled_steps: 7,
+            max_walks: 1_000_000,
+            seed: 0,
+        }
+    }
+}
+
+/// Monte Carlo random-walk planning (Nakhost and Müller's Arvand). From the current state
+/// a burst of random walks is run, only their endpoints are evaluated, and the search
+/// jumps to the best endpoint. Walks grow longer while the heuristic does not improve,
+/// and after `max_stalled_steps` such steps the search restarts from the initial state.
+/// A goal met anywhere along a walk ends the search.
+///
+/// Suited to plateaus: with a constant heuristic the walks still explore, where GBFS
+/// degenerates into a blind search in generation order.
+pub fn random_walk_search<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &RandomWalkConfig,
+    heuristic: H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    if problem.is_goal_state(initial_state) {
+        return Ok(Vec::new());
+    }
+    let mut rng = Rng::new(config.seed);
+    let initial_h = heuristic(initial_state);
+    let mut current = initial_state.clone();
+    let mut plan = Vec::new();
+    let mut best_h = initial_h;
+    let mut length = config.walk_length as f64;
+    let (mut walks, mut steps, mut stalled, mut restarts) = (0, 0, 0, 0);
+
+    while walks < config.max_walks {
+        // The best endpoint of the burst: (h, state, actions from the current state).
+        let mut best: Option<(f64, P::State, Vec<P::Action>)> = None;
+        for _ in 0..config.walks_per_step {
+            walks += 1;
+            let mut state = current.clone();
+            let mut walk = Vec::new();
+            for _ in 0..length as usize {
+                let actions = problem.get_possible_actions(&state);
+                let Some(&action) = rng.choose(&actions) else {
+                    break;
+                };
+                state = problem.apply_action(&state, &action);
+                walk.push(action);
+                if problem.is_goal_state(&state) {
+                    println!("walks: {walks}, steps: {steps}, restarts: {restarts}");
+                    plan.extend(walk);
+                    return Ok(plan);
+                }
+            }
+            let h = heuristic(&state);
+            if h.is_finite() && best.as_ref().is_none_or(|(best_h, _, _)| h < *best_h) {
+                best = Some((h, state, walk));
+            }
+        }
+        steps += 1;
+
+        match best {
+            Some((h, state, walk)) => {
+                if h < best_h {
+                    best_h = h;
+                    stalled = 0;
+                    length = config.walk_length as f64;
+                } else {
+                    stalled += 1;
+                    length *= config.length_growth;
+                }
+                current = state;
+                plan.extend(walk);
+            }
+            None => stalled = config.max_stalled_steps, // Every walk ended in a dead end
+        }
+        if stalled >= config.max_stalled_steps {
+            restarts += 1;
+            stalled = 0;
+            current = initial_state.clone();
+            plan.clear();
+            best_h = initial_h;
+            length = config.walk_length as f64;
+        }
+    }
+    println!("walks: {walks}, steps: {steps}, restarts: {restarts}");
+    Err("No solution found within the walk budget")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::hydropower_problem::hydropower_problem::HydropowerProblem;
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_random_walks_are_reproducible() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        let config = RandomWalkConfig {
+            seed: 5,
+            ..RandomWalkConfig::default()
+        };
+        let heuristic = |s: &_| problem.heuristic(s);
+        let plan = random_walk_search(&problem, &state, &config, heuristic).unwrap();
+        check_plan(&problem, &state, &plan);
+        assert_eq!(
+            random_walk_search(&problem, &state, &config, heuristic).unwrap(),
+            plan
+        );
+
+        // No heuristic guidance at all.
+        let (state, problem) =
+            HydropowerProblem::load_state_from_json("src/inputs/hydropower_problem/pfile1.json");
+        let plan = random_walk_search(&problem, &state, &config, |_| 0.0).unwrap();
+        check_plan(&problem, &state, &plan);
+    }
+}
@@ -1,57 +1,214 @@
 use crate::algorithms::priority_queue::PriorityQueue;
-use crate::search::action::Action;
+use crate::search::action::ActionTrait;
+use crate::search::checkpoint::{SearchHook, SearchProgress};
+use crate::search::compact_tree::CompactSearchTree;
+use crate::search::cost::CostTrait;
+use crate::search::observer::SearchObserver;
 use crate::search::search_tree::SearchTree;
+use crate::search::state_codec::StateCodec;
 use crate::search::state::StateTrait;
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use std::collections::hash_map::DefaultHasher;
 
 
+/// Key of a state in the closed lists of the searches. `DefaultHasher::new` uses fixed
+/// keys, so the key of a state is the same in every run of the same build.
+pub fn state_hash<S: Hash + ?Sized>(state: &S) -> u64 {
+    let mut h = DefaultHasher::new();
+    state.hash(&mut h);
+    h.finish()
+}
+
 // Generic search function that operates on a SearchTree and uses a priority queue for the search strategy
 
 
-pub fn generic_search<F, G, H, Q, I, S>(
-    tree: &mut SearchTree<S>,
+pub fn generic_search<F, G, C, H, Q, I, S, A, W>(
+    tree: &mut SearchTree<S, A, W>,
     get_possible_actions: F,
     apply_action: G,
+    action_cost: C,
     is_goal: H,
-    mut queue: Q,
+    queue: Q,
     heuristic: I,
-) -> Result<Vec<Action>, &'static str>
+) -> Result<Vec<A>, &'static str>
 where
-    F: Fn(&S) -> Vec<Action>,
-    G: Fn(&S, &Action) -> S,
+    F: Fn(&S) -> Vec<A>,
+    G: Fn(&S, &A) -> S,
+    C: Fn(&S, &A) -> W,
     H: Fn(&S) -> bool,
     Q: PriorityQueue,
     I: Fn(&S) -> f64,
     S: StateTrait + Hash,
+    A: ActionTrait,
+    W: CostTrait,
 {
-    use std::collections::{HashSet, hash_map::DefaultHasher};
-    use std::hash::{Hash, Hasher};
+    observed_search(tree, get_possible_actions, apply_action, action_cost, is_goal, queue,
+                    heuristic, &mut ())
+}
 
-    queue.insert(0, 0, f64::MAX);
-    let mut closed: HashSet<u64> = HashSet::new();
-    let (mut nodes, mut uniq) = (0, 0);
+/// `generic_search` reporting its progress to an observer.
+#[allow(clippy::too_many_arguments)]
+pub fn observed_search<F, G, C, H, Q, I, S, A, W, O>(
+    tree: &mut SearchTree<S, A, W>,
+    get_possible_actions: F,
+    apply_action: G,
+    action_cost: C,
+    is_goal: H,
+    queue: Q,
+    heuristic: I,
+    observer: &mut O,
+) -> Result<Vec<A>, &'static str>
+where
+    F: Fn(&S) -> Vec<A>,
+    G: Fn(&S, &A) -> S,
+    C: Fn(&S, &A) -> W,
+    H: Fn(&S) -> bool,
+    Q: PriorityQueue,
+    I: Fn(&S) -> f64,
+    S: StateTrait + Hash,
+    A: ActionTrait,
+    W: CostTrait,
+    O: SearchObserver<S, A, W> + ?Sized,
+{
+    keyed_search(tree, get_possible_actions, apply_action, action_cost, is_goal,
+                 &mut SearchProgress::new(queue), heuristic, observer, state_hash::<S>, &mut ())
+}
 
-    while let Some(cur_idx) = queue.pop() {
-        for &succ_idx in &tree.expand_node(cur_idx, &get_possible_actions, &apply_action) {
-            nodes += 1;
+/// `observed_search` detecting duplicates on `state_key` rather than on the hash of the
+/// state, e.g. on the hash of its canonical form under symmetries. The tree keeps the
+/// states actually reached, so the plan refers to the concrete objects.
+///
+/// The search continues from `progress`, which is either fresh from `SearchProgress::new`
+/// or loaded with the tree from a checkpoint, and calls `hook` after every expansion, e.g.
+/// a `Checkpointer`. A resumed search behaves exactly like one that was never interrupted.
+#[allow(clippy::too_many_arguments)]
+pub fn keyed_search<F, G, C, H, Q, I, S, A, W, O, K, R>(
+    tree: &mut SearchTree<S, A, W>,
+    get_possible_actions: F,
+    apply_action: G,
+    action_cost: C,
+    is_goal: H,
+    progress: &mut SearchProgress<Q>,
+    heuristic: I,
+    observer: &mut O,
+    state_key: K,
+    hook: &mut R,
+) -> Result<Vec<A>, &'static str>
+where
+    F: Fn(&S) -> Vec<A>,
+    G: Fn(&S, &A) -> S,
+    C: Fn(&S, &A) -> W,
+    H: Fn(&S) -> bool,
+    Q: PriorityQueue,
+    I: Fn(&S) -> f64,
+    S: StateTrait + Hash,
+    A: ActionTrait,
+    W: CostTrait,
+    O: SearchObserver<S, A, W> + ?Sized,
+    K: Fn(&S) -> u64,
+    R: SearchHook<S, A, W, Q> + ?Sized,
+{
+    if progress.h_values.is_empty() {
+        progress.h_values.push(heuristic(tree.get_state(0).unwrap()));
+    }
+
+    while let Some(cur_idx) = progress.queue.pop() {
+        let h = progress.h_values[cur_idx];
+        observer.node_expanded(tree, cur_idx, h);
+        let f = tree.get_node(cur_idx).unwrap().cost.to_f64() + h;
+        if f > progress.f_layer {
+            progress.f_layer = f;
+            observer.new_f_layer(f);
+        }
+
+        for &succ_idx in &tree.expand_node(cur_idx, &get_possible_actions, &apply_action, &action_cost) {
+            progress.nodes += 1;
+            progress.h_values.push(f64::NAN);
+            observer.node_generated(tree, succ_idx);
             let state = tree.get_state(succ_idx).unwrap();
 
             // hash-based closed list
-            let mut h = DefaultHasher::new();
-            state.hash(&mut h);
-            if !closed.insert(h.finish()) { continue; }
+            if !progress.closed.insert(state_key(state)) {
+                observer.duplicate_pruned(tree, succ_idx);
+                continue;
+            }
 
-            uniq += 1;
+            progress.uniq += 1;
             if is_goal(state) {
-                println!("nodes: {nodes}, unique: {uniq}");
+                println!("nodes: {}, unique: {}", progress.nodes, progress.uniq);
+                observer.solution_found(tree, succ_idx);
+                observer.search_finished(tree);
                 return Ok(tree.trace_actions(succ_idx));
             }
 
-            queue.insert(succ_idx,
-                         tree.get_node(succ_idx).unwrap().cost,
-                         heuristic(state));
+            let h = heuristic(state);
+            progress.h_values[succ_idx] = h;
+            if h < progress.best_h {
+                progress.best_h = h;
+                observer.new_best_h(tree, succ_idx, h);
+            }
+            progress.queue.insert(succ_idx,
+                                  tree.get_node(succ_idx).unwrap().cost,
+                                  h);
+        }
+        if !hook.after_expansion(tree, progress) {
+            observer.search_finished(tree);
+            return Err("Search interrupted");
+        }
+    }
+    observer.search_finished(tree);
+    Err("No solution found")
+}
+
+/// Same search as `generic_search` over a `CompactSearchTree`: successors are encoded
+/// and checked against the closed list before they are stored, so duplicates never
+/// take up space in the tree.
+pub fn compact_search<F, G, C, H, Q, I, S, A, W, K>(
+    tree: &mut CompactSearchTree<S, A, K, W>,
+    get_possible_actions: F,
+    apply_action: G,
+    action_cost: C,
+    is_goal: H,
+    mut queue: Q,
+    heuristic: I,
+) -> Result<Vec<A>, &'static str>
+where
+    F: Fn(&S) -> Vec<A>,
+    G: Fn(&S, &A) -> S,
+    C: Fn(&S, &A) -> W,
+    H: Fn(&S) -> bool,
+    Q: PriorityQueue,
+    I: Fn(&S) -> f64,
+    A: ActionTrait,
+    W: CostTrait,
+    K: StateCodec<S>,
+{
+    // the root is queued but not closed, exactly as in `keyed_search`
+    queue.insert(0, W::default(), f64::MAX);
+    let mut closed: HashSet<u64> = HashSet::new();
+    let (mut nodes, mut uniq) = (0, 0);
+    let mut bytes = Vec::new();
+
+    while let Some(cur_idx) = queue.pop() {
+        let state = tree.get_state(cur_idx);
+        let cost = tree.nodes[cur_idx].cost;
+        for (operator, action) in get_possible_actions(&state).iter().enumerate() {
+            nodes += 1;
+            let successor = apply_action(&state, action);
+            bytes.clear();
+            tree.codec().encode(&successor, &mut bytes);
+            if !closed.insert(state_hash(bytes.as_slice())) { continue; }
+
+            uniq += 1;
+            let succ_cost = cost + action_cost(&state, action);
+            let succ_idx = tree.add_node(cur_idx, operator, succ_cost, &bytes);
+            if is_goal(&successor) {
+                println!("nodes: {nodes}, unique: {uniq}, bytes: {}", tree.memory_usage());
+                return Ok(tree.trace_actions(succ_idx, &get_possible_actions));
+            }
+
+            queue.insert(succ_idx, succ_cost, heuristic(&successor));
         }
     }
     Err("No solution found")
@@ -1,28 +1,34 @@
-use crate::search::{action::Action, node::Node, state::StateTrait};
-pub struct SearchTree<S: StateTrait> {
-    pub nodes:  Vec<Node>, // topology only
+use crate::search::{action::ActionTrait, cost::CostTrait, node::Node, state::StateTrait};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SearchTree<S: StateTrait, A: ActionTrait, W: CostTrait = i32> {
+    pub nodes:  Vec<Node<A, W>>, // topology only
     pub states: Vec<S>,    // state payloads, kept at the same indices
 }
 
-impl<S: StateTrait> SearchTree<S> {
+impl<S: StateTrait, A: ActionTrait, W: CostTrait> SearchTree<S, A, W> {
     pub fn new(initial_state: S) -> Self {
         Self { nodes: vec![Node::new_root()], states: vec![initial_state] }
     }
 
-    pub fn get_node(&self, idx: usize) -> Option<&Node>        { self.nodes.get(idx) }
+    pub fn get_node(&self, idx: usize) -> Option<&Node<A, W>>  { self.nodes.get(idx) }
     pub fn get_state(&self, idx: usize) -> Option<&S>          { self.states.get(idx) }
 
     // Add a new node to the tree given a parent index and an action
-    pub fn add_node<F>(&mut self,
-                       parent_idx: usize,
-                       action: Action,
-                       apply_action: F) -> usize
+    pub fn add_node<F, C>(&mut self,
+                          parent_idx: usize,
+                          action: A,
+                          apply_action: F,
+                          action_cost: C) -> usize
     where
-        F: Fn(&S, &Action) -> S,
+        F: Fn(&S, &A) -> S,
+        C: Fn(&S, &A) -> W,
     {
         let parent_state = &self.states[parent_idx];
         let new_state    = apply_action(parent_state, &action);
-        let new_cost     = self.nodes[parent_idx].cost + action.cost;
+        let new_cost     = self.nodes[parent_idx].cost + action_cost(parent_state, &action);
 
         let new_idx = self.nodes.len();
         self.nodes.push(Node { parent: Some(parent_idx),
@@ -34,13 +40,13 @@ impl<S: StateTrait> SearchTree<S> {
         new_idx
     }
 
-    pub fn trace_actions(&self, node_index: usize) -> Vec<Action> {
+    pub fn trace_actions(&self, node_index: usize) -> Vec<A> {
         let mut actions = Vec::new();
         let mut current_index = Some(node_index);
         while let Some(index) = current_index {
             if let Some(node) = self.get_node(index) {
                 if let Some(action) = &node.action {
-                    actions.push(action.clone());
+                    actions.push(*action);
                 }
                 current_index = node.parent;
             } else {
@@ -51,18 +57,20 @@ impl<S: StateTrait> SearchTree<S> {
         actions
     }
 
-    pub fn expand_node<F, G>(&mut self,
-                             idx: usize,
-                             get_actions: F,
-                             apply_action: G) -> Vec<usize>
+    pub fn expand_node<F, G, C>(&mut self,
+                                idx: usize,
+                                get_actions: F,
+                                apply_action: G,
+                                action_cost: C) -> Vec<usize>
     where
-        F: Fn(&S) -> Vec<Action>,
-        G: Fn(&S, &Action) -> S,
+        F: Fn(&S) -> Vec<A>,
+        G: Fn(&S, &A) -> S,
+        C: Fn(&S, &A) -> W,
     {
         let mut succ = Vec::new();
         if let Some(state) = self.get_state(idx) {
             for act in get_actions(state) {
-                succ.push(self.add_node(idx, act, &apply_action));
+                succ.push(self.add_node(idx, act, &apply_action, &action_cost));
             }
         }
         succ
@@ -76,7 +84,7 @@ impl<S: StateTrait> SearchTree<S> {
                 "",
                 node_index,
                 node.cost,
-                node.action.as_ref().map(|a| &a.name),
+                node.action,
                 indent = indent
             );
 
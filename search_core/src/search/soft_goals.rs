@@ -0,0 +1,246 @@
+use crate::problems::problem::Problem;
+use crate::search::cost::CostTrait;
+use crate::search::search::state_hash;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+/// A goal condition of a domain's goal list. Soft goals have a utility and may be given up
+/// when achieving them costs more than they are worth; hard goals have none and must hold at
+/// the end of every plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalEntry {
+    pub name: String,
+    pub utility: Option<f64>,
+}
+
+impl GoalEntry {
+    pub fn new(name: impl Into<String>, utility: Option<f64>) -> Self {
+        GoalEntry {
+            name: name.into(),
+            utility,
+        }
+    }
+}
+
+/// Problems whose goal is a list of conditions, some of which can be left unsatisfied.
+pub trait SoftGoalProblem: Problem {
+    /// The goal conditions, always in the same order.
+    fn goal_list(&self) -> Vec<GoalEntry>;
+
+    /// Whether `state` satisfies each condition of `goal_list`.
+    fn goals_achieved(&self, state: &Self::State) -> Vec<bool>;
+
+    /// Conditions outside the goal list that every plan must meet, e.g. vehicles back at
+    /// their depot. None by default.
+    fn final_conditions_met(&self, _state: &Self::State) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NetBenefitConfig {
+    /// States expanded before the search returns the best plan found so far.
+    pub max_expansions: usize,
+}
+
+impl Default for NetBenefitConfig {
+    fn default() -> Self {
+        NetBenefitConfig {
+            max_expansions: 1_000_000,
+        }
+    }
+}
+
+/// A plan with the goals it achieves, its utility and its cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetBenefitPlan<A, W> {
+    pub actions: Vec<A>,
+    pub achieved: Vec<String>,
+    pub utility: f64,
+    pub cost: W,
+}
+
+impl<A, W: CostTrait> NetBenefitPlan<A, W> {
+    pub fn net_benefit(&self) -> f64 {
+        self.utility - self.cost.to_f64()
+    }
+}
+
+/// Utility of the goals achieved in `state`, or None if a hard goal is missed.
+fn utility_of<P: SoftGoalProblem>(
+    problem: &P,
+    goals: &[GoalEntry],
+    state: &P::State,
+) -> Option<f64> {
+    if !problem.final_conditions_met(state) {
+        return None;
+    }
+    let mut utility = 0.0;
+    for (goal, achieved) in goals.iter().zip(problem.goals_achieved(state)) {
+        match (goal.utility, achieved) {
+            (Some(value), true) => utility += value,
+            (None, false) => return None,
+            _ => {}
+        }
+    }
+    Some(utility)
+}
+
+/// Net-benefit planning: the plan maximising the utility of the soft goals it achieves
+/// minus its cost, every hard goal being achieved. States are expanded by increasing cost
+/// and every one of them is a candidate end; the search stops once the utility of all soft
+/// goals minus the cost reached cannot beat the best candidate, which is then optimal.
+pub fn net_benefit_search<P: SoftGoalProblem>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &NetBenefitConfig,
+) -> Result<NetBenefitPlan<P::Action, P::Cost>, &'static str> {
+    let goals = problem.goal_list();
+    let max_utility: f64 = goals.iter().filter_map(|goal| goal.utility).sum();
+
+    // Parent and action of every node, with the cost and state of the open ones
+    let mut parents: Vec<Option<(usize, P::Action)>> = vec![None];
+    let mut states = vec![Some(initial_state.clone())];
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((P::Cost::default(), 0)));
+    let mut closed = HashSet::new();
+    let mut best: Option<(f64, usize, P::Cost)> = None;
+    let mut expanded = 0;
+
+    while let Some(Reverse((g, node))) = open.pop() {
+        if best.is_some_and(|(benefit, _, _)| max_utility - g.to_f64() <= benefit) {
+            break;
+        }
+        let state = states[node].take().expect("Open node without a state");
+        if !closed.insert(state_hash(&state)) {
+            continue;
+        }
+        if let Some(utility) = utility_of(problem, &goals, &state) {
+            let benefit = utility - g.to_f64();
+            if best.is_none_or(|(best_benefit, _, _)| benefit > best_benefit) {
+                best = Some((benefit, node, g));
+            }
+        }
+        expanded += 1;
+        if expanded > config.max_expansions {
+            break;
+        }
+
+        for action in problem.get_possible_actions(&state) {
+            let successor = problem.apply_action(&state, &action);
+            if closed.contains(&state_hash(&successor)) {
+                continue;
+            }
+            let cost = g + problem.action_cost(&state, &action);
+            parents.push(Some((node, action)));
+            states.push(Some(successor));
+            open.push(Reverse((cost, parents.len() - 1)));
+        }
+    }
+
+    println!("nodes: {}, expanded: {}", parents.len(), expanded);
+    let (_, mut node, cost) = best.ok_or("No plan achieves the hard goals")?;
+    let mut actions = Vec::new();
+    while let Some((parent, action)) = parents[node] {
+        actions.push(action);
+        node = parent;
+    }
+    actions.reverse();
+
+    let mut state = initial_state.clone();
+    for action in &actions {
+        state = problem.apply_action(&state, action);
+    }
+    let achieved = goals
+        .iter()
+        .zip(problem.goals_achieved(&state))
+        .filter(|(_, achieved)| *achieved)
+        .map(|(goal, _)| goal.name.clone())
+        .collect();
+    Ok(NetBenefitPlan {
+        actions,
+        achieved,
+        utility: utility_of(problem, &goals, &state).expect("Best plan misses a hard goal"),
+        cost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::rover_problem::rover_problem::RoverProblem;
+    use crate::problems::tpp_problem::tpp_problem::TppProblem;
+    use ordered_float::OrderedFloat;
+
+    #[test]
+    fn test_net_benefit_gives_up_goals_not_worth_their_cost() {
+        let (state, mut problem) =
+            TppProblem::load_state_from_json("src/inputs/tpp_problem/problems_json/pfile1.json");
+        let config = NetBenefitConfig::default();
+
+        // Without utilities every goal is hard: an optimal plan for the whole goal.
+        let full = net_benefit_search(&problem, &state, &config).unwrap();
+        assert_eq!(full.achieved.len(), 2);
+        assert_eq!(full.utility, 0.0);
+
+        // Worthless goals are given up, so staying at the depot is best.
+        for item in ["0", "1"] {
+            problem
+                .goal
+                .utilities
+                .insert(item.to_string(), OrderedFloat(1.0));
+        }
+        let idle = net_benefit_search(&problem, &state, &config).unwrap();
+        assert!(idle.actions.is_empty() && idle.achieved.is_empty());
+        assert_eq!(idle.net_benefit(), 0.0);
+
+        // Worth more than the whole plan, both goals are achieved at the optimal cost.
+        let reward = full.cost.to_f64() + 1.0;
+        for item in ["0", "1"] {
+            problem
+                .goal
+                .utilities
+                .insert(item.to_string(), OrderedFloat(reward));
+        }
+        let both = net_benefit_search(&problem, &state, &config).unwrap();
+        assert_eq!(both.achieved, full.achieved);
+        assert_eq!(both.cost, full.cost);
+
+        // Only one goal worth it: the plan achieves exactly that one.
+        problem
+            .goal
+            .utilities
+            .insert("0".to_string(), OrderedFloat(1.0));
+        let one = net_benefit_search(&problem, &state, &config).unwrap();
+        assert_eq!(one.achieved, vec!["1".to_string()]);
+        assert!(one.cost < full.cost && one.net_benefit() > 0.0);
+    }
+
+    #[test]
+    fn test_rover_utilities_follow_the_goal_conditions() {
+        let (state, mut problem) =
+            RoverProblem::load_state_from_json("src/inputs/rover_problem/pfile1.json");
+        let config = NetBenefitConfig::default();
+        let goals = problem.goal.conditions.len();
+        assert!(goals > 1);
+
+        // Utilities are positional, missing ones making the remaining goals hard.
+        problem.goal.utilities = vec![Some(OrderedFloat(0.5))];
+        let list = problem.goal_list();
+        assert_eq!(list.len(), goals);
+        assert_eq!(list[0].utility, Some(0.5));
+        assert!(list[1..].iter().all(|goal| goal.utility.is_none()));
+
+        // Worthless goals are given up.
+        problem.goal.utilities = vec![Some(OrderedFloat(0.5)); goals];
+        let idle = net_benefit_search(&problem, &state, &config).unwrap();
+        assert!(idle.actions.is_empty() && idle.achieved.is_empty());
+
+        // Only the first goal worth it: the plan achieves exactly that one.
+        problem.goal.utilities[0] = Some(OrderedFloat(1000.0));
+        let one = net_benefit_search(&problem, &state, &config).unwrap();
+        assert_eq!(one.achieved, vec![list[0].name.clone()]);
+        assert_eq!(one.utility, 1000.0);
+        assert!(one.net_benefit() > 0.0);
+    }
+}
@@ -3,40 +3,582 @@ use crate::algorithms::bfs::BfsQueue;
 use crate::algorithms::dfs::DfsQueue;
 use crate::algorithms::gbfs::GBFSQueue;
 use crate::algorithms::search_queue::SearchQueue;
+use crate::analysis::profiler::ProfiledProblem;
+use crate::heuristics::numeric_heuristic::{NumericHeuristic, NumericHeuristicKind};
+use crate::heuristics::numeric_task::NumericProblem;
+use crate::heuristics::pattern_database::{
+    Combination, PatternDatabases, PdbHeader, ProjectableProblem,
+};
 use crate::problems::problem::Problem;
-use crate::search::node::Node;
-use crate::search::search::generic_search;
+use crate::search::alternation::{alternation_search, NamedHeuristic};
+use crate::search::beam::{beam_search, stochastic_beam_search, BeamConfig, StochasticBeamConfig};
+use crate::search::checkpoint::{load_checkpoint, CheckpointHeader, Checkpointer, SearchProgress};
+use crate::search::compact_tree::CompactSearchTree;
+use crate::search::export::{export_state_space, ExportFormat, ExportOptions};
+use crate::search::external_bfs::{external_bfs, ExternalSearchConfig};
+use crate::search::hill_climbing::enforced_hill_climbing;
+use crate::search::mcts::{mcts_search, MctsConfig};
+use crate::search::observer::{Observers, SearchObserver};
+use crate::search::pareto::{pareto_search, weighted_choice, MultiObjectiveProblem, ParetoConfig};
+use crate::search::preferred_search::preferred_gbfs;
+use crate::search::random_walk::{random_walk_search, RandomWalkConfig};
+use crate::search::search::{compact_search, keyed_search, observed_search, state_hash};
 use crate::search::search_tree::SearchTree;
+use crate::search::soft_goals::{net_benefit_search, NetBenefitConfig, SoftGoalProblem};
+use crate::search::state_codec::PackedProblem;
+use crate::search::stubborn_sets::{InterferenceProblem, StubbornProblem};
+use crate::search::symmetry::{canonical_hash, SymmetricProblem};
+use crate::search::top_k::{top_k_search, TopKConfig};
+use crate::search::width::{width_search, WidthSearch};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 
 pub fn solve_problem<P>(json_path: &str, search_strategy: &str)
 where
     P: Problem,
 {
     let (initial_state, problem) = P::load_state_from_json(json_path);
+    solve_with_heuristic(&problem, initial_state, search_strategy, &mut (), |state| {
+        problem.heuristic(state)
+    });
+}
+
+/// Like `solve_problem`, with the search reported to the observers built from `observers`
+/// (see `Observers::from_specs`). The width-based, EHC and GBFS-PREF strategies report only
+/// their plan and the end of the search.
+pub fn solve_problem_observed<P, T>(json_path: &str, search_strategy: &str, observers: &[T])
+where
+    P: Problem,
+    T: AsRef<str>,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let action_label =
+        |state: &P::State, action: &P::Action| problem.describe_action(state, action).name;
+    let mut observers = Observers::from_specs(observers, &action_label);
+    solve_with_heuristic(
+        &problem,
+        initial_state,
+        search_strategy,
+        &mut observers,
+        |state| problem.heuristic(state),
+    );
+}
+
+/// Like `solve_problem`, timing every call to the problem's callbacks. The profile is
+/// printed after the result and written as JSON to `report_path`, if one is given.
+pub fn solve_problem_profiled<P>(json_path: &str, search_strategy: &str, report_path: Option<&str>)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = ProfiledProblem::<P>::load_state_from_json(json_path);
+    solve_with_heuristic(&problem, initial_state, search_strategy, &mut (), |state| {
+        problem.heuristic(state)
+    });
+    let report = problem.report();
+    report.print_summary(20);
+    if let Some(path) = report_path {
+        report
+            .save(path)
+            .unwrap_or_else(|e| panic!("{}: {}", path, e));
+    }
+}
 
-    let initial_node = Node::new_root();
+/// Like `solve_problem`, with successor sets pruned to strong stubborn sets.
+pub fn solve_problem_stubborn<P>(json_path: &str, search_strategy: &str)
+where
+    P: InterferenceProblem,
+{
+    let (initial_state, problem) = StubbornProblem::<P>::load_state_from_json(json_path);
+    solve_with_heuristic(&problem, initial_state, search_strategy, &mut (), |state| {
+        problem.heuristic(state)
+    });
+    let (listed, pruned) = problem.pruning();
+    println!("actions: {listed}, pruned: {pruned}");
+}
+
+/// Like `solve_problem`, with states that only differ by interchangeable objects treated
+/// as duplicates.
+pub fn solve_problem_symmetric<P>(json_path: &str, search_strategy: &str)
+where
+    P: SymmetricProblem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let groups = problem.symmetry_groups(&initial_state);
+    for group in &groups {
+        println!("interchangeable {}s: {:?}", group.kind, group.objects);
+    }
     let mut tree = SearchTree::new(initial_state.clone());
+    let result = keyed_search(
+        &mut tree,
+        |state| problem.get_possible_actions(state),
+        |state, action| problem.apply_action(state, action),
+        |state, action| problem.action_cost(state, action),
+        |state| problem.is_goal_state(state),
+        &mut SearchProgress::new(search_queue(search_strategy)),
+        |state| problem.heuristic(state),
+        &mut (),
+        |state| canonical_hash(&problem, state, &groups),
+        &mut (),
+    );
+    print_result(&problem, &initial_state, result);
+}
 
-    let queue = match search_strategy {
-        "A*" => SearchQueue::AStar(AStarQueue::new()),
-        "GBFS" => SearchQueue::GBFS(GBFSQueue::new()),
-        "BFS" => SearchQueue::BFS(BfsQueue::new()),
-        "DFS" => SearchQueue::DFS(DfsQueue::new()),
-        _ => panic!("Unknown search strategy: {}", search_strategy),
+/// Like `solve_problem`, but guided by a domain-independent heuristic
+/// ("goal_count", "h_add", "h_max", "h_ff" or "landmarks") instead of `Problem::heuristic`.
+/// With "EHC" and "GBFS-PREF", the preferred actions are the helpful actions of h_FF.
+pub fn solve_problem_numeric<P>(json_path: &str, search_strategy: &str, heuristic_name: &str)
+where
+    P: NumericProblem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let kind = NumericHeuristicKind::from_name(heuristic_name)
+        .unwrap_or_else(|| panic!("Unknown heuristic: {}", heuristic_name));
+    let heuristic = NumericHeuristic::new(problem.numeric_task(&initial_state), kind);
+    solve_with_preferences(
+        &problem,
+        initial_state,
+        search_strategy,
+        &mut (),
+        |state| heuristic.evaluate(&problem.numeric_values(state)),
+        |state, actions| {
+            let helpful = heuristic.helpful_actions(&problem.numeric_values(state));
+            actions
+                .iter()
+                .filter(|action| helpful.contains(action))
+                .copied()
+                .collect()
+        },
+    );
+}
+
+/// Greedy search alternating between several heuristics, each with its own open queue:
+/// "domain" for `Problem::heuristic` and the names accepted by `solve_problem_numeric`.
+pub fn solve_problem_alternation<P>(json_path: &str, heuristic_names: &[&str])
+where
+    P: NumericProblem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let problem = &problem;
+    let task = problem.numeric_task(&initial_state);
+    let heuristics: Vec<NamedHeuristic<P::State>> = heuristic_names
+        .iter()
+        .map(|&name| {
+            if name == "domain" {
+                return NamedHeuristic::new(name, |state| problem.heuristic(state));
+            }
+            let kind = NumericHeuristicKind::from_name(name)
+                .unwrap_or_else(|| panic!("Unknown heuristic: {}", name));
+            let heuristic = NumericHeuristic::new(task.clone(), kind);
+            NamedHeuristic::new(name, move |state| {
+                heuristic.evaluate(&problem.numeric_values(state))
+            })
+        })
+        .collect();
+    let (result, _) = alternation_search(problem, &initial_state, &heuristics);
+    print_result(problem, &initial_state, result);
+}
+
+/// Like `solve_problem`, but guided by pattern databases with at most `max_size` entries
+/// each. With `pdb_path`, the databases are loaded from that file if it exists and saved
+/// there after building otherwise. A file built for another instance or with other
+/// parameters is refused.
+pub fn solve_problem_pdb<P>(
+    json_path: &str,
+    search_strategy: &str,
+    max_size: u64,
+    combination: Combination,
+    pdb_path: Option<&str>,
+) where
+    P: ProjectableProblem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let header = PdbHeader {
+        json_path: json_path.to_string(),
+        max_size,
+        combination,
+        patterns: PatternDatabases::patterns(&problem, &initial_state, max_size),
     };
+    let databases = match pdb_path {
+        Some(path) if Path::new(path).exists() => PatternDatabases::load(path, &header)
+            .unwrap_or_else(|e| panic!("Failed to load pattern databases: {}", e)),
+        _ => {
+            let databases =
+                PatternDatabases::build(&problem, &initial_state, max_size, combination);
+            if let Some(path) = pdb_path {
+                databases
+                    .save(path, &header)
+                    .unwrap_or_else(|e| panic!("Failed to save pattern databases: {}", e));
+            }
+            databases
+        }
+    };
+    solve_with_heuristic(&problem, initial_state, search_strategy, &mut (), |state| {
+        databases.evaluate(&problem.values(state))
+    });
+}
+
+fn solve_with_heuristic<P, O, H>(
+    problem: &P,
+    initial_state: P::State,
+    search_strategy: &str,
+    observer: &mut O,
+    heuristic: H,
+) where
+    P: Problem,
+    O: SearchObserver<P::State, P::Action, P::Cost>,
+    H: Fn(&P::State) -> f64,
+{
+    solve_with_preferences(
+        problem,
+        initial_state,
+        search_strategy,
+        observer,
+        heuristic,
+        |state, actions| problem.preferred_actions(state, actions),
+    );
+}
 
-    let result = generic_search(
+fn solve_with_preferences<P, O, H, R>(
+    problem: &P,
+    initial_state: P::State,
+    search_strategy: &str,
+    observer: &mut O,
+    heuristic: H,
+    preferred: R,
+) where
+    P: Problem,
+    O: SearchObserver<P::State, P::Action, P::Cost>,
+    H: Fn(&P::State) -> f64,
+    R: Fn(&P::State, &[P::Action]) -> Vec<P::Action>,
+{
+    // Searches driven by preferred actions, which report only their outcome to the
+    // observer. Enforced hill-climbing falls back to GBFS when it gets stuck.
+    match search_strategy {
+        "EHC" => {
+            let result = enforced_hill_climbing(problem, &initial_state, &heuristic, &preferred)
+                .or_else(|msg| {
+                    println!("{}, falling back to GBFS", msg);
+                    preferred_gbfs(problem, &initial_state, &heuristic, |_, _| Vec::new())
+                });
+            report_outcome(problem, &initial_state, &result, observer);
+            print_result(problem, &initial_state, result);
+            return;
+        }
+        "GBFS-PREF" => {
+            let result = preferred_gbfs(problem, &initial_state, &heuristic, &preferred);
+            report_outcome(problem, &initial_state, &result, observer);
+            print_result(problem, &initial_state, result);
+            return;
+        }
+        _ => {}
+    }
+    // Width-based searches keep their own novelty tables and report only their outcome.
+    if let Some(strategy) = WidthSearch::from_name(search_strategy) {
+        let result = width_search(problem, &initial_state, strategy, heuristic);
+        report_outcome(problem, &initial_state, &result, observer);
+        print_result(problem, &initial_state, result);
+        return;
+    }
+    let mut tree = SearchTree::new(initial_state.clone());
+
+    let result = observed_search(
         &mut tree,
         |state| problem.get_possible_actions(state),
         |state, action| problem.apply_action(state, action),
+        |state, action| problem.action_cost(state, action),
         |state| problem.is_goal_state(state),
-        queue,
+        search_queue(search_strategy),
+        heuristic,
+        observer,
+    );
+
+    print_result(problem, &initial_state, result);
+}
+
+/// Like `solve_problem`, but keeps the search tree in compact form, with every state
+/// bit-packed by the domain's codec.
+pub fn solve_problem_compact<P>(json_path: &str, search_strategy: &str)
+where
+    P: PackedProblem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let codec = problem.state_codec(&initial_state);
+    let mut tree = CompactSearchTree::new(&initial_state, codec, false);
+
+    let result = compact_search(
+        &mut tree,
+        |state| problem.get_possible_actions(state),
+        |state, action| problem.apply_action(state, action),
+        |state, action| problem.action_cost(state, action),
+        |state| problem.is_goal_state(state),
+        search_queue(search_strategy),
         |state| problem.heuristic(state),
     );
 
+    print_result(&problem, &initial_state, result);
+}
+
+/// Like `solve_problem`, but resumes from the checkpoint at `checkpoint_path` if there is
+/// one, and writes checkpoints there every `interval` and on SIGINT or SIGTERM, which also
+/// stop the search. A checkpoint of another problem or strategy is refused. The checkpoint
+/// is removed once the search has finished.
+pub fn solve_problem_checkpointed<P>(
+    json_path: &str,
+    search_strategy: &str,
+    checkpoint_path: &str,
+    interval: Option<Duration>,
+) where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let path = Path::new(checkpoint_path);
+    let header = CheckpointHeader {
+        json_path: json_path.to_string(),
+        search_strategy: search_strategy.to_string(),
+    };
+    let (mut tree, mut progress) = if path.exists() {
+        load_checkpoint(path, &header)
+            .unwrap_or_else(|e| panic!("Failed to load checkpoint: {}", e))
+    } else {
+        (
+            SearchTree::new(initial_state.clone()),
+            SearchProgress::new(search_queue(search_strategy)),
+        )
+    };
+    let mut checkpointer = Checkpointer::new(path, header, interval);
+    checkpointer
+        .handle_signals()
+        .unwrap_or_else(|e| panic!("Failed to install signal handlers: {}", e));
+
+    let result = keyed_search(
+        &mut tree,
+        |state| problem.get_possible_actions(state),
+        |state, action| problem.apply_action(state, action),
+        |state, action| problem.action_cost(state, action),
+        |state| problem.is_goal_state(state),
+        &mut progress,
+        |state| problem.heuristic(state),
+        &mut (),
+        state_hash,
+        &mut checkpointer,
+    );
+    if let Err("Search interrupted") = result {
+        println!("Search interrupted, checkpoint kept at {}", path.display());
+        return;
+    }
+    if path.exists() {
+        fs::remove_file(path).unwrap_or_else(|e| panic!("Failed to remove checkpoint: {}", e));
+    }
+
+    print_result(&problem, &initial_state, result);
+}
+
+/// Breadth-first search with the layers kept on disk, for state spaces that do not fit
+/// in memory.
+pub fn solve_problem_external<P>(json_path: &str, config: &ExternalSearchConfig)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let result = external_bfs(&problem, &initial_state, config);
+    print_result(&problem, &initial_state, result);
+}
+
+/// Writes the reachable state space of a small instance to `output_path`, as Graphviz DOT
+/// if the path ends in `.dot` and as node-link JSON otherwise.
+pub fn export_problem_state_space<P>(json_path: &str, output_path: &str, options: &ExportOptions)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let graph = export_state_space(&problem, &initial_state, options);
+    println!(
+        "states: {}, transitions: {}, truncated: {}",
+        graph.nodes.len(),
+        graph.links.len(),
+        graph.truncated
+    );
+    graph
+        .save(output_path, ExportFormat::from_path(output_path))
+        .unwrap_or_else(|e| panic!("{}: {}", output_path, e));
+}
+
+/// Beam search guided by `Problem::heuristic`, for instances too large for a complete
+/// search.
+pub fn solve_problem_beam<P>(json_path: &str, config: &BeamConfig)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let result = beam_search(&problem, &initial_state, config, |state| {
+        problem.heuristic(state)
+    });
+    print_result(&problem, &initial_state, result);
+}
+
+/// Like `solve_problem_beam`, with sampled beams and restarts of growing width.
+pub fn solve_problem_stochastic_beam<P>(json_path: &str, config: &StochasticBeamConfig)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let result = stochastic_beam_search(&problem, &initial_state, config, |state| {
+        problem.heuristic(state)
+    });
+    print_result(&problem, &initial_state, result);
+}
+
+/// Monte Carlo random-walk planning guided by `Problem::heuristic`.
+pub fn solve_problem_random_walk<P>(json_path: &str, config: &RandomWalkConfig)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let result = random_walk_search(&problem, &initial_state, config, |state| {
+        problem.heuristic(state)
+    });
+    print_result(&problem, &initial_state, result);
+}
+
+pub fn solve_problem_mcts<P>(json_path: &str, config: &MctsConfig)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let result = mcts_search(&problem, &initial_state, config, |state| {
+        problem.heuristic(state)
+    });
+    print_result(&problem, &initial_state, result);
+}
+
+/// Prints the Pareto front, then, given one weight per objective, the plan of the front
+/// with the lowest weighted cost.
+pub fn solve_problem_pareto<P>(json_path: &str, config: &ParetoConfig, weights: Option<&[f64]>)
+where
+    P: MultiObjectiveProblem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let front = match pareto_search(&problem, &initial_state, config) {
+        Ok(front) => front,
+        Err(msg) => return println!("Search failed: {}", msg),
+    };
+    let objectives = problem.objectives();
+    for plan in &front {
+        let costs: Vec<_> = objectives
+            .iter()
+            .zip(&plan.costs)
+            .map(|(name, cost)| format!("{name}: {cost}"))
+            .collect();
+        println!(
+            "Pareto plan ({}), length {}",
+            costs.join(", "),
+            plan.actions.len()
+        );
+    }
+    if let Some(weights) = weights {
+        let chosen = weighted_choice(&front, weights).map(|plan| plan.actions.clone());
+        print_result(&problem, &initial_state, chosen.ok_or("No solution found"));
+    }
+}
+
+/// Plans for the soft goals worth their cost, then reports the goals achieved and the net
+/// benefit of the plan.
+pub fn solve_problem_net_benefit<P>(json_path: &str, config: &NetBenefitConfig)
+where
+    P: SoftGoalProblem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let result = net_benefit_search(&problem, &initial_state, config);
+    if let Ok(plan) = &result {
+        println!("Goals achieved: {:?}", plan.achieved);
+        println!(
+            "Utility: {}, net benefit: {}",
+            plan.utility,
+            plan.net_benefit()
+        );
+    }
+    print_result(&problem, &initial_state, result.map(|plan| plan.actions));
+}
+
+/// Prints up to `config.k` plans, cheapest first, each differing from the previous ones
+/// by `config.min_distance` at least.
+pub fn solve_problem_top_k<P>(json_path: &str, search_strategy: &str, config: &TopKConfig)
+where
+    P: Problem,
+{
+    let (initial_state, problem) = P::load_state_from_json(json_path);
+    let plans = top_k_search(
+        &problem,
+        &initial_state,
+        config,
+        || search_queue(search_strategy),
+        |state| problem.heuristic(state),
+    );
+    if plans.is_empty() {
+        println!("Search failed: No solution found");
+    }
+    for (rank, plan) in plans.into_iter().enumerate() {
+        println!("Plan {}:", rank + 1);
+        print_result(&problem, &initial_state, Ok::<_, &str>(plan));
+    }
+}
+
+fn search_queue(search_strategy: &str) -> SearchQueue {
+    match search_strategy {
+        "A*" => SearchQueue::AStar(AStarQueue::new()),
+        "GBFS" => SearchQueue::GBFS(GBFSQueue::new()),
+        "BFS" => SearchQueue::BFS(BfsQueue::new()),
+        "DFS" => SearchQueue::DFS(DfsQueue::new()),
+        _ => panic!("Unknown search strategy: {}", search_strategy),
+    }
+}
+
+/// Reports the outcome of a search that does not build a `SearchTree` to the observer: the
+/// plan, if any, is replayed into a tree of its own, whose nodes are reported as generated
+/// before the solution and the end of the search. Expansions and pruning go unreported.
+fn report_outcome<P, O, E>(
+    problem: &P,
+    initial_state: &P::State,
+    result: &Result<Vec<P::Action>, E>,
+    observer: &mut O,
+) where
+    P: Problem,
+    O: SearchObserver<P::State, P::Action, P::Cost>,
+{
+    let mut tree = SearchTree::new(initial_state.clone());
+    if let Ok(plan) = result {
+        let mut node = 0;
+        for action in plan {
+            node = tree.add_node(
+                node,
+                *action,
+                |state, action| problem.apply_action(state, action),
+                |state, action| problem.action_cost(state, action),
+            );
+            observer.node_generated(&tree, node);
+        }
+        observer.solution_found(&tree, node);
+    }
+    observer.search_finished(&tree);
+}
+
+fn print_result<P: Problem, E: Display>(
+    problem: &P,
+    initial_state: &P::State,
+    result: Result<Vec<P::Action>, E>,
+) {
     match result {
-        Ok(actions) => {
-            let total_cost: f64 = actions.iter().map(|action| action.cost as f64).sum();
+        Ok(plan) => {
+            let actions = problem.describe_plan(initial_state, &plan);
+            // Summed in the problem's cost type, so that the total is exact.
+            let mut state = initial_state.clone();
+            let mut total_cost = P::Cost::default();
+            for action in &plan {
+                total_cost += problem.action_cost(&state, action);
+                state = problem.apply_action(&state, action);
+            }
             let action_names: Vec<_> = actions.iter().map(|action| &action.name).collect();
             println!("Solution found with actions: {:?}", action_names);
             println!("Total cost of actions: {}", total_cost);
@@ -0,0 +1,245 @@
+use crate::problems::problem::Problem;
+use crate::search::state::StateTrait;
+use std::collections::{BTreeMap, BTreeSet};
+use std::marker::PhantomData;
+
+/// Byte encoding of states, used to store states compactly. Encodings must be canonical:
+/// equal states encode to equal bytes, so that duplicates can be detected on the bytes.
+pub trait StateCodec<S> {
+    /// Appends the encoding of `state` to `out`.
+    fn encode(&self, state: &S, out: &mut Vec<u8>);
+    fn decode(&self, bytes: &[u8]) -> S;
+}
+
+/// Domains with a dedicated bit-packed state encoding.
+pub trait PackedProblem: Problem {
+    type Codec: StateCodec<Self::State>;
+
+    /// Codec for the states reachable from `state`; fields that no action changes are
+    /// taken from `state` when decoding.
+    fn state_codec(&self, state: &Self::State) -> Self::Codec;
+}
+
+/// Domain-independent codec storing the CBOR serialisation of the state.
+pub struct CborCodec<S> {
+    marker: PhantomData<S>,
+}
+
+impl<S> CborCodec<S> {
+    pub fn new() -> Self {
+        CborCodec {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Default for CborCodec<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: StateTrait> StateCodec<S> for CborCodec<S> {
+    fn encode(&self, state: &S, out: &mut Vec<u8>) {
+        serde_cbor::to_writer(out, state).expect("Failed to serialise state");
+    }
+
+    fn decode(&self, bytes: &[u8]) -> S {
+        serde_cbor::from_slice(bytes).expect("Failed to deserialise state")
+    }
+}
+
+/// Number of bits needed to store a value in `0..count`.
+pub fn bits_for(count: usize) -> u32 {
+    usize::BITS - count.saturating_sub(1).leading_zeros()
+}
+
+/// Writes values of arbitrary bit width, appending whole bytes to the output.
+pub struct BitWriter<'a> {
+    out: &'a mut Vec<u8>,
+    used: u32, // bits used in the last byte, 0 when a new byte is needed
+}
+
+impl<'a> BitWriter<'a> {
+    pub fn new(out: &'a mut Vec<u8>) -> Self {
+        BitWriter { out, used: 0 }
+    }
+
+    /// Writes the `bits` low bits of `value`.
+    pub fn write(&mut self, value: u64, bits: u32) {
+        for i in 0..bits {
+            if self.used == 0 {
+                self.out.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.out.last_mut().unwrap() |= 1 << self.used;
+            }
+            self.used = (self.used + 1) % 8;
+        }
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write(value as u64, 1);
+    }
+
+    /// Writes an unbounded integer in groups of seven bits.
+    pub fn write_int(&mut self, value: i64) {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+        loop {
+            self.write(zigzag & 0x7F, 7);
+            zigzag >>= 7;
+            self.write_bool(zigzag != 0);
+            if zigzag == 0 {
+                break;
+            }
+        }
+    }
+}
+
+/// Reads back what a `BitWriter` wrote.
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize, // in bits
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, position: 0 }
+    }
+
+    pub fn read(&mut self, bits: u32) -> u64 {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = self.bytes[self.position / 8];
+            value |= (((byte >> (self.position % 8)) & 1) as u64) << i;
+            self.position += 1;
+        }
+        value
+    }
+
+    pub fn read_bool(&mut self) -> bool {
+        self.read(1) == 1
+    }
+
+    pub fn read_int(&mut self) -> i64 {
+        let mut zigzag = 0u64;
+        let mut shift = 0;
+        loop {
+            zigzag |= self.read(7) << shift;
+            shift += 7;
+            if !self.read_bool() {
+                break;
+            }
+        }
+        ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64)
+    }
+}
+
+/// Ordered set of the names a codec can refer to, each stored as its position.
+#[derive(Debug, Clone)]
+pub struct SymbolTable {
+    names: Vec<String>,
+    positions: BTreeMap<String, usize>,
+    bits: u32,
+}
+
+impl SymbolTable {
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a String>) -> Self {
+        let mut names: Vec<String> = names.into_iter().cloned().collect();
+        names.sort();
+        names.dedup();
+        let positions = names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.clone(), i))
+            .collect();
+        let bits = bits_for(names.len());
+        SymbolTable {
+            names,
+            positions,
+            bits,
+        }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    fn position(&self, name: &str) -> usize {
+        *self
+            .positions
+            .get(name)
+            .unwrap_or_else(|| panic!("{} is not known to the state codec", name))
+    }
+
+    pub fn write(&self, writer: &mut BitWriter, name: &str) {
+        writer.write(self.position(name) as u64, self.bits);
+    }
+
+    pub fn read(&self, reader: &mut BitReader) -> String {
+        self.names[reader.read(self.bits) as usize].clone()
+    }
+
+    /// Panics unless every key of `map` is in the table, since maps are written as one
+    /// presence bit per name.
+    pub fn check_keys<V>(&self, map: &BTreeMap<String, V>) {
+        for name in map.keys() {
+            self.position(name);
+        }
+    }
+
+    /// Writes a subset of the table as one bit per name.
+    pub fn write_set<'a>(&self, writer: &mut BitWriter, set: impl IntoIterator<Item = &'a String>) {
+        let mut members = vec![false; self.names.len()];
+        for name in set {
+            members[self.position(name)] = true;
+        }
+        for member in members {
+            writer.write_bool(member);
+        }
+    }
+
+    pub fn read_set(&self, reader: &mut BitReader) -> BTreeSet<String> {
+        self.names
+            .iter()
+            .filter(|_| reader.read_bool())
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_writer_round_trip() {
+        let mut bytes = vec![0xAB];
+        let mut writer = BitWriter::new(&mut bytes);
+        writer.write(5, 3);
+        writer.write_bool(true);
+        for x in [0, -1, 63, -64, 1_000_000, i64::MIN, i64::MAX] {
+            writer.write_int(x);
+        }
+        writer.write(0x1FF, 9);
+
+        let mut reader = BitReader::new(&bytes[1..]);
+        assert_eq!(reader.read(3), 5);
+        assert!(reader.read_bool());
+        for x in [0, -1, 63, -64, 1_000_000, i64::MIN, i64::MAX] {
+            assert_eq!(reader.read_int(), x);
+        }
+        assert_eq!(reader.read(9), 0x1FF);
+        assert_eq!(bits_for(1), 0);
+        assert_eq!(bits_for(2), 1);
+        assert_eq!(bits_for(5), 3);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::problems::factored_problem::{numeric_values, FactoredGoal, FactoredProblem};
+use crate::problems::problem::Problem;
+use crate::search::action::{Action, ActionTrait};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// Factored variables (indices into `FactoredProblem::variables`) an action touches.
+/// `reads` covers everything its applicability, cost and effects depend on; `writes`
+/// everything its effects may change.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Footprint {
+    pub reads: Vec<usize>,
+    pub writes: Vec<usize>,
+}
+
+/// Problems whose actions declare their footprints. Two actions interfere when one writes
+/// a variable the other reads or writes; actions that do not interfere commute, which
+/// partial-order reduction relies on.
+pub trait InterferenceProblem: FactoredProblem {
+    /// Every action of the instance, applicable in `state` or not; `state` supplies the
+    /// objects (normally the initial state).
+    fn all_actions(&self, state: &Self::State) -> Vec<Self::Action>;
+
+    /// `state` only supplies the objects: the footprint must be the same in every state.
+    fn footprint(&self, state: &Self::State, action: &Self::Action) -> Footprint;
+
+    /// Variables of which at least one must change before `action`, not applicable in
+    /// `state`, can become applicable. All the variables it reads by default.
+    fn enabling_variables(&self, state: &Self::State, action: &Self::Action) -> Vec<usize> {
+        self.footprint(state, action).reads
+    }
+}
+
+/// Strong stubborn sets (Wehrle and Helmert) over the declared footprints. The stubborn
+/// set of a state starts from the achievers of an unsatisfied goal condition and is closed
+/// under interference for its applicable actions and under necessary enabling sets for
+/// the others. Expanding only its applicable actions keeps completeness and optimality.
+pub struct StubbornSets<A> {
+    actions: Vec<A>,
+    index: HashMap<A, usize>,
+    footprints: Vec<Footprint>,
+    readers: Vec<Vec<usize>>, // Actions reading each variable
+    writers: Vec<Vec<usize>>, // Actions writing each variable
+    goal: FactoredGoal,
+}
+
+impl<A: ActionTrait> StubbornSets<A> {
+    pub fn new<P>(problem: &P, state: &P::State) -> Self
+    where
+        P: InterferenceProblem<Action = A>,
+    {
+        let actions = problem.all_actions(state);
+        let variables = problem.variables(state).len();
+        let footprints: Vec<Footprint> = actions
+            .iter()
+            .map(|a| problem.footprint(state, a))
+            .collect();
+        let (mut readers, mut writers) = (vec![Vec::new(); variables], vec![Vec::new(); variables]);
+        for (i, footprint) in footprints.iter().enumerate() {
+            for &v in &footprint.reads {
+                readers[v].push(i);
+            }
+            for &v in &footprint.writes {
+                writers[v].push(i);
+            }
+        }
+        StubbornSets {
+            index: actions.iter().enumerate().map(|(i, &a)| (a, i)).collect(),
+            actions,
+            footprints,
+            readers,
+            writers,
+            goal: problem.goal(state),
+        }
+    }
+
+    /// The actions of `applicable` that belong to the stubborn set of `state`. Nothing is
+    /// pruned when every goal condition holds, the factored goal being possibly inexact.
+    pub fn prune<P>(&self, problem: &P, state: &P::State, applicable: Vec<A>) -> Vec<A>
+    where
+        P: InterferenceProblem<Action = A>,
+    {
+        let values = numeric_values(&problem.values(state));
+        // The unsatisfied goal condition with the fewest achievers.
+        let landmark = self
+            .goal
+            .conditions
+            .iter()
+            .filter(|c| !c.is_satisfied(&values))
+            .map(|c| {
+                let mut achievers: Vec<usize> = c
+                    .expression
+                    .terms
+                    .iter()
+                    .flat_map(|&(_, v)| self.writers[v].iter().copied())
+                    .collect();
+                achievers.sort_unstable();
+                achievers.dedup();
+                achievers
+            })
+            .min_by_key(|achievers| achievers.len());
+        let Some(landmark) = landmark else {
+            return applicable;
+        };
+
+        let mut is_applicable = vec![false; self.actions.len()];
+        for action in &applicable {
+            let i = *self
+                .index
+                .get(action)
+                .unwrap_or_else(|| panic!("Action {:?} missing from all_actions", action));
+            is_applicable[i] = true;
+        }
+        let mut stubborn = vec![false; self.actions.len()];
+        let mut stack = Vec::new();
+        let mut add = |i: usize, stack: &mut Vec<usize>| {
+            if !stubborn[i] {
+                stubborn[i] = true;
+                stack.push(i);
+            }
+        };
+        for i in landmark {
+            add(i, &mut stack);
+        }
+        while let Some(i) = stack.pop() {
+            let footprint = &self.footprints[i];
+            if is_applicable[i] {
+                for &v in &footprint.writes {
+                    for &j in self.readers[v].iter().chain(&self.writers[v]) {
+                        add(j, &mut stack);
+                    }
+                }
+                for &v in &footprint.reads {
+                    for &j in &self.writers[v] {
+                        add(j, &mut stack);
+                    }
+                }
+            } else {
+                for v in problem.enabling_variables(state, &self.actions[i]) {
+                    for &j in &self.writers[v] {
+                        add(j, &mut stack);
+                    }
+                }
+            }
+        }
+        applicable
+            .into_iter()
+            .filter(|a| stubborn[self.index[a]])
+            .collect()
+    }
+}
+
+/// `P` with its successor sets pruned to stubborn sets, usable with any search.
+pub struct StubbornProblem<P: InterferenceProblem> {
+    pub inner: P,
+    sets: StubbornSets<P::Action>,
+    listed: Cell<u64>, // Applicable actions listed by the inner problem
+    kept: Cell<u64>,   // Those left after pruning
+}
+
+impl<P: InterferenceProblem> StubbornProblem<P> {
+    pub fn new(inner: P, state: &P::State) -> Self {
+        StubbornProblem {
+            sets: StubbornSets::new(&inner, state),
+            inner,
+            listed: Cell::new(0),
+            kept: Cell::new(0),
+        }
+    }
+
+    /// Applicable actions listed so far and how many of them were pruned.
+    pub fn pruning(&self) -> (u64, u64) {
+        (self.listed.get(), self.listed.get() - self.kept.get())
+    }
+}
+
+impl<P: InterferenceProblem> Problem for StubbornProblem<P> {
+    type State = P::State;
+    type Action = P::Action;
+    type Cost = P::Cost;
+
+    fn get_possible_actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        let actions = self.inner.get_possible_actions(state);
+        self.listed.set(self.listed.get() + actions.len() as u64);
+        let actions = self.sets.prune(&self.inner, state, actions);
+        self.kept.set(self.kept.get() + actions.len() as u64);
+        actions
+    }
+
+    fn apply_action(&self, state: &Self::State, action: &Self::Action) -> Self::State {
+        self.inner.apply_action(state, action)
+    }
+
+    fn action_cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.inner.action_cost(state, action)
+    }
+
+    fn describe_action(&self, state: &Self::State, action: &Self::Action) -> Action {
+        self.inner.describe_action(state, action)
+    }
+
+    fn is_goal_state(&self, state: &Self::State) -> bool {
+        self.inner.is_goal_state(state)
+    }
+
+    fn heuristic(&self, state: &Self::State) -> f64 {
+        self.inner.heuristic(state)
+    }
+
+    fn preferred_actions(
+        &self,
+        state: &Self::State,
+        actions: &[Self::Action],
+    ) -> Vec<Self::Action> {
+        self.inner.preferred_actions(state, actions)
+    }
+
+    fn load_state_from_json(json_path: &str) -> (Self::State, Self) {
+        let (state, problem) = P::load_state_from_json(json_path);
+        let problem = StubbornProblem::new(problem, &state);
+        (state, problem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::block_grouping_problem::block_grouping_problem::BlockGroupingProblem;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::zenotravel_problem::zenotravel_problem::ZenoTravelProblem;
+    use crate::search::search::generic_search;
+    use crate::search::search_tree::SearchTree;
+    use crate::search::test_util::check_plan;
+
+    /// Cost of an optimal plan found by blind A*, and the number of expanded states.
+    fn optimal_cost<P: Problem>(problem: &P, state: &P::State) -> (P::Cost, usize) {
+        let mut tree = SearchTree::new(state.clone());
+        let plan = generic_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            AStarQueue::new(),
+            |_| 0.0,
+        )
+        .unwrap();
+        (check_plan(problem, state, &plan), tree.nodes.len())
+    }
+
+    fn check_pruning<P: InterferenceProblem>(json_path: &str) {
+        let (state, problem) = StubbornProblem::<P>::load_state_from_json(json_path);
+        let (cost, nodes) = optimal_cost(&problem.inner, &state);
+        let (pruned_cost, pruned_nodes) = optimal_cost(&problem, &state);
+        assert_eq!(pruned_cost, cost, "{}", json_path);
+        assert!(pruned_nodes <= nodes, "{}", json_path);
+        assert!(problem.pruning().1 > 0, "{}", json_path);
+    }
+
+    #[test]
+    fn test_stubborn_sets_keep_optimal_plans() {
+        check_pruning::<CountersProblem>("src/inputs/counters_problem/problems_json/pfile1.json");
+        check_pruning::<BlockGroupingProblem>(
+            "src/inputs/block_grouping_problem/problems_json/pfile1.json",
+        );
+        check_pruning::<ZenoTravelProblem>(
+            "src/inputs/zenotravel_problem/problems_json/pfile1.json",
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::problems::problem::Problem;
+use crate::search::search::state_hash;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Objects of one kind that are interchangeable: swapping any two of them maps the
+/// applicable actions, their effects and costs, and the goal onto each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectGroup {
+    pub kind: &'static str,  // e.g. "airplane"
+    pub objects: Vec<usize>, // Positions of the objects in the state, increasing
+}
+
+/// Problems whose instances may contain interchangeable objects.
+pub trait SymmetricProblem: Problem {
+    /// Groups of interchangeable objects; `state` supplies the objects (normally the
+    /// initial state). Usually detected from the instance with `groups_by_signature`.
+    fn symmetry_groups(&self, state: &Self::State) -> Vec<ObjectGroup>;
+
+    /// `state` with the objects of every group rearranged in a canonical order, so that
+    /// states differing only by permutations within groups share their canonical state.
+    fn canonical_state(&self, state: &Self::State, groups: &[ObjectGroup]) -> Self::State;
+}
+
+/// Groups of the objects of `kind` with equal signatures, the static data (attributes,
+/// goals) that must match for two objects to be interchangeable. Groups of one object are
+/// left out.
+pub fn groups_by_signature<K: Hash + Eq>(
+    kind: &'static str,
+    signatures: impl IntoIterator<Item = K>,
+) -> Vec<ObjectGroup> {
+    let mut groups: Vec<ObjectGroup> = Vec::new();
+    let mut by_signature = HashMap::new();
+    for (object, signature) in signatures.into_iter().enumerate() {
+        let group = *by_signature.entry(signature).or_insert_with(|| {
+            groups.push(ObjectGroup {
+                kind,
+                objects: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[group].objects.push(object);
+    }
+    groups.retain(|group| group.objects.len() > 1);
+    groups
+}
+
+/// Canonical order of the objects of `group`, by increasing `key` and then position:
+/// the object at `order[k]` moves to position `group.objects[k]`.
+pub fn canonical_order<K: Ord>(group: &ObjectGroup, key: impl Fn(usize) -> K) -> Vec<usize> {
+    let mut order = group.objects.clone();
+    order.sort_by_key(|&object| key(object));
+    order
+}
+
+/// Hash of the canonical form of `state`, the duplicate detection key of `keyed_search`
+/// under symmetries.
+pub fn canonical_hash<P: SymmetricProblem>(
+    problem: &P,
+    state: &P::State,
+    groups: &[ObjectGroup],
+) -> u64 {
+    if groups.is_empty() {
+        state_hash(state)
+    } else {
+        state_hash(&problem.canonical_state(state, groups))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::bfs::BfsQueue;
+    use crate::search::checkpoint::SearchProgress;
+    use crate::problems::block_grouping_problem::block_grouping_problem::BlockGroupingProblem;
+    use crate::problems::sailing_problem::sailing_problem::SailingProblem;
+    use crate::problems::zenotravel_problem::zenotravel_problem::ZenoTravelProblem;
+    use crate::search::search::keyed_search;
+    use crate::search::search_tree::SearchTree;
+    use crate::search::test_util::check_plan;
+
+    /// Length of the plan found by BFS and number of generated nodes, with and without
+    /// symmetry reduction.
+    fn bfs<P: SymmetricProblem>(problem: &P, state: &P::State, symmetric: bool) -> (usize, usize) {
+        let groups = if symmetric {
+            problem.symmetry_groups(state)
+        } else {
+            Vec::new()
+        };
+        let mut tree = SearchTree::new(state.clone());
+        let plan = keyed_search(
+            &mut tree,
+            |s| problem.get_possible_actions(s),
+            |s, a| problem.apply_action(s, a),
+            |s, a| problem.action_cost(s, a),
+            |s| problem.is_goal_state(s),
+            &mut SearchProgress::new(BfsQueue::new()),
+            |_| 0.0,
+            &mut (),
+            |s| canonical_hash(problem, s, &groups),
+            &mut (),
+        )
+        .unwrap();
+        // The plan applies to the concrete objects.
+        check_plan(problem, state, &plan);
+        (plan.len(), tree.nodes.len())
+    }
+
+    fn check_symmetries<P: SymmetricProblem>(json_path: &str) {
+        let (state, problem) = P::load_state_from_json(json_path);
+        assert!(!problem.symmetry_groups(&state).is_empty(), "{}", json_path);
+        let (length, nodes) = bfs(&problem, &state, false);
+        let (reduced_length, reduced_nodes) = bfs(&problem, &state, true);
+        assert_eq!(reduced_length, length, "{}", json_path);
+        assert!(reduced_nodes < nodes, "{}", json_path);
+    }
+
+    #[test]
+    fn test_symmetry_reduction_keeps_shortest_plans() {
+        check_symmetries::<ZenoTravelProblem>(
+            "src/inputs/zenotravel_problem/problems_json/pfile1.json",
+        );
+        check_symmetries::<BlockGroupingProblem>(
+            "src/inputs/block_grouping_problem/problems_json/pfile1.json",
+        );
+
+        // Boats swapping places give the same canonical state.
+        let (state, problem) =
+            SailingProblem::load_state_from_json("src/inputs/sailing_problem/pfile1.json");
+        let groups = problem.symmetry_groups(&state);
+        let mut swapped = state.clone();
+        let (first, second) = (&state.boats[0], &state.boats[1]);
+        (swapped.boats[0].x, swapped.boats[0].y) = (second.x, second.y);
+        (swapped.boats[1].x, swapped.boats[1].y) = (first.x, first.y);
+        assert_ne!(swapped, state);
+        assert_eq!(
+            problem.canonical_state(&swapped, &groups),
+            problem.canonical_state(&state, &groups)
+        );
+    }
+}
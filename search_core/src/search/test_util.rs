@@ -0,0 +1,19 @@
+use crate::problems::problem::Problem;
+
+/// Replays `plan` from `state`, checking that every action is applicable where it is
+/// taken and that the plan ends in a goal, and returns the cost of the plan.
+pub fn check_plan<P: Problem>(problem: &P, state: &P::State, plan: &[P::Action]) -> P::Cost {
+    let mut current = state.clone();
+    let mut cost = P::Cost::default();
+    for action in plan {
+        assert!(
+            problem.get_possible_actions(&current).contains(action),
+            "{:?} is not applicable",
+            action
+        );
+        cost += problem.action_cost(&current, action);
+        current = problem.apply_action(&current, action);
+    }
+    assert!(problem.is_goal_state(&current), "The plan does not reach a goal");
+    cost
+}
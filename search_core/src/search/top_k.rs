@@ -0,0 +1,312 @@
+use crate::algorithms::priority_queue::PriorityQueue;
+use crate::problems::problem::Problem;
+use crate::search::action::ActionTrait;
+use crate::search::search::state_hash;
+use std::collections::{HashMap, HashSet};
+
+/// How far apart two plans are, from 0 for identical plans to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanDistance {
+    ActionSet,     // Jaccard distance between the sets of ground actions of the plans
+    StateSequence, // Share of the steps at which the plans visit different states
+}
+
+#[derive(Debug, Clone)]
+pub struct TopKConfig {
+    pub k: usize,
+    /// A plan closer than this to one already returned is skipped; 0 returns the k cheapest
+    /// plans.
+    pub min_distance: f64,
+    pub distance: PlanDistance,
+    /// Plans generated, returned or skipped, before giving up on finding k of them.
+    pub max_candidates: usize,
+}
+
+impl Default for TopKConfig {
+    fn default() -> Self {
+        TopKConfig {
+            k: 10,
+            min_distance: 0.0,
+            distance: PlanDistance::ActionSet,
+            max_candidates: 1000,
+        }
+    }
+}
+
+/// Ground names of the actions of `plan`, run from `initial_state`.
+fn ground_actions<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    plan: &[P::Action],
+) -> HashSet<String> {
+    let mut state = initial_state.clone();
+    let mut names = HashSet::new();
+    for action in plan {
+        names.insert(problem.describe_action(&state, action).name);
+        state = problem.apply_action(&state, action);
+    }
+    names
+}
+
+/// 1 - |A ∩ B| / |A ∪ B| over the ground actions of the two plans, run from
+/// `initial_state`, 0 for two empty plans.
+pub fn action_set_distance<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    a: &[P::Action],
+    b: &[P::Action],
+) -> f64 {
+    let a = ground_actions(problem, initial_state, a);
+    let b = ground_actions(problem, initial_state, b);
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    1.0 - a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Share of the steps at which the two plans, run from `initial_state`, are in different
+/// states; the steps past the end of the shorter plan all count as different.
+pub fn state_sequence_distance<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    a: &[P::Action],
+    b: &[P::Action],
+) -> f64 {
+    let steps = a.len().max(b.len());
+    if steps == 0 {
+        return 0.0;
+    }
+    let (mut state_a, mut state_b) = (initial_state.clone(), initial_state.clone());
+    let mut different = steps - a.len().min(b.len());
+    for (action_a, action_b) in a.iter().zip(b) {
+        state_a = problem.apply_action(&state_a, action_a);
+        state_b = problem.apply_action(&state_b, action_b);
+        if state_a != state_b {
+            different += 1;
+        }
+    }
+    different as f64 / steps as f64
+}
+
+/// Prefix tree of the plans found so far.
+struct PlanTrie<A> {
+    children: Vec<HashMap<A, usize>>,
+    plan_ends: Vec<bool>,
+}
+
+impl<A: ActionTrait> PlanTrie<A> {
+    fn new() -> Self {
+        PlanTrie {
+            children: vec![HashMap::new()],
+            plan_ends: vec![false],
+        }
+    }
+
+    fn insert(&mut self, plan: &[A]) {
+        let mut node = 0;
+        for action in plan {
+            node = match self.children[node].get(action) {
+                Some(&child) => child,
+                None => {
+                    let child = self.children.len();
+                    self.children.push(HashMap::new());
+                    self.plan_ends.push(false);
+                    self.children[node].insert(*action, child);
+                    child
+                }
+            };
+        }
+        self.plan_ends[node] = true;
+    }
+}
+
+/// A state of the reformulated task: the original state and the trie node of the actions
+/// applied so far, None once they left every plan found.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ForbiddingState<S> {
+    state: S,
+    trie_node: Option<usize>,
+}
+
+/// Cheapest plan that is not one of the plans of `trie`, searched on the task reformulated
+/// to forbid those plans. States are closed when they are expanded, where goals are also
+/// recognised, so that a cheaper path found after the first one still counts.
+fn next_plan<P, Q, H>(
+    problem: &P,
+    initial_state: &P::State,
+    trie: &PlanTrie<P::Action>,
+    mut queue: Q,
+    heuristic: &H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    Q: PriorityQueue,
+    H: Fn(&P::State) -> f64,
+{
+    // Parent, action and cost of every node, with the state of the open ones
+    let mut parents: Vec<Option<(usize, P::Action)>> = vec![None];
+    let mut costs = vec![P::Cost::default()];
+    let mut states = vec![Some(ForbiddingState {
+        state: initial_state.clone(),
+        trie_node: Some(0),
+    })];
+    queue.insert(0, P::Cost::default(), heuristic(initial_state));
+    let mut closed = HashSet::new();
+
+    while let Some(node) = queue.pop() {
+        let current = states[node].take().expect("Open node without a state");
+        if !closed.insert(state_hash(&current)) {
+            continue;
+        }
+        let ends_plan_found = current.trie_node.is_some_and(|n| trie.plan_ends[n]);
+        if problem.is_goal_state(&current.state) && !ends_plan_found {
+            let mut plan = Vec::new();
+            let mut node = node;
+            while let Some((parent, action)) = parents[node] {
+                plan.push(action);
+                node = parent;
+            }
+            plan.reverse();
+            return Ok(plan);
+        }
+
+        for action in problem.get_possible_actions(&current.state) {
+            let successor = ForbiddingState {
+                state: problem.apply_action(&current.state, &action),
+                trie_node: current
+                    .trie_node
+                    .and_then(|n| trie.children[n].get(&action).copied()),
+            };
+            if closed.contains(&state_hash(&successor)) {
+                continue;
+            }
+            let cost = costs[node] + problem.action_cost(&current.state, &action);
+            queue.insert(parents.len(), cost, heuristic(&successor.state));
+            parents.push(Some((node, action)));
+            costs.push(cost);
+            states.push(Some(successor));
+        }
+    }
+    Err("No solution found")
+}
+
+/// Top-k planning by plan forbidding: each search looks for the cheapest plan other than
+/// those found before, so with an A* queue and a consistent heuristic the plans come out
+/// cheapest first. With a `min_distance`, a plan too close to one already returned is
+/// forbidden but not returned, which gives diverse plans. Stops after `k` plans, after
+/// `max_candidates` plans or when no plan is left.
+pub fn top_k_search<P, Q, N, H>(
+    problem: &P,
+    initial_state: &P::State,
+    config: &TopKConfig,
+    new_queue: N,
+    heuristic: H,
+) -> Vec<Vec<P::Action>>
+where
+    P: Problem,
+    Q: PriorityQueue,
+    N: Fn() -> Q,
+    H: Fn(&P::State) -> f64,
+{
+    let distance = |a: &[P::Action], b: &[P::Action]| match config.distance {
+        PlanDistance::ActionSet => action_set_distance(problem, initial_state, a, b),
+        PlanDistance::StateSequence => state_sequence_distance(problem, initial_state, a, b),
+    };
+    let mut trie = PlanTrie::new();
+    let mut plans: Vec<Vec<P::Action>> = Vec::new();
+    for _ in 0..config.max_candidates {
+        if plans.len() >= config.k {
+            break;
+        }
+        let Ok(plan) = next_plan(problem, initial_state, &trie, new_queue(), &heuristic) else {
+            break;
+        };
+        trie.insert(&plan);
+        if plans
+            .iter()
+            .all(|other| distance(&plan, other) >= config.min_distance)
+        {
+            plans.push(plan);
+        }
+    }
+    plans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::astar::AStarQueue;
+    use crate::problems::tpp_problem::tpp_problem::TppProblem;
+    use crate::problems::zenotravel_time_problem::zenotravel_time_problem::ZenoTravelTimeProblem;
+    use crate::search::soft_goals::{net_benefit_search, NetBenefitConfig};
+    use crate::search::test_util::check_plan;
+    use ordered_float::OrderedFloat;
+
+    #[test]
+    fn test_top_k_plans_are_distinct_and_cheapest_first() {
+        let (state, problem) = ZenoTravelTimeProblem::load_state_from_json(
+            "src/inputs/zenotravel_time_problem/problems_json/pfile1.json",
+        );
+        let cost = |plan: &[_]| check_plan(&problem, &state, plan);
+        let config = TopKConfig {
+            k: 5,
+            ..TopKConfig::default()
+        };
+        let plans = top_k_search(&problem, &state, &config, AStarQueue::new, |_| 0.0);
+        assert_eq!(plans.len(), 5);
+        let costs: Vec<_> = plans.iter().map(|plan| cost(plan)).collect();
+        assert!(costs.windows(2).all(|pair| pair[0] <= pair[1]));
+        let distinct: HashSet<_> = plans.iter().collect();
+        assert_eq!(distinct.len(), 5);
+
+        // The cheapest plans only reorder the same actions, while diverse ones visit
+        // different states at a third of their steps at least.
+        assert!(plans
+            .iter()
+            .all(|plan| action_set_distance(&problem, &state, plan, &plans[0]) == 0.0));
+        let diverse_config = TopKConfig {
+            k: 3,
+            min_distance: 0.3,
+            distance: PlanDistance::StateSequence,
+            ..config
+        };
+        let diverse = top_k_search(&problem, &state, &diverse_config, AStarQueue::new, |_| 0.0);
+        assert_eq!(diverse.len(), 3);
+        assert_eq!(diverse[0], plans[0]);
+        for (i, plan) in diverse.iter().enumerate() {
+            cost(plan);
+            for other in &diverse[..i] {
+                assert!(state_sequence_distance(&problem, &state, plan, other) >= 0.3);
+            }
+        }
+        assert_eq!(
+            state_sequence_distance(&problem, &state, &plans[0], &plans[0]),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_top_k_finds_paths_cheaper_than_the_first_one_generated() {
+        let (state, mut problem) =
+            TppProblem::load_state_from_json("src/inputs/tpp_problem/problems_json/pfile1.json");
+        // The road from market 2 back to the depot is generated first but now costs more than
+        // the detour through market 0, which the cheapest plan takes.
+        for (to, distance) in problem.distances.get_mut("2").unwrap() {
+            if to == "-1" {
+                *distance = OrderedFloat(2000.0);
+            }
+        }
+        let config = TopKConfig {
+            k: 1,
+            ..TopKConfig::default()
+        };
+        let plans = top_k_search(&problem, &state, &config, AStarQueue::new, |_| 0.0);
+        let optimal = net_benefit_search(&problem, &state, &NetBenefitConfig::default()).unwrap();
+        assert_eq!(check_plan(&problem, &state, &plans[0]), optimal.cost);
+        assert!(problem
+            .describe_plan(&state, &plans[0])
+            .iter()
+            .all(|action| action.name != "drive_truck0_from2_to-1"));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::problems::problem::Problem;
+use crate::search::search::state_hash;
+use crate::search::search_tree::SearchTree;
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+/// Width-based search strategies, selected by name like the `SearchQueue` variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidthSearch {
+    IW(usize),            // Breadth-first search pruning states of novelty above the width
+    IteratedWidth(usize), // IW(1), IW(2), ... up to the given width
+    BFWS,                 // Best-first search on novelty, then heuristic value
+}
+
+impl WidthSearch {
+    /// Parses "IW(1)", "IW(2)", "IW" (IW(1) then IW(2)) and "BFWS".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "IW(1)" => Some(WidthSearch::IW(1)),
+            "IW(2)" => Some(WidthSearch::IW(2)),
+            "IW" => Some(WidthSearch::IteratedWidth(2)),
+            "BFWS" => Some(WidthSearch::BFWS),
+            _ => None,
+        }
+    }
+}
+
+fn collect_atoms(prefix: u64, value: &JsonValue, atoms: &mut Vec<u64>) {
+    match value {
+        JsonValue::Object(fields) => {
+            for (key, field) in fields {
+                collect_atoms(state_hash(&(prefix, key)), field, atoms);
+            }
+        }
+        JsonValue::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_atoms(state_hash(&(prefix, index)), item, atoms);
+            }
+        }
+        leaf => atoms.push(state_hash(&(prefix, leaf.to_string()))),
+    }
+}
+
+/// Atoms of a state, derived from its serde representation: one atom per leaf value,
+/// identified by its path from the root. A numeric variable thus gives a different atom
+/// for each of its values. Maps are keyed by name, so the iteration order of a `HashMap`
+/// does not matter.
+pub fn state_atoms<S: Serialize>(state: &S) -> Vec<u64> {
+    let value = serde_json::to_value(state).expect("Failed to serialise state");
+    let mut atoms = Vec::new();
+    collect_atoms(0, &value, &mut atoms);
+    atoms.sort_unstable();
+    atoms.dedup();
+    atoms
+}
+
+/// Atoms and pairs of atoms seen so far. The novelty of a state is the size of the
+/// smallest tuple of its atoms not seen before: 1 for a new atom, 2 for a new pair, and
+/// `width + 1` if it has neither within the width of the table.
+pub struct NoveltyTable {
+    width: usize,
+    atoms: HashSet<u64>,
+    pairs: HashSet<(u64, u64)>,
+}
+
+impl NoveltyTable {
+    pub fn new(width: usize) -> Self {
+        assert!(
+            width == 1 || width == 2,
+            "Novelty is computed for widths 1 and 2 only, not {}",
+            width
+        );
+        NoveltyTable {
+            width,
+            atoms: HashSet::new(),
+            pairs: HashSet::new(),
+        }
+    }
+
+    /// Novelty of a state with the given sorted atoms, which are then marked as seen.
+    pub fn novelty(&mut self, atoms: &[u64]) -> usize {
+        let mut novelty = self.width + 1;
+        for &atom in atoms {
+            if self.atoms.insert(atom) {
+                novelty = 1;
+            }
+        }
+        if self.width >= 2 {
+            for (i, &first) in atoms.iter().enumerate() {
+                for &second in &atoms[i + 1..] {
+                    if self.pairs.insert((first, second)) {
+                        novelty = novelty.min(2);
+                    }
+                }
+            }
+        }
+        novelty
+    }
+}
+
+/// Runs the width-based search `strategy` on `problem`. `heuristic` only breaks ties
+/// between states of equal novelty in BFWS, so a constant heuristic is fine.
+pub fn width_search<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    strategy: WidthSearch,
+    heuristic: H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    match strategy {
+        WidthSearch::IW(width) => iterated_width(problem, initial_state, width),
+        WidthSearch::IteratedWidth(max_width) => (1..=max_width)
+            .map(|width| iterated_width(problem, initial_state, width))
+            .find(Result::is_ok)
+            .unwrap_or(Err("No solution found within the width bound")),
+        WidthSearch::BFWS => best_first_width_search(problem, initial_state, heuristic),
+    }
+}
+
+/// IW(k): breadth-first search in which every generated state whose novelty exceeds
+/// `width` is pruned. Incomplete, but polynomial in the number of atoms.
+pub fn iterated_width<P: Problem>(
+    problem: &P,
+    initial_state: &P::State,
+    width: usize,
+) -> Result<Vec<P::Action>, &'static str> {
+    if problem.is_goal_state(initial_state) {
+        return Ok(Vec::new());
+    }
+    let mut tree = SearchTree::new(initial_state.clone());
+    let mut table = NoveltyTable::new(width);
+    table.novelty(&state_atoms(initial_state));
+    let mut open = VecDeque::from([0]);
+    let (mut nodes, mut novel) = (0, 1);
+
+    while let Some(cur_idx) = open.pop_front() {
+        let successors = tree.expand_node(
+            cur_idx,
+            |state| problem.get_possible_actions(state),
+            |state, action| problem.apply_action(state, action),
+            |state, action| problem.action_cost(state, action),
+        );
+        for succ_idx in successors {
+            nodes += 1;
+            let state = tree.get_state(succ_idx).unwrap();
+            if table.novelty(&state_atoms(state)) > width {
+                continue;
+            }
+            novel += 1;
+            if problem.is_goal_state(state) {
+                println!("IW({width}) nodes: {nodes}, novel: {novel}");
+                return Ok(tree.trace_actions(succ_idx));
+            }
+            open.push_back(succ_idx);
+        }
+    }
+    println!("IW({width}) nodes: {nodes}, novel: {novel}");
+    Err("No solution found within the width bound")
+}
+
+/// BFWS: best-first search ordered by novelty (1, 2 or 3 for anything less novel), then
+/// by heuristic value. Novelty is measured among the states with the same heuristic value,
+/// so that each improvement of the heuristic opens a fresh table. Complete, as no state is
+/// pruned other than duplicates.
+pub fn best_first_width_search<P, H>(
+    problem: &P,
+    initial_state: &P::State,
+    heuristic: H,
+) -> Result<Vec<P::Action>, &'static str>
+where
+    P: Problem,
+    H: Fn(&P::State) -> f64,
+{
+    if problem.is_goal_state(initial_state) {
+        return Ok(Vec::new());
+    }
+    let mut tree = SearchTree::new(initial_state.clone());
+    let mut tables: HashMap<OrderedFloat<f64>, NoveltyTable> = HashMap::new();
+    let mut closed = HashSet::from([state_hash(initial_state)]);
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((1, OrderedFloat(heuristic(initial_state)), 0)));
+    let (mut nodes, mut uniq) = (0, 1);
+
+    while let Some(Reverse((_, _, cur_idx))) = open.pop() {
+        let successors = tree.expand_node(
+            cur_idx,
+            |state| problem.get_possible_actions(state),
+            |state, action| problem.apply_action(state, action),
+            |state, action| problem.action_cost(state, action),
+        );
+        for succ_idx in successors {
+            nodes += 1;
+            let state = tree.get_state(succ_idx).unwrap();
+            if !closed.insert(state_hash(state)) {
+                continue;
+            }
+            uniq += 1;
+            if problem.is_goal_state(state) {
+                println!("nodes: {nodes}, unique: {uniq}");
+                return Ok(tree.trace_actions(succ_idx));
+            }
+            let h = OrderedFloat(heuristic(state));
+            let novelty = tables
+                .entry(h)
+                .or_insert_with(|| NoveltyTable::new(2))
+                .novelty(&state_atoms(state));
+            open.push(Reverse((novelty, h, succ_idx)));
+        }
+    }
+    println!("nodes: {nodes}, unique: {uniq}");
+    Err("No solution found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::counters_problem::counters_problem::CountersProblem;
+    use crate::problems::red_car_problem::red_car_problem::RedCarProblem;
+    use crate::search::test_util::check_plan;
+
+    #[test]
+    fn test_novelty_table() {
+        let mut table = NoveltyTable::new(2);
+        assert_eq!(table.novelty(&[1, 2]), 1);
+        assert_eq!(table.novelty(&[1, 3]), 1);
+        assert_eq!(table.novelty(&[2, 3]), 2);
+        assert_eq!(table.novelty(&[1, 2, 3]), 3);
+    }
+
+    #[test]
+    fn test_width_searches_solve_without_heuristic() {
+        let (state, problem) = CountersProblem::load_state_from_json(
+            "src/inputs/counters_problem/problems_json/pfile1.json",
+        );
+        for strategy in ["IW", "BFWS"] {
+            let strategy = WidthSearch::from_name(strategy).unwrap();
+            let plan = width_search(&problem, &state, strategy, |_| 0.0).unwrap();
+            check_plan(&problem, &state, &plan);
+        }
+
+        let (state, problem) = RedCarProblem::load_state_from_json(
+            "src/inputs/red_car_problem/red_car/problems_json/pfile1.json",
+        );
+        let plan = width_search(&problem, &state, WidthSearch::BFWS, |_| 0.0).unwrap();
+        check_plan(&problem, &state, &plan);
+    }
+}